@@ -39,7 +39,7 @@ pub async fn main() {
             timeout_commit: None,
         },
     );
-    node.await.run().await.unwrap();
+    node.await.unwrap().run().await.unwrap();
     home.close().unwrap();
 }
 
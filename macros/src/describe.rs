@@ -8,14 +8,21 @@ use syn::*;
 pub fn derive(item: TokenStream) -> TokenStream {
     let item = parse_macro_input!(item as DeriveInput);
 
+    if let Data::Enum(ref data) = item.data {
+        return derive_enum(&item, data);
+    }
+
     let num_to_token = |n: usize| TokenStream2::from_str(&n.to_string()).unwrap();
-    let names = struct_fields(&item).enumerate().map(|(i, field)| {
-        field
-            .ident
-            .clone()
-            .map(|name| name.into_token_stream())
-            .unwrap_or_else(|| num_to_token(i))
-    });
+    let names: Vec<_> = struct_fields(&item)
+        .enumerate()
+        .map(|(i, field)| {
+            field
+                .ident
+                .clone()
+                .map(|name| name.into_token_stream())
+                .unwrap_or_else(|| num_to_token(i))
+        })
+        .collect();
     let types = struct_fields(&item).map(|field| &field.ty);
     let types_where = struct_fields(&item).map(|field| &field.ty);
 
@@ -45,6 +52,9 @@ pub fn derive(item: TokenStream) -> TokenStream {
                 #(
                     .named_child_from_state::<Self, #types>(
                         stringify!(#names),
+                        |value| value
+                            .downcast_ref::<Self>()
+                            .map(|value| &value.#names as &dyn ::std::any::Any),
                     )
                 )*
                 .build()
@@ -55,10 +65,68 @@ pub fn derive(item: TokenStream) -> TokenStream {
     output.into()
 }
 
+/// Derives `Describe` for an enum, describing each variant with the byte it is
+/// encoded with (its index) and its fields.
+fn derive_enum(item: &DeriveInput, data: &DataEnum) -> TokenStream {
+    let variants = data.variants.iter().enumerate().map(|(i, variant)| {
+        let variant_name = &variant.ident;
+        let discriminant = i as u8;
+        let fields = variant.fields.iter().enumerate().map(|(j, field)| {
+            let field_name = field
+                .ident
+                .as_ref()
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| j.to_string());
+            let ty = &field.ty;
+            quote! { .variant_field::<#ty>(#field_name) }
+        });
+
+        quote! {
+            .variant(stringify!(#variant_name), #discriminant)
+            #(#fields)*
+        }
+    });
+    let types_where = data
+        .variants
+        .iter()
+        .flat_map(|variant| variant.fields.iter().map(|field| &field.ty));
+
+    let name = &item.ident;
+    let mut generics = item.generics.clone();
+    generics.params.iter_mut().for_each(|p| {
+        if let GenericParam::Type(tp) = p {
+            tp.default.take();
+        }
+    });
+    let where_clause = generics
+        .where_clause
+        .clone()
+        .unwrap_or(parse_quote!(where))
+        .predicates;
+    let generic_params = gen_param_input(&generics, true);
+
+    let output = quote! {
+        impl #generics ::orga::describe::Describe for #name #generic_params
+        where
+            Self: ::orga::state::State + 'static,
+            #(#types_where: ::orga::state::State + ::orga::describe::Describe + 'static,)*
+            #where_clause
+        {
+            fn describe() -> ::orga::describe::Descriptor {
+                ::orga::describe::Builder::new::<Self>()
+                #(#variants)*
+                .build()
+            }
+        }
+    };
+
+    output.into()
+}
+
 fn struct_fields(item: &DeriveInput) -> impl Iterator<Item = &Field> {
     let data = match item.data {
         Data::Struct(ref data) => data,
-        Data::Enum(ref _data) => todo!("#[derive(Describe)] does not yet support enums"),
+        Data::Enum(_) => unreachable!(),
         Data::Union(_) => panic!("Unions are not supported"),
     };
 
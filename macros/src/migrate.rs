@@ -1,7 +1,7 @@
 use darling::{
     ast,
     usage::{GenericsExt, Options, Purpose, UsesTypeParams},
-    FromDeriveInput,
+    FromDeriveInput, FromField,
 };
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
@@ -22,6 +22,97 @@ struct MigrateInputReceiver {
     #[darling(default)]
     version: u8,
     previous: Option<Path>,
+
+    /// Field-level `#[migrate(..)]` attributes, parsed separately since the
+    /// field receiver above reads `#[state(..)]` attributes.
+    #[darling(skip)]
+    migrate_fields: Vec<MigrateFieldReceiver>,
+}
+
+/// Field-level migration options, used to generate a `MigrateFrom`
+/// implementation from the previous version of the struct:
+///
+/// - `#[migrate(from = "old_name")]` populates the field from the previous
+///   version's field named `old_name`.
+/// - `#[migrate(skip)]` populates the field with its default value, e.g. for a
+///   field which was newly added in this version.
+///
+/// Other fields are populated from the previous version's field of the same
+/// name, converted with `MigrateInto`. Fields of the previous version which
+/// are not used are dropped.
+#[derive(Debug, FromField)]
+#[darling(attributes(migrate))]
+struct MigrateFieldReceiver {
+    ident: Option<Ident>,
+    ty: Type,
+    #[darling(default)]
+    from: Option<Ident>,
+    #[darling(default)]
+    skip: bool,
+    /// Whether the field has a `#[migrate(..)]` attribute.
+    #[darling(skip)]
+    annotated: bool,
+}
+
+impl MigrateInputReceiver {
+    /// Generates a `MigrateFrom` implementation from the previous version if
+    /// any field has a `#[migrate(..)]` attribute. Otherwise, the
+    /// implementation is expected to be written by hand.
+    fn migrate_from_impl(&self) -> TokenStream2 {
+        let prev = match &self.previous {
+            Some(prev) if self.migrate_fields.iter().any(|f| f.annotated) => prev,
+            _ => return quote! {},
+        };
+
+        let ident = &self.ident;
+        let (imp, ty, wher) = self.generics.split_for_impl();
+
+        let search_options: Options = Purpose::BoundImpl.into();
+        let decl_tp = self.generics.declared_type_params();
+        let bounds = self.migrate_fields.iter().filter_map(|f| {
+            let ty = &f.ty;
+            let usages = ty.uses_type_params_cloned(&search_options, &decl_tp);
+            if usages.is_empty() {
+                None
+            } else {
+                Some(quote! { #ty: ::orga::state::State, })
+            }
+        });
+        let wher = match wher {
+            Some(wher) => quote! { #wher #(#bounds)*},
+            None => quote! { where #(#bounds)* },
+        };
+
+        let fields = self.migrate_fields.iter().enumerate().map(|(i, f)| {
+            let field_ident = f.ident.as_ref().map(|v| quote!(#v)).unwrap_or_else(|| {
+                let i = syn::Index::from(i);
+                quote!(#i)
+            });
+            let src_ident = f
+                .from
+                .as_ref()
+                .map(|v| quote!(#v))
+                .unwrap_or_else(|| field_ident.clone());
+
+            if f.skip {
+                quote! { #field_ident: Default::default(), }
+            } else {
+                quote! {
+                    #field_ident: ::orga::migrate::MigrateInto::migrate_into(value.#src_ident)?,
+                }
+            }
+        });
+
+        quote! {
+            impl #imp ::orga::migrate::MigrateFrom<#prev> for #ident #ty #wher {
+                fn migrate_from(value: #prev) -> ::orga::Result<Self> {
+                    Ok(Self {
+                        #(#fields)*
+                    })
+                }
+            }
+        }
+    }
 }
 
 impl ToTokens for MigrateInputReceiver {
@@ -33,6 +124,7 @@ impl ToTokens for MigrateInputReceiver {
             identity,
             version,
             previous,
+            ..
         } = self;
 
         let (imp, ty, wher) = generics.split_for_impl();
@@ -55,6 +147,14 @@ impl ToTokens for MigrateInputReceiver {
 
             if f.skip {
                 quote! { #field_ident: Default::default(), }
+            } else if let Some(transform) = &f.transform {
+                // transformed bytes are decoded before being passed to the
+                // field's migration, as in `load_child_transformed`
+                quote! { #field_ident: ::orga::migrate::Migrate::migrate(
+                    <Self as ::orga::state::State>::field_keyop(stringify!(#field_ident)).unwrap_or(::orga::describe::KeyOp::Append(vec![])).apply(&src),
+                    <Self as ::orga::state::State>::field_keyop(stringify!(#field_ident)).unwrap_or(::orga::describe::KeyOp::Append(vec![])).apply(&dest),
+                    &mut <#transform as ::orga::state::Transform>::decode(bytes)?.as_slice(),
+                )?, }
             } else {
                 quote! { #field_ident: ::orga::migrate::Migrate::migrate(
                     <Self as ::orga::state::State>::field_keyop(stringify!(#field_ident)).unwrap_or(::orga::describe::KeyOp::Append(vec![])).apply(&src),
@@ -97,6 +197,8 @@ impl ToTokens for MigrateInputReceiver {
             }
         };
 
+        tokens.extend(self.migrate_from_impl());
+
         tokens.extend(quote! {
             impl #imp ::orga::migrate::Migrate for #ident #ty #wher
             {
@@ -121,8 +223,18 @@ impl ToTokens for MigrateInputReceiver {
 pub fn derive(item: TokenStream) -> TokenStream {
     let item = parse_macro_input!(item as DeriveInput);
 
-    MigrateInputReceiver::from_derive_input(&item)
-        .unwrap()
-        .into_token_stream()
-        .into()
+    let mut receiver = MigrateInputReceiver::from_derive_input(&item).unwrap();
+    if let Data::Struct(data) = &item.data {
+        receiver.migrate_fields = data
+            .fields
+            .iter()
+            .map(|field| {
+                let mut receiver = MigrateFieldReceiver::from_field(field).unwrap();
+                receiver.annotated = field.attrs.iter().any(|a| a.path().is_ident("migrate"));
+                receiver
+            })
+            .collect();
+    }
+
+    receiver.into_token_stream().into()
 }
@@ -436,6 +436,19 @@ impl OrgaMetaStruct {
                         .unwrap()
                         .contains_key(&channel.as_ref().unwrap())
             })
+            .map(|mut f| {
+                // `#[migrate(..)]` describes how a field is populated when it
+                // is introduced, so it only applies to the first version
+                // containing the field
+                let in_prev = maybe_prev.is_some()
+                    && f.version.as_ref().map_or(true, |versions| {
+                        versions.contains_key(&format_ident!("V{}", version - 1))
+                    });
+                if in_prev {
+                    f.attrs.retain(|attr| !is_attr_with_ident(attr, "migrate"));
+                }
+                f
+            })
             .collect();
         let data = ast::Data::Struct(ast::Fields::new(style, fields));
 
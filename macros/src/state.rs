@@ -5,7 +5,7 @@ use darling::{
     ast,
     export::NestedMeta,
     usage::{GenericsExt, Options, Purpose, UsesTypeParams},
-    uses_type_params, FromDeriveInput, FromField, FromMeta,
+    uses_type_params, FromDeriveInput, FromField, FromMeta, FromVariant,
 };
 use itertools::Itertools;
 use proc_macro::TokenStream;
@@ -156,6 +156,8 @@ impl StateInputReceiver {
                 None => {
                     if field.skip {
                         quote! { .flush_skipped_child(self.#name)?}
+                    } else if let Some(ref transform) = field.transform {
+                        quote! {.flush_child_transformed::<#transform, _>(self.#name)?}
                     } else {
                         quote! {.flush_child(self.#name)?}
                     }
@@ -203,6 +205,8 @@ impl StateInputReceiver {
                 None => {
                     if field.skip {
                         quote! { #name: loader.load_skipped_child()? }
+                    } else if let Some(ref transform) = field.transform {
+                        quote! { #name: loader.load_child_transformed::<Self, #transform, _>()? }
                     } else {
                         quote! { #name: loader.load_child::<Self, _>()? }
                     }
@@ -258,7 +262,8 @@ impl StateInputReceiver {
             .enumerate()
             .map(|(i, (_name, field))| {
                 let field_ty = &field.ty;
-                let maybe_term_bound = if i < n_fields - 1 {
+                // transformed fields are delimited by their transform
+                let maybe_term_bound = if i < n_fields - 1 && field.transform.is_none() {
                     quote! { #field_ty: #terminated_trait, }
                 } else {
                     quote! {}
@@ -363,6 +368,8 @@ pub struct StateFieldReceiver {
     pub skip: bool,
     #[darling(default)]
     pub transparent: bool,
+    #[darling(default)]
+    pub transform: Option<Path>,
     pub prefix: Option<PrefixBytes>,
     pub absolute_prefix: Option<PrefixBytes>,
 }
@@ -424,9 +431,255 @@ impl FromMeta for PrefixBytes {
     }
 }
 
+#[derive(Debug, FromDeriveInput, Clone)]
+#[darling(attributes(state), supports(enum_any))]
+pub struct StateEnumReceiver {
+    pub ident: Ident,
+    pub generics: syn::Generics,
+    pub data: ast::Data<StateVariantReceiver, ()>,
+
+    #[darling(default)]
+    pub version: u8,
+}
+
+#[derive(Debug, FromVariant, Clone)]
+#[darling(attributes(state))]
+pub struct StateVariantReceiver {
+    pub ident: Ident,
+    pub fields: ast::Fields<StateFieldReceiver>,
+}
+
+impl StateVariantReceiver {
+    /// Returns a pattern binding each of the variant's fields, along with the
+    /// bound names in field order.
+    fn pattern(&self) -> (TokenStream2, Vec<Ident>) {
+        let Self { ident, fields } = self;
+
+        for field in fields.iter() {
+            if field.as_type.is_some()
+                || field.skip
+                || field.transparent
+                || field.transform.is_some()
+                || field.prefix().is_some()
+            {
+                panic!("Field attributes are not supported in enum variants");
+            }
+        }
+
+        let names = (0..fields.len())
+            .map(|i| Ident::new(&format!("__field{}", i), proc_macro2::Span::call_site()))
+            .collect_vec();
+
+        let pattern = match fields.style {
+            ast::Style::Unit => quote! { Self::#ident },
+            ast::Style::Tuple => quote! { Self::#ident(#(#names),*) },
+            ast::Style::Struct => {
+                let field_names = fields.iter().map(|f| f.ident.as_ref().unwrap());
+                quote! { Self::#ident { #(#field_names: #names),* } }
+            }
+        };
+
+        (pattern, names)
+    }
+}
+
+impl StateEnumReceiver {
+    fn variants(&self) -> Vec<(u8, &StateVariantReceiver)> {
+        let variants = self.data.as_ref().take_enum().unwrap();
+        if variants.len() > u8::MAX as usize + 1 {
+            panic!("State enums can have at most 256 variants");
+        }
+
+        variants
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (i as u8, v))
+            .collect()
+    }
+
+    fn attach_method(&self) -> TokenStream2 {
+        let Types {
+            attacher_ty,
+            result_ty,
+            store_ty,
+            ..
+        } = Default::default();
+
+        let arms = self.variants().into_iter().map(|(discriminant, variant)| {
+            let (pattern, names) = variant.pattern();
+            quote! {
+                #pattern => {
+                    #attacher_ty::new(store.sub(&[#discriminant]))
+                    #(.attach_child(#names)?)*;
+                }
+            }
+        });
+
+        quote! {
+            fn attach(&mut self, store: #store_ty) -> #result_ty<()> {
+                match self {
+                    #(#arms)*
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn flush_method(&self) -> TokenStream2 {
+        let Types {
+            flusher_ty,
+            result_ty,
+            ..
+        } = Default::default();
+        let Self { version, .. } = self;
+
+        let arms = self.variants().into_iter().map(|(discriminant, variant)| {
+            let (pattern, names) = variant.pattern();
+            quote! {
+                #pattern => {
+                    #flusher_ty::new(out)
+                        .version(#version)?
+                        .discriminant(#discriminant)?
+                        #(.flush_child(#names)?)*;
+                }
+            }
+        });
+
+        quote! {
+            fn flush<__W: ::std::io::Write>(self, out: &mut __W) -> #result_ty<()> {
+                match self {
+                    #(#arms)*
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn load_method(&self) -> TokenStream2 {
+        let Types {
+            loader_ty,
+            store_ty,
+            result_ty,
+            error_ty,
+            ..
+        } = Default::default();
+        let Self { ident, version, .. } = self;
+
+        let arms = self.variants().into_iter().map(|(discriminant, variant)| {
+            let (pattern, names) = variant.pattern();
+            quote! {
+                #discriminant => {
+                    #(let #names = loader.load_child::<Self, _>()?;)*
+                    #pattern
+                }
+            }
+        });
+
+        quote! {
+            fn load(store: #store_ty, bytes: &mut &[u8]) -> #result_ty<Self> {
+                let mut loader = #loader_ty::new(store.clone(), bytes, #version);
+                let mut value: Self = match loader.load_discriminant::<Self>()? {
+                    #(#arms)*
+                    n => {
+                        return Err(#error_ty::State(format!(
+                            "Unknown discriminant {} for {}",
+                            n,
+                            stringify!(#ident),
+                        )))
+                    }
+                };
+                value.attach(store)?;
+
+                Ok(value)
+            }
+        }
+    }
+
+    fn bounds(&self) -> TokenStream2 {
+        let Types {
+            terminated_trait,
+            state_trait,
+            ..
+        } = Default::default();
+        let opts: Options = Purpose::BoundImpl.into();
+        let tys = self.generics.declared_type_params().into();
+
+        let field_bounds: TokenStream2 = self
+            .variants()
+            .into_iter()
+            .flat_map(|(_, variant)| {
+                let n_fields = variant.fields.len();
+                variant
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, field)| (i == n_fields - 1, field))
+            })
+            .map(|(is_last, field)| {
+                let field_ty = &field.ty;
+                let maybe_term_bound = if !is_last {
+                    quote! { #field_ty: #terminated_trait, }
+                } else {
+                    quote! {}
+                };
+                let uses_generic = !field.uses_type_params(&opts, &tys).is_empty();
+                let maybe_state_bound = if uses_generic {
+                    quote! { #field_ty: #state_trait, }
+                } else {
+                    quote! {}
+                };
+                quote! { #maybe_term_bound #maybe_state_bound }
+            })
+            .collect();
+
+        quote! { Self: 'static, #field_bounds }
+    }
+}
+
+impl ToTokens for StateEnumReceiver {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let StateEnumReceiver {
+            ident, generics, ..
+        } = self;
+
+        let Types { state_trait, .. } = Default::default();
+
+        let (imp, ty, wher) = generics.split_for_impl();
+
+        let attach_method = self.attach_method();
+        let flush_method = self.flush_method();
+        let load_method = self.load_method();
+
+        let bounds = self.bounds();
+
+        let wher = if wher.is_some() {
+            quote! { #wher #bounds  }
+        } else {
+            quote! { where #bounds  }
+        };
+
+        tokens.extend(quote! {
+            impl #imp #state_trait for #ident #ty #wher {
+                #attach_method
+                #flush_method
+                #load_method
+            }
+        });
+    }
+}
+
 pub fn derive(item: TokenStream) -> TokenStream {
     let item = parse_macro_input!(item as DeriveInput);
 
+    if let Data::Enum(_) = item.data {
+        return StateEnumReceiver::from_derive_input(&item)
+            .unwrap()
+            .into_token_stream()
+            .into();
+    }
+
     StateInputReceiver::from_derive_input(&item)
         .unwrap()
         .into_token_stream()
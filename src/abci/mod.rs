@@ -1,4 +1,5 @@
 use crate::call::Call;
+use crate::encoding::Decode;
 use crate::query::Query;
 use crate::state::State;
 
@@ -13,6 +14,11 @@ pub mod prost;
 use messages::*;
 pub use tendermint_proto::v0_34::abci as messages;
 
+/// ABCI++ messages, used for hooks which are not part of the v0.34 ABCI
+/// protocol.
+#[cfg(feature = "abci-plus")]
+pub use tendermint_proto::v0_38::abci as messages_plus;
+
 #[cfg(feature = "abci")]
 mod server {
     use super::*;
@@ -21,12 +27,17 @@ mod server {
     use crate::Error;
     use log::info;
     use std::env;
-    use std::net::ToSocketAddrs;
+    use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+    use std::panic::AssertUnwindSafe;
+    use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::mpsc::{self, Receiver, SyncSender};
     use std::sync::{Arc, RwLock};
+    use std::time::{Duration, Instant};
     use tendermint_proto::v0_34::abci::request::Value as Req;
     use tendermint_proto::v0_34::abci::response::Value as Res;
     use tendermint_proto::v0_34::types::Header;
+    #[cfg(feature = "abci-plus")]
+    use tendermint_proto::v0_38::abci::{request::Value as ReqPlus, response::Value as ResPlus};
 
     /// Top-level struct for running an ABCI application. Maintains an ABCI
     /// server, mempool, and handles committing data to the store.
@@ -42,8 +53,83 @@ mod server {
         header: Option<Header>,
         shutdown: Arc<RwLock<Option<Error>>>,
         shutdown_notifier: Arc<RwLock<bool>>,
+        shutdown_requested: Arc<AtomicBool>,
+        worker_count: usize,
+        workers: Vec<Worker>,
+    }
+
+    /// A handle which can stop an [ABCIStateMachine] after it has been moved
+    /// into [ABCIStateMachine::listen], e.g. from another thread.
+    #[derive(Clone)]
+    pub struct ShutdownHandle {
+        shutdown: Arc<RwLock<Option<Error>>>,
+        requested: Arc<AtomicBool>,
+    }
+
+    impl ShutdownHandle {
+        /// Requests that the state machine shut down.
+        /// [ABCIStateMachine::listen] closes the ABCI connections and
+        /// returns `Ok` shortly after.
+        pub fn shutdown(&self) {
+            self.requested.store(true, Ordering::SeqCst);
+            self.shutdown
+                .write()
+                .unwrap()
+                .get_or_insert_with(|| Error::ABCI("ABCI server shut down".into()));
+        }
+    }
+
+    /// Commits the store at the given header. With the `metrics` feature, the
+    /// commit is made through a [MeteredStore](crate::store::MeteredStore) to
+    /// record its latency.
+    fn commit(store: MerkStore, header: Header) -> Result<MerkStore> {
+        #[cfg(feature = "metrics")]
+        {
+            let mut store = crate::store::MeteredStore::new(store);
+            store.commit(header)?;
+            Ok(store.into_inner())
+        }
+
+        #[cfg(not(feature = "metrics"))]
+        {
+            let mut store = store;
+            store.commit(header)?;
+            Ok(store)
+        }
+    }
+
+    /// Runs a request handler, converting a panic into an error.
+    fn catch_panic<T>(op: impl FnOnce() -> Result<T>) -> Result<T> {
+        std::panic::catch_unwind(AssertUnwindSafe(op)).unwrap_or_else(|payload| {
+            let msg = if let Some(msg) = payload.downcast_ref::<&str>() {
+                msg.to_string()
+            } else if let Some(msg) = payload.downcast_ref::<String>() {
+                msg.clone()
+            } else {
+                "unknown panic payload".to_string()
+            };
+            Err(Error::ABCI(format!("Application panicked: {}", msg)))
+        })
+    }
+
+    /// Copies an error as an [Error::ABCI], without wrapping the message of an
+    /// error which already is one.
+    fn to_abci_error(err: &Error) -> Error {
+        match err {
+            Error::ABCI(msg) => Error::ABCI(msg.clone()),
+            err => Error::ABCI(err.to_string()),
+        }
     }
 
+    /// The default number of ABCI connections to accept. Tendermint opens one
+    /// connection each for consensus, mempool, queries, and state sync
+    /// snapshots.
+    pub const DEFAULT_WORKER_COUNT: usize = 4;
+
+    /// How long [ABCIStateMachine::listen] waits for the workers to stop when
+    /// shutting down.
+    const WORKER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
     impl<A: Application> ABCIStateMachine<A> {
         /// Constructs an `ABCIStateMachine` from the given app (a set of
         /// handlers for transactions and blocks), and store (a
@@ -68,6 +154,27 @@ mod server {
                 header: None,
                 shutdown,
                 shutdown_notifier,
+                shutdown_requested: Arc::new(AtomicBool::new(false)),
+                worker_count: DEFAULT_WORKER_COUNT,
+                workers: vec![],
+            }
+        }
+
+        /// Set the number of ABCI connections to accept, each handled by its
+        /// own worker thread. Defaults to [DEFAULT_WORKER_COUNT].
+        #[must_use]
+        pub fn worker_count(mut self, count: usize) -> Self {
+            self.worker_count = count;
+
+            self
+        }
+
+        /// Returns a handle which can be used to shut down the state machine
+        /// while it is listening.
+        pub fn shutdown_handle(&self) -> ShutdownHandle {
+            ShutdownHandle {
+                shutdown: self.shutdown.clone(),
+                requested: self.shutdown_requested.clone(),
             }
         }
 
@@ -278,15 +385,13 @@ mod server {
                         store.flush()?;
                     }
 
-                    self_store_shared
-                        .borrow_mut()
-                        .commit(self.header.clone().unwrap())?;
+                    let self_store =
+                        commit(self_store_shared.into_inner(), self.header.clone().unwrap())?;
 
                     self.mempool_state.replace(Default::default());
                     self.consensus_state.replace(Default::default());
 
                     let mut res_commit = ResponseCommit::default();
-                    let self_store = self_store_shared.into_inner();
 
                     res_commit.data = self_store.root_hash()?.into();
                     self.store = Some(Shared::new(self_store));
@@ -355,8 +460,187 @@ mod server {
             }
         }
 
+        /// Handles a single incoming ABCI++ request which is not part of the
+        /// v0.34 protocol handled by [Self::run], passing `ExtendVote` and
+        /// `VerifyVoteExtension` requests to the
+        /// [`Application`](trait.Application.html).
+        ///
+        /// Vote extensions are created and verified against the last committed
+        /// state, and any writes made by the application are discarded.
+        #[cfg(feature = "abci-plus")]
+        pub fn run_plus(&mut self, req: messages_plus::Request) -> Result<ResPlus> {
+            let value = match req.value {
+                None => {
+                    return Err(Error::ABCI("Received empty request".into()));
+                }
+                Some(value) => value,
+            };
+
+            let app = self.app.take().unwrap();
+            let self_store = self.store.take().unwrap();
+            let store = Shared::new(BufStore::wrap(Shared::new(BufStore::wrap(
+                self_store.clone(),
+            ))));
+
+            let res = match value {
+                ReqPlus::ExtendVote(req) => app.extend_vote(store, req).map(ResPlus::ExtendVote),
+                ReqPlus::VerifyVoteExtension(req) => app
+                    .verify_vote_extension(store, req)
+                    .map(ResPlus::VerifyVoteExtension),
+                _ => Err(Error::ABCI("Unsupported ABCI++ request".into())),
+            };
+
+            self.store.replace(self_store);
+            self.app.replace(app);
+
+            res
+        }
+
+        /// Handles a single incoming ABCI request with [Self::run], converting
+        /// a panic in the application's handlers into an error.
+        ///
+        /// Any error (including a caught panic) is recorded as the shutdown
+        /// reason and the shutdown notifier is set, so the workers close their
+        /// connections and the node stops cleanly. The state machine should
+        /// not be used to handle further requests after an error.
+        pub fn dispatch(&mut self, req: Request) -> Result<Res> {
+            let res = catch_panic(|| self.run(req));
+
+            if let Err(e) = &res {
+                self.shut_down(e);
+            }
+
+            res
+        }
+
+        /// Handles a single incoming ABCI++ request with [Self::run_plus], the
+        /// same way [Self::dispatch] handles ABCI requests.
+        #[cfg(feature = "abci-plus")]
+        pub fn dispatch_plus(&mut self, req: messages_plus::Request) -> Result<ResPlus> {
+            let res = catch_panic(|| self.run_plus(req));
+
+            if let Err(e) = &res {
+                self.shut_down(e);
+            }
+
+            res
+        }
+
+        /// Records the reason for shutting down and notifies the workers and
+        /// node, which will then stop.
+        fn shut_down(&self, reason: &Error) {
+            let mut shutdown = self.shutdown.write().unwrap();
+            *shutdown = Some(to_abci_error(reason));
+            let mut shutdown = self.shutdown_notifier.write().unwrap();
+            *shutdown = true;
+        }
+
+        /// Shuts down the state machine, waiting up to `timeout` for the
+        /// workers to close their connections and exit before setting the
+        /// shutdown notifier.
+        ///
+        /// If no shutdown reason has been recorded yet, a generic one is set
+        /// so the workers know to stop. Each worker's connection is shut down
+        /// so that workers blocked reading from an idle connection wake up,
+        /// and requests still waiting to be handled are dropped. An error is
+        /// returned if any workers are still running after the timeout, in
+        /// which case they are detached.
+        pub fn shutdown(&mut self, timeout: Duration) -> Result<()> {
+            self.shutdown
+                .write()
+                .unwrap()
+                .get_or_insert_with(|| Error::ABCI("ABCI server shut down".into()));
+
+            for worker in self.workers.iter() {
+                if let Err(e) = worker.stream.shutdown(Shutdown::Both) {
+                    log::debug!("Error shutting down ABCI connection: {}", e);
+                }
+            }
+
+            let deadline = Instant::now() + timeout;
+            while Instant::now() < deadline && self.workers.iter().any(|w| !w.thread.is_finished())
+            {
+                // dropping the response sender stops a worker waiting to hand
+                // over a request
+                while let Ok((_req, cb)) = self.receiver.try_recv() {
+                    drop(cb);
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+
+            let mut running = 0;
+            for worker in self.workers.drain(..) {
+                if !worker.thread.is_finished() {
+                    running += 1;
+                    continue;
+                }
+                if worker.thread.join().is_err() {
+                    log::warn!("ABCI worker thread panicked");
+                }
+            }
+
+            *self.shutdown_notifier.write().unwrap() = true;
+
+            if running > 0 {
+                return Err(Error::ABCI(format!(
+                    "{} ABCI worker(s) did not stop within {:?}",
+                    running, timeout
+                )));
+            }
+
+            Ok(())
+        }
+
+        /// Shuts down with [WORKER_SHUTDOWN_TIMEOUT], logging rather than
+        /// returning any error so the original reason for stopping is kept.
+        fn stop(&mut self) {
+            if let Err(e) = self.shutdown(WORKER_SHUTDOWN_TIMEOUT) {
+                log::warn!("{}", e);
+            }
+        }
+
+        /// Accepts the configured number of connections from the server,
+        /// spawning a worker to handle each of them.
+        ///
+        /// If accepting a connection fails, the state machine is shut down so
+        /// any already-spawned workers close their connections.
+        fn spawn_workers(&mut self, listener: &TcpListener) -> Result<()> {
+            // TODO: more intelligently handle connections, e.g. handle tendermint
+            // dying/reconnecting?
+            while self.workers.len() < self.worker_count {
+                let accepted = listener
+                    .accept()
+                    .map_err(Error::from)
+                    .and_then(|(stream, _)| {
+                        let handle = stream.try_clone()?;
+                        Ok((abci2::Connection::new(stream)?, handle))
+                    });
+                let (conn, stream) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        let err = Error::ABCI(format!(
+                            "Failed to accept ABCI connection {} of {}: {}",
+                            self.workers.len() + 1,
+                            self.worker_count,
+                            e
+                        ));
+                        self.shut_down(&err);
+                        self.stop();
+                        return Err(err);
+                    }
+                };
+                let worker = self.create_worker(conn, stream, self.shutdown.clone())?;
+                self.workers.push(worker);
+            }
+
+            Ok(())
+        }
+
         /// Creates a TCP server for the ABCI protocol and begins handling the
         /// incoming connections.
+        ///
+        /// Returns `Ok` once stopped through a [ShutdownHandle], or the reason
+        /// for stopping otherwise.
         pub fn listen<SA: ToSocketAddrs>(mut self, addr: SA) -> Result<Arc<RwLock<bool>>> {
             if let Some(stop_height_str) = env::var_os("ORGA_STOP_HEIGHT") {
                 let _stop_height: u64 = stop_height_str
@@ -366,21 +650,17 @@ mod server {
                     .expect("Invalid ORGA_STOP_HEIGHT value");
             }
 
-            let server = abci2::Server::listen(addr)?;
-
-            // TODO: keep workers in struct
-            // TODO: more intelligently handle connections, e.g. handle tendermint
-            // dying/reconnecting?
-            self.create_worker(server.accept()?, self.shutdown.clone())?;
-            self.create_worker(server.accept()?, self.shutdown.clone())?;
-            self.create_worker(server.accept()?, self.shutdown.clone())?;
-            self.create_worker(server.accept()?, self.shutdown.clone())?;
+            let listener = TcpListener::bind(addr)?;
+            self.spawn_workers(&listener)?;
 
             loop {
-                if let Some(e) = self.shutdown.read().unwrap().as_ref() {
-                    let mut shutdown = self.shutdown_notifier.write().unwrap();
-                    *shutdown = true;
-                    return Err(Error::ABCI(e.to_string()));
+                let reason = self.shutdown.read().unwrap().as_ref().map(to_abci_error);
+                if let Some(e) = reason {
+                    self.stop();
+                    if self.shutdown_requested.load(Ordering::SeqCst) {
+                        return Ok(self.shutdown_notifier.clone());
+                    }
+                    return Err(e);
                 }
                 let (req, cb) = match self
                     .receiver
@@ -393,13 +673,13 @@ mod server {
                     }
                 };
                 let is_commit = matches!(req.value, Some(Req::Commit(_)));
-                let value = match self.run(req) {
-                    Ok(val) => val,
+                let value = match self.dispatch(req) {
+                    Ok(value) => value,
                     Err(e) => {
-                        let mut shutdown = self.shutdown.write().unwrap();
-                        *shutdown = Some(Error::ABCI(e.to_string()));
-                        let mut shutdown = self.shutdown_notifier.write().unwrap();
-                        *shutdown = true;
+                        // dropping the callback without a response stops the
+                        // worker which sent the request
+                        drop(cb);
+                        self.stop();
                         return Err(e);
                     }
                 };
@@ -414,12 +694,10 @@ mod server {
                             .parse()
                             .expect("Invalid ORGA_STOP_HEIGHT value");
                         if self.height >= stop_height {
-                            let mut shutdown = self.shutdown_notifier.write().unwrap();
-                            *shutdown = true;
-                            break Err(Error::ABCI(format!(
-                                "Reached stop height ({})",
-                                stop_height
-                            )));
+                            let err = Error::ABCI(format!("Reached stop height ({})", stop_height));
+                            self.shut_down(&err);
+                            self.stop();
+                            break Err(err);
                         }
                     }
                 }
@@ -431,50 +709,65 @@ mod server {
         fn create_worker(
             &self,
             conn: abci2::Connection,
+            stream: TcpStream,
             shutdown: Arc<RwLock<Option<Error>>>,
         ) -> Result<Worker> {
-            Ok(Worker::new(self.sender.clone(), conn, shutdown))
+            Ok(Worker::new(self.sender.clone(), conn, stream, shutdown))
         }
     }
 
+    /// Handles the requests from a single ABCI connection on its own thread.
+    ///
+    /// The worker owns its connection, closing it when it sees that the state
+    /// machine is shutting down. The state machine keeps a handle to the
+    /// underlying socket so it can interrupt a worker blocked on a read.
     struct Worker {
-        #[allow(dead_code)]
-        thread: std::thread::JoinHandle<()>, /* TODO: keep handle to connection or socket so we
-                                              * can close it */
+        thread: std::thread::JoinHandle<()>,
+        stream: TcpStream,
     }
 
     impl Worker {
         fn new(
             req_sender: SyncSender<(Request, SyncSender<Response>)>,
             mut conn: abci2::Connection,
+            stream: TcpStream,
             shutdown: Arc<RwLock<Option<Error>>>,
         ) -> Self {
             let thread = std::thread::spawn(move || {
-                let (res_sender, res_receiver) = mpsc::sync_channel(0);
                 loop {
                     if shutdown.read().unwrap().is_some() {
-                        if let Err(e) = conn.close() {
-                            log::debug!("Error closing connection: {}", e);
-                        };
                         break;
                     }
                     let req = match conn.read() {
                         Ok(req) => req,
                         Err(e) => {
-                            let mut shutdown = shutdown.write().unwrap();
-                            *shutdown = Some(Error::ABCI2(e));
+                            shutdown.write().unwrap().get_or_insert(Error::ABCI2(e));
                             return;
                         }
                     };
-                    if let Err(err) = req_sender.send((req, res_sender.clone())) {
+                    // the state machine drops the response sender without
+                    // responding when it stops, e.g. after an application
+                    // error
+                    let (res_sender, res_receiver) = mpsc::sync_channel(0);
+                    if let Err(err) = req_sender.send((req, res_sender)) {
                         log::warn!("Error sending request from worker: {}", err);
                         break;
                     }
-                    let res = res_receiver.recv().unwrap();
-                    conn.write(res).unwrap();
+                    let res = match res_receiver.recv() {
+                        Ok(res) => res,
+                        Err(_) => break,
+                    };
+                    if let Err(e) = conn.write(res) {
+                        shutdown.write().unwrap().get_or_insert(Error::ABCI2(e));
+                        return;
+                    }
                 }
+
+                if let Err(e) = conn.close() {
+                    log::debug!("Error closing connection: {}", e);
+                };
             });
-            Worker { thread }
+            Worker { thread, stream }
         }
     }
 
@@ -532,9 +825,36 @@ mod server {
         }
 
         /// Handle an ABCI Query.
+        ///
+        /// The query should be resolved against the state as of `req.height`,
+        /// or the latest committed state if the height is 0.
         fn query(&self, _store: Shared<MerkStore>, _req: RequestQuery) -> Result<ResponseQuery> {
             Ok(Default::default())
         }
+
+        /// Process an ABCI++ `ExtendVote` request, returning the extension to
+        /// attach to this node's precommit vote.
+        #[cfg(feature = "abci-plus")]
+        fn extend_vote(
+            &self,
+            _store: WrappedMerk,
+            _req: messages_plus::RequestExtendVote,
+        ) -> Result<messages_plus::ResponseExtendVote> {
+            Ok(Default::default())
+        }
+
+        /// Process an ABCI++ `VerifyVoteExtension` request. Accepts all vote
+        /// extensions by default.
+        #[cfg(feature = "abci-plus")]
+        fn verify_vote_extension(
+            &self,
+            _store: WrappedMerk,
+            _req: messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<messages_plus::ResponseVerifyVoteExtension> {
+            Ok(messages_plus::ResponseVerifyVoteExtension {
+                status: messages_plus::response_verify_vote_extension::VerifyStatus::Accept as i32,
+            })
+        }
     }
 
     /// Interface for persisting ABCI app state, as a supertrait of
@@ -640,6 +960,103 @@ mod server {
             Ok(Default::default())
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::net::TcpStream;
+
+        struct Noop;
+
+        impl Application for Noop {}
+
+        fn connect(
+            home: &tempfile::TempDir,
+            addr: &str,
+            count: usize,
+        ) -> Result<(ABCIStateMachine<Noop>, Vec<TcpStream>)> {
+            let mut abci = ABCIStateMachine::new(
+                Noop,
+                MerkStore::new(home.path()),
+                false,
+                Default::default(),
+                Default::default(),
+            )
+            .worker_count(count);
+
+            let listener = TcpListener::bind(addr)?;
+            let clients = (0..count)
+                .map(|_| TcpStream::connect(addr))
+                .collect::<std::io::Result<Vec<_>>>()
+                .unwrap();
+            abci.spawn_workers(&listener)?;
+
+            Ok((abci, clients))
+        }
+
+        #[test]
+        fn spawns_configured_workers() -> Result<()> {
+            let home = tempfile::TempDir::new().unwrap();
+            let (abci, _clients) = connect(&home, "127.0.0.1:26690", 2)?;
+
+            assert_eq!(abci.workers.len(), 2);
+            assert!(abci.shutdown.read().unwrap().is_none());
+
+            Ok(())
+        }
+
+        #[test]
+        fn shutdown_joins_workers() -> Result<()> {
+            let home = tempfile::TempDir::new().unwrap();
+            let (mut abci, clients) = connect(&home, "127.0.0.1:26691", 2)?;
+
+            // workers blocked reading from an open connection are woken up
+            abci.shutdown(Duration::from_secs(5))?;
+            assert!(abci.workers.is_empty());
+            assert!(*abci.shutdown_notifier.read().unwrap());
+            drop(clients);
+
+            let home = tempfile::TempDir::new().unwrap();
+            let (mut abci, clients) = connect(&home, "127.0.0.1:26692", 2)?;
+            drop(clients);
+            abci.shutdown(Duration::from_secs(5))?;
+            assert!(abci.workers.is_empty());
+            assert!(abci.shutdown.read().unwrap().is_some());
+            assert!(*abci.shutdown_notifier.read().unwrap());
+
+            Ok(())
+        }
+
+        #[test]
+        fn shutdown_handle_stops_listen() -> Result<()> {
+            let home = tempfile::TempDir::new().unwrap();
+            let abci = ABCIStateMachine::new(
+                Noop,
+                MerkStore::new(home.path()),
+                false,
+                Default::default(),
+                Default::default(),
+            )
+            .worker_count(1);
+            let handle = abci.shutdown_handle();
+
+            let addr = "127.0.0.1:26693";
+            let server = std::thread::spawn(move || abci.listen(addr));
+            let client = loop {
+                match TcpStream::connect(addr) {
+                    Ok(client) => break client,
+                    Err(_) => std::thread::sleep(Duration::from_millis(10)),
+                }
+            };
+
+            handle.shutdown();
+            let notifier = server.join().unwrap()?;
+            assert!(*notifier.read().unwrap());
+            drop(client);
+
+            Ok(())
+        }
+    }
 }
 
 #[cfg(feature = "abci")]
@@ -674,6 +1091,40 @@ impl<S> EndBlock for S {
     }
 }
 
+/// A trait for composite apps to declare the order in which their modules'
+/// [BeginBlock] and [EndBlock] hooks run.
+///
+/// Implementing this trait provides [BeginBlock] and [EndBlock] for the type,
+/// calling each module's hook in the order returned here, stopping at the first
+/// error. Modules not returned are not called.
+pub trait ModuleOrder {
+    /// Returns the modules whose [BeginBlock] hooks should run, in order.
+    fn begin_block_modules(&mut self) -> Vec<&mut dyn BeginBlock>;
+
+    /// Returns the modules whose [EndBlock] hooks should run, in order.
+    fn end_block_modules(&mut self) -> Vec<&mut dyn EndBlock>;
+}
+
+impl<T: ModuleOrder> BeginBlock for T {
+    default fn begin_block(&mut self, ctx: &BeginBlockCtx) -> Result<()> {
+        for module in self.begin_block_modules() {
+            module.begin_block(ctx)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: ModuleOrder> EndBlock for T {
+    default fn end_block(&mut self, ctx: &EndBlockCtx) -> Result<()> {
+        for module in self.end_block_modules() {
+            module.end_block(ctx)?;
+        }
+
+        Ok(())
+    }
+}
+
 /// A trait for types to handle the [InitChain] step.
 pub trait InitChain {
     /// Handle an [InitChain] step.
@@ -687,6 +1138,77 @@ impl<S> InitChain for S {
     }
 }
 
+/// A trait for types to validate transactions in the `CheckTx` step, before
+/// they are admitted to the mempool.
+///
+/// By default the full call is run against the mempool state, exactly as in
+/// `DeliverTx`. Apps may implement this to instead reject obviously-invalid
+/// calls (e.g. with bad signatures or far-future nonces) with cheap, stateless
+/// checks, skipping execution of the call.
+pub trait CheckTx: Call {
+    /// Validate a call from a `CheckTx` request.
+    fn check_tx(&mut self, call: Self::Call) -> Result<()>;
+}
+
+/// Default implementation of [CheckTx] for all types, running the full call.
+impl<S: Call> CheckTx for S {
+    default fn check_tx(&mut self, call: Self::Call) -> Result<()> {
+        self.call(call)
+    }
+}
+
+/// ABCI query path answered with the app's version, the node's orga version
+/// and the network version, see [well_known_query].
+pub const APP_VERSION_PATH: &str = "/app/version";
+
+/// ABCI query path answered with the node's health and the queried height, see
+/// [well_known_query].
+pub const HEALTH_PATH: &str = "/health";
+
+/// Handles the well-known ABCI query paths which are answered the same way for
+/// every app, so monitoring tools work without app-specific support. Returns
+/// `None` for any other path.
+///
+/// - [APP_VERSION_PATH] returns a JSON object with the given `app_version`
+///   (`version`, or `null` if the app did not provide one), the semantic
+///   version of orga the node was built with (`orga_version`), and the
+///   hex-encoded network version stored by the [crate::upgrade::Upgrade] module
+///   (`network_version`, or `null` if none is stored).
+/// - [HEALTH_PATH] returns a JSON object with `status` set to `"ok"` and the
+///   `height` the query was resolved at.
+pub fn well_known_query(
+    request: &RequestQuery,
+    store: crate::store::Store,
+    height: u64,
+    app_version: Option<&str>,
+) -> Result<Option<ResponseQuery>> {
+    let value = match request.path.as_str() {
+        APP_VERSION_PATH => {
+            let network_version = crate::upgrade::load_version(store)?
+                .map(|bytes| crate::upgrade::Version::decode(bytes.as_slice()))
+                .transpose()?
+                .map(|version| hex::encode(version.as_slice()));
+            serde_json::json!({
+                "version": app_version,
+                "orga_version": env!("CARGO_PKG_VERSION"),
+                "network_version": network_version,
+            })
+        }
+        HEALTH_PATH => serde_json::json!({
+            "status": "ok",
+            "height": height,
+        }),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(ResponseQuery {
+        code: 0,
+        height: height.try_into()?,
+        value: value.to_string().into_bytes().into(),
+        ..Default::default()
+    }))
+}
+
 /// A trait for raw handling of ABCI queries.
 pub trait AbciQuery {
     /// Handle an ABCI query, returning a raw [ResponseQuery].to send back via
@@ -707,9 +1229,311 @@ impl<S> AbciQuery for S {
     }
 }
 
+/// A trait for types to attach an extension to this node's precommit votes
+/// (the ABCI++ `ExtendVote` step), e.g. oracle data.
+#[cfg(feature = "abci-plus")]
+pub trait ExtendVote {
+    /// Handle an `ExtendVote` request, returning the vote extension bytes.
+    fn extend_vote(
+        &mut self,
+        req: &messages_plus::RequestExtendVote,
+    ) -> Result<messages_plus::ResponseExtendVote>;
+}
+
+/// Default implementation of [ExtendVote] for all types. Returns an empty
+/// vote extension.
+#[cfg(feature = "abci-plus")]
+impl<S> ExtendVote for S {
+    default fn extend_vote(
+        &mut self,
+        _req: &messages_plus::RequestExtendVote,
+    ) -> Result<messages_plus::ResponseExtendVote> {
+        Ok(Default::default())
+    }
+}
+
+/// A trait for types to verify the vote extensions of other validators' votes
+/// (the ABCI++ `VerifyVoteExtension` step).
+///
+/// Verification must be deterministic, and should not depend on or modify
+/// state other than that of the last committed block.
+#[cfg(feature = "abci-plus")]
+pub trait VerifyVoteExtension {
+    /// Handle a `VerifyVoteExtension` request, accepting or rejecting the
+    /// extension.
+    fn verify_vote_extension(
+        &self,
+        req: &messages_plus::RequestVerifyVoteExtension,
+    ) -> Result<messages_plus::ResponseVerifyVoteExtension>;
+}
+
+/// Default implementation of [VerifyVoteExtension] for all types. Accepts all
+/// vote extensions.
+#[cfg(feature = "abci-plus")]
+impl<S> VerifyVoteExtension for S {
+    default fn verify_vote_extension(
+        &self,
+        _req: &messages_plus::RequestVerifyVoteExtension,
+    ) -> Result<messages_plus::ResponseVerifyVoteExtension> {
+        Ok(messages_plus::ResponseVerifyVoteExtension {
+            status: messages_plus::response_verify_vote_extension::VerifyStatus::Accept as i32,
+        })
+    }
+}
+
 /// Convenience trait for types which implement all ABCI methods.
 pub trait App:
     BeginBlock + EndBlock + InitChain + State + Call + Query + Default + AbciQuery
 {
 }
 impl<T: Default + BeginBlock + EndBlock + InitChain + State + Call + Query + AbciQuery> App for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Add(Rc<RefCell<u64>>);
+
+    impl BeginBlock for Add {
+        fn begin_block(&mut self, _ctx: &BeginBlockCtx) -> Result<()> {
+            *self.0.borrow_mut() += 1;
+            Ok(())
+        }
+    }
+
+    struct Double(Rc<RefCell<u64>>);
+
+    impl BeginBlock for Double {
+        fn begin_block(&mut self, _ctx: &BeginBlockCtx) -> Result<()> {
+            *self.0.borrow_mut() *= 2;
+            Ok(())
+        }
+    }
+
+    impl EndBlock for Double {
+        fn end_block(&mut self, _ctx: &EndBlockCtx) -> Result<()> {
+            *self.0.borrow_mut() *= 2;
+            Ok(())
+        }
+    }
+
+    struct Composite {
+        add: Add,
+        double: Double,
+    }
+
+    impl ModuleOrder for Composite {
+        fn begin_block_modules(&mut self) -> Vec<&mut dyn BeginBlock> {
+            vec![&mut self.add, &mut self.double]
+        }
+
+        fn end_block_modules(&mut self) -> Vec<&mut dyn EndBlock> {
+            vec![&mut self.double]
+        }
+    }
+
+    fn begin_block_ctx() -> BeginBlockCtx {
+        RequestBeginBlock {
+            header: Some(Default::default()),
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[cfg(feature = "abci-plus")]
+    #[derive(Default)]
+    struct Oracle {
+        price: u64,
+        extended: u32,
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl ExtendVote for Oracle {
+        fn extend_vote(
+            &mut self,
+            _req: &messages_plus::RequestExtendVote,
+        ) -> Result<messages_plus::ResponseExtendVote> {
+            self.extended += 1;
+            Ok(messages_plus::ResponseExtendVote {
+                vote_extension: self.price.to_be_bytes().to_vec().into(),
+            })
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl VerifyVoteExtension for Oracle {
+        fn verify_vote_extension(
+            &self,
+            req: &messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<messages_plus::ResponseVerifyVoteExtension> {
+            use messages_plus::response_verify_vote_extension::VerifyStatus;
+
+            let price: Option<[u8; 8]> = req.vote_extension[..].try_into().ok();
+            let status = match price.map(u64::from_be_bytes) {
+                Some(price) if price == self.price => VerifyStatus::Accept,
+                _ => VerifyStatus::Reject,
+            };
+            Ok(messages_plus::ResponseVerifyVoteExtension {
+                status: status as i32,
+            })
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    #[test]
+    fn vote_extension() -> Result<()> {
+        use messages_plus::response_verify_vote_extension::VerifyStatus;
+        use messages_plus::{RequestExtendVote, RequestVerifyVoteExtension};
+
+        let mut oracle = Oracle {
+            price: 1234,
+            ..Default::default()
+        };
+        let res = oracle.extend_vote(&RequestExtendVote::default())?;
+        assert_eq!(oracle.extended, 1);
+
+        let mut req = RequestVerifyVoteExtension {
+            vote_extension: res.vote_extension,
+            ..Default::default()
+        };
+        let res = oracle.verify_vote_extension(&req)?;
+        assert_eq!(res.status, VerifyStatus::Accept as i32);
+
+        req.vote_extension = vec![1, 2, 3].into();
+        let res = oracle.verify_vote_extension(&req)?;
+        assert_eq!(res.status, VerifyStatus::Reject as i32);
+
+        // types without hooks use the no-op defaults
+        let mut counter = Rc::new(RefCell::new(0u64));
+        let res = counter.extend_vote(&RequestExtendVote::default())?;
+        assert!(res.vote_extension.is_empty());
+        let res = counter.verify_vote_extension(&req)?;
+        assert_eq!(res.status, VerifyStatus::Accept as i32);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "abci")]
+    struct Panicky;
+
+    #[cfg(feature = "abci")]
+    impl Application for Panicky {
+        fn deliver_tx(
+            &self,
+            _store: WrappedMerk,
+            _req: RequestDeliverTx,
+        ) -> Result<ResponseDeliverTx> {
+            panic!("attempt to subtract with overflow");
+        }
+    }
+
+    #[cfg(feature = "abci")]
+    #[test]
+    fn handler_panic_shuts_down() {
+        use crate::merk::MerkStore;
+        use std::sync::{Arc, RwLock};
+        use tendermint_proto::v0_34::abci::request::Value as Req;
+
+        let home = tempfile::TempDir::new().unwrap();
+        let shutdown = Arc::new(RwLock::new(None));
+        let notifier = Arc::new(RwLock::new(false));
+        let mut abci = ABCIStateMachine::new(
+            Panicky,
+            MerkStore::new(home.path()),
+            false,
+            shutdown.clone(),
+            notifier.clone(),
+        );
+
+        let res = abci.dispatch(Request {
+            value: Some(Req::Flush(Default::default())),
+        });
+        assert!(res.is_ok());
+        assert!(shutdown.read().unwrap().is_none());
+
+        let err = abci
+            .dispatch(Request {
+                value: Some(Req::DeliverTx(Default::default())),
+            })
+            .unwrap_err();
+        let msg = "ABCI Error: Application panicked: attempt to subtract with overflow";
+        assert_eq!(err.to_string(), msg);
+        assert_eq!(shutdown.read().unwrap().as_ref().unwrap().to_string(), msg);
+        assert!(*notifier.read().unwrap());
+    }
+
+    #[test]
+    fn module_order() -> Result<()> {
+        let value = Rc::new(RefCell::new(0));
+        let mut app = Composite {
+            add: Add(value.clone()),
+            double: Double(value.clone()),
+        };
+
+        app.begin_block(&begin_block_ctx())?;
+        assert_eq!(*value.borrow(), 2);
+        app.begin_block(&begin_block_ctx())?;
+        assert_eq!(*value.borrow(), 6);
+
+        app.end_block(&EndBlockCtx { height: 2 })?;
+        assert_eq!(*value.borrow(), 12);
+
+        Ok(())
+    }
+
+    fn query_json(
+        path: &str,
+        store: crate::store::Store,
+        app_version: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let request = RequestQuery {
+            path: path.to_string(),
+            ..Default::default()
+        };
+        let res = well_known_query(&request, store, 12, app_version)?.unwrap();
+        assert_eq!(res.code, 0);
+        assert_eq!(res.height, 12);
+
+        Ok(serde_json::from_slice(&res.value).unwrap())
+    }
+
+    #[test]
+    fn well_known_queries() -> Result<()> {
+        use crate::store::Write;
+
+        let mut store = crate::store::Store::with_map_store();
+        assert_eq!(
+            query_json(APP_VERSION_PATH, store.clone(), None)?,
+            serde_json::json!({
+                "version": null,
+                "orga_version": env!("CARGO_PKG_VERSION"),
+                "network_version": null,
+            })
+        );
+
+        store.put(crate::upgrade::VERSION_KEY.to_vec(), vec![2, 1, 5])?;
+        assert_eq!(
+            query_json(APP_VERSION_PATH, store.clone(), Some("1.2.3"))?,
+            serde_json::json!({
+                "version": "1.2.3",
+                "orga_version": env!("CARGO_PKG_VERSION"),
+                "network_version": "0105",
+            })
+        );
+
+        assert_eq!(
+            query_json(HEALTH_PATH, store.clone(), None)?,
+            serde_json::json!({ "status": "ok", "height": 12 })
+        );
+
+        let request = RequestQuery {
+            path: "/foo".to_string(),
+            ..Default::default()
+        };
+        assert!(well_known_query(&request, store, 12, None)?.is_none());
+
+        Ok(())
+    }
+}
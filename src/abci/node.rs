@@ -1,16 +1,18 @@
-use super::{ABCIStateMachine, ABCIStore, AbciQuery, App, Application, WrappedMerk};
+use super::{
+    ABCIStateMachine, ABCIStore, AbciQuery, App, Application, WrappedMerk, DEFAULT_WORKER_COUNT,
+};
 use crate::call::Call;
 use crate::context::Context;
-use crate::encoding::Decode;
+use crate::encoding::{decode_with_limit, Decode};
 use crate::merk::memsnapshot::MemSnapshot;
 use crate::merk::{MerkStore, ProofBuilder};
 use crate::migrate::Migrate;
-use crate::plugins::{ABCICall, ABCIPlugin};
+use crate::plugins::{sdk_compat::MAX_CALL_SIZE, ABCICall, ABCIPlugin};
 use crate::query::Query;
 use crate::state::State;
 use crate::store::{BackingStore, Read, Shared, Store, Write};
 use crate::tendermint::Child as TendermintChild;
-use crate::tendermint::Tendermint;
+use crate::tendermint::{apply_default_config, Tendermint};
 use crate::{Error, Result};
 use home::home_dir;
 use std::borrow::Borrow;
@@ -87,6 +89,9 @@ pub struct Node<A> {
     logs: bool,
     skip_init_chain: bool,
     flags: Vec<String>,
+    abci_worker_count: usize,
+    retain_heights: Option<u64>,
+    app_version: Option<String>,
 }
 
 impl Node<()> {
@@ -123,16 +128,32 @@ pub struct DefaultConfig {
     pub seeds: Option<String>,
     /// Default timeout_commit setting.
     pub timeout_commit: Option<String>,
+    /// Default maximum number of transactions in the mempool.
+    pub mempool_size: Option<u64>,
+    /// Default maximum total size of all transactions in the mempool, in
+    /// bytes.
+    pub mempool_max_txs_bytes: Option<u64>,
+    /// Default maximum size of a single transaction accepted into the mempool,
+    /// in bytes.
+    pub mempool_max_tx_bytes: Option<u64>,
+    /// Default maximum size of a p2p message packet payload, in bytes.
+    pub max_packet_msg_payload_size: Option<u64>,
+    /// Default maximum number of inbound peers.
+    pub max_num_inbound_peers: Option<u32>,
+    /// Default maximum number of outbound peers.
+    pub max_num_outbound_peers: Option<u32>,
 }
 
 impl<A: App> Node<A> {
     /// Create a new node, initializing the application's home directory if it
     /// was not present.
+    ///
+    /// Returns an error if the Tendermint binary can not be installed.
     pub async fn new<P: AsRef<Path>>(
         home: P,
         chain_id: Option<&str>,
         cfg_defaults: DefaultConfig,
-    ) -> Self {
+    ) -> Result<Self> {
         let home = home.as_ref().to_path_buf();
         let merk_home = home.join("merk");
         let tm_home = home.join("tendermint");
@@ -144,11 +165,11 @@ impl<A: App> Node<A> {
 
         let cfg_path = tm_home.join("config/config.toml");
         let tm_previously_configured = cfg_path.exists();
-        let _ = Tendermint::new(tm_home.clone())
+        Tendermint::new(tm_home.clone())
             .stdout(std::process::Stdio::null())
             .stderr(std::process::Stdio::null())
             .init()
-            .await;
+            .await?;
 
         let read_toml = || {
             let config =
@@ -163,17 +184,9 @@ impl<A: App> Node<A> {
         };
 
         if !tm_previously_configured {
-            if let Some(seeds) = cfg_defaults.seeds {
-                let mut toml = read_toml();
-                toml["p2p"]["seeds"] = toml_edit::value(seeds);
-                write_toml(toml);
-            }
-
-            if let Some(timeout_commit) = cfg_defaults.timeout_commit {
-                let mut toml = read_toml();
-                toml["consensus"]["timeout_commit"] = toml_edit::value(timeout_commit);
-                write_toml(toml);
-            }
+            let mut toml = read_toml();
+            apply_default_config(&mut toml, &cfg_defaults);
+            write_toml(toml);
 
             let mut genesis_json: serde_json::Value =
                 std::fs::read_to_string(tm_home.join("config/genesis.json"))
@@ -210,7 +223,7 @@ impl<A: App> Node<A> {
             26658
         };
 
-        Node {
+        Ok(Node {
             _app: PhantomData,
             merk_home,
             tm_home,
@@ -223,7 +236,10 @@ impl<A: App> Node<A> {
             stderr: Stdio::null(),
             logs: false,
             flags: vec![],
-        }
+            abci_worker_count: DEFAULT_WORKER_COUNT,
+            retain_heights: None,
+            app_version: None,
+        })
     }
 
     /// Start the application, including Tendermint and the ABCI server.
@@ -250,7 +266,7 @@ impl<A: App> Node<A> {
             tm_process = tm_process.p2p_persistent_peers(peers);
         }
 
-        let tm_child = tm_process.start().await;
+        let tm_child = tm_process.start().await?;
 
         let genesis: serde_json::Value =
             std::fs::read_to_string(self.tm_home.join("config/genesis.json"))?
@@ -264,8 +280,11 @@ impl<A: App> Node<A> {
         let notifier = shutdown_notifier.clone();
 
         std::thread::spawn(move || {
-            let app = InternalApp::<ABCIPlugin<A>>::new();
-            let store = MerkStore::new(self.merk_home.clone());
+            let app = InternalApp::<ABCIPlugin<A>>::new().app_version(self.app_version);
+            let mut store = MerkStore::new(self.merk_home.clone());
+            if let Some(retain_heights) = self.retain_heights {
+                store = store.with_retain_heights(retain_heights);
+            }
             let res = ABCIStateMachine::new(
                 app,
                 store,
@@ -273,6 +292,7 @@ impl<A: App> Node<A> {
                 shutdown.clone(),
                 shutdown_notifier,
             )
+            .worker_count(self.abci_worker_count)
             .listen(format!("127.0.0.1:{}", self.abci_port));
             let mut shutdown = shutdown.write().unwrap();
 
@@ -316,8 +336,7 @@ impl<A: App> Node<A> {
 
     /// Reset the node. This clears the Merk store data and Tendermint data (via
     /// `unsafe_reset_all`).
-    #[must_use]
-    pub async fn reset(self) -> Self {
+    pub async fn reset(self) -> Result<Self> {
         if self.merk_home.exists() {
             std::fs::remove_dir_all(&self.merk_home).expect("Failed to clear Merk data");
         }
@@ -325,9 +344,9 @@ impl<A: App> Node<A> {
         Tendermint::new(&self.tm_home)
             .stdout(std::process::Stdio::null())
             .unsafe_reset_all()
-            .await;
+            .await?;
 
-        self
+        Ok(self)
     }
 
     // TODO: remove when we don't require compat migrations
@@ -453,6 +472,48 @@ impl<A: App> Node<A> {
 
         self
     }
+
+    /// Set the number of ABCI connections to accept from Tendermint.
+    #[must_use]
+    pub fn abci_worker_count(mut self, count: usize) -> Self {
+        self.abci_worker_count = count;
+
+        self
+    }
+
+    /// Retain only the state of the given number of most recent heights,
+    /// pruning older snapshots (including those offered for state sync) as
+    /// blocks are committed. By default, pruning is left to the store's
+    /// snapshot filters.
+    #[must_use]
+    pub fn retain_heights(mut self, heights: u64) -> Self {
+        self.retain_heights = Some(heights);
+
+        self
+    }
+
+    /// Set the app's version, reported by the well-known
+    /// [APP_VERSION_PATH](super::APP_VERSION_PATH) query, e.g.
+    /// `env!("CARGO_PKG_VERSION")` of the app's crate.
+    #[must_use]
+    pub fn app_version(mut self, version: impl Into<String>) -> Self {
+        self.app_version = Some(version.into());
+
+        self
+    }
+}
+
+/// Returns the backing store for the app's state. With the `metrics` feature,
+/// the store's operations are recorded by a
+/// [MeteredStore](crate::store::MeteredStore).
+fn app_backing_store(store: WrappedMerk) -> BackingStore {
+    #[cfg(feature = "metrics")]
+    return BackingStore::Other(Shared::new(Box::new(crate::store::MeteredStore::new(
+        store,
+    ))));
+
+    #[cfg(not(feature = "metrics"))]
+    store.into()
 }
 
 impl<A: App> InternalApp<ABCIPlugin<A>> {
@@ -461,7 +522,7 @@ impl<A: App> InternalApp<ABCIPlugin<A>> {
         store: WrappedMerk,
         op: F,
     ) -> Result<T> {
-        let mut store = Store::new(store.into());
+        let mut store = Store::new(app_backing_store(store));
         let state_bytes = match store.get(&[])? {
             Some(inner) => inner,
             None => {
@@ -558,7 +619,7 @@ impl<A: App> Application for InternalApp<ABCIPlugin<A>> {
     fn deliver_tx(&self, store: WrappedMerk, req: RequestDeliverTx) -> Result<ResponseDeliverTx> {
         let run_res = self.run(store, move |state| -> Result<_> {
             let res = catch_unwind(|| {
-                let inner_call = Decode::decode(req.tx.to_vec().as_slice())?;
+                let inner_call = decode_with_limit(req.tx.as_ref(), MAX_CALL_SIZE)?;
                 state.lock().unwrap().call(ABCICall::DeliverTx(inner_call))
             })
             .map_err(|_| crate::Error::Call("Panicked".to_string()));
@@ -568,26 +629,34 @@ impl<A: App> Application for InternalApp<ABCIPlugin<A>> {
                 res,
                 state.events.take().unwrap_or_default(),
                 state.logs.take().unwrap_or_default(),
+                state.gas.take(),
             ))
         })?;
 
         let mut deliver_tx_res = ResponseDeliverTx::default();
         match run_res {
-            Ok((res, events, logs)) => match res {
-                Ok(Ok(())) => {
-                    deliver_tx_res.code = 0;
-                    deliver_tx_res.log = logs.join("\n");
-                    deliver_tx_res.events = events;
+            Ok((res, events, logs, gas)) => {
+                if let Some(gas) = gas {
+                    deliver_tx_res.gas_wanted = gas.limit().try_into().unwrap_or(i64::MAX);
+                    deliver_tx_res.gas_used = gas.used().try_into().unwrap_or(i64::MAX);
                 }
-                Err(err) | Ok(Err(err)) => {
-                    deliver_tx_res.code = 1;
-                    if logs.is_empty() {
-                        deliver_tx_res.log = err.to_string();
-                    } else {
+
+                match res {
+                    Ok(Ok(())) => {
+                        deliver_tx_res.code = 0;
                         deliver_tx_res.log = logs.join("\n");
+                        deliver_tx_res.events = events;
+                    }
+                    Err(err) | Ok(Err(err)) => {
+                        deliver_tx_res.code = 1;
+                        if logs.is_empty() {
+                            deliver_tx_res.log = err.to_string();
+                        } else {
+                            deliver_tx_res.log = logs.join("\n");
+                        }
                     }
                 }
-            },
+            }
             Err(err) => {
                 deliver_tx_res.code = 1;
                 deliver_tx_res.log = err.to_string();
@@ -600,7 +669,7 @@ impl<A: App> Application for InternalApp<ABCIPlugin<A>> {
     fn check_tx(&self, store: WrappedMerk, req: RequestCheckTx) -> Result<ResponseCheckTx> {
         let run_res = self.run(store, move |state| -> Result<_> {
             let res = catch_unwind(|| {
-                let inner_call = Decode::decode(req.tx.to_vec().as_slice())?;
+                let inner_call = decode_with_limit(req.tx.as_ref(), MAX_CALL_SIZE)?;
                 state.lock().unwrap().call(ABCICall::CheckTx(inner_call))
             })
             .map_err(|_| crate::Error::Call("Panicked".to_string()));
@@ -642,6 +711,28 @@ impl<A: App> Application for InternalApp<ABCIPlugin<A>> {
         Ok(check_tx_res)
     }
 
+    #[cfg(feature = "abci-plus")]
+    fn extend_vote(
+        &self,
+        store: WrappedMerk,
+        req: super::messages_plus::RequestExtendVote,
+    ) -> Result<super::messages_plus::ResponseExtendVote> {
+        self.run(store, move |state| {
+            super::ExtendVote::extend_vote(&mut *state.lock().unwrap(), &req)
+        })?
+    }
+
+    #[cfg(feature = "abci-plus")]
+    fn verify_vote_extension(
+        &self,
+        store: WrappedMerk,
+        req: super::messages_plus::RequestVerifyVoteExtension,
+    ) -> Result<super::messages_plus::ResponseVerifyVoteExtension> {
+        self.run(store, move |state| {
+            super::VerifyVoteExtension::verify_vote_extension(&*state.lock().unwrap(), &req)
+        })?
+    }
+
     fn query(&self, merk_store: Shared<MerkStore>, req: RequestQuery) -> Result<ResponseQuery> {
         let create_state = |store| {
             let store = Store::new(store);
@@ -651,29 +742,42 @@ impl<A: App> Application for InternalApp<ABCIPlugin<A>> {
             ABCIPlugin::<A>::load(store, &mut state_bytes.as_slice())
         };
 
-        let (height, snapshot) = {
-            let merk_store_ref = merk_store.borrow();
-            if req.height == 0 {
-                merk_store_ref.mem_snapshots().last_key_value()
-            } else {
-                merk_store_ref
-                    .mem_snapshots()
-                    .get_key_value(&req.height.try_into()?)
+        // queries are resolved against the state at the requested height (or
+        // the latest height if 0), as long as it is still retained in memory
+        let snapshot = req
+            .height
+            .try_into()
+            .map_err(|_| crate::Error::Query(format!("Invalid query height {}", req.height)))
+            .and_then(|height| merk_store.borrow().snapshot_at(height));
+        let (height, snapshot) = match snapshot {
+            Ok(res) => res,
+            Err(err) => {
+                return Ok(ResponseQuery {
+                    code: 1,
+                    log: err.to_string(),
+                    height: req.height,
+                    ..Default::default()
+                })
             }
-            .map(|(k, v)| (*k, (*v).clone()))
-            .ok_or_else(|| crate::Error::Query(format!("Cannot query for height {}", req.height)))?
         };
 
         let mss = Shared::new(MemSnapshot::new(snapshot, merk_store));
 
         if !req.path.is_empty() {
             let store = BackingStore::MemSnapshot(mss);
+            let app_version = self.app_version.as_deref();
+            if let Some(res) =
+                super::well_known_query(&req, Store::new(store.clone()), height, app_version)?
+            {
+                return Ok(res);
+            }
+
             let state = Mutex::new(create_state(store)?);
             #[allow(clippy::needless_borrow)]
             let mut res = catch_unwind(|| (&*state.lock().unwrap()).abci_query(&req))
                 .map_err(|_| crate::Error::Query("Panicked".to_string()))??;
 
-            res.height = height.try_into().unwrap();
+            res.height = height.try_into()?;
             drop(state);
 
             return Ok(res);
@@ -693,6 +797,7 @@ impl<A: App> Application for InternalApp<ABCIPlugin<A>> {
                 let res = ResponseQuery {
                     code: 1,
                     log: err.to_string(),
+                    height: height.try_into()?,
                     ..Default::default()
                 };
                 return Ok(res);
@@ -731,11 +836,23 @@ impl Drop for Child {
 
 struct InternalApp<A> {
     _app: PhantomData<A>,
+    app_version: Option<String>,
 }
 
 impl<A: App> InternalApp<ABCIPlugin<A>> {
     pub fn new() -> Self {
-        Self { _app: PhantomData }
+        Self {
+            _app: PhantomData,
+            app_version: None,
+        }
+    }
+
+    /// Sets the version reported for the app by the well-known
+    /// [APP_VERSION_PATH](super::APP_VERSION_PATH) query.
+    pub fn app_version(mut self, version: Option<String>) -> Self {
+        self.app_version = version;
+
+        self
     }
 }
 
@@ -818,9 +935,11 @@ mod tests {
                 orga::abci::DefaultConfig {
                     seeds: None,
                     timeout_commit: None,
+                    ..Default::default()
                 },
             )
-            .await;
+            .await
+            .unwrap();
             let _res = node.run().await.unwrap();
             loop {
                 tokio::time::sleep(std::time::Duration::from_secs(1)).await;
@@ -844,6 +963,87 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "abci-plus")]
+    impl crate::abci::ExtendVote for App {
+        fn extend_vote(
+            &mut self,
+            _req: &crate::abci::messages_plus::RequestExtendVote,
+        ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+            Ok(crate::abci::messages_plus::ResponseExtendVote {
+                vote_extension: self.count.to_be_bytes().to_vec().into(),
+            })
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl crate::abci::VerifyVoteExtension for App {
+        fn verify_vote_extension(
+            &self,
+            req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+            use crate::abci::messages_plus::response_verify_vote_extension::VerifyStatus;
+
+            let status = if req.vote_extension.len() == 4 {
+                VerifyStatus::Accept
+            } else {
+                VerifyStatus::Reject
+            };
+            Ok(crate::abci::messages_plus::ResponseVerifyVoteExtension {
+                status: status as i32,
+            })
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    #[test]
+    fn vote_extension_dispatch() -> Result<()> {
+        use crate::abci::messages_plus::{
+            request::Value as Req, response::Value as Res,
+            response_verify_vote_extension::VerifyStatus, Request, RequestVerifyVoteExtension,
+        };
+
+        let home = tempfile::TempDir::new().unwrap();
+        let mut abci = ABCIStateMachine::new(
+            InternalApp::<ABCIPlugin<DefaultPlugins<FooCoin, App>>>::new(),
+            MerkStore::new(home.path()),
+            false,
+            Default::default(),
+            Default::default(),
+        );
+
+        let res = abci.dispatch_plus(Request {
+            value: Some(Req::ExtendVote(Default::default())),
+        })?;
+        let vote_extension = match res {
+            Res::ExtendVote(res) => res.vote_extension,
+            _ => panic!("Unexpected response"),
+        };
+        assert_eq!(&vote_extension[..], &0u32.to_be_bytes()[..]);
+
+        let verify = |abci: &mut ABCIStateMachine<_>, vote_extension: Vec<u8>| {
+            let res = abci.dispatch_plus(Request {
+                value: Some(Req::VerifyVoteExtension(RequestVerifyVoteExtension {
+                    vote_extension: vote_extension.into(),
+                    ..Default::default()
+                })),
+            });
+            match res.unwrap() {
+                Res::VerifyVoteExtension(res) => res.status,
+                _ => panic!("Unexpected response"),
+            }
+        };
+        assert_eq!(
+            verify(&mut abci, vote_extension.to_vec()),
+            VerifyStatus::Accept as i32
+        );
+        assert_eq!(
+            verify(&mut abci, vec![1, 2, 3]),
+            VerifyStatus::Reject as i32
+        );
+
+        Ok(())
+    }
+
     #[ignore]
     #[tokio::test]
     #[serial_test::serial]
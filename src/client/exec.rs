@@ -16,6 +16,7 @@ use crate::{
 
 #[cfg(feature = "merk-verify")]
 use crate::merk::ProofStore;
+use tendermint_proto::v0_34::abci::Event;
 
 /// Result of a single execution step of the client.
 #[derive(Debug, Clone)]
@@ -34,6 +35,31 @@ pub enum StepResult<T: Query, U> {
     FetchQuery(T::Query),
 }
 
+/// The result of a transmitted call, as reported by the node which delivered
+/// it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallResult {
+    /// The hash of the transaction.
+    pub txid: [u8; 32],
+    /// The `DeliverTx` response code, where 0 indicates success.
+    pub code: u32,
+    /// The `DeliverTx` response log.
+    pub log: String,
+    /// The events emitted while delivering the transaction.
+    pub events: Vec<Event>,
+}
+
+/// The unverified response to a proven query.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProvenResponse {
+    /// The height of the state the query was resolved against.
+    pub height: u64,
+    /// The root hash the proof should be verified against.
+    pub root_hash: [u8; 32],
+    /// The encoded Merk proof.
+    pub proof: Vec<u8>,
+}
+
 /// Trait for asynchronously executing queries and calls.
 #[allow(async_fn_in_trait)]
 pub trait Transport<T: Query + Call>: Send + Sync {
@@ -42,8 +68,17 @@ pub trait Transport<T: Query + Call>: Send + Sync {
     /// store.
     fn query(&self, query: T::Query) -> impl std::future::Future<Output = Result<Store>> + Send;
 
-    /// Transmit a call.
-    fn call(&self, call: T::Call) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// Transmit a call, returning the result of its delivery.
+    fn call(&self, call: T::Call) -> impl std::future::Future<Output = Result<CallResult>> + Send;
+
+    /// Fetch the raw proof for a query without verifying it. Transports which
+    /// can not provide proofs return an error.
+    fn query_proof(
+        &self,
+        _query: T::Query,
+    ) -> impl std::future::Future<Output = Result<ProvenResponse>> + Send {
+        async { Err(Error::Client("Transport does not provide proofs".into())) }
+    }
 }
 
 impl<T: Transport<U>, U: Query + Call> Transport<U> for &mut T {
@@ -51,9 +86,54 @@ impl<T: Transport<U>, U: Query + Call> Transport<U> for &mut T {
         (**self).query(query).await
     }
 
-    async fn call(&self, call: <U as Call>::Call) -> Result<()> {
+    async fn call(&self, call: <U as Call>::Call) -> Result<CallResult> {
         (**self).call(call).await
     }
+
+    async fn query_proof(&self, query: <U as Query>::Query) -> Result<ProvenResponse> {
+        (**self).query_proof(query).await
+    }
+}
+
+/// Trait for exchanging opaque request and response bytes with a node.
+///
+/// This is a lower-level alternative to [Transport] which is not tied to the
+/// query and call types of an app, so alternative backends (e.g. in-process,
+/// gRPC, or mock transports) only need to move bytes. Wrap an implementation in
+/// [Raw] to use it as a [Transport].
+#[allow(async_fn_in_trait)]
+pub trait RawTransport: Send + Sync {
+    /// Send an encoded query, returning the encoded response.
+    fn query_bytes(
+        &self,
+        query: Vec<u8>,
+    ) -> impl std::future::Future<Output = Result<Vec<u8>>> + Send;
+
+    /// Send an encoded call, returning the result of its delivery.
+    fn call_bytes(
+        &self,
+        call: Vec<u8>,
+    ) -> impl std::future::Future<Output = Result<CallResult>> + Send;
+
+    /// Build a store containing the entries of an encoded query response,
+    /// verifying the response if the backend provides proofs. The returned
+    /// store will be joined into the client's local store.
+    fn decode_response(&self, response: &[u8]) -> Result<Store>;
+}
+
+/// A [Transport] for any app over a [RawTransport], which transmits queries
+/// and calls as the encoding of the app's query and call types.
+pub struct Raw<R>(pub R);
+
+impl<T: Query + Call, R: RawTransport> Transport<T> for Raw<R> {
+    async fn query(&self, query: T::Query) -> Result<Store> {
+        let res = self.0.query_bytes(query.encode()?).await?;
+        self.0.decode_response(res.as_slice())
+    }
+
+    async fn call(&self, call: T::Call) -> Result<CallResult> {
+        self.0.call_bytes(call.encode()?).await
+    }
 }
 
 // TODO: remove need for ABCIPlugin wrapping at this level, and App bound
@@ -211,6 +291,33 @@ pub fn join_store(dst: Store, src: Store) -> Result<Store> {
     }
 }
 
+/// Copy a partial store into a new store which shares no data with the
+/// original, so that separate executions can each fetch data into their own
+/// copy without affecting each other.
+///
+/// Proof-backed stores can not be copied, so they are only accepted if there
+/// are no other references to their data.
+pub fn snapshot_store(store: Store) -> Result<Store> {
+    let copy = match &*store.backing_store().borrow() {
+        BackingStore::Null(_) => Some(BackingStore::default()),
+        BackingStore::PartialMapStore(map) => Some(BackingStore::PartialMapStore(Shared::new(
+            map.borrow().clone(),
+        ))),
+        _ => None,
+    };
+    if let Some(backing) = copy {
+        return Ok(Store::new(backing).with_prefix(store.prefix().to_vec()));
+    }
+
+    let prefix = store.prefix().to_vec();
+    match store.into_backing_store().try_into_inner() {
+        Ok(backing) => Ok(Store::new(backing).with_prefix(prefix)),
+        Err(_) => Err(Error::Client(
+            "Could not snapshot a shared store of this type".into(),
+        )),
+    }
+}
+
 impl Descriptor {
     /// Resolve the key prefix for a value in the state hierarchy based on data
     /// collected during traced execution.
@@ -227,7 +334,9 @@ impl Descriptor {
 
         let child_key = &read_key[self_store_key.len()..];
         match self.children() {
-            Children::None => Err(Error::Client("No matching child".to_string())),
+            Children::None | Children::Variants(_) => {
+                Err(Error::Client("No matching child".to_string()))
+            }
             Children::Named(children) => {
                 for child in children {
                     match child.store_key {
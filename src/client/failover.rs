@@ -0,0 +1,288 @@
+//! Failover across multiple transport endpoints.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use super::exec::{CallResult, ProvenResponse, Transport};
+use crate::call::Call;
+use crate::encoding::{Decode, Encode};
+use crate::query::Query;
+use crate::store::Store;
+use crate::{Error, Result};
+
+/// A [Transport] which sends each query and call to one of several endpoints,
+/// failing over to the next endpoint when one returns a transient error (e.g.
+/// a connection error or an HTTP 5xx response).
+///
+/// Requests go to the last endpoint which succeeded, starting with the first
+/// endpoint. When every endpoint has failed, the transport waits for the
+/// backoff duration (doubling after each round) and tries them all again, up
+/// to the configured number of retries.
+///
+/// Errors returned by the app itself ([Error::Query] and [Error::Call]) are
+/// not transient, so they are returned immediately. Note that a call which
+/// fails with a transient error may still have been broadcast by the failing
+/// endpoint, in which case the retried call will be rejected by the nonce
+/// check.
+pub struct MultiEndpointTransport<C> {
+    endpoints: Vec<C>,
+    preferred: AtomicUsize,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl<C> MultiEndpointTransport<C> {
+    /// Creates a new transport over the given endpoints, in order of
+    /// preference. Defaults to 2 retries with a backoff of 500 milliseconds.
+    pub fn new(endpoints: Vec<C>) -> Result<Self> {
+        if endpoints.is_empty() {
+            return Err(Error::Client("No endpoints provided".into()));
+        }
+
+        Ok(Self {
+            endpoints,
+            preferred: AtomicUsize::new(0),
+            retries: 2,
+            backoff: Duration::from_millis(500),
+        })
+    }
+
+    /// Sets the number of times to retry all endpoints after each of them has
+    /// failed.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets how long to wait before the first retry. The wait doubles after
+    /// each retry.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Returns the endpoints, in order of preference.
+    pub fn endpoints(&self) -> &[C] {
+        &self.endpoints
+    }
+
+    /// Returns the index of the endpoint which requests are sent to first.
+    pub fn preferred(&self) -> usize {
+        self.preferred.load(Ordering::Relaxed)
+    }
+
+    /// Runs `op` with the index of each endpoint in turn, starting with the
+    /// preferred one, until it succeeds or returns an error which is not
+    /// transient.
+    async fn failover<R, F, Fut>(&self, mut op: F) -> Result<R>
+    where
+        F: FnMut(usize) -> Fut,
+        Fut: std::future::Future<Output = Result<R>>,
+    {
+        let mut backoff = self.backoff;
+        let mut round = 0;
+        loop {
+            let start = self.preferred();
+            let mut last_err = None;
+
+            for i in 0..self.endpoints.len() {
+                let index = (start + i) % self.endpoints.len();
+                match op(index).await {
+                    Ok(res) => {
+                        self.preferred.store(index, Ordering::Relaxed);
+                        return Ok(res);
+                    }
+                    Err(err) if is_transient(&err) => {
+                        log::debug!("Endpoint {} failed: {}", index, err);
+                        last_err = Some(err);
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+
+            if round >= self.retries {
+                return Err(last_err.unwrap());
+            }
+            round += 1;
+
+            tokio::time::sleep(backoff).await;
+            backoff = backoff.saturating_mul(2);
+        }
+    }
+}
+
+/// Returns `true` if the error may be resolved by retrying the request, i.e.
+/// it was not returned by the app itself.
+fn is_transient(err: &Error) -> bool {
+    !matches!(err, Error::Query(_) | Error::Call(_))
+}
+
+impl<T, C> Transport<T> for MultiEndpointTransport<C>
+where
+    T: Query + Call,
+    C: Transport<T>,
+{
+    async fn query(&self, query: T::Query) -> Result<Store> {
+        let query = &query.encode()?;
+        self.failover(|i| async move {
+            let query = T::Query::decode(query.as_slice())?;
+            self.endpoints[i].query(query).await
+        })
+        .await
+    }
+
+    async fn call(&self, call: T::Call) -> Result<CallResult> {
+        let call = &call.encode()?;
+        self.failover(|i| async move {
+            let call = T::Call::decode(call.as_slice())?;
+            self.endpoints[i].call(call).await
+        })
+        .await
+    }
+
+    async fn query_proof(&self, query: T::Query) -> Result<ProvenResponse> {
+        let query = &query.encode()?;
+        self.failover(|i| async move {
+            let query = T::Query::decode(query.as_slice())?;
+            self.endpoints[i].query_proof(query).await
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::exec::execute;
+    use crate::client::mock::MockClient;
+    use crate::orga;
+    use crate::plugins::{ABCIPlugin, QueryPlugin};
+    use crate::state::State;
+    use crate::store::Write;
+
+    #[orga]
+    struct Foo {
+        pub bar: u32,
+    }
+
+    type App = ABCIPlugin<QueryPlugin<Foo>>;
+
+    fn setup(n: usize) -> MultiEndpointTransport<MockClient<App>> {
+        let mut store = Store::with_map_store();
+        let mut app = App::default();
+        app.attach(store.clone()).unwrap();
+        app.inner.inner.borrow_mut().bar = 123;
+        let mut bytes = vec![];
+        app.flush(&mut bytes).unwrap();
+        store.put(vec![], bytes).unwrap();
+
+        let endpoints = (0..n)
+            .map(|_| MockClient::with_store(store.clone()))
+            .collect();
+        MultiEndpointTransport::new(endpoints)
+            .unwrap()
+            .with_backoff(Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_healthy_endpoint() {
+        let transport = setup(2);
+        transport.endpoints()[0].fail_next_queries(100, || Error::Tendermint("down".into()));
+
+        let (res, _) = execute(Store::default(), &transport, |app| {
+            Ok(app.inner.inner.borrow().bar)
+        })
+        .await
+        .unwrap();
+        assert_eq!(res, 123);
+        assert_eq!(transport.preferred(), 1);
+        assert!(transport.endpoints()[0].queries.lock().unwrap().is_empty());
+        assert_eq!(transport.endpoints()[1].queries.lock().unwrap().len(), 1);
+
+        // later requests go straight to the healthy endpoint
+        execute(Store::default(), &transport, |app| {
+            Ok(app.inner.inner.borrow().bar)
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            transport.endpoints()[0].query_faults.lock().unwrap().len(),
+            99
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_with_backoff() {
+        let transport = setup(2).with_retries(1);
+        for endpoint in transport.endpoints() {
+            endpoint.fail_next_queries(1, || Error::Tendermint("down".into()));
+        }
+
+        let (res, _) = execute(Store::default(), &transport, |app| {
+            Ok(app.inner.inner.borrow().bar)
+        })
+        .await
+        .unwrap();
+        assert_eq!(res, 123);
+        assert_eq!(transport.preferred(), 0);
+
+        for endpoint in transport.endpoints() {
+            endpoint.fail_next_queries(2, || Error::Tendermint("down".into()));
+        }
+        let res = execute(Store::default(), &transport, |app| {
+            Ok(app.inner.inner.borrow().bar)
+        })
+        .await;
+        assert!(matches!(res, Err(Error::Tendermint(_))));
+    }
+
+    #[tokio::test]
+    async fn app_errors_are_not_retried() {
+        let transport = setup(2);
+        transport.endpoints()[0].fail_next_queries(1, || Error::Query("rejected".into()));
+
+        let res = execute(Store::default(), &transport, |app| {
+            Ok(app.inner.inner.borrow().bar)
+        })
+        .await;
+        assert!(matches!(res, Err(Error::Query(_))));
+        assert!(transport.endpoints()[1].queries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn injected_delays_do_not_block_runtime() {
+        let transport = setup(2);
+        transport.endpoints()[0].delay_next_query(Duration::from_secs(60));
+
+        let res = tokio::time::timeout(
+            Duration::from_millis(10),
+            execute(Store::default(), &transport, |app| {
+                Ok(app.inner.inner.borrow().bar)
+            }),
+        )
+        .await;
+        assert!(res.is_err());
+        assert!(transport.endpoints()[0]
+            .query_faults
+            .lock()
+            .unwrap()
+            .is_empty());
+
+        // a delayed endpoint is still healthy once it responds
+        transport.endpoints()[0].delay_next_query(Duration::from_millis(1));
+        transport.endpoints()[0].fail_next_queries(1, || Error::Tendermint("down".into()));
+        let (res, _) = execute(Store::default(), &transport, |app| {
+            Ok(app.inner.inner.borrow().bar)
+        })
+        .await
+        .unwrap();
+        assert_eq!(res, 123);
+        assert_eq!(transport.preferred(), 0);
+
+        execute(Store::default(), &transport, |app| {
+            Ok(app.inner.inner.borrow().bar)
+        })
+        .await
+        .unwrap();
+        assert_eq!(transport.preferred(), 1);
+    }
+}
@@ -1,5 +1,10 @@
 //! Mock client for use in tests.
-use std::{any::Any, collections::BTreeMap, marker::PhantomData, sync::Mutex};
+use std::{
+    any::Any,
+    collections::{BTreeMap, VecDeque},
+    marker::PhantomData,
+    sync::Mutex,
+};
 
 use crate::{
     abci::App,
@@ -12,7 +17,21 @@ use crate::{
     Error, Result,
 };
 
-use super::exec::Transport;
+use super::exec::{CallResult, Transport};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "tokio")]
+use std::time::Duration;
+
+/// A fault to inject into a [MockClient] transport operation.
+#[derive(Debug)]
+pub enum Fault {
+    /// Fail the operation with the given error.
+    Error(Error),
+    /// Wait for the given duration before serving the operation, without
+    /// blocking the runtime.
+    #[cfg(feature = "tokio")]
+    Delay(Duration),
+}
 
 /// A mock client for use in tests.
 #[derive(Default)]
@@ -23,6 +42,10 @@ pub struct MockClient<T> {
     pub calls: Mutex<Vec<Vec<u8>>>,
     /// The client's store.
     pub store: Store,
+    /// Faults to inject into upcoming queries, consumed one per query.
+    pub query_faults: Mutex<VecDeque<Fault>>,
+    /// Faults to inject into upcoming calls, consumed one per call.
+    pub call_faults: Mutex<VecDeque<Fault>>,
     _marker: PhantomData<fn(T)>,
 }
 
@@ -33,15 +56,63 @@ impl<T> MockClient<T> {
             queries: Mutex::new(vec![]),
             calls: Mutex::new(vec![]),
             store,
+            query_faults: Mutex::new(VecDeque::new()),
+            call_faults: Mutex::new(VecDeque::new()),
             _marker: PhantomData,
         }
     }
+
+    /// Fail the next `n` queries with errors built by `err`.
+    pub fn fail_next_queries(&self, n: usize, err: impl Fn() -> Error) {
+        let mut faults = self.query_faults.lock().unwrap();
+        faults.extend((0..n).map(|_| Fault::Error(err())));
+    }
+
+    /// Fail the next `n` calls with errors built by `err`.
+    pub fn fail_next_calls(&self, n: usize, err: impl Fn() -> Error) {
+        let mut faults = self.call_faults.lock().unwrap();
+        faults.extend((0..n).map(|_| Fault::Error(err())));
+    }
+
+    /// Delay the next query by the given duration.
+    #[cfg(feature = "tokio")]
+    pub fn delay_next_query(&self, duration: Duration) {
+        self.query_faults
+            .lock()
+            .unwrap()
+            .push_back(Fault::Delay(duration));
+    }
+
+    /// Delay the next call by the given duration.
+    #[cfg(feature = "tokio")]
+    pub fn delay_next_call(&self, duration: Duration) {
+        self.call_faults
+            .lock()
+            .unwrap()
+            .push_back(Fault::Delay(duration));
+    }
+}
+
+/// Pops the next fault from the queue, if any, and applies it.
+async fn inject_fault(faults: &Mutex<VecDeque<Fault>>) -> Result<()> {
+    let fault = faults.lock().unwrap().pop_front();
+    match fault {
+        Some(Fault::Error(err)) => Err(err),
+        #[cfg(feature = "tokio")]
+        Some(Fault::Delay(duration)) => {
+            tokio::time::sleep(duration).await;
+            Ok(())
+        }
+        None => Ok(()),
+    }
 }
 
 impl<T: App + State + Query + Call> Transport<ABCIPlugin<QueryPlugin<T>>>
     for MockClient<ABCIPlugin<QueryPlugin<T>>>
 {
     async fn query(&self, query: <ABCIPlugin<QueryPlugin<T>> as Query>::Query) -> Result<Store> {
+        inject_fault(&self.query_faults).await?;
+
         let query_bytes = query.encode()?;
         self.queries.lock().unwrap().push(query_bytes);
 
@@ -103,19 +174,29 @@ impl<T: App + State + Query + Call> Transport<ABCIPlugin<QueryPlugin<T>>>
         ))))
     }
 
-    async fn call(&self, call: <ABCIPlugin<QueryPlugin<T>> as Call>::Call) -> Result<()> {
-        self.calls.lock().unwrap().push(call.encode()?);
+    async fn call(&self, call: <ABCIPlugin<QueryPlugin<T>> as Call>::Call) -> Result<CallResult> {
+        inject_fault(&self.call_faults).await?;
+
+        let call_bytes = call.encode()?;
+        self.calls.lock().unwrap().push(call_bytes.clone());
 
         let root_bytes = self.store.get(&[])?.unwrap_or_default();
         let mut app =
             ABCIPlugin::<QueryPlugin<T>>::load(self.store.clone(), &mut root_bytes.as_slice())?;
-        let call = <ABCIPlugin<QueryPlugin<T>> as Call>::Call::decode(call.encode()?.as_slice())?;
+        let call = <ABCIPlugin<QueryPlugin<T>> as Call>::Call::decode(call_bytes.as_slice())?;
         app.call(call)?;
 
+        let res = CallResult {
+            txid: Sha256::digest(&call_bytes).into(),
+            code: 0,
+            log: app.logs.take().unwrap_or_default().join("\n"),
+            events: app.events.take().unwrap_or_default(),
+        };
+
         let mut out = vec![];
         app.flush(&mut out)?;
         self.store.clone().put(vec![], out)?;
 
-        Ok(())
+        Ok(res)
     }
 }
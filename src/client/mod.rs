@@ -6,23 +6,40 @@ use crate::encoding::{Decode, Encode};
 
 use crate::abci::App;
 use crate::plugins::{sdk_compat, ABCICall, ABCIPlugin, ConvertSdkTx};
-use crate::plugins::{PaidCall, PayableCall};
+use crate::plugins::{BatchCall, PaidCall, PayableCall};
 use crate::query::Query;
 use crate::state::State;
 use crate::store::Store;
+use crate::upgrade::{Upgrade, UpgradeMethodCall, UpgradeStatus, Version};
+#[cfg(feature = "merk-verify")]
+use crate::{
+    merk::ProofStore,
+    store::{BackingStore, Shared},
+};
 
-use crate::Result;
+use crate::{Error, Result};
 
+use futures_lite::{Stream, StreamExt};
+use std::any::TypeId;
 use std::future::Future;
 use std::marker::PhantomData;
+use std::pin::pin;
 
 pub mod exec;
+#[cfg(feature = "tokio")]
+pub mod failover;
 pub mod mock;
+#[cfg(feature = "abci")]
+pub mod subscribe;
 pub mod trace;
 pub mod wallet;
 
-pub use exec::Transport;
-pub use wallet::Wallet;
+pub use exec::{CallResult, ProvenResponse, Raw, RawTransport, Transport};
+#[cfg(feature = "tokio")]
+pub use failover::MultiEndpointTransport;
+#[cfg(feature = "abci")]
+pub use subscribe::{subscribe_events, EventBatch};
+pub use wallet::{AsyncWallet, Wallet};
 
 /// High-level trait for performing calls and queries remotely.
 pub trait Client<T: Query + Call>: Send + Sync {
@@ -38,15 +55,16 @@ pub trait Client<T: Query + Call>: Send + Sync {
         &self,
         payer: impl FnOnce(&T) -> T::Call,
         payee: impl FnOnce(&T) -> T::Call,
-    ) -> impl Future<Output = Result<()>>;
+    ) -> impl Future<Output = Result<CallResult>>;
 }
 
 /// A generic client for an app.
-pub struct AppClient<T, U, Transport, Symbol, Wallet> {
+pub struct AppClient<T: Call, U: Call, Transport, Symbol, Wallet> {
     _pd: PhantomData<Symbol>,
     transport: Transport,
     wallet: Wallet,
     sub: fn(T) -> U,
+    wrap_call: Option<fn(U::Call) -> T::Call>,
 }
 
 impl<T, U, Transport, Symbol, Wallet> Client<U> for AppClient<T, U, Transport, Symbol, Wallet>
@@ -54,7 +72,7 @@ where
     Transport: exec::Transport<ABCIPlugin<DefaultPlugins<Symbol, T>>>,
     T: App + Call + State + Query + Default + Describe + ConvertSdkTx<Output = PaidCall<T::Call>>,
     U: App + Call + State + Query + Default + Describe,
-    Wallet: wallet::Wallet + Clone,
+    Wallet: wallet::AsyncWallet,
     Symbol: crate::coins::Symbol,
 {
     fn query<R, F: FnMut(U) -> Result<R> + Send>(
@@ -66,17 +84,20 @@ where
 
     async fn call(
         &self,
-        _payer: impl FnOnce(&U) -> U::Call,
-        _payee: impl FnOnce(&U) -> U::Call,
-    ) -> Result<()> {
-        todo!()
-        // self.call(payer, payee)
+        payer: impl FnOnce(&U) -> U::Call,
+        payee: impl FnOnce(&U) -> U::Call,
+    ) -> Result<CallResult> {
+        self.call_inner(
+            |app| self.root_call(payer(app)),
+            |app| Ok(vec![self.root_call(payee(app))?]),
+        )
+        .await
     }
 }
 
 use crate::plugins::DefaultPlugins;
 
-impl<T, U, Transport, Symbol, Wallet> AppClient<T, U, Transport, Symbol, Wallet> {
+impl<T: Call, U: Call, Transport, Symbol, Wallet> AppClient<T, U, Transport, Symbol, Wallet> {
     /// Create a new client from a transport and wallet.
     pub fn new(client: Transport, wallet: Wallet) -> Self
     where
@@ -87,6 +108,7 @@ impl<T, U, Transport, Symbol, Wallet> AppClient<T, U, Transport, Symbol, Wallet>
             transport: client,
             wallet,
             sub: Into::into,
+            wrap_call: None,
         }
     }
 
@@ -97,17 +119,45 @@ impl<T, U, Transport, Symbol, Wallet> AppClient<T, U, Transport, Symbol, Wallet>
             transport: self.transport,
             wallet,
             sub: self.sub,
+            wrap_call: self.wrap_call,
+        }
+    }
+
+    /// Wraps a call for the subclient's app into a call for the root app.
+    fn root_call(&self, call: U::Call) -> Result<T::Call>
+    where
+        T: 'static,
+        U: 'static,
+    {
+        match self.wrap_call {
+            Some(wrap_call) => Ok(wrap_call(call)),
+            // T and U are the same type, so this re-encoding is lossless
+            None if TypeId::of::<T>() == TypeId::of::<U>() => {
+                Ok(<T as Call>::Call::decode(call.encode()?.as_slice())?)
+            }
+            None => Err(Error::Client(
+                "Client has no way to wrap calls into root app calls".to_string(),
+            )),
         }
     }
 
-    /// Create a subclient of this one..
+    /// Create a subclient of this one.
+    ///
+    /// `sub` selects the child from the root app, and `wrap_call` wraps a call
+    /// for the child into a call for the root app, e.g.
+    /// `|call| Item::Field(AppFieldCall::Child(call))`.
     #[allow(clippy::should_implement_trait)]
-    pub fn sub<U2>(self, sub: fn(T) -> U2) -> AppClient<T, U2, Transport, Symbol, Wallet> {
+    pub fn sub<U2: Call>(
+        self,
+        sub: fn(T) -> U2,
+        wrap_call: fn(U2::Call) -> T::Call,
+    ) -> AppClient<T, U2, Transport, Symbol, Wallet> {
         AppClient {
             _pd: PhantomData,
             transport: self.transport,
             wallet: self.wallet,
             sub,
+            wrap_call: Some(wrap_call),
         }
     }
 }
@@ -116,22 +166,65 @@ impl<T, U, Transport, Symbol, Wallet> AppClient<T, U, Transport, Symbol, Wallet>
 where
     Transport: exec::Transport<ABCIPlugin<DefaultPlugins<Symbol, T>>>,
     T: App + Call + State + Query + Default + Describe + ConvertSdkTx<Output = PaidCall<T::Call>>,
-    Wallet: wallet::Wallet + Clone,
+    U: Call + 'static,
+    Wallet: wallet::AsyncWallet,
     Symbol: crate::coins::Symbol,
 {
-    // TODO: support subclients
-    // TODO: return object with result data (e.g. txid)
-    /// Call a method on the app.
+    /// Call a method on the app. Calls are built for the subclient's app, and
+    /// wrapped into calls for the root app.
     pub async fn call(
         &self,
-        payer: impl FnOnce(&U) -> T::Call,
-        payee: impl FnOnce(&U) -> T::Call,
+        payer: impl FnOnce(&U) -> U::Call,
+        payee: impl FnOnce(&U) -> U::Call,
+    ) -> Result<CallResult> {
+        self.call_inner(
+            |app| self.root_call(payer(app)),
+            |app| Ok(vec![self.root_call(payee(app))?]),
+        )
+        .await
+    }
+
+    /// Call a method on the app with a batch of paid calls. The paid calls run
+    /// in order, and if any of them fails the whole batch is rolled back.
+    /// Calls are built for the subclient's app, and wrapped into calls for the
+    /// root app.
+    pub async fn call_batch(
+        &self,
+        payer: impl FnOnce(&U) -> U::Call,
+        paid: impl FnOnce(&U) -> Vec<U::Call>,
+    ) -> Result<CallResult> {
+        self.call_inner(
+            |app| self.root_call(payer(app)),
+            |app| {
+                paid(app)
+                    .into_iter()
+                    .map(|call| self.root_call(call))
+                    .collect()
+            },
+        )
+        .await
+    }
+
+    /// Call a method on the app, discarding the [CallResult].
+    pub async fn send(
+        &self,
+        payer: impl FnOnce(&U) -> U::Call,
+        payee: impl FnOnce(&U) -> U::Call,
     ) -> Result<()> {
+        self.call(payer, payee).await?;
+        Ok(())
+    }
+
+    async fn call_inner(
+        &self,
+        payer: impl FnOnce(&U) -> Result<T::Call>,
+        paid: impl FnOnce(&U) -> Result<Vec<T::Call>>,
+    ) -> Result<CallResult> {
         let (chain_id, store) = exec::execute(Store::default(), &self.transport, |app| {
             Ok(app.inner.inner.borrow().inner.inner.chain_id.to_vec())
         })
         .await?;
-        let (nonce, store) = match self.wallet.address()? {
+        let (nonce, store) = match self.wallet.signer_address()? {
             None => (None, store),
             Some(addr) => {
                 exec::execute(store, &self.transport, |app| {
@@ -145,22 +238,30 @@ where
 
         let app = self.query_with_store(store, Ok).await?;
 
-        let payer_call = payer(&app);
+        let payer_call = payer(&app)?;
         let payer_call_bytes = payer_call.encode()?;
         let payer = <T as Call>::Call::decode(payer_call_bytes.as_slice())?;
 
-        let paid = payee(&app);
-        let call = PayableCall::Paid(PaidCall { payer, paid });
+        let mut paid = paid(&app)?;
+        let call = if paid.len() == 1 {
+            let paid = paid.pop().unwrap();
+            PayableCall::Paid(PaidCall { payer, paid })
+        } else {
+            PayableCall::Batch(BatchCall { payer, paid })
+        };
+        let call = crate::plugins::TimeoutHeightCall {
+            timeout_height: 0,
+            inner_call: call,
+        };
         let call = crate::plugins::NonceCall {
             nonce,
             inner_call: call,
         };
         let call = [chain_id, call.encode()?].concat();
-        let call = self.wallet.sign(&call)?;
+        let call = self.wallet.sign_async(&call).await?;
         let call = ABCICall::DeliverTx(sdk_compat::Call::Native(call));
-        self.transport.call(call).await?;
 
-        Ok(())
+        self.transport.call(call).await
     }
 
     /// Queries the root app.
@@ -173,31 +274,153 @@ where
     }
 
     /// Performs the provided query op with a default initial store.
+    ///
+    /// Each query fetches data into its own store, so queries may run
+    /// concurrently through the same client.
     pub async fn query<U2, F2: FnMut(U) -> Result<U2>>(&self, op: F2) -> Result<U2> {
         self.query_with_store(Store::default(), op).await
     }
 
+    /// Reads the value at the given raw store key with a proof, verifying the
+    /// proof against `trusted_root_hash` before decoding the value.
+    ///
+    /// The root hash returned by the node is not trusted, since a malicious
+    /// node could return any state along with a matching root. The trusted
+    /// root must instead come from a verified source, e.g. the app hash of a
+    /// header verified by a light client.
+    ///
+    /// Returns the value along with the height it was read at.
+    #[cfg(feature = "merk-verify")]
+    pub async fn query_proven<V: State>(
+        &self,
+        key: Vec<u8>,
+        trusted_root_hash: [u8; 32],
+    ) -> Result<(V, u64)> {
+        let res = self
+            .transport
+            .query_proof(crate::plugins::query::Query::RawKey(key.clone()))
+            .await?;
+        if res.root_hash != trusted_root_hash {
+            return Err(Error::Client(
+                "Proof root hash does not match the trusted root hash".into(),
+            ));
+        }
+
+        let map = merk::proofs::query::verify(res.proof.as_slice(), trusted_root_hash)?;
+        let bytes = map
+            .get(key.as_slice())?
+            .ok_or_else(|| Error::Client("Proven key has no value".into()))?
+            .to_vec();
+
+        let store = Store::new(BackingStore::ProofMap(Shared::new(ProofStore(map))));
+        let value = V::load(store.sub(key.as_slice()), &mut bytes.as_slice())?;
+
+        Ok((value, res.height))
+    }
+
+    /// Watches the result of a query, calling `on_change` with the initial
+    /// result and again each time the result changes.
+    ///
+    /// The query is re-run each time an item arrives from `events`, e.g. a
+    /// stream of new block events from a node subscription. Returns once the
+    /// event stream ends.
+    pub async fn watch<R, F2, E>(
+        &self,
+        events: E,
+        mut query: F2,
+        mut on_change: impl FnMut(R),
+    ) -> Result<()>
+    where
+        R: PartialEq + Clone,
+        F2: FnMut(U) -> Result<R>,
+        E: Stream,
+    {
+        let mut last = self.query(&mut query).await?;
+        on_change(last.clone());
+
+        let mut events = pin!(events);
+        while events.next().await.is_some() {
+            let value = self.query(&mut query).await?;
+            if value != last {
+                last = value;
+                on_change(last.clone());
+            }
+        }
+
+        Ok(())
+    }
+
     /// Queries the inner app type with the provided store.
+    ///
+    /// The query runs against its own snapshot of `store`, so any number of
+    /// queries may run concurrently through the same client (e.g. with
+    /// `join!`), including from the same initial store, and values returned by
+    /// earlier queries may be held while others run.
     async fn query_with_store<U2, F2: FnMut(U) -> Result<U2>>(
         &self,
         store: Store,
         mut op: F2,
     ) -> Result<U2> {
-        let (res, _) = exec::execute(store, &self.transport, |app| {
-            let inner = app
-                .inner
-                .inner
-                .into_inner()
-                .inner
-                .inner
-                .inner
-                .inner
-                .inner
-                .inner;
-            op((self.sub)(inner))
+        let store = exec::snapshot_store(store)?;
+        let (res, _) = exec::execute(store, &self.transport, |app| op(self.sub_app(app))).await?;
+        Ok(res)
+    }
+
+    /// Selects the subclient's app from the root app.
+    fn sub_app(&self, app: ABCIPlugin<DefaultPlugins<Symbol, T>>) -> U {
+        let inner = app
+            .inner
+            .inner
+            .into_inner()
+            .inner
+            .inner
+            .inner
+            .inner
+            .inner
+            .inner
+            .inner;
+        (self.sub)(inner)
+    }
+}
+
+impl<T, Transport, Symbol, Wallet> AppClient<T, Upgrade, Transport, Symbol, Wallet>
+where
+    Transport: exec::Transport<ABCIPlugin<DefaultPlugins<Symbol, T>>>,
+    T: App + Call + State + Query + Default + Describe + ConvertSdkTx<Output = PaidCall<T::Call>>,
+    Wallet: wallet::AsyncWallet,
+    Symbol: crate::coins::Symbol,
+{
+    /// Signals readiness to upgrade to the given network version, signed by
+    /// the wallet's key (which must be the operator key of a validator).
+    ///
+    /// Signaling is exempt from fees, so the signal is sent as the payer half
+    /// of the call with a no-op paid half.
+    pub async fn signal_upgrade(&self, version: Vec<u8>) -> Result<()> {
+        let version: Version = version.try_into()?;
+        self.call_inner(
+            |upgrade| self.root_call(crate::call::build_call!(upgrade.signal(version))),
+            |_| {
+                Ok(vec![self.root_call(crate::call::Item::Method(
+                    UpgradeMethodCall::Noop(PhantomData),
+                ))?])
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Queries the current network version and the state of upgrade
+    /// signaling, tallied against the current validator set as of the last
+    /// block.
+    pub async fn query_upgrade_status(&self) -> Result<UpgradeStatus> {
+        let (status, _) = exec::execute(Store::default(), &self.transport, |app| {
+            let _context_remover = app.add_query_context()?;
+            self.sub_app(app).status()
         })
         .await?;
-        Ok(res)
+
+        Ok(status)
     }
 }
 
@@ -206,9 +429,9 @@ mod tests {
 
     use super::*;
 
-    use crate::call::build_call;
+    use crate::call::{build_call, Item};
     use crate::client::mock::MockClient;
-    use crate::client::wallet::{DerivedKey, Unsigned};
+    use crate::client::wallet::{DerivedKey, ExternalSigner, Unsigned};
     use crate::coins::{Address, Symbol};
     use crate::collections::{Deque, Map};
     use crate::context::Context;
@@ -216,6 +439,7 @@ mod tests {
     use crate::plugins::PaidCall;
     use crate::{orga, Error};
     use crate::{plugins::Signer, store::Write};
+    use sha2::{Digest, Sha256};
 
     #[orga]
     #[derive(Debug)]
@@ -260,6 +484,8 @@ mod tests {
         pub bar: Bar,
         #[call]
         pub staking: crate::coins::Staking<Simp>,
+        #[call]
+        pub upgrade: Upgrade,
     }
 
     impl ConvertSdkTx for Foo {
@@ -295,7 +521,7 @@ mod tests {
         #[call]
         pub fn signed_method(&mut self, address: Address) -> Result<()> {
             let signer = Context::resolve::<Signer>().unwrap();
-            if signer.signer != Some(address) {
+            if !signer.is_signer(address) {
                 return Err(Error::App("wrong signer".into()));
             }
 
@@ -324,6 +550,7 @@ mod tests {
                 .inner
                 .inner
                 .inner
+                .inner
                 .inner;
 
             let mut inner_map = Map::<u32, u64>::default();
@@ -356,8 +583,13 @@ mod tests {
                 },
             )?;
             inner_app.bar.b = 8;
+            inner_app.upgrade.activation_delay_seconds = 0;
         };
 
+        let mut validators = app.validators();
+        validators.set_voting_power([7; 32], 10);
+        validators.set_operator([7; 32], DerivedKey::address_for(b"alice")?)?;
+
         let mut bytes = vec![];
         app.flush(&mut bytes)?;
         store.put(vec![], bytes)?;
@@ -380,15 +612,8 @@ mod tests {
             let bar_b = client.query(|app| Ok(app.bar.b)).await?;
             assert_eq!(bar_b, 8);
 
-            {
-                // TODO: if bar doesn't drop, other queries will fail because
-                // Bar holds on to a reference to the store. this should isntead
-                // be locked at a different level so we can do concurrent
-                // queries with the same client, and either join the values
-                // separately or e.g. wait for equivalent queries to finish
-                let bar = client.query(|app| Ok(app.bar)).await?;
-                assert_eq!(bar.b, 8);
-            }
+            let bar = client.query(|app| Ok(app.bar)).await?;
+            assert_eq!(bar.b, 8);
 
             let value = client
                 .query(|app| app.e.get(12)?.unwrap().get_from_map(14, 2))
@@ -410,10 +635,22 @@ mod tests {
                 .await?;
             assert_eq!(value, 3);
 
-            client.call(
-                |app| build_call!(app.bar.inc_b(4)),
-                |app| build_call!(app.signed_method(DerivedKey::address_for(b"alice").unwrap())),
-            ).await?;
+            let res =
+                client
+                    .call(
+                        |app| build_call!(app.bar.inc_b(4)),
+                        |app| {
+                            build_call!(
+                                app.signed_method(DerivedKey::address_for(b"alice").unwrap())
+                            )
+                        },
+                    )
+                    .await?;
+
+            let calls = client.transport.calls.lock().unwrap();
+            let expected_txid: [u8; 32] = Sha256::digest(calls.last().unwrap()).into();
+            assert_eq!(res.txid, expected_txid);
+            assert_eq!(res.code, 0);
         }
 
         {
@@ -425,6 +662,116 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn concurrent_queries() -> Result<()> {
+        let mut mock_client = setup()?;
+        let client = AppClient::<Foo, Foo, _, _, _>::new(&mut mock_client, Unsigned);
+
+        // values returned by earlier queries may be held while others run
+        let bar = client.query(|app| Ok(app.bar)).await?;
+
+        let (bar_b, map_value, deque_value) = tokio::join!(
+            client.query(|app| Ok(app.bar.b)),
+            client.query(|app| app.e.get(12)?.unwrap().get_from_map(14, 2)),
+            client.query(|app| Ok(app.deque.get(0)?.unwrap().get(13)?.unwrap().a)),
+        );
+        assert_eq!(bar_b?, 8);
+        assert_eq!(map_value?, Some(32));
+        assert_eq!(deque_value?, 3);
+        assert_eq!(bar.b, 8);
+
+        // queries starting from the same partially-fetched store each fetch
+        // into their own snapshot of it
+        let (_, store) = exec::execute(Store::default(), &client.transport, |_| Ok(())).await?;
+        let (bar_b, map_value) = tokio::join!(
+            client.query_with_store(store.clone(), |app| Ok(app.bar.b)),
+            client.query_with_store(store.clone(), |app| {
+                app.e.get(12)?.unwrap().get_from_map(14, 2)
+            }),
+        );
+        assert_eq!(bar_b?, 8);
+        assert_eq!(map_value?, Some(32));
+
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn external_signer_call() -> Result<()> {
+        let mut mock_client = setup()?;
+
+        let key = DerivedKey::new(b"alice")?;
+        let privkey = *key.privkey();
+        let signer =
+            ExternalSigner::new(key.pubkey().serialize(), move |bytes: Vec<u8>| async move {
+                // stands in for I/O with a hardware wallet or remote signer
+                tokio::task::yield_now().await;
+                let secp = secp256k1::Secp256k1::new();
+                let msg =
+                    secp256k1::Message::from_hashed_data::<secp256k1::hashes::sha256::Hash>(&bytes);
+                Ok(secp.sign_ecdsa(&msg, &privkey).serialize_compact())
+            });
+        assert_eq!(signer.address(), key.address());
+
+        let client = AppClient::<Foo, Foo, _, _, _>::new(&mut mock_client, signer);
+        let res = client
+            .call(
+                |app| build_call!(app.bar.inc_b(4)),
+                |app| build_call!(app.signed_method(DerivedKey::address_for(b"alice").unwrap())),
+            )
+            .await?;
+        assert_eq!(res.code, 0);
+
+        let my_field = client.query(|app| Ok(app.my_field)).await?;
+        assert_eq!(my_field, 1);
+
+        Ok(())
+    }
+
+    async fn call_through_trait<C: Client<Foo>>(client: &C) -> Result<CallResult> {
+        client
+            .call(
+                |app| build_call!(app.bar.inc_b(4)),
+                |app| build_call!(app.signed_method(DerivedKey::address_for(b"alice").unwrap())),
+            )
+            .await
+    }
+
+    #[serial_test::serial]
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn trait_call() -> Result<()> {
+        let mut mock_client = setup()?;
+
+        {
+            let client = AppClient::<Foo, Foo, _, _, _>::new(
+                &mut mock_client,
+                DerivedKey::new(b"alice").unwrap(),
+            );
+            call_through_trait(&client).await?;
+
+            let bar_b = client.query(|app| Ok(app.bar.b)).await?;
+            assert_eq!(bar_b, 12);
+            let my_field = client.query(|app| Ok(app.my_field)).await?;
+            assert_eq!(my_field, 1);
+
+            let bar_client = client.sub(|app| app.bar, |call| Item::Field(FooFieldCall::Bar(call)));
+            Client::<Bar>::call(
+                &bar_client,
+                |bar| build_call!(bar.inc_b(4)),
+                |bar| build_call!(bar.inc_b(4)),
+            )
+            .await?;
+            let bar_b = bar_client.query(|bar| Ok(bar.b)).await?;
+            assert_eq!(bar_b, 20);
+        }
+
+        Ok(())
+    }
+
     #[serial_test::serial]
     #[cfg(feature = "tokio")]
     #[tokio::test]
@@ -435,18 +782,317 @@ mod tests {
             DerivedKey::new(b"alice").unwrap(),
         );
 
-        let bar_client = client.sub(|app| app.bar);
+        let bar_client = client.sub(|app| app.bar, |call| Item::Field(FooFieldCall::Bar(call)));
 
         let bar_b = bar_client.query(|bar| Ok(bar.b)).await?;
         assert_eq!(bar_b, 8);
 
-        // TODO
-        // bar_client
-        //     .call(
-        //         |bar| build_call!(bar.inc_b(4)),
-        //         |bar| build_call!(bar.inc_b(4)),
-        //     )
-        //     .await?;
+        Client::<Bar>::call(
+            &bar_client,
+            |bar| build_call!(bar.inc_b(4)),
+            |bar| build_call!(bar.inc_b(4)),
+        )
+        .await?;
+
+        let bar_b = bar_client.query(|bar| Ok(bar.b)).await?;
+        assert_eq!(bar_b, 16);
+
+        // the inherent methods also take calls for the subclient's app
+        bar_client
+            .call(
+                |bar| build_call!(bar.inc_b(4)),
+                |bar| build_call!(bar.inc_b(4)),
+            )
+            .await?;
+        bar_client
+            .call_batch(
+                |bar| build_call!(bar.inc_b(4)),
+                |bar| vec![build_call!(bar.inc_b(4)), build_call!(bar.inc_b(4))],
+            )
+            .await?;
+
+        let bar_b = bar_client.query(|bar| Ok(bar.b)).await?;
+        assert_eq!(bar_b, 36);
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "merk-full", feature = "merk-verify"))]
+    struct ProvenTransport(ProvenResponse);
+
+    #[cfg(all(feature = "merk-full", feature = "merk-verify"))]
+    impl exec::Transport<App> for ProvenTransport {
+        async fn query(&self, _query: <App as Query>::Query) -> Result<Store> {
+            Err(Error::Client("Unexpected query".into()))
+        }
+
+        async fn call(&self, _call: <App as Call>::Call) -> Result<CallResult> {
+            Err(Error::Client("Unexpected call".into()))
+        }
+
+        async fn query_proof(&self, _query: <App as Query>::Query) -> Result<ProvenResponse> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[cfg(all(feature = "merk-full", feature = "merk-verify", feature = "tokio"))]
+    #[tokio::test]
+    async fn query_proven() -> Result<()> {
+        use crate::merk::{MerkStore, ProofBuilder};
+        use crate::store::{Read, Shared};
+
+        let home = tempfile::TempDir::new().unwrap();
+        let mut store = Shared::new(MerkStore::new(home.path()));
+        store.put(vec![5, 6], 42u64.encode()?)?;
+        store.put(vec![7], 43u64.encode()?)?;
+        store.borrow_mut().write(vec![])?;
+
+        let builder = ProofBuilder::new(store.clone());
+        builder.get(&[5, 6])?;
+        let (proof, _) = builder.build()?;
+        let root_hash = store.borrow().merk().root_hash();
+
+        let response = ProvenResponse {
+            height: 7,
+            root_hash,
+            proof,
+        };
+        let client =
+            AppClient::<Foo, Foo, _, Simp, _>::new(ProvenTransport(response.clone()), Unsigned);
+        let (value, height) = client.query_proven::<u64>(vec![5, 6], root_hash).await?;
+        assert_eq!(value, 42);
+        assert_eq!(height, 7);
+
+        // a consistent proof of other state is rejected
+        let home = tempfile::TempDir::new().unwrap();
+        let mut forged = Shared::new(MerkStore::new(home.path()));
+        forged.put(vec![5, 6], 43u64.encode()?)?;
+        forged.borrow_mut().write(vec![])?;
+        let builder = ProofBuilder::new(forged.clone());
+        builder.get(&[5, 6])?;
+        let (proof, _) = builder.build()?;
+        let forged = ProvenResponse {
+            height: 7,
+            root_hash: forged.borrow().merk().root_hash(),
+            proof,
+        };
+        let client = AppClient::<Foo, Foo, _, Simp, _>::new(ProvenTransport(forged), Unsigned);
+        assert!(client
+            .query_proven::<u64>(vec![5, 6], root_hash)
+            .await
+            .is_err());
+
+        let mut tampered = response;
+        let value_bytes = 42u64.encode()?;
+        let pos = tampered
+            .proof
+            .windows(value_bytes.len())
+            .position(|window| window == value_bytes.as_slice())
+            .unwrap();
+        tampered.proof[pos + value_bytes.len() - 1] = 43;
+        let client = AppClient::<Foo, Foo, _, Simp, _>::new(ProvenTransport(tampered), Unsigned);
+        assert!(client
+            .query_proven::<u64>(vec![5, 6], root_hash)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn watch() -> Result<()> {
+        let mut mock_client = setup()?;
+        let writer = AppClient::<Foo, Foo, _, _, _>::new(
+            MockClient::<App>::with_store(mock_client.store.clone()),
+            DerivedKey::new(b"alice").unwrap(),
+        );
+        let client = AppClient::<Foo, Foo, _, _, _>::new(&mut mock_client, Unsigned);
+
+        // each block event optionally increments bar.b before being delivered
+        let blocks = futures_lite::stream::iter(vec![Some(4), None, Some(2), None]).then(|inc| {
+            let writer = &writer;
+            async move {
+                if let Some(n) = inc {
+                    writer
+                        .send(
+                            |app| build_call!(app.bar.inc_b(n)),
+                            |app| build_call!(app.bar.inc_b(0)),
+                        )
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+
+        let mut seen = vec![];
+        client
+            .watch(blocks, |app| Ok(app.bar.b), |b| seen.push(b))
+            .await?;
+        assert_eq!(seen, vec![8, 12, 14]);
+
+        Ok(())
+    }
+
+    enum ChannelRequest {
+        Query(Vec<u8>, std::sync::mpsc::Sender<Result<Vec<u8>>>),
+        Call(Vec<u8>, std::sync::mpsc::Sender<Result<CallResult>>),
+    }
+
+    /// A [RawTransport] which sends requests to a node running in another
+    /// thread.
+    struct ChannelTransport(std::sync::Mutex<std::sync::mpsc::Sender<ChannelRequest>>);
+
+    impl ChannelTransport {
+        /// Spawns a thread which serves requests from the mock node's state.
+        fn spawn(node: MockClient<App>) -> Self {
+            use crate::store::Read;
+
+            let (sender, receiver) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                for req in receiver {
+                    match req {
+                        // the node holds all of its state in memory, so it
+                        // responds to every query with all of its entries
+                        ChannelRequest::Query(_, reply) => {
+                            let entries = node
+                                .store
+                                .clone()
+                                .into_iter(..)
+                                .collect::<Result<Vec<_>>>()
+                                .and_then(|entries| {
+                                    serde_json::to_vec(&entries)
+                                        .map_err(|err| Error::Client(err.to_string()))
+                                });
+                            reply.send(entries).unwrap();
+                        }
+                        ChannelRequest::Call(call_bytes, reply) => {
+                            let res = <App as Call>::Call::decode(call_bytes.as_slice())
+                                .map_err(Error::from)
+                                .and_then(|call| {
+                                    futures_lite::future::block_on(exec::Transport::<App>::call(
+                                        &node, call,
+                                    ))
+                                });
+                            reply.send(res).unwrap();
+                        }
+                    }
+                }
+            });
+
+            Self(std::sync::Mutex::new(sender))
+        }
+
+        fn request<T>(
+            &self,
+            build: impl FnOnce(std::sync::mpsc::Sender<Result<T>>) -> ChannelRequest,
+        ) -> Result<T> {
+            let (reply, response) = std::sync::mpsc::channel();
+            self.0
+                .lock()
+                .unwrap()
+                .send(build(reply))
+                .map_err(|_| Error::Client("Node disconnected".into()))?;
+            response
+                .recv()
+                .map_err(|_| Error::Client("Node disconnected".into()))?
+        }
+    }
+
+    impl RawTransport for ChannelTransport {
+        async fn query_bytes(&self, query: Vec<u8>) -> Result<Vec<u8>> {
+            self.request(|reply| ChannelRequest::Query(query, reply))
+        }
+
+        async fn call_bytes(&self, call: Vec<u8>) -> Result<CallResult> {
+            self.request(|reply| ChannelRequest::Call(call, reply))
+        }
+
+        fn decode_response(&self, response: &[u8]) -> Result<Store> {
+            use crate::store::{BackingStore, PartialMapStore, Shared};
+
+            let entries: Vec<(Vec<u8>, Vec<u8>)> =
+                serde_json::from_slice(response).map_err(|err| Error::Client(err.to_string()))?;
+            let map = entries
+                .into_iter()
+                .map(|(key, value)| (key, (true, value)))
+                .collect();
+            let store = PartialMapStore::from_map(map, true);
+
+            Ok(Store::new(BackingStore::PartialMapStore(Shared::new(
+                store,
+            ))))
+        }
+    }
+
+    #[serial_test::serial]
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn raw_transport() -> Result<()> {
+        let node = setup()?;
+        let client = AppClient::<Foo, Foo, _, Simp, _>::new(
+            Raw(ChannelTransport::spawn(node)),
+            DerivedKey::new(b"alice").unwrap(),
+        );
+
+        let bar_b = client.query(|app| Ok(app.bar.b)).await?;
+        assert_eq!(bar_b, 8);
+
+        let res = client
+            .call(
+                |app| build_call!(app.bar.inc_b(4)),
+                |app| build_call!(app.bar.inc_b(0)),
+            )
+            .await?;
+        assert_eq!(res.code, 0);
+
+        let bar_b = client.query(|app| Ok(app.bar.b)).await?;
+        assert_eq!(bar_b, 12);
+
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn signal_upgrade() -> Result<()> {
+        use crate::plugins::Time;
+
+        Context::add(Time::from_seconds(100));
+        let mut mock_client = setup()?;
+        let client = AppClient::<Foo, Foo, _, _, _>::new(
+            &mut mock_client,
+            DerivedKey::new(b"alice").unwrap(),
+        )
+        .sub(
+            |app| app.upgrade,
+            |call| Item::Field(FooFieldCall::Upgrade(call)),
+        );
+
+        let status = client.query_upgrade_status().await?;
+        assert_eq!(status.current_version.as_slice(), &[0]);
+        assert!(status.signals.is_empty());
+        assert!(status.signal_fractions.is_empty());
+
+        client.signal_upgrade(vec![1]).await?;
+        assert_eq!(client.transport.calls.lock().unwrap().len(), 1);
+
+        let status = client.query_upgrade_status().await?;
+        assert_eq!(status.current_version.as_slice(), &[0]);
+        assert_eq!(status.signals.len(), 1);
+        let (cons_key, signal) = &status.signals[0];
+        assert_eq!(cons_key, &[7; 32]);
+        assert_eq!(signal.version.as_slice(), &[1]);
+        assert_eq!(signal.time, 100);
+        assert_eq!(status.signal_fractions.len(), 1);
+        assert_eq!(status.signal_fractions[0].0.as_slice(), &[1]);
+        assert_eq!(status.signal_fractions[0].1, crate::coins::Decimal::one());
+
+        // signals are rate limited
+        assert!(client.signal_upgrade(vec![2]).await.is_err());
+
+        Context::remove::<Time>();
 
         Ok(())
     }
@@ -0,0 +1,271 @@
+//! Subscriptions to Tendermint events over websocket.
+use std::time::Duration;
+
+use crate::{Error, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use futures_util::{stream, SinkExt, Stream, StreamExt};
+use serde_json::{json, Value};
+use tendermint_proto::v0_34::abci::{Event, EventAttribute};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{self, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+/// How long to wait before reconnecting after the connection is dropped or
+/// fails to open.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The events of a new block or transaction, received through a subscription.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventBatch {
+    /// The type of the event data, either `tendermint/event/NewBlock` or
+    /// `tendermint/event/Tx`.
+    pub kind: String,
+    /// The height of the block the events were emitted in.
+    pub height: u64,
+    /// The events, including those emitted by the app through
+    /// [Events](crate::plugins::Events). For new blocks, these are the events
+    /// emitted in `BeginBlock` followed by those emitted in `EndBlock`.
+    pub events: Vec<Event>,
+}
+
+/// Subscribes to the events matching the given query (e.g.
+/// `tm.event='NewBlock'` or `tm.event='Tx'`) through the websocket endpoint of
+/// a Tendermint node, e.g. `ws://localhost:26657/websocket`.
+///
+/// Only `NewBlock` and `Tx` events are yielded. If the connection is dropped,
+/// the stream reconnects and subscribes again after a short delay, so it never
+/// ends. Events emitted while disconnected are missed, and failures to connect
+/// are yielded as errors before retrying.
+pub fn subscribe_events(url: &str, query: &str) -> impl Stream<Item = Result<EventBatch>> + Send {
+    let subscription = Subscription {
+        url: url.to_string(),
+        query: query.to_string(),
+        socket: None,
+        reconnecting: false,
+    };
+
+    stream::unfold(subscription, |mut subscription| async move {
+        let batch = subscription.next_batch().await;
+        Some((batch, subscription))
+    })
+}
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// The connection state of a stream returned by [subscribe_events].
+struct Subscription {
+    url: String,
+    query: String,
+    socket: Option<Socket>,
+    reconnecting: bool,
+}
+
+impl Subscription {
+    /// Opens a connection and sends the subscription request, waiting first if
+    /// this is a reconnection.
+    async fn connect(&mut self) -> Result<Socket> {
+        if self.reconnecting {
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+        self.reconnecting = true;
+
+        let (mut socket, _) = connect_async(self.url.as_str())
+            .await
+            .map_err(websocket_error)?;
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "subscribe",
+            "params": { "query": self.query },
+        });
+        socket
+            .send(Message::Text(request.to_string()))
+            .await
+            .map_err(websocket_error)?;
+        self.reconnecting = false;
+
+        Ok(socket)
+    }
+
+    /// Waits for the next batch of events, connecting if needed.
+    async fn next_batch(&mut self) -> Result<EventBatch> {
+        loop {
+            let mut socket = match self.socket.take() {
+                Some(socket) => socket,
+                None => self.connect().await?,
+            };
+
+            match socket.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    self.socket = Some(socket);
+                    if let Some(batch) = parse_event_batch(&text)? {
+                        return Ok(batch);
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => self.reconnecting = true,
+                Some(Ok(_)) => self.socket = Some(socket),
+            }
+        }
+    }
+}
+
+fn websocket_error(err: tungstenite::Error) -> Error {
+    Error::Client(format!("Websocket error: {}", err))
+}
+
+/// Parses a message received through a subscription, returning `None` for
+/// messages which do not carry `NewBlock` or `Tx` events, e.g. the response
+/// confirming the subscription.
+fn parse_event_batch(message: &str) -> Result<Option<EventBatch>> {
+    let message: Value = serde_json::from_str(message)?;
+    if let Some(error) = message.get("error") {
+        return Err(Error::Tendermint(format!("Subscription error: {}", error)));
+    }
+
+    let data = &message["result"]["data"];
+    let value = &data["value"];
+    let (height, events) = match data["type"].as_str() {
+        Some("tendermint/event/Tx") => {
+            let tx_result = &value["TxResult"];
+            (&tx_result["height"], vec![&tx_result["result"]["events"]])
+        }
+        Some("tendermint/event/NewBlock") => (
+            &value["block"]["header"]["height"],
+            vec![
+                &value["result_begin_block"]["events"],
+                &value["result_end_block"]["events"],
+            ],
+        ),
+        _ => return Ok(None),
+    };
+
+    let height = height
+        .as_str()
+        .ok_or_else(|| Error::Tendermint("Event is missing its height".into()))?
+        .parse()?;
+    let events = events
+        .into_iter()
+        .map(parse_events)
+        .collect::<Result<Vec<_>>>()?
+        .concat();
+
+    Ok(Some(EventBatch {
+        kind: data["type"].as_str().unwrap_or_default().to_string(),
+        height,
+        events,
+    }))
+}
+
+fn parse_events(events: &Value) -> Result<Vec<Event>> {
+    let events = match events.as_array() {
+        Some(events) => events,
+        None => return Ok(vec![]),
+    };
+
+    events
+        .iter()
+        .map(|event| {
+            let attributes = event["attributes"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+                .iter()
+                .map(|attr| {
+                    Ok(EventAttribute {
+                        key: decode_attribute(&attr["key"])?.into(),
+                        value: decode_attribute(&attr["value"])?.into(),
+                        index: attr["index"].as_bool().unwrap_or_default(),
+                    })
+                })
+                .collect::<Result<_>>()?;
+
+            Ok(Event {
+                r#type: event["type"].as_str().unwrap_or_default().to_string(),
+                attributes,
+            })
+        })
+        .collect()
+}
+
+/// Decodes an event attribute key or value, which Tendermint encodes as
+/// base64.
+fn decode_attribute(value: &Value) -> Result<Vec<u8>> {
+    BASE64_STANDARD
+        .decode(value.as_str().unwrap_or_default())
+        .map_err(|err| Error::Tendermint(format!("Invalid event attribute: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    const ACK: &str = r#"{"jsonrpc":"2.0","id":0,"result":{}}"#;
+
+    const TX: &str = r#"{"jsonrpc":"2.0","id":0,"result":{"query":"tm.event='Tx'","data":{"type":"tendermint/event/Tx","value":{"TxResult":{"height":"5","index":0,"tx":"","result":{"events":[{"type":"unbond","attributes":[{"key":"dmFsaWRhdG9y","value":"YWJj","index":true}]}]}}}}}}"#;
+
+    const NEW_BLOCK: &str = r#"{"jsonrpc":"2.0","id":0,"result":{"query":"tm.event='Tx'","data":{"type":"tendermint/event/NewBlock","value":{"block":{"header":{"height":"6"}},"result_begin_block":{"events":[{"type":"begin","attributes":[]}]},"result_end_block":{"events":null}}}}}"#;
+
+    #[tokio::test]
+    async fn mock_server_events() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("ws://{}", listener.local_addr()?);
+
+        // the server closes the connection after each frame, so the second
+        // frame is only received if the client reconnects
+        let server = tokio::spawn(async move {
+            for frame in [TX, NEW_BLOCK] {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+                let request = socket.next().await.unwrap().unwrap();
+                let request: Value = serde_json::from_str(request.to_text().unwrap()).unwrap();
+                assert_eq!(request["method"], "subscribe");
+                assert_eq!(request["params"]["query"], "tm.event='Tx'");
+
+                socket.send(Message::Text(ACK.into())).await.unwrap();
+                socket.send(Message::Text(frame.into())).await.unwrap();
+                socket.close(None).await.unwrap();
+            }
+        });
+
+        let batches: Vec<_> = subscribe_events(&url, "tm.event='Tx'")
+            .take(2)
+            .collect()
+            .await;
+        server.await.unwrap();
+
+        let batches = batches.into_iter().collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            batches[0],
+            EventBatch {
+                kind: "tendermint/event/Tx".into(),
+                height: 5,
+                events: vec![Event {
+                    r#type: "unbond".into(),
+                    attributes: vec![EventAttribute {
+                        key: "validator".into(),
+                        value: "abc".into(),
+                        index: true,
+                    }],
+                }],
+            }
+        );
+        assert_eq!(
+            batches[1],
+            EventBatch {
+                kind: "tendermint/event/NewBlock".into(),
+                height: 6,
+                events: vec![Event {
+                    r#type: "begin".into(),
+                    attributes: vec![],
+                }],
+            }
+        );
+
+        Ok(())
+    }
+}
@@ -1,11 +1,12 @@
 //! Key management for clients.
+use std::future::Future;
 use std::path::Path;
 
 use secp256k1::SecretKey;
 
 use crate::{
     coins::Address,
-    plugins::{SigType, SignerCall},
+    plugins::{eth_personal_sign_digest, KeyType, SigType, SignerCall},
     Result,
 };
 
@@ -24,6 +25,29 @@ pub trait Wallet: Clone + Send + Sync {
     }
 }
 
+/// A trait for wallets which sign calls asynchronously, e.g. by doing I/O to
+/// sign with a hardware wallet or a remote key management service.
+///
+/// This is implemented for all [Wallet]s, and is what
+/// [AppClient](super::AppClient) uses to sign calls.
+pub trait AsyncWallet: Clone + Send + Sync {
+    /// Sign a call.
+    fn sign_async(&self, call_bytes: &[u8]) -> impl Future<Output = Result<SignerCall>> + Send;
+
+    /// Returns the address for this wallet if it has one.
+    fn signer_address(&self) -> Result<Option<Address>>;
+}
+
+impl<W: Wallet> AsyncWallet for W {
+    fn sign_async(&self, call_bytes: &[u8]) -> impl Future<Output = Result<SignerCall>> + Send {
+        std::future::ready(self.sign(call_bytes))
+    }
+
+    fn signer_address(&self) -> Result<Option<Address>> {
+        self.address()
+    }
+}
+
 /// A wallet without keys. It produces unsigned calls and has no address.
 #[derive(Clone, Debug, Default)]
 pub struct Unsigned;
@@ -35,6 +59,7 @@ impl Wallet for Unsigned {
             signature: None,
             pubkey: None,
             sigtype: SigType::Native,
+            memo: None,
         })
     }
 
@@ -88,22 +113,49 @@ impl DerivedKey {
     pub fn address(&self) -> Address {
         Address::from_pubkey(self.pubkey().serialize())
     }
-}
 
-impl Wallet for DerivedKey {
-    fn sign(&self, call_bytes: &[u8]) -> Result<SignerCall> {
-        use secp256k1::hashes::sha256;
-        let secp = secp256k1::Secp256k1::new();
-        let msg = secp256k1::Message::from_hashed_data::<sha256::Hash>(call_bytes);
-        let sig = secp.sign_ecdsa(&msg, &self.privkey);
-        let sig_bytes = sig.serialize_compact();
-
-        Ok(SignerCall {
+    /// Sign a call, attaching the given memo.
+    pub fn sign_with_memo(&self, call_bytes: &[u8], memo: Option<&str>) -> Result<SignerCall> {
+        let mut call = SignerCall {
             call_bytes: call_bytes.to_vec(),
-            signature: Some(sig_bytes),
+            signature: None,
             pubkey: Some(self.pubkey().serialize()),
             sigtype: SigType::Native,
-        })
+            memo: memo.map(TryInto::try_into).transpose()?,
+        };
+        call.signature = Some(sign_native(&self.privkey, &call.sign_bytes()?));
+
+        Ok(call)
+    }
+
+    /// Signs the given call bytes with a [SigType::Tagged] signature, so the
+    /// signer's address is derived as specified by `key_type`.
+    pub fn sign_tagged(&self, call_bytes: &[u8], key_type: KeyType) -> Result<SignerCall> {
+        let mut call = SignerCall {
+            call_bytes: call_bytes.to_vec(),
+            signature: None,
+            pubkey: Some(self.pubkey().serialize()),
+            sigtype: SigType::Tagged(key_type),
+            memo: None,
+        };
+        call.signature = Some(sign_native(&self.privkey, &call.sign_bytes()?));
+
+        Ok(call)
+    }
+}
+
+/// Creates a compact secp256k1 signature over the SHA-256 hash of the given
+/// bytes.
+fn sign_native(privkey: &SecretKey, bytes: &[u8]) -> [u8; 64] {
+    use secp256k1::hashes::sha256;
+    let secp = secp256k1::Secp256k1::new();
+    let msg = secp256k1::Message::from_hashed_data::<sha256::Hash>(bytes);
+    secp.sign_ecdsa(&msg, privkey).serialize_compact()
+}
+
+impl Wallet for DerivedKey {
+    fn sign(&self, call_bytes: &[u8]) -> Result<SignerCall> {
+        self.sign_with_memo(call_bytes, None)
     }
 
     fn address(&self) -> Result<Option<Address>> {
@@ -151,16 +203,128 @@ impl Wallet for SimpleWallet {
     }
 
     fn sign(&self, call_bytes: &[u8]) -> Result<SignerCall> {
-        use secp256k1::hashes::sha256;
-        let secp = secp256k1::Secp256k1::new();
-        let msg = secp256k1::Message::from_hashed_data::<sha256::Hash>(call_bytes);
-        let sig = secp.sign_ecdsa(&msg, &self.privkey).serialize_compact();
-
         Ok(SignerCall {
             call_bytes: call_bytes.to_vec(),
-            signature: Some(sig),
+            signature: Some(sign_native(&self.privkey, call_bytes)),
             pubkey: Some(self.pubkey.serialize()),
             sigtype: SigType::Native,
+            memo: None,
         })
     }
 }
+
+/// A wallet with a secp256k1 key which signs calls with Ethereum's
+/// `personal_sign` (EIP-191), as done by e.g. MetaMask. Its address is the
+/// Ethereum address of its public key.
+///
+/// Signed calls omit the public key, which is recovered from the signature by
+/// the [SignerPlugin](crate::plugins::SignerPlugin).
+#[derive(Clone, Debug)]
+pub struct EthWallet {
+    privkey: secp256k1::SecretKey,
+}
+
+impl EthWallet {
+    /// Create a new wallet from a secret key.
+    pub fn from_secret_key(privkey: SecretKey) -> Self {
+        Self { privkey }
+    }
+
+    /// Returns the public key for this wallet.
+    pub fn pubkey(&self) -> secp256k1::PublicKey {
+        let secp = secp256k1::Secp256k1::new();
+        secp256k1::PublicKey::from_secret_key(&secp, &self.privkey)
+    }
+
+    /// Returns the Ethereum address for this wallet.
+    pub fn address(&self) -> Address {
+        let pubkey_bytes = self.pubkey().serialize_uncompressed();
+        let mut eth_pubkey = [0; 64];
+        eth_pubkey.copy_from_slice(&pubkey_bytes[1..]);
+        Address::from_pubkey_eth(eth_pubkey)
+    }
+
+    /// Sign a call, attaching the given memo.
+    pub fn sign_with_memo(&self, call_bytes: &[u8], memo: Option<&str>) -> Result<SignerCall> {
+        let mut call = SignerCall {
+            call_bytes: call_bytes.to_vec(),
+            signature: None,
+            pubkey: None,
+            sigtype: SigType::Eth(0),
+            memo: memo.map(TryInto::try_into).transpose()?,
+        };
+
+        let secp = secp256k1::Secp256k1::new();
+        let msg =
+            secp256k1::Message::from_digest_slice(&eth_personal_sign_digest(&call.sign_bytes()?))?;
+        let (recovery_id, signature) = secp
+            .sign_ecdsa_recoverable(&msg, &self.privkey)
+            .serialize_compact();
+        call.signature = Some(signature);
+        call.sigtype = SigType::Eth(27 + recovery_id.to_i32() as u8);
+
+        Ok(call)
+    }
+}
+
+impl Wallet for EthWallet {
+    fn sign(&self, call_bytes: &[u8]) -> Result<SignerCall> {
+        self.sign_with_memo(call_bytes, None)
+    }
+
+    fn address(&self) -> Result<Option<Address>> {
+        Ok(Some(self.address()))
+    }
+}
+
+/// A wallet which signs calls with an external signer, such as a Ledger or a
+/// remote key management service.
+///
+/// The signer is a user-supplied async function which is called with the bytes
+/// to sign, and returns a compact secp256k1 signature over their SHA-256 hash
+/// (the same signatures made by [DerivedKey]).
+#[derive(Clone)]
+pub struct ExternalSigner<F> {
+    pubkey: [u8; 33],
+    sign: F,
+}
+
+impl<F, Fut> ExternalSigner<F>
+where
+    F: Fn(Vec<u8>) -> Fut + Clone + Send + Sync,
+    Fut: Future<Output = Result<[u8; 64]>> + Send,
+{
+    /// Create a new wallet from the signer's compressed secp256k1 public key
+    /// and its signing function.
+    pub fn new(pubkey: [u8; 33], sign: F) -> Self {
+        Self { pubkey, sign }
+    }
+
+    /// Returns the address for this wallet.
+    pub fn address(&self) -> Address {
+        Address::from_pubkey(self.pubkey)
+    }
+}
+
+impl<F, Fut> AsyncWallet for ExternalSigner<F>
+where
+    F: Fn(Vec<u8>) -> Fut + Clone + Send + Sync,
+    Fut: Future<Output = Result<[u8; 64]>> + Send,
+{
+    async fn sign_async(&self, call_bytes: &[u8]) -> Result<SignerCall> {
+        let mut call = SignerCall {
+            call_bytes: call_bytes.to_vec(),
+            signature: None,
+            pubkey: Some(self.pubkey),
+            sigtype: SigType::Native,
+            memo: None,
+        };
+        call.signature = Some((self.sign)(call.sign_bytes()?).await?);
+
+        Ok(call)
+    }
+
+    fn signer_address(&self) -> Result<Option<Address>> {
+        Ok(Some(self.address()))
+    }
+}
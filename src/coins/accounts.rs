@@ -3,13 +3,17 @@ use crate::coins::{Address, Amount, Coin, Give, Symbol, Take};
 use crate::collections::map::Iter as MapIter;
 use crate::collections::Map;
 use crate::context::GetContext;
+use crate::migrate::MigrateFrom;
 use crate::orga;
 use crate::plugins::Paid;
 use crate::plugins::Signer;
 use crate::{Error, Result};
 
 /// Manages accounts and their coin balances for a specific symbol.
-#[orga]
+///
+/// Accounts may be frozen by the admin address, if one is configured, which
+/// prevents them from sending or receiving coins until they are unfrozen.
+#[orga(version = 1)]
 pub struct Accounts<S: Symbol> {
     /// Whether transfers are allowed.
     transfers_allowed: bool,
@@ -17,6 +21,24 @@ pub struct Accounts<S: Symbol> {
     transfer_exceptions: Map<Address, ()>,
     /// Mapping of addresses to their coin balances.
     accounts: Map<Address, Coin<S>>,
+    /// The address allowed to freeze and unfreeze accounts, if any.
+    #[orga(version(V1))]
+    admin: Option<Address>,
+    /// Addresses which may not send or receive coins.
+    #[orga(version(V1))]
+    frozen: Map<Address, ()>,
+}
+
+impl<S: Symbol> MigrateFrom<AccountsV0<S>> for AccountsV1<S> {
+    fn migrate_from(value: AccountsV0<S>) -> Result<Self> {
+        Ok(Self {
+            transfers_allowed: value.transfers_allowed,
+            transfer_exceptions: value.transfer_exceptions,
+            accounts: value.accounts,
+            admin: None,
+            frozen: Map::new(),
+        })
+    }
 }
 
 #[orga]
@@ -33,6 +55,7 @@ impl<S: Symbol> Accounts<S> {
         if !self.transfers_allowed && !self.transfer_exceptions.contains_key(signer)? {
             return Err(Error::Coins("Transfers are currently disabled".into()));
         }
+        self.check_not_frozen(to)?;
         let taken_coins = self.take_own_coins(amount)?;
         let mut receiver = self.accounts.entry(to)?.or_insert_default()?;
         receiver.give(taken_coins)?;
@@ -56,6 +79,7 @@ impl<S: Symbol> Accounts<S> {
     /// Takes coins from the signer's account.
     fn take_own_coins(&mut self, amount: Amount) -> Result<Coin<S>> {
         let signer = self.signer()?;
+        self.check_not_frozen(signer)?;
 
         let taken_coins = self
             .accounts
@@ -101,6 +125,7 @@ impl<S: Symbol> Accounts<S> {
     /// Gives coins to the signer's account.
     fn give_own_coins(&mut self, coins: Coin<S>) -> Result<()> {
         let signer = self.signer()?;
+        self.check_not_frozen(signer)?;
 
         self.accounts
             .entry(signer)?
@@ -136,8 +161,60 @@ impl<S: Symbol> Accounts<S> {
         self.transfer_exceptions.insert(address, ())
     }
 
+    /// Sets the address allowed to freeze and unfreeze accounts, or disables
+    /// freezing if `None`.
+    pub fn set_admin(&mut self, admin: Option<Address>) {
+        self.admin = admin;
+    }
+
+    /// Freezes the given address, preventing it from sending or receiving
+    /// coins. Must be signed by the admin address.
+    #[call]
+    pub fn freeze(&mut self, address: Address) -> Result<()> {
+        self.check_admin()?;
+        self.frozen.insert(address, ())
+    }
+
+    /// Unfreezes the given address. Must be signed by the admin address.
+    #[call]
+    pub fn unfreeze(&mut self, address: Address) -> Result<()> {
+        self.check_admin()?;
+        self.frozen.remove(address)?;
+
+        Ok(())
+    }
+
+    /// Returns `true` if the given address is frozen.
+    #[query]
+    pub fn is_frozen(&self, address: Address) -> Result<bool> {
+        self.frozen.contains_key(address)
+    }
+
+    /// Returns an error if the given address is frozen.
+    fn check_not_frozen(&self, address: Address) -> Result<()> {
+        if self.is_frozen(address)? {
+            return Err(Error::Coins("account frozen".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if the signer is not the admin address.
+    fn check_admin(&mut self) -> Result<()> {
+        let admin = self.admin;
+        let signer = self
+            .context::<Signer>()
+            .ok_or_else(|| Error::Signer("No Signer context available".into()))?;
+        if !admin.map_or(false, |admin| signer.is_signer(admin)) {
+            return Err(Error::Coins("Unauthorized account action".into()));
+        }
+
+        Ok(())
+    }
+
     /// Deposits coins into the specified address's account.
     pub fn deposit(&mut self, address: Address, coins: Coin<S>) -> Result<()> {
+        self.check_not_frozen(address)?;
         let mut account = self.accounts.entry(address)?.or_insert_default()?;
         account.give(coins)?;
 
@@ -146,7 +223,81 @@ impl<S: Symbol> Accounts<S> {
 
     /// Withdraws coins from the specified address's account.
     pub fn withdraw(&mut self, address: Address, amount: Amount) -> Result<Coin<S>> {
+        self.check_not_frozen(address)?;
         let mut account = self.accounts.entry(address)?.or_insert_default()?;
         account.take(amount)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+    use serial_test::serial;
+
+    #[orga]
+    #[derive(Clone, Debug)]
+    struct Simp;
+    impl Symbol for Simp {
+        const INDEX: u8 = 0;
+        const NAME: &'static str = "SIMP";
+    }
+
+    fn set_signer(address: Address) {
+        Context::add(Signer {
+            signer: Some(address),
+        })
+    }
+
+    #[test]
+    #[serial]
+    fn freeze() -> Result<()> {
+        let admin = Address::from_pubkey([0; 33]);
+        let alice = Address::from_pubkey([1; 33]);
+        let bob = Address::from_pubkey([2; 33]);
+
+        let mut accounts: Accounts<Simp> = Default::default();
+        accounts.allow_transfers(true);
+        accounts.deposit(alice, Simp::mint(100))?;
+        accounts.deposit(bob, Simp::mint(100))?;
+
+        // only the admin may freeze accounts
+        set_signer(alice);
+        assert!(accounts.freeze(bob).is_err());
+        accounts.set_admin(Some(admin));
+        assert!(accounts.freeze(bob).is_err());
+        set_signer(admin);
+        accounts.freeze(bob)?;
+        assert!(accounts.is_frozen(bob)?);
+        assert!(!accounts.is_frozen(alice)?);
+
+        // frozen recipient
+        set_signer(alice);
+        assert!(accounts.transfer(bob, 10.into()).is_err());
+        assert!(accounts.deposit(bob, Simp::mint(10)).is_err());
+
+        // frozen sender
+        set_signer(bob);
+        assert!(accounts.transfer(alice, 10.into()).is_err());
+        assert!(accounts.withdraw(bob, 10.into()).is_err());
+        assert_eq!(accounts.balance(alice)?, 100);
+        assert_eq!(accounts.balance(bob)?, 100);
+
+        set_signer(bob);
+        assert!(accounts.unfreeze(bob).is_err());
+        set_signer(admin);
+        accounts.unfreeze(bob)?;
+        assert!(!accounts.is_frozen(bob)?);
+
+        set_signer(bob);
+        accounts.transfer(alice, 10.into())?;
+        set_signer(alice);
+        accounts.transfer(bob, 20.into())?;
+        assert_eq!(accounts.balance(alice)?, 90);
+        assert_eq!(accounts.balance(bob)?, 110);
+
+        Context::remove::<Signer>();
+
+        Ok(())
+    }
+}
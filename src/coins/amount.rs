@@ -1,13 +1,22 @@
 //! Safe integer amounts.
 use crate::{Error, Result};
 use orga::orga;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::convert::TryFrom;
 
 /// Represents an amount (usually of coins) with safe arithmetic operations to
 /// prevent overflows.
-#[orga]
+///
+/// Arithmetic operators on amounts return a [MathResult](super::MathResult)
+/// rather than panicking or wrapping, which holds [Error::Overflow] if the
+/// result is negative or exceeds [Amount::MAX]. The `checked_*` methods are
+/// equivalent but return a [Result] directly.
+///
+/// Serializes as a decimal integer string (as in the Cosmos SDK) so that
+/// JavaScript clients do not lose precision above 2^53. Deserialization
+/// accepts either a string or a number.
+#[orga(skip(Serialize))]
 #[derive(Debug, Clone, Copy, PartialOrd, Ord)]
-#[serde(transparent)]
 pub struct Amount {
     /// The value of the amount.
     pub(crate) value: u64,
@@ -21,11 +30,86 @@ impl std::fmt::Display for Amount {
 
 impl Eq for Amount {}
 
+impl Serialize for Amount {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.value.to_string().as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct AmountVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for AmountVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("an unsigned integer or a decimal integer string")
+            }
+
+            fn visit_u64<E>(self, value: u64) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(Amount::new(value))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                u64::try_from(value)
+                    .map(Amount::new)
+                    .map_err(serde::de::Error::custom)
+            }
+
+            fn visit_str<E>(self, value: &str) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                value
+                    .parse::<u64>()
+                    .map(Amount::new)
+                    .map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
 impl Amount {
+    /// The largest representable amount.
+    pub const MAX: Amount = Amount { value: u64::MAX };
+
     /// Creates a new amount with the given value.
     pub fn new(value: u64) -> Self {
         Amount { value }
     }
+
+    /// Adds two amounts, returning [Error::Overflow] if the result exceeds
+    /// [Amount::MAX].
+    pub fn checked_add(self, other: Amount) -> Result<Amount> {
+        (self + other).result()
+    }
+
+    /// Subtracts `other` from this amount, returning [Error::Overflow] if the
+    /// result would be negative.
+    pub fn checked_sub(self, other: Amount) -> Result<Amount> {
+        (self - other).result()
+    }
+
+    /// Multiplies two amounts, returning [Error::Overflow] if the result
+    /// exceeds [Amount::MAX].
+    pub fn checked_mul(self, other: Amount) -> Result<Amount> {
+        (self * other).result()
+    }
 }
 
 impl From<u64> for Amount {
@@ -47,3 +131,56 @@ impl TryFrom<Result<Amount>> for Amount {
         value
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_string_roundtrip() {
+        let amount = Amount::new(u64::MAX);
+        let json = serde_json::to_string(&amount).unwrap();
+        assert_eq!(json, "\"18446744073709551615\"");
+
+        let decoded: Amount = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, amount);
+    }
+
+    #[test]
+    fn overflow_at_max() -> Result<()> {
+        let one = Amount::new(1);
+
+        assert!(matches!(Amount::MAX.checked_add(one), Err(Error::Overflow)));
+        assert!(matches!(
+            Amount::MAX.checked_mul(Amount::new(2)),
+            Err(Error::Overflow)
+        ));
+        assert!(matches!(
+            Amount::new(0).checked_sub(one),
+            Err(Error::Overflow)
+        ));
+        assert_eq!(Amount::MAX.checked_sub(one)?, u64::MAX - 1);
+        assert_eq!(Amount::MAX.checked_mul(one)?, Amount::MAX);
+        assert_eq!(Amount::new(u64::MAX - 1).checked_add(one)?, Amount::MAX);
+
+        // errors are preserved through chained operators and `?`
+        let chained: Result<Amount> = ((Amount::MAX + one) - one).into();
+        assert!(matches!(chained, Err(Error::Overflow)));
+        fn add_one(amount: Amount) -> Result<Amount> {
+            let sum = (amount + Amount::new(1))?;
+            Ok(sum)
+        }
+        assert!(matches!(add_one(Amount::MAX), Err(Error::Overflow)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn json_number_input() {
+        let decoded: Amount = serde_json::from_str("1234").unwrap();
+        assert_eq!(decoded, Amount::new(1234));
+
+        assert!(serde_json::from_str::<Amount>("-1").is_err());
+        assert!(serde_json::from_str::<Amount>("\"12a\"").is_err());
+    }
+}
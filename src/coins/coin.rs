@@ -103,3 +103,48 @@ impl<S: Symbol> From<u64> for Coin<S> {
         Self::mint(amount)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{Decode, Encode};
+    use crate::state::State;
+    use crate::store::{Read, Store};
+
+    #[orga]
+    #[derive(Debug, Clone)]
+    struct Simp;
+
+    impl Symbol for Simp {
+        const INDEX: u8 = 0;
+        const NAME: &'static str = "SIMP";
+    }
+
+    #[test]
+    fn symbol_marker_encoding() -> Result<()> {
+        let amount = Amount::new(1234);
+        let coin: Coin<Simp> = Coin::mint(amount);
+
+        // only the version byte and amount are encoded, the symbol marker adds
+        // no bytes
+        let expected = [vec![0], amount.encode()?].concat();
+        assert_eq!(coin.encode()?, expected);
+        assert_eq!(coin.encoding_length()?, expected.len());
+
+        let decoded = Coin::<Simp>::decode(expected.as_slice())?;
+        assert_eq!(decoded.amount, amount);
+
+        let store = Store::with_map_store();
+        let mut coin: Coin<Simp> = Coin::mint(amount);
+        coin.attach(store.clone())?;
+        let mut bytes = vec![];
+        coin.flush(&mut bytes)?;
+        assert_eq!(bytes, expected);
+        assert!(store.get_next(&[])?.is_none());
+
+        let loaded = Coin::<Simp>::load(store, &mut bytes.as_slice())?;
+        assert_eq!(loaded.amount, amount);
+
+        Ok(())
+    }
+}
@@ -7,14 +7,19 @@ use crate::migrate::Migrate;
 use crate::orga;
 use crate::{Error, Result};
 use rust_decimal::{prelude::ToPrimitive, Decimal as NumDecimal};
+use serde::{Deserialize, Serialize};
 
 use std::convert::TryFrom;
 use std::str::FromStr;
 
 /// A decimal type for precise financial calculations.
-#[orga(simple, skip(Describe, Migrate))]
+///
+/// Decimals are parsed from and displayed as fixed-point strings such as
+/// `"-12.5"` or `"0.000001"`, and are serialized with serde as strings so that
+/// no precision is lost to floats. Like [Amount], they can be deserialized from
+/// either strings or numbers.
+#[orga(simple, skip(Describe, Migrate, Serialize))]
 #[derive(Copy, Debug, PartialOrd, Ord)]
-#[serde(transparent)]
 pub struct Decimal {
     /// The underlying numeric decimal value.
     pub(crate) value: NumDecimal,
@@ -24,7 +29,7 @@ impl Migrate for Decimal {}
 
 impl Describe for Decimal {
     fn describe() -> crate::describe::Descriptor {
-        Builder::new::<Self>().build()
+        Builder::new::<Self>().layout_id("Decimal").build()
     }
 }
 
@@ -85,6 +90,24 @@ impl Decimal {
         }
     }
 
+    /// Adds two decimals, returning [Error::Overflow] if the result is out of
+    /// range.
+    pub fn checked_add(self, other: Decimal) -> Result<Decimal> {
+        (self + other).result()
+    }
+
+    /// Subtracts `other` from this decimal, returning [Error::Overflow] if the
+    /// result is out of range.
+    pub fn checked_sub(self, other: Decimal) -> Result<Decimal> {
+        (self - other).result()
+    }
+
+    /// Multiplies two decimals, returning [Error::Overflow] if the result is
+    /// out of range.
+    pub fn checked_mul(self, other: Decimal) -> Result<Decimal> {
+        (self * other).result()
+    }
+
     /// Returns the absolute value of the decimal.
     pub fn abs(&self) -> Self {
         Decimal {
@@ -132,10 +155,91 @@ impl From<Amount> for Decimal {
 impl FromStr for Decimal {
     type Err = Error;
 
+    /// Parses a fixed-point decimal string, with an optional leading `-`, e.g.
+    /// `"-12.5"` or `"007.50"`. Exponents, digit separators, whitespace, and
+    /// values which can not be represented exactly are rejected.
     fn from_str(s: &str) -> Result<Self> {
-        Ok(Self {
-            value: NumDecimal::from_str(s)?,
-        })
+        let invalid = |reason: &str| Error::Coins(format!("Invalid decimal {:?}: {}", s, reason));
+
+        let unsigned = s.strip_prefix('-').unwrap_or(s);
+        let (int, frac) = match unsigned.split_once('.') {
+            Some((int, frac)) => (int, Some(frac)),
+            None => (unsigned, None),
+        };
+        if int.is_empty() {
+            return Err(invalid("missing integer digits"));
+        }
+        if frac == Some("") {
+            return Err(invalid("missing fractional digits"));
+        }
+        if !int
+            .chars()
+            .chain(frac.unwrap_or_default().chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid("expected only digits and a decimal point"));
+        }
+
+        let value = NumDecimal::from_str_exact(s).map_err(|err| invalid(&err.to_string()))?;
+        Ok(Self { value })
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct DecimalVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for DecimalVisitor {
+            type Value = Decimal;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a number or a fixed-point decimal string")
+            }
+
+            fn visit_u64<E>(self, value: u64) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(NumDecimal::from(value).into())
+            }
+
+            fn visit_i64<E>(self, value: i64) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(NumDecimal::from(value).into())
+            }
+
+            fn visit_f64<E>(self, value: f64) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                // the shortest representation which round-trips to the same
+                // float, e.g. `1.5`, so it is parsed exactly
+                self.visit_str(&value.to_string())
+            }
+
+            fn visit_str<E>(self, value: &str) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                value.parse().map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_any(DecimalVisitor)
     }
 }
 
@@ -148,4 +252,96 @@ mod tests {
         let formatted: Decimal = rust_decimal_macros::dec!(1.23).into();
         assert_eq!(format!("{}", formatted), "1.23");
     }
+
+    #[test]
+    fn overflow() -> Result<()> {
+        let max_amount: Decimal = Amount::MAX.into();
+        assert!(matches!(
+            max_amount.checked_mul(max_amount),
+            Err(Error::Overflow)
+        ));
+        assert!(matches!(
+            (Amount::MAX * max_amount).result(),
+            Err(Error::Overflow)
+        ));
+        assert_eq!(
+            max_amount.checked_mul(Decimal::one())?.amount()?,
+            Amount::MAX
+        );
+
+        let max: Decimal = NumDecimal::MAX.into();
+        let min: Decimal = NumDecimal::MIN.into();
+        assert!(matches!(
+            max.checked_add(Decimal::one()),
+            Err(Error::Overflow)
+        ));
+        assert!(matches!(
+            min.checked_sub(Decimal::one()),
+            Err(Error::Overflow)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_round_trip() -> Result<()> {
+        for s in ["0.000001", "1.0", "0", "-12.5", "123456789.987654321"] {
+            let decimal: Decimal = s.parse()?;
+            assert_eq!(decimal.to_string(), s);
+            assert_eq!(decimal.to_string().parse::<Decimal>()?, decimal);
+        }
+
+        let decimal: Decimal = "007.50".parse()?;
+        assert_eq!(decimal, rust_decimal_macros::dec!(7.5).into());
+        assert_eq!(decimal.to_string(), "7.50");
+        assert_eq!(
+            "-0.25".parse::<Decimal>()?,
+            rust_decimal_macros::dec!(-0.25).into()
+        );
+
+        for s in [
+            "", "-", ".5", "5.", "1.2.3", "1e5", "abc", " 1", "1 ", "+1", "--1", "1_000", "1,5",
+        ] {
+            assert!(s.parse::<Decimal>().is_err(), "{:?} should be rejected", s);
+        }
+        assert!("0.00000000000000000000000000001"
+            .parse::<Decimal>()
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn serde_string() -> Result<()> {
+        let decimal: Decimal = "0.000001".parse()?;
+        let json = serde_json::to_string(&decimal).unwrap();
+        assert_eq!(json, "\"0.000001\"");
+        assert_eq!(serde_json::from_str::<Decimal>(&json).unwrap(), decimal);
+
+        let decimal: Decimal = "-1.0".parse()?;
+        let json = serde_json::to_string(&decimal).unwrap();
+        assert_eq!(json, "\"-1.0\"");
+        assert_eq!(serde_json::from_str::<Decimal>(&json).unwrap(), decimal);
+
+        assert!(serde_json::from_str::<Decimal>("\"1.5x\"").is_err());
+        assert!(serde_json::from_str::<Decimal>("true").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn serde_number() -> Result<()> {
+        let from_json = |json: &str| serde_json::from_str::<Decimal>(json).unwrap();
+        assert_eq!(from_json("1.5"), "1.5".parse::<Decimal>()?);
+        assert_eq!(from_json("-0.25"), "-0.25".parse::<Decimal>()?);
+        assert_eq!(from_json("0.1"), "0.1".parse::<Decimal>()?);
+        assert_eq!(from_json("7"), "7".parse::<Decimal>()?);
+        assert_eq!(from_json("-7"), "-7".parse::<Decimal>()?);
+        assert_eq!(
+            from_json("18446744073709551615"),
+            Decimal::from(Amount::MAX)
+        );
+
+        Ok(())
+    }
 }
@@ -1,14 +1,21 @@
 //! Scheduled coin issuance.
-use super::{Amount, Coin, Decimal, Symbol};
+use super::{Address, Amount, Coin, Decimal, Symbol};
+use crate::abci::BeginBlock;
+use crate::collections::Map;
 use crate::context::GetContext;
+use crate::migrate::MigrateFrom;
 use crate::orga;
-use crate::plugins::Time;
+use crate::plugins::{BeginBlockCtx, Paid, Signer, Time};
 use crate::{Error, Result};
 use std::marker::PhantomData;
 use std::time::Duration;
 
 /// A faucet for minting coins of a specific symbol over time.
-#[orga]
+///
+/// Separately from the scheduled issuance, the faucet can be configured to
+/// hand out a fixed amount per claim (e.g. on a testnet), rate limited per
+/// address by a cooldown and optionally capped per block.
+#[orga(version = 2)]
 pub struct Faucet<S: Symbol> {
     /// Phantom data to hold the symbol type.
     _symbol: PhantomData<S>,
@@ -28,8 +35,80 @@ pub struct Faucet<S: Symbol> {
     seconds_per_period: u64,
     /// Number of minting periods.
     num_periods: u32,
+    /// Amount of coins handed out per claim, or zero if claims are disabled.
+    #[orga(version(V1, V2))]
+    claim_amount: Amount,
+    /// Minimum number of seconds between claims by the same address.
+    #[orga(version(V1, V2))]
+    claim_cooldown_seconds: u64,
+    /// Maximum number of claims per block, if any.
+    #[orga(version(V1, V2))]
+    max_claims_per_block: Option<u32>,
+    /// Time of the block of the most recent claim, in unix seconds.
+    #[orga(version(V1))]
+    claim_block_seconds: i64,
+    /// Height of the current block, updated at the beginning of each block.
+    #[orga(version(V2))]
+    height: u64,
+    /// Number of claims made in the current block.
+    #[orga(version(V1, V2))]
+    claims_in_block: u32,
+    /// Time of each address's most recent claim, in unix seconds.
+    #[orga(version(V1, V2))]
+    last_claims: Map<Address, i64>,
+}
+
+impl<S: Symbol> MigrateFrom<FaucetV0<S>> for FaucetV1<S> {
+    fn migrate_from(value: FaucetV0<S>) -> Result<Self> {
+        Ok(Self {
+            _symbol: PhantomData,
+            configured: value.configured,
+            amount_minted: value.amount_minted,
+            start_seconds: value.start_seconds,
+            multiplier_total: value.multiplier_total,
+            total_to_mint: value.total_to_mint,
+            period_decay: value.period_decay,
+            seconds_per_period: value.seconds_per_period,
+            num_periods: value.num_periods,
+            ..Default::default()
+        })
+    }
 }
 
+impl<S: Symbol> MigrateFrom<FaucetV1<S>> for FaucetV2<S> {
+    fn migrate_from(value: FaucetV1<S>) -> Result<Self> {
+        Ok(Self {
+            _symbol: PhantomData,
+            configured: value.configured,
+            amount_minted: value.amount_minted,
+            start_seconds: value.start_seconds,
+            multiplier_total: value.multiplier_total,
+            total_to_mint: value.total_to_mint,
+            period_decay: value.period_decay,
+            seconds_per_period: value.seconds_per_period,
+            num_periods: value.num_periods,
+            claim_amount: value.claim_amount,
+            claim_cooldown_seconds: value.claim_cooldown_seconds,
+            max_claims_per_block: value.max_claims_per_block,
+            height: 0,
+            claims_in_block: 0,
+            last_claims: value.last_claims,
+        })
+    }
+}
+
+impl<S: Symbol> BeginBlock for Faucet<S> {
+    fn begin_block(&mut self, ctx: &BeginBlockCtx) -> Result<()> {
+        if ctx.height != self.height {
+            self.height = ctx.height;
+            self.claims_in_block = 0;
+        }
+
+        Ok(())
+    }
+}
+
+#[orga]
 impl<S: Symbol> Faucet<S> {
     /// Initializes the faucet with the given options.
     pub fn configure(&mut self, opts: FaucetOptions) -> Result<()> {
@@ -103,6 +182,70 @@ impl<S: Symbol> Faucet<S> {
         total.amount()
     }
 
+    /// Enables claims with the given options.
+    pub fn configure_claims(&mut self, opts: ClaimOptions) {
+        self.claim_amount = opts.amount;
+        self.claim_cooldown_seconds = opts.cooldown.as_secs();
+        self.max_claims_per_block = opts.max_claims_per_block;
+    }
+
+    /// Mints the claim amount for the signer into the [Paid] context,
+    /// returning an error if the signer has claimed within the cooldown or the
+    /// block's claim cap is reached.
+    ///
+    /// The per-block cap is reset when [BeginBlock] reaches a new height, so
+    /// the faucet must be included in the app's `begin_block`.
+    #[call]
+    pub fn claim(&mut self) -> Result<()> {
+        let signer = self.signer()?;
+        let coins = self.claim_for(signer)?;
+
+        self.context::<Paid>()
+            .ok_or_else(|| Error::Coins("No Paid context found".into()))?
+            .give::<S, _>(coins.amount)
+    }
+
+    /// Mints the claim amount for the given address, enforcing the cooldown
+    /// and the per-block claim cap.
+    fn claim_for(&mut self, address: Address) -> Result<Coin<S>> {
+        if self.claim_amount == 0 {
+            return Err(Error::Coins("Faucet claims are not enabled".into()));
+        }
+        let current_seconds = self.current_seconds()?;
+
+        if let Some(last_claim) = self.last_claims.get(address)? {
+            let elapsed = current_seconds.saturating_sub(*last_claim);
+            let cooldown = self.claim_cooldown_seconds as i64;
+            if elapsed < cooldown {
+                return Err(Error::Coins(format!(
+                    "Must wait {} seconds before claiming again",
+                    cooldown - elapsed
+                )));
+            }
+        }
+
+        if let Some(max_claims) = self.max_claims_per_block {
+            if self.claims_in_block >= max_claims {
+                return Err(Error::Coins(
+                    "Faucet claim limit reached for this block".into(),
+                ));
+            }
+        }
+
+        self.claims_in_block += 1;
+        self.last_claims.insert(address, current_seconds)?;
+
+        Ok(self.claim_amount.into())
+    }
+
+    /// Returns the signer's address.
+    fn signer(&mut self) -> Result<Address> {
+        self.context::<Signer>()
+            .ok_or_else(|| Error::Signer("No Signer context available".into()))?
+            .signer
+            .ok_or_else(|| Error::Coins("Unauthorized account action".into()))
+    }
+
     /// Retrieves the current time in seconds from the Time context.
     fn current_seconds(&mut self) -> Result<i64> {
         Ok(self
@@ -126,6 +269,16 @@ pub struct FaucetOptions {
     pub start_seconds: i64,
 }
 
+/// Options for configuring claims from a Faucet.
+pub struct ClaimOptions {
+    /// Amount of coins handed out per claim.
+    pub amount: Amount,
+    /// Minimum time between claims by the same address.
+    pub cooldown: Duration,
+    /// Maximum number of claims per block, if any.
+    pub max_claims_per_block: Option<u32>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,4 +378,78 @@ mod tests {
 
         Ok(())
     }
+
+    fn begin_block(faucet: &mut Faucet<Simp>, height: u64) -> Result<()> {
+        faucet.begin_block(&BeginBlockCtx {
+            hash: vec![],
+            height,
+            header: Default::default(),
+            last_commit_info: None,
+            byzantine_validators: vec![],
+        })
+    }
+
+    fn claim_as(faucet: &mut Faucet<Simp>, address: Address) -> Result<Amount> {
+        Context::add(Signer {
+            signer: Some(address),
+        });
+        Context::add(Paid::default());
+        faucet.claim()?;
+        let paid = Context::resolve::<Paid>().unwrap();
+        Ok(paid.take::<Simp, _>(faucet.claim_amount)?.amount)
+    }
+
+    #[test]
+    #[serial]
+    fn claim_cooldown() -> Result<()> {
+        let mut faucet: Faucet<Simp> = Faucet::default();
+        let alice = Address::from_pubkey([0; 33]);
+        let bob = Address::from_pubkey([1; 33]);
+        let carol = Address::from_pubkey([2; 33]);
+
+        Context::add(Time::from_seconds(100));
+        begin_block(&mut faucet, 1)?;
+        assert!(claim_as(&mut faucet, alice).is_err());
+
+        faucet.configure_claims(ClaimOptions {
+            amount: 10.into(),
+            cooldown: Duration::from_secs(60),
+            max_claims_per_block: Some(2),
+        });
+        assert_eq!(claim_as(&mut faucet, alice)?, 10);
+
+        // second immediate claim fails
+        let err = claim_as(&mut faucet, alice).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Coins Error: Must wait 60 seconds before claiming again"
+        );
+
+        // per-block cap
+        assert_eq!(claim_as(&mut faucet, bob)?, 10);
+        assert!(claim_as(&mut faucet, carol).is_err());
+
+        // the cap is per height, even if the block time is unchanged
+        begin_block(&mut faucet, 2)?;
+        assert_eq!(claim_as(&mut faucet, carol)?, 10);
+
+        Context::add(Time::from_seconds(130));
+        begin_block(&mut faucet, 3)?;
+        let err = claim_as(&mut faucet, alice).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Coins Error: Must wait 30 seconds before claiming again"
+        );
+
+        // claim after the cooldown succeeds
+        Context::add(Time::from_seconds(160));
+        begin_block(&mut faucet, 4)?;
+        assert_eq!(claim_as(&mut faucet, alice)?, 10);
+
+        // claims require a signer
+        Context::add(Signer { signer: None });
+        assert!(faucet.claim().is_err());
+
+        Ok(())
+    }
 }
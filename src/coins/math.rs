@@ -43,8 +43,11 @@ impl<T, U, E: std::error::Error> FromResidual<StdResult<U, E>> for MathResult<T>
 }
 
 impl<T> FromResidual<MathResult<!>> for Result<T> {
-    fn from_residual(_residual: MathResult<!>) -> Self {
-        Result::Err(Error::Unknown)
+    fn from_residual(residual: MathResult<!>) -> Self {
+        match residual {
+            MathResult::Err(err) => Result::Err(err),
+            _ => unreachable!(),
+        }
     }
 }
 
@@ -64,7 +67,7 @@ impl<T> Try for MathResult<T> {
     fn branch(self) -> ControlFlow<Self::Residual, Self::Output> {
         match self {
             MathResult::Ok(value) => ControlFlow::Continue(value),
-            MathResult::Err(_err) => ControlFlow::Break(MathResult::Err(Error::Unknown)),
+            MathResult::Err(err) => ControlFlow::Break(MathResult::Err(err)),
         }
     }
 
@@ -1,7 +1,7 @@
 //! Financial primitives.
 
 pub mod amount;
-use std::{fmt::Display, str::FromStr};
+use std::{borrow::Cow, fmt::Display, str::FromStr, sync::RwLock};
 
 pub use amount::*;
 
@@ -53,6 +53,44 @@ use crate::collections::Next;
 use ripemd::{Digest as _, Ripemd160};
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
+use thiserror::Error;
+
+/// The default bech32 human-readable prefix for addresses.
+pub const DEFAULT_ADDRESS_PREFIX: &str = "nomic";
+
+static ADDRESS_PREFIX: RwLock<Cow<'static, str>> =
+    RwLock::new(Cow::Borrowed(DEFAULT_ADDRESS_PREFIX));
+
+/// Returns the bech32 human-readable prefix used to display and parse
+/// addresses.
+pub fn address_prefix() -> String {
+    ADDRESS_PREFIX.read().unwrap().to_string()
+}
+
+/// Set the bech32 human-readable prefix used to display and parse addresses.
+///
+/// This is process-wide and should typically be set once at startup, before
+/// any addresses are displayed or parsed.
+pub fn set_address_prefix(prefix: impl Into<String>) {
+    *ADDRESS_PREFIX.write().unwrap() = Cow::Owned(prefix.into());
+}
+
+/// Errors which can occur when parsing an [Address] from a string.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    /// The string is not valid bech32 or does not contain 20 bytes of data.
+    #[error(transparent)]
+    Bech32(#[from] bech32::Error),
+    /// The string's human-readable prefix does not match the configured
+    /// prefix.
+    #[error("Invalid address prefix: expected {expected:?}, got {actual:?}")]
+    Prefix {
+        /// The configured prefix.
+        expected: String,
+        /// The prefix found in the parsed string.
+        actual: String,
+    },
+}
 
 /// 20-byte `ripemd160(sha256(pubkey))` address.
 #[orga(skip(Serialize, Deserialize))]
@@ -107,28 +145,51 @@ impl Address {
     pub fn is_null(&self) -> bool {
         *self == Self::NULL
     }
+
+    /// Compares two addresses in constant time, for use where an address is
+    /// derived from secret data. Unlike `==`, this does not return early at
+    /// the first differing byte (timing resistance is best-effort).
+    pub fn ct_eq(&self, other: &Address) -> bool {
+        let diff = self
+            .bytes
+            .iter()
+            .zip(other.bytes.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+
+        std::hint::black_box(diff) == 0
+    }
 }
 
 impl Display for Address {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        encode_to_fmt(f, "nomic", self.bytes.to_base32(), Variant::Bech32).unwrap()
+        encode_to_fmt(
+            f,
+            &address_prefix(),
+            self.bytes.to_base32(),
+            Variant::Bech32,
+        )
+        .unwrap()
     }
 }
 
 impl FromStr for Address {
-    type Err = bech32::Error;
+    type Err = AddressError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (hrp, data, variant) = bech32::decode(s)?;
-        if hrp != "nomic" {
-            return Err(bech32::Error::MissingSeparator);
+        let expected = address_prefix();
+        if hrp != expected {
+            return Err(AddressError::Prefix {
+                expected,
+                actual: hrp,
+            });
         }
         if variant != Variant::Bech32 {
-            return Err(bech32::Error::InvalidData(0));
+            return Err(bech32::Error::InvalidData(0).into());
         }
         let data: Vec<u8> = FromBase32::from_base32(&data)?;
 
         if data.len() != Address::LENGTH {
-            return Err(bech32::Error::InvalidData(1));
+            return Err(bech32::Error::InvalidData(1).into());
         }
         let mut bytes = [0u8; Address::LENGTH];
         bytes.copy_from_slice(&data);
@@ -200,7 +261,7 @@ pub struct VersionedAddress {
 
 impl Display for VersionedAddress {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        encode_to_fmt(f, "nomic", self.bytes.to_base32(), Variant::Bech32).unwrap()
+        Address::from(*self).fmt(f)
     }
 }
 
@@ -215,3 +276,57 @@ impl From<VersionedAddress> for Address {
         Address { bytes: addr.bytes }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn address_ct_eq() {
+        let a = Address::from([7; Address::LENGTH]);
+        let mut bytes = [7; Address::LENGTH];
+        bytes[Address::LENGTH - 1] = 8;
+        let b = Address::from(bytes);
+
+        assert!(a.ct_eq(&a));
+        assert!(a.ct_eq(&Address::from([7; Address::LENGTH])));
+        assert!(!a.ct_eq(&b));
+        assert!(!b.ct_eq(&a));
+        assert!(!a.ct_eq(&Address::NULL));
+        assert!(Address::NULL.ct_eq(&Address::NULL));
+    }
+
+    #[test]
+    #[serial]
+    fn address_default_prefix() {
+        let address = Address::from([7; Address::LENGTH]);
+        let string = address.to_string();
+        assert!(string.starts_with("nomic1"));
+        assert_eq!(string.parse::<Address>().unwrap(), address);
+    }
+
+    #[test]
+    #[serial]
+    fn address_custom_prefix() {
+        let address = Address::from([7; Address::LENGTH]);
+        let nomic_string = address.to_string();
+
+        set_address_prefix("cosmos");
+        let string = address.to_string();
+        assert!(string.starts_with("cosmos1"));
+        assert_eq!(string.parse::<Address>().unwrap(), address);
+        assert_eq!(VersionedAddress::from(address).to_string(), string);
+        assert_eq!(
+            nomic_string.parse::<Address>().unwrap_err(),
+            AddressError::Prefix {
+                expected: "cosmos".to_string(),
+                actual: "nomic".to_string(),
+            }
+        );
+
+        set_address_prefix(DEFAULT_ADDRESS_PREFIX);
+        assert_eq!(address.to_string(), nomic_string);
+        assert!(string.parse::<Address>().is_err());
+    }
+}
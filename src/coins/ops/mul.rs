@@ -7,12 +7,11 @@ impl Mul<Amount> for Amount {
     type Output = MathResult<Amount>;
 
     fn mul(self, other: Amount) -> Self::Output {
-        MathResult::Ok(
-            self.value
-                .checked_mul(other.value)
-                .ok_or(Error::Overflow)?
-                .into(),
-        )
+        self.value
+            .checked_mul(other.value)
+            .map(|value| value.into())
+            .ok_or(Error::Overflow)
+            .into()
     }
 }
 
@@ -1,7 +1,7 @@
 //! Asset distribution with minimal iteration.
 use super::{Amount, Balance, Coin, Decimal, Give, Symbol};
 use crate::collections::map::{ChildMut as MapChildMut, Ref as MapRef};
-use crate::collections::{Map, Next};
+use crate::collections::{Map, Next, Value};
 use crate::encoding::{Decode, Encode, Terminated};
 use crate::orga;
 use crate::state::State;
@@ -23,6 +23,10 @@ use std::ops::{Deref, DerefMut, Drop, RangeBounds};
 /// When a [ChildMut] drops, it will update its parent pool based on any
 /// changes.
 ///
+/// Slashing the pool via [Pool::slash] adjusts the pool's totals immediately,
+/// and is settled into each entry the next time it is accessed, so the cost
+/// of a slash does not depend on the number of entries.
+///
 /// [F1 Pool]: https://github.com/cosmos/cosmos-sdk/blob/main/docs/spec/fee_distribution/f1_fee_distr.pdf
 #[orga]
 pub struct Pool<K, V, S>
@@ -41,6 +45,27 @@ where
     #[state(skip)]
     drop_errored: bool,
     symbol: PhantomData<S>,
+    slashes: Map<u64, SlashRecord>,
+}
+
+/// A slash applied to a [Pool], retained so it can be settled lazily into
+/// each entry.
+#[orga]
+pub struct SlashRecord {
+    multiplier: Decimal,
+    liveness_fault: bool,
+    contributions: Decimal,
+    shares_issued: Decimal,
+    period_entries: Map<u8, Decimal>,
+}
+
+/// Trait for pool values whose holdings can be reduced by a slash.
+///
+/// This is called by the pool when settling a slash into an entry, the first
+/// time the entry is accessed after the slash.
+pub trait Slash {
+    /// Scale the value's holdings by `multiplier`, e.g. `0.95` for a 5% slash.
+    fn slash(&mut self, multiplier: Decimal, liveness_fault: bool) -> Result<()>;
 }
 
 impl<K, V, S> Balance<S, Decimal> for Pool<K, V, S>
@@ -64,6 +89,7 @@ where
     shares: Decimal,
     last_update_period_entry: Map<u8, Decimal>,
     inner: T,
+    slashes_settled: Value<u64>,
 }
 
 impl<T: State> Deref for Entry<T> {
@@ -97,16 +123,84 @@ where
 impl<K, V, S> Pool<K, V, S>
 where
     K: Encode + Decode + Terminated + Clone + Send + Sync + 'static,
-    V: State + Balance<S, Decimal> + Give<(u8, Amount)> + Default,
+    V: State + Balance<S, Decimal> + Give<(u8, Amount)> + Slash + Default,
     S: Symbol,
 {
     /// Mutably access an adjusted value in the pool. Changes will be propagated
     /// to the pool when the returned [ChildMut] drops.
     pub fn get_mut(&mut self, key: K) -> Result<ChildMut<K, V, S>> {
         self.assert_no_unhandled_drop_err()?;
+        let period_entries = self.settle_period()?;
+        let exists = self.map.contains_key(key.clone())?;
+        let slash_count = self.slash_count()?;
+
         let mut child = self.map.entry(key)?.or_default()?;
         let entry = child.get_mut();
 
+        if exists {
+            Self::settle_slashes(&self.slashes, entry)?;
+        } else if slash_count > 0 {
+            // new entries have nothing to slash
+            entry.slashes_settled.set(slash_count)?;
+        }
+
+        let initial_balance = entry.balance()?;
+
+        Self::adjust_entry(
+            self.contributions,
+            self.shares_issued,
+            period_entries,
+            entry,
+        )?;
+
+        Ok(ChildMut {
+            parent_num_tokens: &mut self.contributions,
+            parent_shares_issued: &mut self.shares_issued,
+            drop_errored: &mut self.drop_errored,
+            entry: child,
+            initial_balance,
+            _symbol: PhantomData,
+        })
+    }
+
+    /// Slash every entry in the pool by `multiplier`, e.g. `0.95` for a 5%
+    /// slash.
+    ///
+    /// The pool's total is reduced immediately. Each entry is settled the
+    /// next time it is accessed: rewards accrued before the slash are paid
+    /// out at its pre-slash balance, then [Slash::slash] is called on the
+    /// value.
+    pub fn slash(&mut self, multiplier: Decimal, liveness_fault: bool) -> Result<()> {
+        self.assert_no_unhandled_drop_err()?;
+        self.settle_period()?;
+
+        let mut period_entries = Map::new();
+        for entry in self.last_period_entry.iter()? {
+            let (denom_index, period_entry) = entry?;
+            period_entries.insert(*denom_index, *period_entry)?;
+        }
+
+        let index = self.slash_count()?;
+        self.slashes.insert(
+            index,
+            SlashRecord {
+                multiplier,
+                liveness_fault,
+                contributions: self.contributions,
+                shares_issued: self.shares_issued,
+                period_entries,
+            },
+        )?;
+
+        self.contributions = (self.contributions * multiplier)?;
+        self.shares_issued = (self.shares_issued * multiplier)?;
+
+        Ok(())
+    }
+
+    /// Moves the rewards given since the last access into the period entries,
+    /// returning the updated period entry for each rewarded denom.
+    fn settle_period(&mut self) -> Result<BTreeMap<u8, Decimal>> {
         let denoms: Vec<u8> = self
             .rewards_this_period
             .iter()?
@@ -119,8 +213,6 @@ where
             *reward = (*reward + *reward_this_period)?;
         }
 
-        let initial_balance = entry.balance()?;
-
         let mut period_entry_hashmap = BTreeMap::new();
         if self.shares_issued > 0 {
             for denom_index in denoms.iter() {
@@ -138,23 +230,50 @@ where
             *reward_this_period = 0.into();
         }
 
-        Self::adjust_entry(
-            self.contributions,
-            self.shares_issued,
-            period_entry_hashmap,
-            entry,
-        )?;
+        Ok(period_entry_hashmap)
+    }
 
-        Ok(ChildMut {
-            parent_num_tokens: &mut self.contributions,
-            parent_shares_issued: &mut self.shares_issued,
-            drop_errored: &mut self.drop_errored,
-            entry: child,
-            initial_balance,
-            _symbol: PhantomData,
+    /// The number of slashes applied to the pool.
+    fn slash_count(&self) -> Result<u64> {
+        Ok(match self.slashes.iter()?.next_back().transpose()? {
+            Some((index, _)) => *index + 1,
+            None => 0,
         })
     }
 
+    /// Applies the slashes the entry has not yet seen, in order, paying out
+    /// the rewards it accrued before each one.
+    fn settle_slashes(slashes: &Map<u64, SlashRecord>, entry: &mut Entry<V>) -> Result<()> {
+        let settled = match entry.slashes_settled.try_get()? {
+            Some(settled) => *settled,
+            None => 0,
+        };
+
+        for record in slashes.range(settled..)? {
+            let (index, record) = record?;
+
+            let mut period_entries = BTreeMap::new();
+            for period_entry in record.period_entries.iter()? {
+                let (denom_index, period_entry) = period_entry?;
+                period_entries.insert(*denom_index, *period_entry);
+            }
+            Self::adjust_entry(
+                record.contributions,
+                record.shares_issued,
+                period_entries,
+                entry,
+            )?;
+
+            entry.shares = (entry.shares * record.multiplier)?;
+            entry
+                .inner
+                .slash(record.multiplier, record.liveness_fault)?;
+            entry.slashes_settled.set(*index + 1)?;
+        }
+
+        Ok(())
+    }
+
     fn adjust_entry(
         contributions: Decimal,
         shares_issued: Decimal,
@@ -208,9 +327,13 @@ where
                 period_entry_hashmap.insert(*denom_index, updated_last_entry);
             }
         }
+        let exists = self.map.contains_key(key.clone())?;
         let entry = self.map.get_or_default(key)?;
         {
             let mut entry_mut = entry.borrow_mut();
+            if exists {
+                Self::settle_slashes(&self.slashes, &mut entry_mut)?;
+            }
             Self::adjust_entry(
                 self.contributions,
                 self.shares_issued,
@@ -231,7 +354,7 @@ impl<K, V, S> Pool<K, V, S>
 where
     S: Symbol,
     K: Encode + Decode + Terminated + Clone + Next + Send + Sync + 'static,
-    V: State + Balance<S, Decimal> + Give<(u8, Amount)> + Default,
+    V: State + Balance<S, Decimal> + Give<(u8, Amount)> + Slash + Default,
 {
     /// Iterate over a range of entries in the pool.
     pub fn range<B>(&self, bounds: B) -> Result<impl Iterator<Item = IterEntry<K, V, S>>>
@@ -447,6 +570,7 @@ mod tests {
     use crate::coins::{Address, Amount, Share};
     use crate::encoding::{Decode, Encode};
     use crate::orga;
+    use rust_decimal_macros::dec;
 
     #[orga]
     #[derive(Clone, Debug)]
@@ -521,6 +645,13 @@ mod tests {
         }
     }
 
+    impl Slash for SimpAccount {
+        fn slash(&mut self, multiplier: Decimal, _liveness_fault: bool) -> Result<()> {
+            self.locked = (self.locked * multiplier)?;
+            Ok(())
+        }
+    }
+
     #[test]
     fn dividends() -> Result<()> {
         let mut pool: Pool<Address, SimpAccount, Simp> = Default::default();
@@ -548,6 +679,45 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn slash() -> Result<()> {
+        let mut pool: Pool<Address, SimpAccount, Simp> = Default::default();
+
+        let alice = Address::from_pubkey([0; 33]);
+        let bob = Address::from_pubkey([1; 33]);
+        let carol = Address::from_pubkey([2; 33]);
+
+        pool.get_mut(alice)?.deposit_locked(40)?;
+        pool.get_mut(bob)?.deposit_locked(160)?;
+        pool.give(Simp::mint(100))?;
+
+        let half: Decimal = dec!(0.5).into();
+        pool.slash(half, false)?;
+        assert_eq!(pool.balance()?, 100);
+
+        // rewards given before the slash are paid at the pre-slash balance
+        assert_eq!(pool.get(alice)?.balance()?, 20);
+        assert_eq!(pool.get(alice)?.liquid, 20);
+        assert_eq!(pool.get_mut(bob)?.balance()?, 80);
+        assert_eq!(pool.get_mut(bob)?.liquid, 80);
+
+        // new entries are not affected by earlier slashes
+        pool.get_mut(carol)?.deposit_locked(100)?;
+        pool.slash(half, false)?;
+        pool.give(Simp::mint(100))?;
+        assert_eq!(pool.balance()?, 100);
+
+        assert_eq!(pool.get_mut(alice)?.balance()?, 10);
+        assert_eq!(pool.get_mut(alice)?.liquid, 30);
+        assert_eq!(pool.get_mut(bob)?.balance()?, 40);
+        assert_eq!(pool.get_mut(bob)?.liquid, 120);
+        assert_eq!(pool.get_mut(carol)?.balance()?, 50);
+        assert_eq!(pool.get_mut(carol)?.liquid, 50);
+        assert_eq!(pool.balance()?, 100);
+
+        Ok(())
+    }
+
     #[test]
     fn emptied_pool() -> Result<()> {
         use crate::coins::Take;
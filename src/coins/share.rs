@@ -1,5 +1,5 @@
 //! Tokens with decimal amounts.
-use super::{Amount, Balance, Coin, Decimal, Give, Symbol, Take};
+use super::{Amount, Balance, Coin, Decimal, Give, Slash, Symbol, Take};
 use crate::orga;
 use crate::{Error, Result};
 use std::marker::PhantomData;
@@ -63,6 +63,14 @@ impl<S: Symbol> Give<Coin<S>> for Share<S> {
     }
 }
 
+impl<S: Symbol> Slash for Share<S> {
+    fn slash(&mut self, multiplier: Decimal, _liveness_fault: bool) -> Result<()> {
+        self.shares = (self.shares * multiplier)?;
+
+        Ok(())
+    }
+}
+
 impl<S: Symbol> From<Decimal> for Share<S> {
     fn from(amount: Decimal) -> Self {
         Self {
@@ -1,4 +1,4 @@
-use crate::coins::{Amount, Balance, Coin, Decimal, Give, Share, Symbol, Take};
+use crate::coins::{Amount, Balance, Coin, Decimal, Give, Share, Slash, Symbol, Take};
 use crate::coins::{MultiShare, VersionedAddress as Address};
 use crate::collections::Deque;
 use crate::context::GetContext;
@@ -66,6 +66,71 @@ impl<S: Symbol> Delegator<S> {
         }
     }
 
+    /// Cancel part or all of the unbonds which started at `start_seconds`,
+    /// returning the coins to the delegator's stake. Unbonds are reduced in
+    /// queue order, and any which are fully canceled are removed.
+    ///
+    /// Returns `true` if any unbonds with the given start time remain.
+    pub(super) fn cancel_unbond<A: Into<Amount>>(
+        &mut self,
+        amount: A,
+        start_seconds: i64,
+    ) -> Result<bool> {
+        let amount = amount.into();
+        let mut unbonding: Amount = 0.into();
+        for i in 0..self.unbonding.len() {
+            let unbond = self
+                .unbonding
+                .get(i)?
+                .ok_or_else(|| Error::Coins("Failed to iterate over unbonds".into()))?;
+            if unbond.start_seconds == start_seconds {
+                unbonding = (unbonding + unbond.coins.amount()?)?;
+            }
+        }
+
+        if unbonding == 0 {
+            return Err(Error::Coins("No unbond found with given start time".into()));
+        }
+        if amount > unbonding {
+            return Err(Error::Coins(
+                "Cannot cancel more than the unbonding amount".into(),
+            ));
+        }
+
+        let mut remaining = amount;
+        let mut canceled: Decimal = 0.into();
+        for i in 0..self.unbonding.len() {
+            if remaining == 0 {
+                break;
+            }
+            let mut unbond = self
+                .unbonding
+                .get_mut(i)?
+                .ok_or_else(|| Error::Coins("Failed to iterate over unbonds".into()))?;
+            if unbond.start_seconds != start_seconds {
+                continue;
+            }
+
+            let unbond_amount = unbond.coins.amount()?;
+            if remaining >= unbond_amount {
+                // take all shares so no fractional dust is left behind
+                canceled = (canceled + unbond.coins.shares)?;
+                unbond.coins.shares = 0.into();
+                remaining = (remaining - unbond_amount)?;
+            } else {
+                canceled = (canceled + remaining)?;
+                unbond.coins.shares = (unbond.coins.shares - remaining)?;
+                remaining = 0.into();
+            }
+        }
+
+        self.unbonding
+            .retain(|unbond| Ok(unbond.coins.shares > 0))?;
+        self.staked.shares = (self.staked.shares + canceled)?;
+
+        Ok(amount < unbonding)
+    }
+
     /// Build a summary of the delegator's staking state.
     pub(super) fn info(&self) -> Result<DelegationInfo> {
         let mut unbonds = vec![];
@@ -85,41 +150,6 @@ impl<S: Symbol> Delegator<S> {
         })
     }
 
-    /// Slash the stake of this delegator by the given multiplier, and return
-    /// any redelegations also subject to the slash.
-    ///
-    /// If the slash is due to a liveness fault, outbound
-    /// redelegations are not affected.
-    pub(super) fn slash(
-        &mut self,
-        multiplier: Decimal,
-        liveness_fault: bool,
-    ) -> Result<Vec<Redelegation>> {
-        self.staked.shares = (self.staked.shares * multiplier)?;
-        if liveness_fault {
-            return Ok(vec![]);
-        }
-        for i in 0..self.unbonding.len() {
-            let mut unbond = self
-                .unbonding
-                .get_mut(i)?
-                .ok_or_else(|| Error::Coins("Failed to iterate over unbonds".into()))?;
-
-            unbond.coins.shares = (unbond.coins.shares * multiplier)?;
-        }
-
-        let mut redelegations = vec![];
-        for i in 0..self.redelegations_out.len() {
-            let redelegation = self
-                .redelegations_out
-                .get(i)?
-                .ok_or_else(|| Error::Coins("Failed to iterate over redelegations".into()))?;
-            redelegations.push(redelegation.clone());
-        }
-
-        Ok(redelegations)
-    }
-
     /// Slash a redelation by the given amount.
     pub(super) fn slash_redelegation(&mut self, amount: Amount) -> Result<()> {
         let stake_slash = if amount > self.staked.shares.amount()? {
@@ -160,9 +190,11 @@ impl<S: Symbol> Delegator<S> {
         Ok(())
     }
 
-    /// Process matured unbonds.
-    pub(super) fn process_unbonds(&mut self) -> Result<()> {
+    /// Process matured unbonds, returning the amount of principal which moved
+    /// into the liquid balance.
+    pub(super) fn process_unbonds(&mut self) -> Result<Amount> {
         let now = self.current_seconds()?;
+        let mut matured: Amount = 0.into();
 
         while let Some(unbond) = self.unbonding.front()? {
             let unbond_matured = now - unbond.start_seconds >= UNBONDING_SECONDS as i64;
@@ -171,13 +203,15 @@ impl<S: Symbol> Delegator<S> {
                     .unbonding
                     .pop_front()?
                     .ok_or_else(|| Error::Coins("Failed to pop unbond".into()))?;
-                self.liquid.give(S::mint(unbond.coins.shares.amount()?))?;
+                let amount = unbond.coins.shares.amount()?;
+                self.liquid.give(S::mint(amount))?;
+                matured = (matured + amount)?;
             } else {
                 break;
             }
         }
 
-        Ok(())
+        Ok(matured)
     }
 
     /// Process matured redelegations with this DVP as their destination.
@@ -284,6 +318,29 @@ impl<S: Symbol> Balance<S, Decimal> for Delegator<S> {
     }
 }
 
+/// Slashes the delegator's staked coins by the given multiplier. If the slash
+/// is not due to a liveness fault, pending unbonds are also slashed.
+///
+/// Outbound redelegations are slashed separately by the staking module.
+impl<S: Symbol> Slash for Delegator<S> {
+    fn slash(&mut self, multiplier: Decimal, liveness_fault: bool) -> Result<()> {
+        self.staked.shares = (self.staked.shares * multiplier)?;
+        if liveness_fault {
+            return Ok(());
+        }
+        for i in 0..self.unbonding.len() {
+            let mut unbond = self
+                .unbonding
+                .get_mut(i)?
+                .ok_or_else(|| Error::Coins("Failed to iterate over unbonds".into()))?;
+
+            unbond.coins.shares = (unbond.coins.shares * multiplier)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl<S: Symbol> Give<(u8, Amount)> for Delegator<S> {
     fn give(&mut self, coins: (u8, Amount)) -> Result<()> {
         self.liquid.give(coins)
@@ -17,28 +17,29 @@
 //! - [F1 Pool]
 //! - [x/staking](https://github.com/cosmos/cosmos-sdk/blob/main/x/staking/README.md)
 //!
-//! Current limitations:
-//! - Slashing currently iterates over all delegations.
-//! - Redelegation to inactive validators is not supported.
+//! Slashes are also settled lazily: slashing a validator records the slash in
+//! its [Pool] of delegators, and each delegation is slashed the next time it is
+//! accessed.
 //!
 //! [F1 Pool]: https://github.com/cosmos/cosmos-sdk/blob/main/docs/spec/fee_distribution/f1_fee_distr.pdf
 
 use super::pool::{Child as PoolChild, ChildMut as PoolChildMut};
 use super::{Address, Amount, Balance, Coin, Decimal, Give, Pool, Symbol, VersionedAddress};
 use crate::abci::{BeginBlock, EndBlock};
-use crate::collections::{Deque, Entry, EntryMap, Map};
+use crate::collections::{Deque, Entry, EntryMap, Map, Set};
 use crate::context::GetContext;
 use crate::encoding::{Decode, Encode};
 use crate::migrate::{Migrate, MigrateFrom};
 use crate::orga;
 use crate::plugins::{BeginBlockCtx, EndBlockCtx, Events, Validators};
-use crate::plugins::{Paid, Signer, Time};
+use crate::plugins::{ChainId, Paid, Signer, Time};
 use crate::state::State;
 use crate::{Error, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
 use std::convert::TryInto;
-use tendermint_proto::v0_34::abci::{Event, EventAttribute, EvidenceType};
+use tendermint_proto::v0_34::abci::EvidenceType;
 
 /// Delegator entries within a validator.
 mod delegator;
@@ -60,7 +61,13 @@ pub const UNBONDING_SECONDS: u64 = 60 * 60 * 24 * 14; // 2 weeks
 const EDIT_INTERVAL_SECONDS: u64 = 60 * 60 * 24; // 1 day
 
 /// A vanilla Cosmos-style staking module.
-#[orga(version = 1)]
+///
+/// Coins of the symbol `S` are the bond denom: they are the only coins which
+/// can be delegated, and determine voting power. Coins of any symbol (including
+/// `S`) given to the module are paid out to delegators as rewards, accruing in
+/// their liquid balances. Non-bond reward denoms are tracked in a registry,
+/// see [Staking::reward_denoms].
+#[orga(version = 8)]
 pub struct Staking<S: Symbol> {
     /// Validators indexed by operator address.
     validators: Pool<Address, Validator<S>, S>,
@@ -99,7 +106,41 @@ pub struct Staking<S: Symbol> {
     redelegation_queue: Deque<RedelegationEntry>,
     /// Index of which validators a delegator has delegated to for faster
     /// iteration.
-    delegation_index: Map<Address, Map<Address, ()>>,
+    delegation_index: Map<Address, Set<Address>>,
+    /// Maximum number of matured queue entries processed per block, across
+    /// all queues. Entries beyond the cap are deferred to subsequent blocks in
+    /// queue order. Zero means no cap.
+    #[orga(version(V2, V3, V4, V5, V6, V7, V8))]
+    pub max_queue_ops_per_block: u64,
+    /// Denoms other than the bond denom which have been paid as rewards.
+    #[orga(version(V3, V4, V5, V6, V7, V8))]
+    reward_denoms: Map<u8, ()>,
+    /// Minimum commission rate for declared and edited validators.
+    #[orga(version(V4, V5, V6, V7, V8))]
+    pub min_commission_rate: Decimal,
+    /// Addresses which receive the claimed rewards of delegators, for
+    /// delegators which have set an address other than their own.
+    #[orga(version(V5, V6, V7, V8))]
+    withdraw_addresses: Map<Address, Address>,
+    /// Rewards claimed on behalf of withdrawal addresses, paid out when the
+    /// withdrawal address claims.
+    #[orga(version(V5, V6, V7, V8))]
+    pending_withdrawals: Map<Address, Map<u8, Amount>>,
+    /// Whether declarations must include a proof that the declarer controls
+    /// the consensus key, see [Staking::declare_self_with_proof].
+    #[orga(version(V6, V7, V8))]
+    #[migrate(skip)]
+    pub require_consensus_key_proof: bool,
+    /// Delegator addresses of pending redelegations, indexed by source
+    /// validator address, in redelegation queue order.
+    #[orga(version(V7, V8))]
+    redelegations_by_src: Map<Address, Deque<VersionedAddress>>,
+    /// Unbonded principal which has matured into delegators' liquid balances,
+    /// indexed by validator address then delegator address. Principal is
+    /// always paid to the delegator, only rewards go to its withdrawal
+    /// address.
+    #[orga(version(V8))]
+    unbonded_principal: Map<Address, Map<Address, Amount>>,
 }
 
 impl<S: Symbol> MigrateFrom<StakingV0<S>> for StakingV1<S> {
@@ -108,6 +149,191 @@ impl<S: Symbol> MigrateFrom<StakingV0<S>> for StakingV1<S> {
     }
 }
 
+impl<S: Symbol> MigrateFrom<StakingV1<S>> for StakingV2<S> {
+    fn migrate_from(value: StakingV1<S>) -> Result<Self> {
+        Ok(Self {
+            validators: value.validators,
+            min_self_delegation_min: value.min_self_delegation_min,
+            consensus_keys: value.consensus_keys,
+            last_signed_block: value.last_signed_block,
+            validators_by_power: value.validators_by_power,
+            last_validator_powers: value.last_validator_powers,
+            max_validators: value.max_validators,
+            last_indexed_power: value.last_indexed_power,
+            address_for_tm_hash: value.address_for_tm_hash,
+            unbonding_seconds: value.unbonding_seconds,
+            max_offline_blocks: value.max_offline_blocks,
+            slash_fraction_double_sign: value.slash_fraction_double_sign,
+            slash_fraction_downtime: value.slash_fraction_downtime,
+            downtime_jail_seconds: value.downtime_jail_seconds,
+            validator_queue: value.validator_queue,
+            unbonding_delegation_queue: value.unbonding_delegation_queue,
+            redelegation_queue: value.redelegation_queue,
+            delegation_index: value.delegation_index,
+            max_queue_ops_per_block: 0,
+        })
+    }
+}
+
+impl<S: Symbol> MigrateFrom<StakingV2<S>> for StakingV3<S> {
+    fn migrate_from(value: StakingV2<S>) -> Result<Self> {
+        Ok(Self {
+            validators: value.validators,
+            min_self_delegation_min: value.min_self_delegation_min,
+            consensus_keys: value.consensus_keys,
+            last_signed_block: value.last_signed_block,
+            validators_by_power: value.validators_by_power,
+            last_validator_powers: value.last_validator_powers,
+            max_validators: value.max_validators,
+            last_indexed_power: value.last_indexed_power,
+            address_for_tm_hash: value.address_for_tm_hash,
+            unbonding_seconds: value.unbonding_seconds,
+            max_offline_blocks: value.max_offline_blocks,
+            slash_fraction_double_sign: value.slash_fraction_double_sign,
+            slash_fraction_downtime: value.slash_fraction_downtime,
+            downtime_jail_seconds: value.downtime_jail_seconds,
+            validator_queue: value.validator_queue,
+            unbonding_delegation_queue: value.unbonding_delegation_queue,
+            redelegation_queue: value.redelegation_queue,
+            delegation_index: value.delegation_index,
+            max_queue_ops_per_block: value.max_queue_ops_per_block,
+            reward_denoms: Map::new(),
+        })
+    }
+}
+
+impl<S: Symbol> MigrateFrom<StakingV3<S>> for StakingV4<S> {
+    fn migrate_from(value: StakingV3<S>) -> Result<Self> {
+        Ok(Self {
+            validators: value.validators,
+            min_self_delegation_min: value.min_self_delegation_min,
+            consensus_keys: value.consensus_keys,
+            last_signed_block: value.last_signed_block,
+            validators_by_power: value.validators_by_power,
+            last_validator_powers: value.last_validator_powers,
+            max_validators: value.max_validators,
+            last_indexed_power: value.last_indexed_power,
+            address_for_tm_hash: value.address_for_tm_hash,
+            unbonding_seconds: value.unbonding_seconds,
+            max_offline_blocks: value.max_offline_blocks,
+            slash_fraction_double_sign: value.slash_fraction_double_sign,
+            slash_fraction_downtime: value.slash_fraction_downtime,
+            downtime_jail_seconds: value.downtime_jail_seconds,
+            validator_queue: value.validator_queue,
+            unbonding_delegation_queue: value.unbonding_delegation_queue,
+            redelegation_queue: value.redelegation_queue,
+            delegation_index: value.delegation_index,
+            max_queue_ops_per_block: value.max_queue_ops_per_block,
+            reward_denoms: value.reward_denoms,
+            min_commission_rate: Decimal::zero(),
+        })
+    }
+}
+
+impl<S: Symbol> MigrateFrom<StakingV4<S>> for StakingV5<S> {
+    fn migrate_from(value: StakingV4<S>) -> Result<Self> {
+        Ok(Self {
+            validators: value.validators,
+            min_self_delegation_min: value.min_self_delegation_min,
+            consensus_keys: value.consensus_keys,
+            last_signed_block: value.last_signed_block,
+            validators_by_power: value.validators_by_power,
+            last_validator_powers: value.last_validator_powers,
+            max_validators: value.max_validators,
+            last_indexed_power: value.last_indexed_power,
+            address_for_tm_hash: value.address_for_tm_hash,
+            unbonding_seconds: value.unbonding_seconds,
+            max_offline_blocks: value.max_offline_blocks,
+            slash_fraction_double_sign: value.slash_fraction_double_sign,
+            slash_fraction_downtime: value.slash_fraction_downtime,
+            downtime_jail_seconds: value.downtime_jail_seconds,
+            validator_queue: value.validator_queue,
+            unbonding_delegation_queue: value.unbonding_delegation_queue,
+            redelegation_queue: value.redelegation_queue,
+            delegation_index: value.delegation_index,
+            max_queue_ops_per_block: value.max_queue_ops_per_block,
+            reward_denoms: value.reward_denoms,
+            min_commission_rate: value.min_commission_rate,
+            withdraw_addresses: Map::new(),
+            pending_withdrawals: Map::new(),
+        })
+    }
+}
+
+impl<S: Symbol> MigrateFrom<StakingV6<S>> for StakingV7<S> {
+    fn migrate_from(value: StakingV6<S>) -> Result<Self> {
+        let mut redelegations_by_src: Map<Address, Deque<VersionedAddress>> = Map::new();
+        for entry in value.redelegation_queue.iter()? {
+            let entry = entry?;
+            redelegations_by_src
+                .entry(entry.src_validator_address.into())?
+                .or_insert_default()?
+                .push_back(entry.delegator_address)?;
+        }
+
+        Ok(Self {
+            validators: value.validators,
+            min_self_delegation_min: value.min_self_delegation_min,
+            consensus_keys: value.consensus_keys,
+            last_signed_block: value.last_signed_block,
+            validators_by_power: value.validators_by_power,
+            last_validator_powers: value.last_validator_powers,
+            max_validators: value.max_validators,
+            last_indexed_power: value.last_indexed_power,
+            address_for_tm_hash: value.address_for_tm_hash,
+            unbonding_seconds: value.unbonding_seconds,
+            max_offline_blocks: value.max_offline_blocks,
+            slash_fraction_double_sign: value.slash_fraction_double_sign,
+            slash_fraction_downtime: value.slash_fraction_downtime,
+            downtime_jail_seconds: value.downtime_jail_seconds,
+            validator_queue: value.validator_queue,
+            unbonding_delegation_queue: value.unbonding_delegation_queue,
+            redelegation_queue: value.redelegation_queue,
+            delegation_index: value.delegation_index,
+            max_queue_ops_per_block: value.max_queue_ops_per_block,
+            reward_denoms: value.reward_denoms,
+            min_commission_rate: value.min_commission_rate,
+            withdraw_addresses: value.withdraw_addresses,
+            pending_withdrawals: value.pending_withdrawals,
+            require_consensus_key_proof: value.require_consensus_key_proof,
+            redelegations_by_src,
+        })
+    }
+}
+
+impl<S: Symbol> MigrateFrom<StakingV7<S>> for StakingV8<S> {
+    fn migrate_from(value: StakingV7<S>) -> Result<Self> {
+        Ok(Self {
+            validators: value.validators,
+            min_self_delegation_min: value.min_self_delegation_min,
+            consensus_keys: value.consensus_keys,
+            last_signed_block: value.last_signed_block,
+            validators_by_power: value.validators_by_power,
+            last_validator_powers: value.last_validator_powers,
+            max_validators: value.max_validators,
+            last_indexed_power: value.last_indexed_power,
+            address_for_tm_hash: value.address_for_tm_hash,
+            unbonding_seconds: value.unbonding_seconds,
+            max_offline_blocks: value.max_offline_blocks,
+            slash_fraction_double_sign: value.slash_fraction_double_sign,
+            slash_fraction_downtime: value.slash_fraction_downtime,
+            downtime_jail_seconds: value.downtime_jail_seconds,
+            validator_queue: value.validator_queue,
+            unbonding_delegation_queue: value.unbonding_delegation_queue,
+            redelegation_queue: value.redelegation_queue,
+            delegation_index: value.delegation_index,
+            max_queue_ops_per_block: value.max_queue_ops_per_block,
+            reward_denoms: value.reward_denoms,
+            min_commission_rate: value.min_commission_rate,
+            withdraw_addresses: value.withdraw_addresses,
+            pending_withdrawals: value.pending_withdrawals,
+            require_consensus_key_proof: value.require_consensus_key_proof,
+            redelegations_by_src: value.redelegations_by_src,
+            unbonded_principal: Map::new(),
+        })
+    }
+}
+
 /// An entry in the validator queue, used to track progress toward a validator
 /// status change.
 #[derive(Entry, Clone, Serialize, Deserialize, State, Migrate)]
@@ -161,12 +387,19 @@ pub struct RedelegationEntry {
     start_seconds: i64,
 }
 
+/// An entry of the validator power index.
+///
+/// Entries are iterated in the order of their encoded keys, i.e. by descending
+/// power, then by ascending address bytes among validators with equal power.
+/// This order determines which validators make up the active set, so it must
+/// not depend on anything other than the key (e.g. insertion order).
 #[derive(Entry, State, Migrate)]
 struct ValidatorPowerEntry {
     /// `u64::MAX - power`, to allow for descending order iteration.
     #[key]
     inverted_power: u64,
-    /// Validator operator address bytes.
+    /// Validator operator address bytes, used to break ties between validators
+    /// with equal power.
     #[key]
     address_bytes: [u8; 20],
 }
@@ -268,6 +501,10 @@ impl<S: Symbol> BeginBlock for Staking<S> {
 #[orga]
 impl<S: Symbol> Staking<S> {
     /// Initiate a new delegation.
+    ///
+    /// Any rewards accrued by an existing delegation are settled into the
+    /// delegator's liquid balance before the new stake is added, so they are
+    /// not shared with the newly-delegated coins.
     pub fn delegate(
         &mut self,
         val_address: Address,
@@ -282,8 +519,9 @@ impl<S: Symbol> Staking<S> {
                     "Cannot delegate to a tombstoned validator".into(),
                 ));
             }
-            let mut delegator = validator.get_mut(delegator_address)?;
-            delegator.add_stake(coins)?;
+            // getting the entry from the pool settles pending rewards before
+            // the stake changes
+            validator.with_delegator(delegator_address, |delegator| delegator.add_stake(coins))?;
         }
         self.index_delegation(val_address, delegator_address)?;
         self.update_vp(val_address)
@@ -294,7 +532,7 @@ impl<S: Symbol> Staking<S> {
         self.delegation_index
             .entry(delegator_address)?
             .or_insert_default()?
-            .insert(val_address, ())
+            .insert(val_address)
     }
 
     /// Query a consensus key by validator operator address.
@@ -308,6 +546,17 @@ impl<S: Symbol> Staking<S> {
         Ok(consensus_key)
     }
 
+    /// Query the denoms other than the bond denom which have been paid as
+    /// rewards, in ascending order. These accrue in delegators' liquid
+    /// balances and can be claimed, but can not be delegated.
+    #[query]
+    pub fn reward_denoms(&self) -> Result<Vec<u8>> {
+        self.reward_denoms
+            .iter()?
+            .map(|entry| Ok(*entry?.0))
+            .collect()
+    }
+
     /// Query all consensus keys.
     #[query]
     pub fn consensus_keys(&self) -> Result<Vec<(Address, [u8; 32])>> {
@@ -357,6 +606,21 @@ impl<S: Symbol> Staking<S> {
         val_address: Address,
         declaration: Declaration,
         coins: Coin<S>,
+    ) -> Result<()> {
+        self.declare_with_proof(val_address, declaration, None, coins)
+    }
+
+    /// Like [Staking::declare], but with an optional signature by the
+    /// consensus key over [consensus_key_proof_bytes], proving the declarer
+    /// controls the key. The proof is required if
+    /// [Staking::require_consensus_key_proof] is set, and verified whenever it
+    /// is provided.
+    pub fn declare_with_proof(
+        &mut self,
+        val_address: Address,
+        declaration: Declaration,
+        consensus_key_proof: Option<[u8; 64]>,
+        coins: Coin<S>,
     ) -> Result<()> {
         let Declaration {
             min_self_delegation,
@@ -369,6 +633,16 @@ impl<S: Symbol> Staking<S> {
         if declared {
             return Err(Error::Coins("Validator is already declared".into()));
         }
+        match consensus_key_proof {
+            Some(proof) => {
+                let chain_id = self.chain_id()?;
+                verify_consensus_key_proof(consensus_key, &chain_id, val_address, proof)?
+            }
+            None if self.require_consensus_key_proof => {
+                return Err(Error::Coins("Missing consensus key proof".into()));
+            }
+            None => {}
+        }
         if coins.amount < min_self_delegation {
             return Err(Error::Coins("Insufficient self-delegation".into()));
         }
@@ -402,6 +676,11 @@ impl<S: Symbol> Staking<S> {
                 "Min self-delegation setting is too small".into(),
             ));
         }
+        if commission.rate < self.min_commission_rate {
+            return Err(Error::Coins(
+                "Commission is below the minimum commission rate".into(),
+            ));
+        }
 
         self.consensus_keys.insert(val_address, consensus_key)?;
 
@@ -448,7 +727,21 @@ impl<S: Symbol> Staking<S> {
             ));
         }
 
-        if commission < Decimal::zero() || commission > validator.commission.max {
+        // validators left below the minimum commission rate when it was
+        // raised are forced up to it, regardless of their commission settings
+        let forced = validator.commission.rate < self.min_commission_rate
+            && commission <= self.min_commission_rate;
+        let commission = if forced {
+            self.min_commission_rate
+        } else if commission < self.min_commission_rate {
+            return Err(Error::Coins(
+                "Commission is below the minimum commission rate".into(),
+            ));
+        } else {
+            commission
+        };
+
+        if commission < Decimal::zero() || (commission > validator.commission.max && !forced) {
             return Err(Error::Coins(
                 "Commission must be between 0 and max commission".into(),
             ));
@@ -457,7 +750,7 @@ impl<S: Symbol> Staking<S> {
         validate_info(&validator_info)?;
 
         let change = (commission - validator.commission.rate)?.abs();
-        if change > validator.commission.max_change {
+        if change > validator.commission.max_change && !forced {
             return Err(Error::Coins(
                 "Commission change is greater than the validator's commission max change setting"
                     .into(),
@@ -469,6 +762,9 @@ impl<S: Symbol> Staking<S> {
             ));
         }
         validator.commission.rate = commission;
+        if commission > validator.commission.max {
+            validator.commission.max = commission;
+        }
         validator.info = validator_info;
         validator.min_self_delegation = min_self_delegation;
 
@@ -495,21 +791,53 @@ impl<S: Symbol> Staking<S> {
     /// Slash a validator for double signing, preventing them from re-entering
     /// the active validator set indefinitely.
     fn punish_double_sign(&mut self, val_address: Address) -> Result<()> {
-        let redelegations = {
+        let slashed = {
             let mut validator = self.validators.get_mut(val_address)?;
             validator.jail_forever();
             validator.slash(self.slash_fraction_double_sign, false)?
         };
+        if slashed {
+            self.slash_redelegations(val_address)?;
+        }
+        self.update_vp(val_address)
+    }
+
+    /// Slash the pending outbound redelegations from the given validator by
+    /// the double sign slash fraction, taking the slashed amount from each
+    /// delegator's stake at the destination validator.
+    ///
+    /// Delegators with pending redelegations are found via the index of
+    /// redelegations by source validator, so this does not iterate over all of
+    /// the validator's delegators or over unrelated redelegations.
+    fn slash_redelegations(&mut self, val_address: Address) -> Result<()> {
+        let mut delegators = BTreeSet::new();
+        if let Some(pending) = self.redelegations_by_src.get(val_address)? {
+            for delegator_address in pending.iter()? {
+                delegators.insert(Address::from(*delegator_address?));
+            }
+        }
+
         let multiplier = (Decimal::one() - self.slash_fraction_double_sign)?;
-        for entry in redelegations.iter() {
-            let del_address = entry.delegator_address;
-            for redelegation in entry.outbound_redelegations.iter() {
+        for del_address in delegators {
+            let redelegations = {
+                let validator = self.validators.get(val_address)?;
+                let delegator = validator.get(del_address)?;
+                delegator
+                    .redelegations_out
+                    .iter()?
+                    .map(|redelegation| Ok(redelegation?.clone()))
+                    .collect::<Result<Vec<_>>>()?
+            };
+            for redelegation in redelegations {
+                let amount = (multiplier * redelegation.amount)?.amount()?;
                 let mut validator = self.validators.get_mut(redelegation.address.into())?;
-                let mut delegator = validator.get_mut(del_address.into())?;
-                delegator.slash_redelegation((multiplier * redelegation.amount)?.amount()?)?;
+                validator.with_delegator(del_address, |delegator| {
+                    delegator.slash_redelegation(amount)
+                })?;
             }
         }
-        self.update_vp(val_address)
+
+        Ok(())
     }
 
     /// Slash a validator for a light client attack, with the same punishment as
@@ -530,11 +858,15 @@ impl<S: Symbol> Staking<S> {
         let amount = amount.into();
         let mut validator = self.validators.get_mut(val_address)?;
         let mut delegator = validator.get_mut(delegator_address)?;
-        delegator.process_unbonds()?;
-
-        delegator.deduct(amount, denom)?;
-
-        Ok(())
+        let matured = delegator.process_unbonds()?;
+        add_unbonded_principal(
+            &mut self.unbonded_principal,
+            val_address,
+            delegator_address,
+            matured,
+        )?;
+
+        delegator.deduct(amount, denom)
     }
 
     /// Initiate an unbond of staking tokens. The unbond's start time will be:
@@ -543,14 +875,17 @@ impl<S: Symbol> Staking<S> {
     /// - The time the validator started unbonding if they're already unbonding.
     /// - `None` if the validator is fully unbonded, resolving the unbond
     ///   immediately.
+    ///
+    /// Outstanding rewards are settled into the delegator's liquid balance
+    /// before the stake is reduced.
     pub fn unbond<A: Into<Amount>>(
         &mut self,
         validator_address: Address,
         delegator_address: Address,
         amount: A,
     ) -> Result<()> {
+        let amount: Amount = amount.into();
         let start_seconds = {
-            let amount = amount.into();
             let now = self.current_seconds()?;
             let mut validator = self.validators.get_mut(validator_address)?;
             let start_seconds = match validator.status() {
@@ -558,11 +893,17 @@ impl<S: Symbol> Staking<S> {
                 Status::Unbonding { start_seconds } => Some(start_seconds),
                 Status::Unbonded => None,
             };
-            let mut delegator = validator.get_mut(delegator_address)?;
-
-            delegator.unbond(amount, start_seconds)?;
-
-            start_seconds
+            validator.with_delegator(delegator_address, |delegator| {
+                // coins redelegated in remain subject to the source validator's
+                // unbonding period, even if this validator is not bonded
+                let start_seconds = match delegator.redelegations_in.back()? {
+                    Some(redelegation) => start_seconds.max(Some(redelegation.start_seconds)),
+                    None => start_seconds,
+                };
+                delegator.unbond(amount, start_seconds)?;
+
+                Ok(start_seconds)
+            })?
         };
 
         if let Some(start_seconds) = start_seconds {
@@ -572,12 +913,72 @@ impl<S: Symbol> Staking<S> {
                     validator_address: validator_address.into(),
                     start_seconds,
                 })?;
+        } else {
+            add_unbonded_principal(
+                &mut self.unbonded_principal,
+                validator_address,
+                delegator_address,
+                amount,
+            )?;
         }
 
         self.update_vp(validator_address)
     }
 
+    /// Cancel part or all of a pending unbond, delegating the coins back to
+    /// the validator. The unbond is identified by the time it started, as
+    /// reported in the delegator's unbonding entries.
+    ///
+    /// Unbonds which have matured can no longer be canceled. Unbonds from a
+    /// fully unbonded validator resolve immediately, so there is never a
+    /// pending unbond to cancel for them.
+    pub fn cancel_unbond<A: Into<Amount>>(
+        &mut self,
+        val_address: Address,
+        delegator_address: Address,
+        amount: A,
+        start_seconds: i64,
+    ) -> Result<()> {
+        let now = self.current_seconds()?;
+        if now - start_seconds >= self.unbonding_seconds as i64 {
+            return Err(Error::Coins(
+                "Cannot cancel an unbond which has matured".into(),
+            ));
+        }
+
+        let unbonds_remaining = {
+            let mut validator = self.validators.get_mut(val_address)?;
+            if validator.tombstoned {
+                return Err(Error::Coins(
+                    "Cannot delegate to a tombstoned validator".into(),
+                ));
+            }
+            validator.with_delegator(delegator_address, |delegator| {
+                delegator.cancel_unbond(amount, start_seconds)
+            })?
+        };
+
+        if !unbonds_remaining {
+            let val_address: VersionedAddress = val_address.into();
+            let delegator_address: VersionedAddress = delegator_address.into();
+            self.unbonding_delegation_queue.retain(|entry| {
+                Ok(entry.validator_address != val_address
+                    || entry.delegator_address != delegator_address
+                    || entry.start_seconds != start_seconds)
+            })?;
+        }
+
+        self.update_vp(val_address)
+    }
+
     /// Redelegate staked tokens from one validator to another.
+    ///
+    /// Outstanding rewards on both the source and destination delegations are
+    /// settled into their liquid balances before the stake is moved.
+    ///
+    /// The destination may be unbonding or unbonded (but not tombstoned). The
+    /// redelegation is still subject to the source validator's unbonding
+    /// period, including when unbonding from the destination.
     pub fn redelegate<A: Into<Amount>>(
         &mut self,
         src_validator_address: Address,
@@ -600,15 +1001,10 @@ impl<S: Symbol> Staking<S> {
                 Status::Unbonding { start_seconds } => Some(start_seconds),
                 Status::Unbonded => None,
             };
-            let mut src_delegator = src_validator.get_mut(delegator_address)?;
-            (
-                src_delegator.redelegate_out(
-                    dst_validator_address.into(),
-                    amount,
-                    start_seconds,
-                )?,
-                start_seconds,
-            )
+            let coins = src_validator.with_delegator(delegator_address, |src_delegator| {
+                src_delegator.redelegate_out(dst_validator_address.into(), amount, start_seconds)
+            })?;
+            (coins, start_seconds)
         };
 
         {
@@ -619,17 +1015,10 @@ impl<S: Symbol> Staking<S> {
                     "Cannot redelegate to a tombstoned validator".into(),
                 ));
             }
-            if matches!(
-                dst_validator.status(),
-                Status::Unbonded | Status::Unbonding { .. }
-            ) {
-                return Err(Error::Coins(
-                    "Cannot redelegate to an unbonding or unbonded validator".into(),
-                ));
-            }
 
-            let mut dst_delegator = dst_validator.get_mut(delegator_address)?;
-            dst_delegator.redelegate_in(src_validator_address.into(), coins, start_seconds)?;
+            dst_validator.with_delegator(delegator_address, |dst_delegator| {
+                dst_delegator.redelegate_in(src_validator_address.into(), coins, start_seconds)
+            })?;
         }
 
         if let Some(start_seconds) = start_seconds {
@@ -639,6 +1028,10 @@ impl<S: Symbol> Staking<S> {
                 delegator_address: delegator_address.into(),
                 start_seconds,
             })?;
+            self.redelegations_by_src
+                .entry(src_validator_address)?
+                .or_insert_default()?
+                .push_back(delegator_address.into())?;
         }
 
         self.index_delegation(dst_validator_address, delegator_address)?;
@@ -674,7 +1067,7 @@ impl<S: Symbol> Staking<S> {
             .get_or_default(delegator_address)?
             .iter()?
             .map(|entry| {
-                let (val_address, _) = entry?;
+                let val_address = entry?;
                 let validator = self.validators.get(*val_address)?;
                 let delegator = validator.get(delegator_address)?;
 
@@ -683,6 +1076,92 @@ impl<S: Symbol> Staking<S> {
             .collect()
     }
 
+    /// Query the rewards a delegator can currently claim with
+    /// [Staking::claim_all], in (denom, amount) pairs for each validator,
+    /// without claiming them. Validators with nothing to claim are omitted.
+    ///
+    /// This includes all liquid balances, e.g. matured unbondings, but not
+    /// rewards held for the delegator as another delegator's withdrawal
+    /// address.
+    #[query]
+    pub fn claimable_rewards(
+        &self,
+        delegator: Address,
+    ) -> Result<Vec<(Address, Vec<(u8, Amount)>)>> {
+        Ok(self
+            .delegations(delegator)?
+            .into_iter()
+            .filter_map(|(val_address, delegation)| {
+                let liquid: Vec<_> = delegation
+                    .liquid
+                    .into_iter()
+                    .filter(|(_, amount)| *amount > 0)
+                    .collect();
+                (!liquid.is_empty()).then_some((val_address, liquid))
+            })
+            .collect())
+    }
+
+    /// Query the pending unbonds of a delegator across all of its
+    /// delegations, including the time at which each will complete.
+    #[query]
+    pub fn unbonding_delegations(
+        &self,
+        delegator_address: Address,
+    ) -> Result<Vec<UnbondingDelegationInfo>> {
+        let mut unbonds = vec![];
+        for entry in self
+            .delegation_index
+            .get_or_default(delegator_address)?
+            .iter()?
+        {
+            let val_address = entry?;
+            let validator = self.validators.get(*val_address)?;
+            let delegator = validator.get(delegator_address)?;
+
+            for unbond in delegator.unbonding.iter()? {
+                let unbond = unbond?;
+                unbonds.push(UnbondingDelegationInfo {
+                    validator_address: *val_address,
+                    amount: unbond.coins.amount()?,
+                    start_seconds: unbond.start_seconds,
+                    completion_seconds: unbond.start_seconds + self.unbonding_seconds as i64,
+                });
+            }
+        }
+
+        Ok(unbonds)
+    }
+
+    /// Query the pending outbound redelegations of a delegator across all of
+    /// its delegations, including the time at which each will complete.
+    #[query]
+    pub fn redelegations(&self, delegator_address: Address) -> Result<Vec<RedelegationInfo>> {
+        let mut redelegations = vec![];
+        for entry in self
+            .delegation_index
+            .get_or_default(delegator_address)?
+            .iter()?
+        {
+            let val_address = entry?;
+            let validator = self.validators.get(*val_address)?;
+            let delegator = validator.get(delegator_address)?;
+
+            for redelegation in delegator.redelegations_out.iter()? {
+                let redelegation = redelegation?;
+                redelegations.push(RedelegationInfo {
+                    src_validator_address: *val_address,
+                    dst_validator_address: redelegation.address.into(),
+                    amount: redelegation.amount,
+                    start_seconds: redelegation.start_seconds,
+                    completion_seconds: redelegation.start_seconds + self.unbonding_seconds as i64,
+                });
+            }
+        }
+
+        Ok(redelegations)
+    }
+
     /// Query all active delegations to the provided validator address.
     #[query]
     pub fn validator_delegations(
@@ -700,6 +1179,72 @@ impl<S: Symbol> Staking<S> {
             .collect()
     }
 
+    /// Query the amount of stake each delegator to the provided validator would
+    /// lose if the validator were slashed by `fraction`, without modifying any
+    /// state.
+    ///
+    /// Tombstoned validators can not be slashed again, so all previewed losses
+    /// will be zero.
+    #[query]
+    pub fn slash_preview(&self, val: Address, fraction: Decimal) -> Result<Vec<(Address, Amount)>> {
+        validator::check_slash_fraction(fraction)?;
+        let validator = self.validators.get(val)?;
+        let multiplier = (Decimal::one() - fraction)?;
+
+        validator
+            .delegators
+            .iter()?
+            .map(|entry| -> Result<(Address, Amount)> {
+                let (delegator, delegation) = entry?;
+                if validator.tombstoned {
+                    return Ok((delegator, 0.into()));
+                }
+                let staked = delegation.staked.shares;
+                let remaining = (staked * multiplier)?.amount()?;
+                Ok((delegator, (staked.amount()? - remaining)?))
+            })
+            .collect()
+    }
+
+    /// Query the number of declared, active and jailed validators.
+    ///
+    /// This walks the whole voting power index, so its cost grows with the
+    /// number of declared validators. Jailed validators have no voting power,
+    /// so validators are only loaded for the zero-power entries at the end of
+    /// the index, to check whether they are jailed.
+    #[query]
+    pub fn validator_count(&self) -> Result<ValidatorCounts> {
+        let mut counts = ValidatorCounts {
+            active: self.active_set_size()?,
+            ..Default::default()
+        };
+
+        let zero_power = (u64::MAX, [0; 20]);
+        for entry in self.validators_by_power.range(..zero_power)? {
+            entry?;
+            counts.total += 1;
+        }
+        for entry in self.validators_by_power.range(zero_power..)? {
+            let entry = entry?;
+            counts.total += 1;
+            if self.validators.get(entry.address_bytes.into())?.jailed() {
+                counts.jailed += 1;
+            }
+        }
+
+        Ok(counts)
+    }
+
+    /// Query the number of validators in the active set as of the last
+    /// block.
+    ///
+    /// Only the active set is walked, so the cost is bounded by
+    /// `max_validators`.
+    #[query]
+    pub fn active_set_size(&self) -> Result<u64> {
+        self.last_validator_powers.len()
+    }
+
     /// Query all validators (expensive).
     #[query]
     pub fn all_validators(&self) -> Result<Vec<ValidatorQueryInfo>> {
@@ -723,30 +1268,42 @@ impl<S: Symbol> Staking<S> {
 
         let denom = S::NAME;
 
-        ev_ctx.add(Event {
-            r#type: "unbond".to_string(),
-            attributes: vec![
-                EventAttribute {
-                    key: "validator".into(),
-                    value: val_address.to_string().into(),
-                    index: true,
-                },
-                EventAttribute {
-                    key: "delegator".into(),
-                    value: signer.to_string().into(),
-                    index: true,
-                },
-                EventAttribute {
-                    key: "amount".into(),
-                    value: format!("{}{}", amount, denom).into(), // "1000unom"
-                    index: true,
-                },
-            ],
-        });
+        ev_ctx
+            .emit("unbond")
+            .attr("validator", val_address)
+            .attr("delegator", signer)
+            .attr("amount", format!("{}{}", amount, denom))
+            .index_all();
 
         self.unbond(val_address, signer, amount)
     }
 
+    /// Cancels part or all of a pending unbond, delegating the coins back to
+    /// the validator.
+    #[call]
+    pub fn cancel_unbond_self(
+        &mut self,
+        val_address: Address,
+        amount: Amount,
+        start_seconds: i64,
+    ) -> Result<()> {
+        assert_positive(amount)?;
+        let signer = self.signer()?;
+        let ev_ctx = self.events()?;
+
+        let denom = S::NAME;
+
+        ev_ctx
+            .emit("cancel_unbond")
+            .attr("validator", val_address)
+            .attr("delegator", signer)
+            .attr("amount", format!("{}{}", amount, denom))
+            .attr("start_seconds", start_seconds)
+            .index_all();
+
+        self.cancel_unbond(val_address, signer, amount, start_seconds)
+    }
+
     /// Redelegates staking tokens from a source validator to a destination.
     #[call]
     pub fn redelegate_self(
@@ -761,31 +1318,13 @@ impl<S: Symbol> Staking<S> {
 
         let denom = S::NAME;
 
-        ev_ctx.add(Event {
-            r#type: "redelegate".to_string(),
-            attributes: vec![
-                EventAttribute {
-                    key: "source_validator".into(),
-                    value: src_val_address.to_string().into(),
-                    index: true,
-                },
-                EventAttribute {
-                    key: "destination_validator".into(),
-                    value: dst_val_address.to_string().into(),
-                    index: true,
-                },
-                EventAttribute {
-                    key: "delegator".into(),
-                    value: signer.to_string().into(),
-                    index: true,
-                },
-                EventAttribute {
-                    key: "amount".into(),
-                    value: format!("{}{}", amount, denom).into(), // "1000unom"
-                    index: true,
-                },
-            ],
-        });
+        ev_ctx
+            .emit("redelegate")
+            .attr("source_validator", src_val_address)
+            .attr("destination_validator", dst_val_address)
+            .attr("delegator", signer)
+            .attr("amount", format!("{}{}", amount, denom))
+            .index_all();
 
         self.redelegate(src_val_address, dst_val_address, signer, amount)
     }
@@ -810,26 +1349,12 @@ impl<S: Symbol> Staking<S> {
 
         let denom = S::NAME;
 
-        ev_ctx.add(Event {
-            r#type: "delegate".to_string(),
-            attributes: vec![
-                EventAttribute {
-                    key: "validator".into(),
-                    value: validator_address.to_string().into(),
-                    index: true,
-                },
-                EventAttribute {
-                    key: "delegator".into(),
-                    value: signer.to_string().into(),
-                    index: true,
-                },
-                EventAttribute {
-                    key: "amount".into(),
-                    value: format!("{}{}", amount, denom).into(), // "1000unom"
-                    index: true,
-                },
-            ],
-        });
+        ev_ctx
+            .emit("delegate")
+            .attr("validator", validator_address)
+            .attr("delegator", signer)
+            .attr("amount", format!("{}{}", amount, denom))
+            .index_all();
 
         self.delegate(validator_address, signer, payment)
     }
@@ -854,24 +1379,35 @@ impl<S: Symbol> Staking<S> {
         let ev_ctx = self.events()?;
         let denom_as_string = S::NAME;
 
-        ev_ctx.add(Event {
-            r#type: "coin_spend".to_string(),
-            attributes: vec![
-                EventAttribute {
-                    key: "spender".into(),
-                    value: signer.to_string().into(),
-                    index: true,
-                },
-                EventAttribute {
-                    key: "amount".into(),
-                    value: format!("{}{}", amount, denom_as_string).into(), // "1000unom"
-                    index: true,
-                },
-            ],
-        });
+        ev_ctx
+            .emit("coin_spend")
+            .attr("spender", signer)
+            .attr("amount", format!("{}{}", amount, denom_as_string))
+            .index_all();
 
         self.deduct(validator_address, signer, amount, denom)?;
-        self.paid()?.give_denom(amount, denom)
+
+        // matured principal is always paid to the delegator, only rewards go to
+        // the withdrawal address
+        let principal = if denom == S::INDEX {
+            self.take_unbonded_principal(validator_address, signer, amount)?
+        } else {
+            0.into()
+        };
+        let rewards = (amount - principal)?;
+        let recipient = self.withdraw_address(signer)?;
+        if recipient == signer || rewards == 0 {
+            return self.paid()?.give_denom(amount, denom);
+        }
+        if principal > 0 {
+            self.paid()?.give_denom(principal, denom)?;
+        }
+
+        let mut pending = self.pending_withdrawals.entry(recipient)?.or_default()?;
+        let mut balance = pending.entry(denom)?.or_default()?;
+        *balance = (*balance + rewards)?;
+
+        Ok(())
     }
 
     /// Claim all rewards for a delegator, transferring them to the [Paid]
@@ -879,39 +1415,89 @@ impl<S: Symbol> Staking<S> {
     #[call]
     pub fn claim_all(&mut self) -> Result<()> {
         let signer = self.signer()?;
-        let delegations = self.delegations(signer)?;
+        let rewards = self.claimable_rewards(signer)?;
+
+        rewards.iter().try_for_each(|(val_address, liquid)| {
+            for (denom, amount) in liquid.iter() {
+                let ev_ctx = self.events()?;
+                ev_ctx
+                    .emit("withdraw_rewards")
+                    .attr("validator", val_address)
+                    .attr("delegator", signer)
+                    .index_all();
+
+                self.take_as_funding(*val_address, *amount, *denom)?;
+            }
+            Ok::<_, Error>(())
+        })?;
 
-        delegations
-            .iter()
-            .try_for_each(|(val_address, delegation)| {
-                for (denom, amount) in delegation.liquid.iter() {
-                    if *amount > 0 {
-                        let ev_ctx = self.events()?;
-                        ev_ctx.add(Event {
-                            r#type: "withdraw_rewards".to_string(),
-                            attributes: vec![
-                                EventAttribute {
-                                    key: "validator".into(),
-                                    value: val_address.clone().to_string().into(),
-                                    index: true,
-                                },
-                                EventAttribute {
-                                    key: "delegator".into(),
-                                    value: signer.to_string().into(),
-                                    index: true,
-                                },
-                            ],
-                        });
-
-                        self.take_as_funding(*val_address, *amount, *denom)?;
-                    }
-                }
-                Ok::<_, Error>(())
-            })?;
+        if let Some(pending) = self.pending_withdrawals.remove(signer)? {
+            for entry in pending.iter()? {
+                let (denom, amount) = entry?;
+                self.paid()?.give_denom(*amount, *denom)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reduces the delegator's matured unbonded principal by up to `max`,
+    /// returning the amount taken.
+    fn take_unbonded_principal(
+        &mut self,
+        val_address: Address,
+        delegator_address: Address,
+        max: Amount,
+    ) -> Result<Amount> {
+        let mut principals = match self.unbonded_principal.get_mut(val_address)? {
+            Some(principals) => principals,
+            None => return Ok(0.into()),
+        };
+        let principal = match principals.get(delegator_address)? {
+            Some(principal) => *principal,
+            None => return Ok(0.into()),
+        };
+
+        let taken = if principal > max { max } else { principal };
+        let remaining = (principal - taken)?;
+        if remaining == 0 {
+            principals.remove(delegator_address)?;
+        } else {
+            principals.insert(delegator_address, remaining)?;
+        }
+
+        Ok(taken)
+    }
+
+    /// Set the address which receives the delegator's claimed rewards.
+    ///
+    /// Rewards claimed with [Staking::claim_all] or [Staking::take_as_funding]
+    /// are held for the withdrawal address, which receives them as funding on
+    /// its own next call to [Staking::claim_all]. This applies to all liquid
+    /// balances, including matured unbondings. Setting the delegator's own
+    /// address restores the default.
+    #[call]
+    pub fn set_withdraw_address(&mut self, addr: Address) -> Result<()> {
+        let signer = self.signer()?;
+        if addr == signer {
+            self.withdraw_addresses.remove(signer)?;
+        } else {
+            self.withdraw_addresses.insert(signer, addr)?;
+        }
 
         Ok(())
     }
 
+    /// The address which receives the delegator's claimed rewards, defaulting
+    /// to the delegator.
+    #[query]
+    pub fn withdraw_address(&self, delegator_address: Address) -> Result<Address> {
+        Ok(self
+            .withdraw_addresses
+            .get(delegator_address)?
+            .map_or(delegator_address, |addr| *addr))
+    }
+
     /// Attempt to unjail a validator, restoring it to the active set if
     /// eligible.
     #[call]
@@ -939,6 +1525,23 @@ impl<S: Symbol> Staking<S> {
         self.edit_validator(val_address, commission, min_self_delegation, validator_info)
     }
 
+    /// Declare a new validator like [Staking::declare_self], including a proof
+    /// that the signer controls the consensus key.
+    ///
+    /// This is a separate call so that the encoding of [Declaration] and of
+    /// the existing calls is unchanged for older clients.
+    #[call]
+    pub fn declare_self_with_proof(
+        &mut self,
+        declaration: Declaration,
+        consensus_key_proof: [u8; 64],
+    ) -> Result<()> {
+        assert_positive(declaration.amount)?;
+        let signer = self.signer()?;
+        let payment = self.paid()?.take(declaration.amount)?;
+        self.declare_with_proof(signer, declaration, Some(consensus_key_proof), payment)
+    }
+
     /// Returns the address of the current call's signer.
     fn signer(&mut self) -> Result<Address> {
         self.context::<Signer>()
@@ -947,6 +1550,14 @@ impl<S: Symbol> Staking<S> {
             .ok_or_else(|| Error::Coins("Call must be signed".into()))
     }
 
+    /// Returns the chain ID from the [ChainId] context.
+    fn chain_id(&mut self) -> Result<String> {
+        Ok(self
+            .context::<ChainId>()
+            .ok_or_else(|| Error::Coins("No ChainId context available".into()))?
+            .to_string())
+    }
+
     /// [Paid] context helper.
     fn paid(&mut self) -> Result<&mut Paid> {
         self.context::<Paid>()
@@ -983,50 +1594,64 @@ impl<S: Symbol> Staking<S> {
     /// - Validator status transitions
     /// - Unbonding delegations
     /// - Redelegations
+    ///
+    /// At most `max_queue_ops_per_block` matured entries are processed in
+    /// total (if non-zero), in the order above.
     fn process_all_queues(&mut self) -> Result<()> {
-        self.process_validator_queue()?;
-        self.process_unbonding_delegation_queue()?;
-        self.process_redelegation_queue()
+        let mut remaining = match self.max_queue_ops_per_block {
+            0 => u64::MAX,
+            max => max,
+        };
+
+        self.process_validator_queue(&mut remaining)?;
+        self.process_unbonding_delegation_queue(&mut remaining)?;
+        self.process_redelegation_queue(&mut remaining)
     }
 
     /// Process the validator queue, possibly transitioning validators to the
     /// unbonded state.
-    fn process_validator_queue(&mut self) -> Result<()> {
+    fn process_validator_queue(&mut self, remaining: &mut u64) -> Result<()> {
         let now = self.current_seconds()?;
-        // TODO: should be one pass (needs drain iterator)
-        self.validator_queue
-            .iter()?
-            .take_while(|entry| match entry {
-                Ok(entry) => now - entry.start_seconds >= self.unbonding_seconds as i64,
-                Err(_) => true,
-            })
-            .collect::<Vec<_>>()
-            .into_iter()
-            .try_for_each(|entry| {
-                let entry = entry?;
-                self.transition_to_unbonded(entry.address_bytes.into())?;
-                self.validator_queue.delete(entry.clone())
-            })
+        let unbonding_seconds = self.unbonding_seconds as i64;
+        let matured = self
+            .validator_queue
+            .drain_while(|entry| now - entry.start_seconds >= unbonding_seconds)
+            .take((*remaining).try_into().unwrap_or(usize::MAX));
+
+        for entry in matured {
+            let entry = entry?;
+            let mut validator = self.validators.get_mut(entry.address_bytes.into())?;
+            validator.unbonding = false;
+            *remaining -= 1;
+        }
+
+        Ok(())
     }
 
     /// Iterates through the unbonding delegation queue, processing matured
     /// unbonds.
-    fn process_unbonding_delegation_queue(&mut self) -> Result<()> {
+    fn process_unbonding_delegation_queue(&mut self, remaining: &mut u64) -> Result<()> {
         let now = self.current_seconds()?;
-
-        while let Some(unbond) = self.unbonding_delegation_queue.front()? {
-            let matured = now - unbond.start_seconds >= self.unbonding_seconds as i64;
-            if matured {
-                let unbond = self
-                    .unbonding_delegation_queue
-                    .pop_front()?
-                    .ok_or_else(|| Error::Coins("Unbonding delegation queue is empty".into()))?;
+        let unbonding_seconds = self.unbonding_seconds as i64;
+        let matured = self
+            .unbonding_delegation_queue
+            .drain_while(|unbond| now - unbond.start_seconds >= unbonding_seconds)
+            .take((*remaining).try_into().unwrap_or(usize::MAX));
+
+        for unbond in matured {
+            let unbond = unbond?;
+            let amount = {
                 let mut validator = self.validators.get_mut(unbond.validator_address.into())?;
                 let mut delegator = validator.get_mut(unbond.delegator_address.into())?;
-                delegator.process_unbonds()?;
-            } else {
-                break;
-            }
+                delegator.process_unbonds()?
+            };
+            add_unbonded_principal(
+                &mut self.unbonded_principal,
+                unbond.validator_address.into(),
+                unbond.delegator_address.into(),
+                amount,
+            )?;
+            *remaining -= 1;
         }
 
         Ok(())
@@ -1034,68 +1659,87 @@ impl<S: Symbol> Staking<S> {
 
     /// Iterates through the redelegation queue, processing matured
     /// redelegations.
-    fn process_redelegation_queue(&mut self) -> Result<()> {
+    fn process_redelegation_queue(&mut self, remaining: &mut u64) -> Result<()> {
         let now = self.current_seconds()?;
+        let unbonding_seconds = self.unbonding_seconds as i64;
+        let matured = self
+            .redelegation_queue
+            .drain_while(|redelegation| now - redelegation.start_seconds >= unbonding_seconds)
+            .take((*remaining).try_into().unwrap_or(usize::MAX));
+
+        for redelegation in matured {
+            let redelegation = redelegation?;
+
+            {
+                let mut src_validator = self
+                    .validators
+                    .get_mut(redelegation.src_validator_address.into())?;
+                let mut src_delegator =
+                    src_validator.get_mut(redelegation.delegator_address.into())?;
+                src_delegator.process_redelegations_out()?;
+            }
 
-        while let Some(redelegation) = self.redelegation_queue.front()? {
-            let matured = now - redelegation.start_seconds >= self.unbonding_seconds as i64;
-            if matured {
-                let redelegation = self
-                    .redelegation_queue
-                    .pop_front()?
-                    .ok_or_else(|| Error::Coins("Redelegation queue is empty".into()))?;
-
-                {
-                    let mut src_validator = self
-                        .validators
-                        .get_mut(redelegation.src_validator_address.into())?;
-                    let mut src_delegator =
-                        src_validator.get_mut(redelegation.delegator_address.into())?;
-                    src_delegator.process_redelegations_out()?;
-                }
+            {
+                let mut dst_validator = self
+                    .validators
+                    .get_mut(redelegation.dst_validator_address.into())?;
+                let mut dst_delegator =
+                    dst_validator.get_mut(redelegation.delegator_address.into())?;
+                dst_delegator.process_redelegations_in()?;
+            }
 
-                {
-                    let mut dst_validator = self
-                        .validators
-                        .get_mut(redelegation.dst_validator_address.into())?;
-                    let mut dst_delegator =
-                        dst_validator.get_mut(redelegation.delegator_address.into())?;
-                    dst_delegator.process_redelegations_in()?;
-                }
-            } else {
-                break;
+            // the queue is processed in order, so the matured redelegation is
+            // the first pending one from its source validator
+            let src_address = redelegation.src_validator_address.into();
+            let emptied = {
+                let mut pending = self
+                    .redelegations_by_src
+                    .get_mut(src_address)?
+                    .ok_or_else(|| Error::Coins("Redelegation is not indexed".into()))?;
+                pending.pop_front()?;
+                pending.is_empty()
+            };
+            if emptied {
+                self.redelegations_by_src.remove(src_address)?;
             }
+            *remaining -= 1;
         }
 
         Ok(())
     }
 
-    /// In the EndBlock step, all queues are processed and the minimum set of
-    /// updates required to send back to Tendermint are computed.
-    fn end_block_step(&mut self, ctx: &EndBlockCtx) -> Result<()> {
-        self.process_all_queues()?;
-        use std::collections::HashSet;
-        let max_vals = self.max_validators;
-        let mut new_val_entries: Vec<(Address, u64)> = vec![];
-        let mut i = 0;
-        // Collect the top validators by voting power
+    /// Returns the addresses and voting power of the validators which should
+    /// make up the active set: the top `max_validators` validators with
+    /// non-zero power.
+    ///
+    /// When validators at the cutoff have equal power, the validators with the
+    /// lowest address bytes are selected, regardless of which of them were
+    /// previously in the active set, so every node selects the same set.
+    fn active_set(&self) -> Result<Vec<(Address, u64)>> {
+        let mut active_set = vec![];
         for entry in self.validators_by_power.iter()? {
             let entry = entry?;
-            let address: Address = entry.address_bytes.into();
-            let new_power = entry.power();
-
-            if new_power == 0 {
+            let power = entry.power();
+            if power == 0 {
                 break;
             }
 
-            new_val_entries.push((address, new_power));
-
-            i += 1;
-            if i == max_vals {
+            active_set.push((entry.address_bytes.into(), power));
+            if active_set.len() as u64 == self.max_validators {
                 break;
             }
         }
 
+        Ok(active_set)
+    }
+
+    /// In the EndBlock step, all queues are processed and the minimum set of
+    /// updates required to send back to Tendermint are computed.
+    fn end_block_step(&mut self, ctx: &EndBlockCtx) -> Result<()> {
+        self.process_all_queues()?;
+        use std::collections::HashSet;
+        let new_val_entries = self.active_set()?;
+
         // Find the minimal set of updates required to send back to Tendermint
         let mut new_power_updates = vec![];
         for (address, power) in new_val_entries.iter() {
@@ -1198,14 +1842,6 @@ impl<S: Symbol> Staking<S> {
         })
     }
 
-    /// Transition a validator to the unbonded state.
-    fn transition_to_unbonded(&mut self, val_address: Address) -> Result<()> {
-        let mut validator = self.validators.get_mut(val_address)?;
-        validator.unbonding = false;
-
-        Ok(())
-    }
-
     /// Current unix second timestamp, from the [Time] context.
     fn current_seconds(&mut self) -> Result<i64> {
         let time = self
@@ -1231,11 +1867,31 @@ impl<S: Symbol> Staking<S> {
         self.unbonding_delegation_queue
             .retain_unordered(|_| Ok(false))?;
         self.redelegation_queue.retain_unordered(|_| Ok(false))?;
+        self.redelegations_by_src.clear()?;
 
         Ok(())
     }
 }
 
+/// Records unbonded principal which has matured into a delegator's liquid
+/// balance, see [Staking::unbonded_principal].
+fn add_unbonded_principal(
+    unbonded_principal: &mut Map<Address, Map<Address, Amount>>,
+    val_address: Address,
+    delegator_address: Address,
+    amount: Amount,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let mut principals = unbonded_principal.entry(val_address)?.or_default()?;
+    let mut principal = principals.entry(delegator_address)?.or_default()?;
+    *principal = (*principal + amount)?;
+
+    Ok(())
+}
+
 /// Error if the amount is not positive.
 fn assert_positive(amount: Amount) -> Result<()> {
     if amount > 0 {
@@ -1254,8 +1910,15 @@ fn validate_info(info: &ValidatorInfo) -> Result<()> {
     Ok(())
 }
 
+/// Coins given to the staking module are distributed as rewards. Coins of a
+/// denom other than the bond denom are only ever credited to liquid balances,
+/// and are recorded in the reward denom registry.
 impl<S: Symbol, T: Symbol> Give<Coin<T>> for Staking<S> {
     fn give(&mut self, coins: Coin<T>) -> Result<()> {
+        if T::INDEX != S::INDEX && !self.reward_denoms.contains_key(T::INDEX)? {
+            self.reward_denoms.insert(T::INDEX, ())?;
+        }
+
         self.validators.give(coins)
     }
 }
@@ -1270,6 +1933,77 @@ fn tm_pubkey_hash(consensus_key: [u8; 32]) -> Result<[u8; 20]> {
         .map_err(|_| Error::Coins("Invalid consensus key".into()))
 }
 
+/// Returns the bytes which must be signed by a validator's consensus key to
+/// prove possession of it when declaring with the given operator address on
+/// the given chain. Including the chain ID prevents a proof from being replayed
+/// on another chain.
+pub fn consensus_key_proof_bytes(chain_id: &str, val_address: Address) -> Vec<u8> {
+    let mut bytes = b"orga/consensus-key-proof".to_vec();
+    bytes.extend_from_slice(chain_id.as_bytes());
+    bytes.extend_from_slice(&val_address.bytes());
+    bytes
+}
+
+/// Verifies that `proof` is a signature by the ed25519 `consensus_key` over
+/// [consensus_key_proof_bytes] for the chain ID and operator address.
+fn verify_consensus_key_proof(
+    consensus_key: [u8; 32],
+    chain_id: &str,
+    val_address: Address,
+    proof: [u8; 64],
+) -> Result<()> {
+    use ed25519_dalek::{Signature, VerifyingKey};
+
+    let invalid = || Error::Coins("Invalid consensus key proof".into());
+    let key = VerifyingKey::from_bytes(&consensus_key).map_err(|_| invalid())?;
+    let signature = Signature::from_bytes(&proof);
+    key.verify_strict(
+        &consensus_key_proof_bytes(chain_id, val_address),
+        &signature,
+    )
+    .map_err(|_| invalid())
+}
+
+/// A pending unbond, as returned by [Staking::unbonding_delegations].
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnbondingDelegationInfo {
+    /// The validator the coins are being unbonded from.
+    pub validator_address: Address,
+    /// Amount of the staking token being unbonded.
+    pub amount: Amount,
+    /// The time (in unix seconds) when the unbonding started.
+    pub start_seconds: i64,
+    /// The time (in unix seconds) when the unbonded coins become liquid.
+    pub completion_seconds: i64,
+}
+
+/// Validator counts, as returned by [Staking::validator_count].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidatorCounts {
+    /// Number of declared validators.
+    pub total: u64,
+    /// Number of validators in the active set as of the last block.
+    pub active: u64,
+    /// Number of jailed validators, including tombstoned validators.
+    pub jailed: u64,
+}
+
+/// A pending redelegation, as returned by [Staking::redelegations].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedelegationInfo {
+    /// The validator the coins are being redelegated from.
+    pub src_validator_address: Address,
+    /// The validator the coins are being redelegated to.
+    pub dst_validator_address: Address,
+    /// Amount of the staking token being redelegated.
+    pub amount: Amount,
+    /// The time (in unix seconds) when the redelegation started.
+    pub start_seconds: i64,
+    /// The time (in unix seconds) when the redelegated coins are no longer
+    /// slashable for faults of the source validator.
+    pub completion_seconds: i64,
+}
+
 /// Validator declaration information.
 #[derive(Debug, Encode, Decode, Clone)]
 pub struct Declaration {
@@ -1296,7 +2030,9 @@ pub struct Commission {
     /// The amount of rewards taken by the validator as a commission (0-1). May
     /// be changed by validator edits.
     pub rate: Decimal,
-    /// The max commission rate the validator can set. May not be edited.
+    /// The max commission rate the validator can set. May not be edited,
+    /// except to be raised along with a rate forced up to the staking module's
+    /// minimum commission rate.
     pub max: Decimal,
     /// The maximum amount that the rate may change in a single edit. May not be
     /// edited.
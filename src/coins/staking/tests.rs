@@ -1368,6 +1368,57 @@ fn unclaimed_rewards_slash() -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn rewards_settled_on_delegation_change() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    staking.declare(
+        Address::from_pubkey([0; 33]),
+        Declaration {
+            consensus_key: [0; 32],
+            commission: Commission {
+                rate: dec!(0.0).into(),
+                max: dec!(1.0).into(),
+                max_change: dec!(0.1).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        },
+        Amount::new(100).into(),
+    )?;
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    let staker = Address::from_pubkey([1; 33]);
+
+    staking.end_block_step(&Default::default())?;
+
+    // staker holds half of the stake while the first rewards accrue
+    staking.delegate(val_0, staker, 100.into())?;
+    staking.give(Simp::mint(100))?;
+    staking.end_block_step(&Default::default())?;
+
+    // delegating more settles the first rewards at the old share
+    staking.delegate(val_0, staker, 200.into())?;
+    assert_eq!(simp_balance(&staking.get(val_0)?.get(staker)?.liquid), 50);
+    staking.end_block_step(&Default::default())?;
+
+    // staker now holds 3/4 of the stake
+    staking.give(Simp::mint(100))?;
+    staking.end_block_step(&Default::default())?;
+
+    assert_eq!(simp_balance(&staking.get(val_0)?.get(staker)?.liquid), 125);
+    assert_eq!(simp_balance(&staking.get(val_0)?.get(val_0)?.liquid), 75);
+
+    staking.deduct(val_0, staker, 125, Simp::INDEX)?;
+    assert_eq!(simp_balance(&staking.get(val_0)?.get(staker)?.liquid), 0);
+    assert!(staking.deduct(val_0, staker, 1, Simp::INDEX).is_err());
+
+    Ok(())
+}
+
 #[cfg(feature = "abci")]
 #[test]
 #[serial]
@@ -1464,6 +1515,75 @@ fn redelegate_from_to_failure() {
         .unwrap();
 }
 
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn redelegate_to_unbonding_validator() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    for i in 0..2 {
+        staking.declare(
+            Address::from_pubkey([i; 33]),
+            Declaration {
+                consensus_key: [i; 32],
+                commission: Commission {
+                    rate: dec!(0.0).into(),
+                    max: dec!(1.0).into(),
+                    max_change: dec!(0.1).into(),
+                },
+                amount: Amount::new(100),
+                min_self_delegation: 1.into(),
+                validator_info: vec![].try_into()?,
+            },
+            Amount::new(100).into(),
+        )?;
+    }
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    let val_1 = Address::from_pubkey([1; 33]);
+    let staker = Address::from_pubkey([2; 33]);
+
+    staking.delegate(val_0, staker, 100.into())?;
+    staking.end_block_step(&Default::default())?;
+
+    staking.punish_downtime(val_1)?;
+    staking.end_block_step(&Default::default())?;
+    assert!(matches!(
+        staking.get(val_1)?.status(),
+        Status::Unbonding { start_seconds: 0 }
+    ));
+
+    Context::add(Time::from_seconds(2));
+    staking.redelegate(val_0, val_1, staker, Amount::from(100))?;
+    assert_eq!(staking.get(val_1)?.get(staker)?.staked.amount()?, 100);
+    assert_eq!(staking.get(val_1)?.get(staker)?.redelegations_in.len(), 1);
+    // the destination is still jailed, so it has no voting power
+    assert_eq!(*staking.last_indexed_power.get(val_1)?.unwrap(), 0);
+
+    Context::add(Time::from_seconds(6));
+    staking.get_mut(val_1)?.try_unjail()?;
+    staking.update_vp(val_1)?;
+    assert_eq!(*staking.last_indexed_power.get(val_1)?.unwrap(), 150);
+
+    // unbonding from the destination honors the source's unbonding period,
+    // which started after the destination's
+    staking.unbond(val_1, staker, Amount::from(100))?;
+    let unbonds = staking.unbonding_delegations(staker)?;
+    assert_eq!(unbonds.len(), 1);
+    assert_eq!(unbonds[0].start_seconds, 2);
+    assert_eq!(unbonds[0].completion_seconds, 12);
+
+    Context::add(Time::from_seconds(10));
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(simp_balance(&staking.get(val_1)?.get(staker)?.liquid), 0);
+
+    Context::add(Time::from_seconds(12));
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(simp_balance(&staking.get(val_1)?.get(staker)?.liquid), 100);
+
+    Ok(())
+}
+
 #[cfg(feature = "abci")]
 #[test]
 #[serial]
@@ -1573,3 +1693,933 @@ fn alt_coin_rewards() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn non_bond_reward_denom() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    let staker = Address::from_pubkey([1; 33]);
+
+    staking.declare(
+        val_0,
+        Declaration {
+            consensus_key: [0; 32],
+            commission: Commission {
+                rate: dec!(0.0).into(),
+                max: dec!(1.0).into(),
+                max_change: dec!(0.1).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        },
+        Amount::new(100).into(),
+    )?;
+    staking.delegate(val_0, staker, 100.into())?;
+    staking.end_block_step(&Default::default())?;
+    assert!(staking.reward_denoms()?.is_empty());
+
+    staking.give(Simp::mint(100))?;
+    assert!(staking.reward_denoms()?.is_empty());
+    staking.give(Alt::mint(100))?;
+    assert_eq!(staking.reward_denoms()?, vec![Alt::INDEX]);
+    staking.end_block_step(&Default::default())?;
+
+    // the reward accrues as claimable liquid balance without adding stake
+    let delegation = staking.get(val_0)?.get(staker)?;
+    assert_eq!(alt_balance(&delegation.liquid), 50);
+    assert_eq!(simp_balance(&delegation.liquid), 50);
+    assert_eq!(delegation.staked.amount()?, 100);
+    drop(delegation);
+
+    Context::add(Signer {
+        signer: Some(staker),
+    });
+    Context::add(Paid::default());
+    staking.take_as_funding(val_0, 50.into(), Alt::INDEX)?;
+    assert_eq!(alt_balance(&staking.get(val_0)?.get(staker)?.liquid), 0);
+
+    // claimed reward coins can not be used to delegate
+    let err = staking.delegate_from_self(val_0, 50.into()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Coins Error: Insufficient funding for paid call"
+    );
+    assert_eq!(staking.get(val_0)?.get(staker)?.staked.amount()?, 100);
+
+    Context::remove::<Signer>();
+    Context::remove::<Paid>();
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn queue_ops_per_block_cap() -> Result<()> {
+    let mut staking = setup_state()?;
+    staking.max_queue_ops_per_block = 2;
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    staking.declare(
+        val_0,
+        Declaration {
+            consensus_key: [0; 32],
+            commission: Commission {
+                rate: dec!(0.0).into(),
+                max: dec!(1.0).into(),
+                max_change: dec!(0.1).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        },
+        Amount::new(100).into(),
+    )?;
+
+    let stakers: Vec<_> = (1..=3).map(|i| Address::from_pubkey([i; 33])).collect();
+    for staker in stakers.iter() {
+        staking.delegate(val_0, *staker, 100.into())?;
+    }
+    staking.end_block_step(&Default::default())?;
+
+    for staker in stakers.iter() {
+        staking.unbond(val_0, *staker, Amount::from(100))?;
+    }
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(staking.unbonding_delegation_queue.len(), 3);
+
+    Context::add(Time::from_seconds(10));
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(staking.unbonding_delegation_queue.len(), 1);
+    assert_eq!(
+        simp_balance(&staking.get(val_0)?.get(stakers[0])?.liquid),
+        100
+    );
+    assert_eq!(
+        simp_balance(&staking.get(val_0)?.get(stakers[1])?.liquid),
+        100
+    );
+    assert_eq!(
+        simp_balance(&staking.get(val_0)?.get(stakers[2])?.liquid),
+        0
+    );
+
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(staking.unbonding_delegation_queue.len(), 0);
+    assert_eq!(
+        simp_balance(&staking.get(val_0)?.get(stakers[2])?.liquid),
+        100
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn cancel_unbond() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    staking.declare(
+        val_0,
+        Declaration {
+            consensus_key: [0; 32],
+            commission: Commission {
+                rate: dec!(0.0).into(),
+                max: dec!(1.0).into(),
+                max_change: dec!(0.1).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        },
+        Amount::new(100).into(),
+    )?;
+
+    let ctx = Context::resolve::<Validators>().unwrap();
+    let staker = Address::from_pubkey([1; 33]);
+    staking.delegate(val_0, staker, 100.into())?;
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(ctx.updates.get(&[0; 32]).unwrap().power, 200);
+
+    staking.unbond(val_0, staker, Amount::from(100))?;
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(ctx.updates.get(&[0; 32]).unwrap().power, 100);
+
+    staking
+        .cancel_unbond(val_0, staker, Amount::from(101), 0)
+        .expect_err("Cannot cancel more than the unbonding amount");
+    staking
+        .cancel_unbond(val_0, staker, Amount::from(10), 5)
+        .expect_err("No unbond started at the given time");
+
+    staking.cancel_unbond(val_0, staker, Amount::from(40), 0)?;
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(ctx.updates.get(&[0; 32]).unwrap().power, 140);
+    assert_eq!(staking.get(val_0)?.get(staker)?.staked.amount()?, 40);
+    assert_eq!(staking.get(val_0)?.get(staker)?.unbonding.len(), 1);
+    assert_eq!(staking.unbonding_delegation_queue.len(), 1);
+
+    staking.cancel_unbond(val_0, staker, Amount::from(60), 0)?;
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(ctx.updates.get(&[0; 32]).unwrap().power, 200);
+    assert_eq!(staking.get(val_0)?.get(staker)?.staked.amount()?, 100);
+    assert_eq!(staking.get(val_0)?.get(staker)?.unbonding.len(), 0);
+    assert_eq!(staking.unbonding_delegation_queue.len(), 0);
+
+    Context::add(Time::from_seconds(5));
+    staking.unbond(val_0, staker, Amount::from(50))?;
+    Context::add(Time::from_seconds(15));
+    staking
+        .cancel_unbond(val_0, staker, Amount::from(50), 5)
+        .expect_err("Cannot cancel a matured unbond");
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(simp_balance(&staking.get(val_0)?.get(staker)?.liquid), 50);
+    assert_eq!(staking.get(val_0)?.get(staker)?.staked.amount()?, 50);
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn pending_unbonds_and_redelegations() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    for i in 0..2 {
+        staking.declare(
+            Address::from_pubkey([i; 33]),
+            Declaration {
+                consensus_key: [i; 32],
+                commission: Commission {
+                    rate: dec!(0.0).into(),
+                    max: dec!(1.0).into(),
+                    max_change: dec!(0.1).into(),
+                },
+                amount: Amount::new(100),
+                min_self_delegation: 1.into(),
+                validator_info: vec![].try_into()?,
+            },
+            Amount::new(100).into(),
+        )?;
+    }
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    let val_1 = Address::from_pubkey([1; 33]);
+    let staker = Address::from_pubkey([2; 33]);
+    staking.delegate(val_0, staker, 100.into())?;
+    staking.end_block_step(&Default::default())?;
+    assert!(staking.unbonding_delegations(staker)?.is_empty());
+    assert!(staking.redelegations(staker)?.is_empty());
+
+    Context::add(Time::from_seconds(2));
+    staking.unbond(val_0, staker, Amount::from(30))?;
+    staking.redelegate(val_0, val_1, staker, Amount::from(50))?;
+    staking.end_block_step(&Default::default())?;
+
+    assert_eq!(
+        staking.unbonding_delegations(staker)?,
+        vec![UnbondingDelegationInfo {
+            validator_address: val_0,
+            amount: 30.into(),
+            start_seconds: 2,
+            completion_seconds: 2 + UNBONDING_SECONDS as i64,
+        }]
+    );
+    assert_eq!(
+        staking.redelegations(staker)?,
+        vec![RedelegationInfo {
+            src_validator_address: val_0,
+            dst_validator_address: val_1,
+            amount: 50.into(),
+            start_seconds: 2,
+            completion_seconds: 2 + UNBONDING_SECONDS as i64,
+        }]
+    );
+    assert!(staking
+        .unbonding_delegations(Address::from_pubkey([3; 33]))?
+        .is_empty());
+
+    Context::add(Time::from_seconds(2 + UNBONDING_SECONDS as i64));
+    staking.end_block_step(&Default::default())?;
+    assert!(staking.unbonding_delegations(staker)?.is_empty());
+    assert!(staking.redelegations(staker)?.is_empty());
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn slash_preview() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    let staker = Address::from_pubkey([1; 33]);
+
+    staking.declare(
+        val_0,
+        Declaration {
+            consensus_key: [0; 32],
+            commission: Commission {
+                rate: dec!(0.0).into(),
+                max: dec!(1.0).into(),
+                max_change: dec!(0.1).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        },
+        Amount::new(100).into(),
+    )?;
+    staking.delegate(val_0, staker, 300.into())?;
+    staking.end_block_step(&Default::default())?;
+
+    let fraction = staking.slash_fraction_downtime;
+    let total_stake = staking.get(val_0)?.delegators.balance()?.amount()?;
+    let preview = staking.slash_preview(val_0, fraction)?;
+    assert_eq!(preview.len(), 2);
+    assert!(staking.slash_preview(val_0, dec!(1.5).into()).is_err());
+    assert!(staking.slash_preview(val_0, dec!(-0.5).into()).is_err());
+
+    let mut total_loss = Amount::new(0);
+    for (_, loss) in preview.iter() {
+        total_loss = (total_loss + *loss)?;
+    }
+    assert_eq!(total_loss, (fraction * total_stake)?.amount()?);
+    assert_eq!(staking.get(val_0)?.delegators.balance()?.amount()?, 400);
+
+    let before = staking.validator_delegations(val_0)?;
+    staking.punish_downtime(val_0)?;
+    staking.end_block_step(&Default::default())?;
+    let after = staking.validator_delegations(val_0)?;
+
+    for (((addr, loss), (before_addr, before)), (after_addr, after)) in
+        preview.iter().zip(before).zip(after)
+    {
+        assert_eq!(*addr, before_addr);
+        assert_eq!(*addr, after_addr);
+        assert_eq!(after.staked, (before.staked - *loss)?);
+    }
+
+    Ok(())
+}
+
+/// Returns the number of store reads made while slashing a validator with the
+/// given number of delegators, with the given number of redelegations between
+/// other validators pending in the redelegation queue.
+#[cfg(feature = "abci")]
+fn double_sign_reads(delegator_count: u8, unrelated_redelegations: u8) -> Result<usize> {
+    use crate::store::{log::ReadLog, BackingStore, Shared, Store};
+    use std::any::Any;
+
+    let store = Store::with_map_store();
+    let mut staking = setup_state()?;
+    staking.attach(store.clone())?;
+
+    let declaration = |i: u8| -> Result<Declaration> {
+        Ok(Declaration {
+            consensus_key: [i; 32],
+            commission: Commission {
+                rate: dec!(0.0).into(),
+                max: dec!(1.0).into(),
+                max_change: dec!(0.1).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        })
+    };
+    let val_0 = Address::from_pubkey([0; 33]);
+    let val_1 = Address::from_pubkey([254; 33]);
+    let val_2 = Address::from_pubkey([255; 33]);
+    staking.declare(val_0, declaration(0)?, Amount::new(100).into())?;
+    staking.declare(val_1, declaration(254)?, Amount::new(100).into())?;
+    staking.declare(val_2, declaration(255)?, Amount::new(100).into())?;
+    for i in 1..=delegator_count {
+        staking.delegate(val_0, Address::from_pubkey([i; 33]), 100.into())?;
+    }
+    staking.end_block_step(&Default::default())?;
+
+    let staker = Address::from_pubkey([253; 33]);
+    staking.delegate(val_1, staker, 100.into())?;
+    for _ in 0..unrelated_redelegations {
+        staking.redelegate(val_1, val_2, staker, 1.into())?;
+    }
+    staking.end_block_step(&Default::default())?;
+
+    let mut bytes = vec![];
+    staking.flush(&mut bytes)?;
+
+    let logged = Store::new(BackingStore::Other(Shared::new(Box::new(ReadLog::new(
+        store,
+    )))));
+    let mut staking = Staking::<Simp>::load(logged.clone(), &mut bytes.as_slice())?;
+    staking.punish_double_sign(val_0)?;
+    drop(staking);
+
+    let backing = logged.into_backing_store().into_inner().into_other()?;
+    let log = backing.into_inner() as Box<dyn Any>;
+    let reads = log.downcast::<ReadLog<Store>>().unwrap().reads().len();
+
+    Ok(reads)
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn double_sign_cost_independent_of_delegators() -> Result<()> {
+    assert_eq!(double_sign_reads(2, 0)?, double_sign_reads(50, 0)?);
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn double_sign_cost_independent_of_unrelated_redelegations() -> Result<()> {
+    assert_eq!(double_sign_reads(2, 0)?, double_sign_reads(2, 20)?);
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn min_commission_rate() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    let declaration = |i: u8, rate: Decimal| -> Result<Declaration> {
+        Ok(Declaration {
+            consensus_key: [i; 32],
+            commission: Commission {
+                rate,
+                max: dec!(1.0).into(),
+                max_change: dec!(0.01).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        })
+    };
+    let val_0 = Address::from_pubkey([0; 33]);
+    let val_1 = Address::from_pubkey([1; 33]);
+
+    staking.declare(val_0, declaration(0, dec!(0.0).into())?, 100.into())?;
+
+    staking.min_commission_rate = dec!(0.05).into();
+    staking
+        .declare(val_1, declaration(1, dec!(0.04).into())?, 100.into())
+        .expect_err("Should not be able to declare below the minimum commission rate");
+    staking.declare(val_1, declaration(1, dec!(0.05).into())?, 100.into())?;
+
+    // val_0 is forced up to the minimum, beyond its max change setting
+    staking.edit_validator(val_0, dec!(0.0).into(), 1.into(), vec![].try_into()?)?;
+    assert_eq!(
+        staking.get(val_0)?.commission.rate,
+        Decimal::from(dec!(0.05))
+    );
+
+    staking
+        .edit_validator(val_1, dec!(0.04).into(), 1.into(), vec![].try_into()?)
+        .expect_err("Should not be able to edit below the minimum commission rate");
+
+    Context::add(Time::from_seconds(EDIT_INTERVAL_SECONDS as i64));
+    staking
+        .edit_validator(val_0, dec!(0.04).into(), 1.into(), vec![].try_into()?)
+        .expect_err("Should not be able to edit below the minimum commission rate");
+    staking.edit_validator(val_0, dec!(0.06).into(), 1.into(), vec![].try_into()?)?;
+    assert_eq!(
+        staking.get(val_0)?.commission.rate,
+        Decimal::from(dec!(0.06))
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn validator_count() -> Result<()> {
+    let mut staking = setup_state()?;
+    staking.max_validators = 2;
+    assert_eq!(staking.validator_count()?, ValidatorCounts::default());
+
+    let vals: Vec<_> = (0..4).map(|i| Address::from_pubkey([i; 33])).collect();
+    for (i, val) in vals.iter().enumerate() {
+        staking.declare(
+            *val,
+            Declaration {
+                consensus_key: [i as u8; 32],
+                commission: Commission {
+                    rate: dec!(0.0).into(),
+                    max: dec!(1.0).into(),
+                    max_change: dec!(0.1).into(),
+                },
+                amount: Amount::new(100 * (i as u64 + 1)),
+                min_self_delegation: 1.into(),
+                validator_info: vec![].try_into()?,
+            },
+            Amount::new(100 * (i as u64 + 1)).into(),
+        )?;
+    }
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(
+        staking.validator_count()?,
+        ValidatorCounts {
+            total: 4,
+            active: 2,
+            jailed: 0,
+        }
+    );
+    assert_eq!(staking.active_set_size()?, 2);
+
+    // the jailed validator is replaced in the active set
+    staking.punish_downtime(vals[3])?;
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(
+        staking.validator_count()?,
+        ValidatorCounts {
+            total: 4,
+            active: 2,
+            jailed: 1,
+        }
+    );
+
+    // tombstoned validators are counted as jailed
+    staking.punish_double_sign(vals[2])?;
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(
+        staking.validator_count()?,
+        ValidatorCounts {
+            total: 4,
+            active: 2,
+            jailed: 2,
+        }
+    );
+    assert_eq!(staking.active_set_size()?, 2);
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn claimable_rewards() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    let val_1 = Address::from_pubkey([1; 33]);
+    let staker = Address::from_pubkey([2; 33]);
+
+    for (i, val) in [val_0, val_1].into_iter().enumerate() {
+        staking.declare(
+            val,
+            Declaration {
+                consensus_key: [i as u8; 32],
+                commission: Commission {
+                    rate: dec!(0.0).into(),
+                    max: dec!(1.0).into(),
+                    max_change: dec!(0.1).into(),
+                },
+                amount: Amount::new(100),
+                min_self_delegation: 1.into(),
+                validator_info: vec![].try_into()?,
+            },
+            Amount::new(100).into(),
+        )?;
+        staking.delegate(val, staker, 100.into())?;
+    }
+    staking.end_block_step(&Default::default())?;
+    assert!(staking.claimable_rewards(staker)?.is_empty());
+
+    staking.give(Simp::mint(400))?;
+    staking.give(Alt::mint(200))?;
+    staking.end_block_step(&Default::default())?;
+
+    let mut claimable = staking.claimable_rewards(staker)?;
+    claimable.sort_by_key(|(val, _)| *val);
+    let mut expected: Vec<(Address, Vec<(u8, Amount)>)> = vec![
+        (
+            val_0,
+            vec![(Simp::INDEX, 100.into()), (Alt::INDEX, 50.into())],
+        ),
+        (
+            val_1,
+            vec![(Simp::INDEX, 100.into()), (Alt::INDEX, 50.into())],
+        ),
+    ];
+    expected.sort_by_key(|(val, _)| *val);
+    assert_eq!(claimable, expected);
+
+    // querying does not settle or claim the rewards
+    assert_eq!(staking.claimable_rewards(staker)?.len(), 2);
+    assert_eq!(simp_balance(&staking.get(val_0)?.get(staker)?.liquid), 100);
+
+    Context::add(Signer {
+        signer: Some(staker),
+    });
+    Context::add(Paid::default());
+    staking.claim_all()?;
+    let paid = Context::resolve::<Paid>().unwrap();
+    assert_eq!(paid.balance::<Simp>()?, 200);
+    assert_eq!(paid.balance::<Alt>()?, 100);
+    assert!(staking.claimable_rewards(staker)?.is_empty());
+
+    Context::remove::<Signer>();
+    Context::remove::<Paid>();
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn withdraw_address() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    let staker = Address::from_pubkey([1; 33]);
+    let recipient = Address::from_pubkey([2; 33]);
+
+    staking.declare(
+        val_0,
+        Declaration {
+            consensus_key: [0; 32],
+            commission: Commission {
+                rate: dec!(0.0).into(),
+                max: dec!(1.0).into(),
+                max_change: dec!(0.1).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        },
+        Amount::new(100).into(),
+    )?;
+    staking.delegate(val_0, staker, 100.into())?;
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(staking.withdraw_address(staker)?, staker);
+
+    Context::add(Signer {
+        signer: Some(staker),
+    });
+    staking.set_withdraw_address(recipient)?;
+    assert_eq!(staking.withdraw_address(staker)?, recipient);
+
+    staking.give(Simp::mint(100))?;
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(simp_balance(&staking.get(val_0)?.get(staker)?.liquid), 50);
+
+    // the delegator's claim does not fund the delegator's call
+    Context::add(Paid::default());
+    staking.claim_all()?;
+    assert_eq!(simp_balance(&staking.get(val_0)?.get(staker)?.liquid), 0);
+    assert_eq!(Context::resolve::<Paid>().unwrap().balance::<Simp>()?, 0);
+
+    // the withdrawal address receives the rewards on its own claim
+    Context::add(Signer {
+        signer: Some(recipient),
+    });
+    Context::add(Paid::default());
+    staking.claim_all()?;
+    assert_eq!(Context::resolve::<Paid>().unwrap().balance::<Simp>()?, 50);
+
+    Context::add(Paid::default());
+    staking.claim_all()?;
+    assert_eq!(Context::resolve::<Paid>().unwrap().balance::<Simp>()?, 0);
+
+    // resetting to the delegator's own address restores the default
+    Context::add(Signer {
+        signer: Some(staker),
+    });
+    staking.set_withdraw_address(staker)?;
+    assert_eq!(staking.withdraw_address(staker)?, staker);
+
+    staking.give(Simp::mint(100))?;
+    staking.end_block_step(&Default::default())?;
+    Context::add(Paid::default());
+    staking.claim_all()?;
+    assert_eq!(Context::resolve::<Paid>().unwrap().balance::<Simp>()?, 50);
+
+    Context::remove::<Signer>();
+    Context::remove::<Paid>();
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn withdraw_address_unbonded_principal() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    let staker = Address::from_pubkey([1; 33]);
+    let recipient = Address::from_pubkey([2; 33]);
+
+    staking.declare(
+        val_0,
+        Declaration {
+            consensus_key: [0; 32],
+            commission: Commission {
+                rate: dec!(0.0).into(),
+                max: dec!(1.0).into(),
+                max_change: dec!(0.1).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        },
+        Amount::new(100).into(),
+    )?;
+    staking.delegate(val_0, staker, 100.into())?;
+    staking.end_block_step(&Default::default())?;
+
+    Context::add(Signer {
+        signer: Some(staker),
+    });
+    staking.set_withdraw_address(recipient)?;
+
+    staking.give(Simp::mint(100))?;
+    staking.end_block_step(&Default::default())?;
+    staking.unbond(val_0, staker, 60)?;
+    Context::add(Time::from_seconds(10));
+    staking.end_block_step(&Default::default())?;
+    assert_eq!(simp_balance(&staking.get(val_0)?.get(staker)?.liquid), 110);
+
+    // the matured principal is paid to the delegator, and only the rewards
+    // are held for the withdrawal address
+    Context::add(Paid::default());
+    staking.claim_all()?;
+    assert_eq!(Context::resolve::<Paid>().unwrap().balance::<Simp>()?, 60);
+
+    Context::add(Signer {
+        signer: Some(recipient),
+    });
+    Context::add(Paid::default());
+    staking.claim_all()?;
+    assert_eq!(Context::resolve::<Paid>().unwrap().balance::<Simp>()?, 50);
+
+    Context::remove::<Signer>();
+    Context::remove::<Paid>();
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn consensus_key_proof() -> Result<()> {
+    use ed25519_dalek::{Signer as _, SigningKey};
+
+    let mut staking = setup_state()?;
+    staking.require_consensus_key_proof = true;
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    let val_1 = Address::from_pubkey([1; 33]);
+    let cons_key = SigningKey::from_bytes(&[7; 32]);
+    let other_key = SigningKey::from_bytes(&[8; 32]);
+
+    let declaration = || -> Result<Declaration> {
+        Ok(Declaration {
+            consensus_key: cons_key.verifying_key().to_bytes(),
+            commission: Commission {
+                rate: dec!(0.0).into(),
+                max: dec!(1.0).into(),
+                max_change: dec!(0.1).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        })
+    };
+    let proof = |key: &SigningKey, chain_id: &str, address: Address| {
+        key.sign(&consensus_key_proof_bytes(chain_id, address))
+            .to_bytes()
+    };
+
+    Context::add(ChainId("test-chain".to_string()));
+
+    staking
+        .declare(val_0, declaration()?, 100.into())
+        .expect_err("Should not be able to declare without a proof");
+    staking
+        .declare_with_proof(
+            val_0,
+            declaration()?,
+            Some(proof(&other_key, "test-chain", val_0)),
+            100.into(),
+        )
+        .expect_err("Should not be able to declare with a proof by another key");
+    staking
+        .declare_with_proof(
+            val_0,
+            declaration()?,
+            Some(proof(&cons_key, "test-chain", val_1)),
+            100.into(),
+        )
+        .expect_err("Should not be able to declare with a proof for another address");
+    staking
+        .declare_with_proof(
+            val_0,
+            declaration()?,
+            Some(proof(&cons_key, "other-chain", val_0)),
+            100.into(),
+        )
+        .expect_err("Should not be able to declare with a proof for another chain");
+    assert!(staking
+        .address_by_consensus_key(cons_key.verifying_key().to_bytes())?
+        .is_none());
+
+    staking.declare_with_proof(
+        val_0,
+        declaration()?,
+        Some(proof(&cons_key, "test-chain", val_0)),
+        100.into(),
+    )?;
+    assert_eq!(
+        staking.address_by_consensus_key(cons_key.verifying_key().to_bytes())?,
+        Some(val_0)
+    );
+
+    Context::remove::<ChainId>();
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn active_set_tie_break() -> Result<()> {
+    let declare = |staking: &mut Staking<Simp>, i: u8| -> Result<()> {
+        staking.declare(
+            Address::from_pubkey([i; 33]),
+            Declaration {
+                consensus_key: [i; 32],
+                commission: Commission {
+                    rate: dec!(0.0).into(),
+                    max: dec!(1.0).into(),
+                    max_change: dec!(0.1).into(),
+                },
+                amount: Amount::new(100),
+                min_self_delegation: 1.into(),
+                validator_info: vec![].try_into()?,
+            },
+            Amount::new(100).into(),
+        )
+    };
+
+    // declares validators in the given batches, ending a block after each,
+    // and returns the consensus keys of the resulting active set
+    let select = |batches: &[&[u8]]| -> Result<Vec<[u8; 32]>> {
+        Context::add(Validators::new(
+            Rc::new(RefCell::new(Some(EntryMap::new()))),
+            Rc::new(RefCell::new(Some(Default::default()))),
+        ));
+        Context::add(Time::from_seconds(0));
+
+        let mut staking: Staking<Simp> = Staking {
+            max_validators: 3,
+            ..Default::default()
+        };
+        for batch in batches {
+            for i in batch.iter() {
+                declare(&mut staking, *i)?;
+            }
+            staking.end_block_step(&Default::default())?;
+        }
+
+        let ctx = Context::resolve::<Validators>().unwrap();
+        let mut active: Vec<[u8; 32]> = ctx
+            .updates
+            .iter()
+            .filter(|(_, update)| update.power > 0)
+            .map(|(key, _)| *key)
+            .collect();
+        active.sort();
+
+        Ok(active)
+    };
+
+    // with equal power, the validators with the lowest addresses are selected
+    let mut by_address: Vec<u8> = (1..=4).collect();
+    by_address.sort_by_key(|i| Address::from_pubkey([*i; 33]).bytes());
+    let mut expected: Vec<[u8; 32]> = by_address[..3].iter().map(|i| [*i; 32]).collect();
+    expected.sort();
+
+    assert_eq!(select(&[&[1, 2, 3, 4]])?, expected);
+    assert_eq!(select(&[&[4, 3, 2, 1]])?, expected);
+    assert_eq!(select(&[&[2, 4], &[3, 1]])?, expected);
+
+    // incumbency does not affect the selection at the cutoff
+    let incumbents: Vec<u8> = (1..=4).filter(|i| *i != by_address[0]).collect();
+    assert_eq!(select(&[&incumbents[..], &[by_address[0]]])?, expected);
+
+    Ok(())
+}
+
+#[cfg(feature = "abci")]
+#[test]
+#[serial]
+fn delegator_count() -> Result<()> {
+    let mut staking = setup_state()?;
+
+    let val_0 = Address::from_pubkey([0; 33]);
+    let stakers: Vec<Address> = (1..=3).map(|i| Address::from_pubkey([i; 33])).collect();
+
+    staking.declare(
+        val_0,
+        Declaration {
+            consensus_key: [0; 32],
+            commission: Commission {
+                rate: dec!(0.0).into(),
+                max: dec!(1.0).into(),
+                max_change: dec!(0.1).into(),
+            },
+            amount: Amount::new(100),
+            min_self_delegation: 1.into(),
+            validator_info: vec![].try_into()?,
+        },
+        Amount::new(100).into(),
+    )?;
+    let count = |staking: &Staking<Simp>| -> Result<u64> {
+        Ok(staking.get(val_0)?.query_info()?.delegator_count)
+    };
+    assert_eq!(count(&staking)?, 1);
+
+    for staker in stakers.iter() {
+        staking.delegate(val_0, *staker, 100.into())?;
+    }
+    staking.delegate(val_0, stakers[0], 50.into())?;
+    assert_eq!(count(&staking)?, 4);
+
+    // partial unbonds keep the delegator counted, full unbonds remove it
+    staking.unbond(val_0, stakers[0], 100)?;
+    assert_eq!(count(&staking)?, 4);
+    staking.unbond(val_0, stakers[1], 100)?;
+    assert_eq!(count(&staking)?, 3);
+    assert_eq!(
+        staking.all_validators()?[0].delegator_count,
+        staking
+            .validator_delegations(val_0)?
+            .iter()
+            .filter(|(_, info)| info.staked > 0)
+            .count() as u64
+    );
+
+    // delegating again after a full unbond counts the delegator again
+    staking.delegate(val_0, stakers[1], 10.into())?;
+    assert_eq!(count(&staking)?, 4);
+
+    // a full slash zeroes every stake
+    staking.slash_fraction_downtime = Decimal::one();
+    staking.punish_downtime(val_0)?;
+    assert_eq!(count(&staking)?, 0);
+
+    Ok(())
+}
@@ -1,12 +1,15 @@
 use crate::coins::pool::{Child as PoolChild, ChildMut as PoolChildMut};
-use crate::coins::{Address, Amount, Balance, Coin, Decimal, Give, Pool, Symbol, VersionedAddress};
+use crate::coins::{
+    Address, Amount, Balance, Coin, Decimal, Give, Pool, Slash, Symbol, VersionedAddress,
+};
 use crate::context::GetContext;
 use crate::encoding::{Decode, Encode, LengthVec};
+use crate::migrate::MigrateFrom;
 use crate::orga;
 use crate::plugins::Time;
 use crate::{Error, Result};
 
-use super::{Commission, Delegator, Redelegation};
+use super::{Commission, Delegator};
 
 /// [Pool] of [Delegator] indexed by validator [Address]
 type Delegators<S> = Pool<Address, Delegator<S>, S>;
@@ -15,7 +18,7 @@ type Delegators<S> = Pool<Address, Delegator<S>, S>;
 ///
 /// A `Validator` tracks the staking state of a single validator, including its
 /// commission rate, delegators, and unbonding status.
-#[orga]
+#[orga(version = 1)]
 pub struct Validator<S: Symbol> {
     /// If the validator is jailed, the time (in unix seconds) when it will be
     /// eligible to unjail.
@@ -40,6 +43,37 @@ pub struct Validator<S: Symbol> {
     pub(super) last_edited_seconds: i64,
     /// The minimum amount this validator must keep self-delegated to remain
     pub(super) min_self_delegation: Amount,
+    /// The number of delegators with a non-zero stake, maintained as
+    /// delegations change so it can be queried without iterating.
+    #[orga(version(V1))]
+    pub(super) delegator_count: u64,
+}
+
+impl<S: Symbol> MigrateFrom<ValidatorV0<S>> for ValidatorV1<S> {
+    fn migrate_from(value: ValidatorV0<S>) -> Result<Self> {
+        let mut delegator_count = 0;
+        for entry in value.delegators.iter()? {
+            let (_, delegator) = entry?;
+            if delegator.staked.amount()? > 0 {
+                delegator_count += 1;
+            }
+        }
+
+        Ok(Self {
+            jailed_until: value.jailed_until,
+            tombstoned: value.tombstoned,
+            address: value.address,
+            commission: value.commission,
+            delegators: value.delegators,
+            info: value.info,
+            in_active_set: value.in_active_set,
+            unbonding: value.unbonding,
+            unbonding_start_seconds: value.unbonding_start_seconds,
+            last_edited_seconds: value.last_edited_seconds,
+            min_self_delegation: value.min_self_delegation,
+            delegator_count,
+        })
+    }
 }
 
 /// Queryable information about a validator, aggregated for convenience.
@@ -69,6 +103,8 @@ pub struct ValidatorQueryInfo {
     pub jailed: bool,
     /// The total amount staked to this validator.
     pub amount_staked: Amount,
+    /// The number of delegators with a non-zero stake.
+    pub delegator_count: u64,
 }
 
 /// Metadata used for display purposes. Not parsed on-chain.
@@ -90,14 +126,6 @@ pub enum Status {
     },
 }
 
-/// Data required to slash redelegations from a single DVP.
-pub(super) struct SlashableRedelegation {
-    /// Delegator address
-    pub delegator_address: VersionedAddress,
-    /// Outbound redelegations that may be slashed.
-    pub outbound_redelegations: Vec<Redelegation>,
-}
-
 impl<S: Symbol + Default> Validator<S> {
     /// Returns a [PoolChildMut] for the given delegator address, resolving
     /// mutations efficiently on drop.
@@ -108,6 +136,29 @@ impl<S: Symbol + Default> Validator<S> {
         self.delegators.get_mut(address)
     }
 
+    /// Runs `op` on the delegator with the given address, updating the
+    /// validator's delegator count if the delegator's stake changes between
+    /// zero and non-zero.
+    pub(super) fn with_delegator<T, F>(&mut self, address: Address, op: F) -> Result<T>
+    where
+        F: FnOnce(&mut Delegator<S>) -> Result<T>,
+    {
+        let (was_staked, is_staked, res) = {
+            let mut delegator = self.get_mut(address)?;
+            let was_staked = delegator.staked.amount()? > 0;
+            let res = op(&mut delegator)?;
+            (was_staked, delegator.staked.amount()? > 0, res)
+        };
+
+        match (was_staked, is_staked) {
+            (false, true) => self.delegator_count += 1,
+            (true, false) => self.delegator_count = self.delegator_count.saturating_sub(1),
+            _ => {}
+        }
+
+        Ok(res)
+    }
+
     /// Returns a [PoolChild] for the given delegator address, ensuring
     /// correctness of the [Delegator] state on deref.
     pub fn get(&self, address: Address) -> Result<PoolChild<Delegator<S>, S>> {
@@ -183,32 +234,22 @@ impl<S: Symbol + Default> Validator<S> {
         Ok(())
     }
 
-    /// Slash all funds staked to the validator by the given `penalty`.
-    pub(super) fn slash(
-        &mut self,
-        penalty: Decimal,
-        liveness_fault: bool,
-    ) -> Result<Vec<SlashableRedelegation>> {
+    /// Slash all funds staked to the validator by the given `penalty`,
+    /// returning `false` if the validator has already been tombstoned.
+    ///
+    /// The slash is settled into each delegator the next time it is accessed.
+    pub(super) fn slash(&mut self, penalty: Decimal, liveness_fault: bool) -> Result<bool> {
+        check_slash_fraction(penalty)?;
         if self.tombstoned {
-            return Ok(vec![]);
+            return Ok(false);
         }
         if !liveness_fault {
             self.tombstoned = true;
         }
         let slash_multiplier = (Decimal::one() - penalty)?;
-        let delegator_keys = self.delegator_keys()?;
-        let mut redelegations = vec![];
-        delegator_keys.iter().try_for_each(|k| -> Result<()> {
-            let mut delegator = self.get_mut(*k)?;
-            let slashable_redelegations = delegator.slash(slash_multiplier, liveness_fault)?;
-            redelegations.push(SlashableRedelegation {
-                delegator_address: (*k).into(),
-                outbound_redelegations: slashable_redelegations,
-            });
-            Ok(())
-        })?;
-
-        Ok(redelegations)
+        Slash::slash(self, slash_multiplier, liveness_fault)?;
+
+        Ok(true)
     }
 
     /// Returns all addresses delegated to this validator.
@@ -241,6 +282,7 @@ impl<S: Symbol + Default> Validator<S> {
 
             jailed: self.jailed(),
             amount_staked: self.delegators.balance()?.amount()?,
+            delegator_count: self.delegator_count,
         })
     }
 
@@ -264,6 +306,17 @@ impl<S: Symbol + Default> Validator<S> {
     }
 }
 
+/// Checks that a slash fraction is between 0 and 1.
+pub(super) fn check_slash_fraction(fraction: Decimal) -> Result<()> {
+    if fraction < Decimal::zero() || fraction > Decimal::one() {
+        return Err(Error::Coins(
+            "Slash fraction must be between 0 and 1".into(),
+        ));
+    }
+
+    Ok(())
+}
+
 impl<S: Symbol> Balance<S, Decimal> for Validator<S> {
     fn balance(&self) -> Result<Decimal> {
         if self.jailed() || !self.in_active_set || self.below_required_self_delegation()? {
@@ -274,6 +327,19 @@ impl<S: Symbol> Balance<S, Decimal> for Validator<S> {
     }
 }
 
+impl<S: Symbol> Slash for Validator<S> {
+    fn slash(&mut self, multiplier: Decimal, liveness_fault: bool) -> Result<()> {
+        self.delegators.slash(multiplier, liveness_fault)?;
+        // a full slash zeroes every stake through the pool multiplier, without
+        // going through `with_delegator`
+        if multiplier == Decimal::zero() {
+            self.delegator_count = 0;
+        }
+
+        Ok(())
+    }
+}
+
 impl<S: Symbol, T: Symbol> Give<Coin<T>> for Validator<S> {
     fn give(&mut self, coins: Coin<T>) -> Result<()> {
         let one: Decimal = 1.into();
@@ -0,0 +1,265 @@
+//! A fixed-capacity double-ended queue backed by a store
+use serde::Serialize;
+
+use super::deque::{Deque, Iter};
+use super::map::{ReadOnly, Ref};
+use crate::call::Call;
+use crate::describe::{Builder, Describe, Descriptor};
+use crate::migrate::Migrate;
+use crate::query::{FieldQuery, MethodQuery};
+use crate::state::State;
+use crate::store::Store;
+use crate::Result;
+
+/// A [Deque] which holds at most `N` elements, evicting the oldest element
+/// when pushing onto a full deque.
+///
+/// This is useful for rolling buffers of recent data, e.g. recent block hashes.
+/// The storage layout is the same as the underlying [Deque].
+pub struct BoundedDeque<T, const N: usize> {
+    deque: Deque<T>,
+}
+
+impl<T, const N: usize> BoundedDeque<T, N> {
+    /// Create a new empty bounded deque.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum number of elements the deque can hold.
+    pub const fn capacity(&self) -> u64 {
+        N as u64
+    }
+}
+
+impl<T: State, const N: usize> BoundedDeque<T, N> {
+    /// Create a new bounded deque with the provided backing store.
+    pub fn with_store(store: Store) -> Result<Self> {
+        Ok(Self {
+            deque: Deque::with_store(store)?,
+        })
+    }
+
+    /// The number of elements in the deque.
+    pub fn len(&self) -> u64 {
+        self.deque.len()
+    }
+
+    /// Returns `true` if the deque contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.deque.is_empty()
+    }
+
+    /// Returns `true` if the deque contains `N` elements, meaning the next push
+    /// will evict the oldest element.
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.capacity()
+    }
+
+    /// Returns the element at the given index, where index 0 is the oldest
+    /// element, or `None` if the index is out of bounds.
+    pub fn get(&self, index: u64) -> Result<Option<Ref<T>>> {
+        self.deque.get(index)
+    }
+
+    /// Returns the oldest element, or `None` if the deque is empty.
+    pub fn front(&self) -> Result<Option<Ref<T>>> {
+        self.deque.front()
+    }
+
+    /// Returns the newest element, or `None` if the deque is empty.
+    pub fn back(&self) -> Result<Option<Ref<T>>> {
+        self.deque.back()
+    }
+
+    /// Push a value onto the back of the deque. If the deque is full, the
+    /// oldest element is removed and returned.
+    pub fn push(&mut self, value: T) -> Result<Option<ReadOnly<T>>> {
+        if N == 0 {
+            return Ok(Some(ReadOnly::new(value)));
+        }
+
+        let mut evicted = None;
+        while self.is_full() {
+            evicted = self.deque.pop_front()?;
+        }
+        self.deque.push_back(value)?;
+
+        Ok(evicted)
+    }
+
+    /// Remove and return the oldest element, or `None` if the deque is empty.
+    pub fn pop_front(&mut self) -> Result<Option<ReadOnly<T>>> {
+        self.deque.pop_front()
+    }
+
+    /// Create an iterator over the elements of the deque, from oldest to
+    /// newest.
+    pub fn iter(&self) -> Result<Iter<T>> {
+        self.deque.iter()
+    }
+}
+
+impl<T, const N: usize> Default for BoundedDeque<T, N> {
+    fn default() -> Self {
+        Self {
+            deque: Deque::default(),
+        }
+    }
+}
+
+impl<T, const N: usize> std::fmt::Debug for BoundedDeque<T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BoundedDeque")
+            .field("capacity", &N)
+            .field("deque", &self.deque)
+            .finish()
+    }
+}
+
+impl<T: Serialize + State, const N: usize> Serialize for BoundedDeque<T, N> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.deque.serialize(serializer)
+    }
+}
+
+impl<T: State, const N: usize> State for BoundedDeque<T, N> {
+    fn attach(&mut self, store: Store) -> Result<()> {
+        self.deque.attach(store)
+    }
+
+    fn flush<W: std::io::Write>(self, out: &mut W) -> Result<()> {
+        self.deque.flush(out)
+    }
+
+    fn load(store: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            deque: Deque::load(store, bytes)?,
+        })
+    }
+}
+
+impl<T: State + Migrate, const N: usize> Migrate for BoundedDeque<T, N> {
+    fn migrate(src: Store, dest: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            deque: Deque::migrate(src, dest, bytes)?,
+        })
+    }
+}
+
+impl<T: State + Describe, const N: usize> Describe for BoundedDeque<T, N> {
+    fn describe() -> Descriptor {
+        Builder::new::<Self>()
+            .dynamic_child::<u64, T>(|mut query_bytes| {
+                query_bytes.extend_from_slice(&[129]);
+                query_bytes
+            })
+            .build()
+    }
+}
+
+// the query and call derive macros do not support const generics, so queries
+// and calls are delegated to the inner deque by hand
+impl<T, const N: usize> FieldQuery for BoundedDeque<T, N>
+where
+    Deque<T>: FieldQuery,
+{
+    type FieldQuery = <Deque<T> as FieldQuery>::FieldQuery;
+
+    fn field_query(&self, query: Self::FieldQuery) -> Result<()> {
+        self.deque.field_query(query)
+    }
+}
+
+impl<T, const N: usize> MethodQuery for BoundedDeque<T, N>
+where
+    Deque<T>: MethodQuery,
+{
+    type MethodQuery = <Deque<T> as MethodQuery>::MethodQuery;
+
+    fn method_query(&self, query: Self::MethodQuery) -> Result<()> {
+        self.deque.method_query(query)
+    }
+}
+
+impl<T, const N: usize> Call for BoundedDeque<T, N>
+where
+    Deque<T>: Call,
+{
+    type Call = <Deque<T> as Call>::Call;
+
+    fn call(&mut self, call: Self::Call) -> Result<()> {
+        self.deque.call(call)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::describe::Children;
+    use crate::store::Write;
+
+    #[test]
+    fn push_evicts_oldest() -> Result<()> {
+        let mut deque: BoundedDeque<u32, 3> = BoundedDeque::new();
+
+        assert!(deque.push(1)?.is_none());
+        assert!(deque.push(2)?.is_none());
+        assert!(deque.push(3)?.is_none());
+        assert!(deque.is_full());
+        assert_eq!(*deque.push(4)?.unwrap(), 1);
+
+        assert_eq!(deque.len(), 3);
+        let values: Vec<u32> = deque.iter()?.map(|v| *v.unwrap()).collect();
+        assert_eq!(values, vec![2, 3, 4]);
+        assert_eq!(*deque.front()?.unwrap(), 2);
+        assert_eq!(*deque.back()?.unwrap(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn store_roundtrip() -> Result<()> {
+        let mut store = Store::with_map_store();
+        let mut deque: BoundedDeque<u32, 2> = BoundedDeque::with_store(store.clone())?;
+        for i in 0..5 {
+            deque.push(i)?;
+        }
+
+        let mut bytes = vec![];
+        deque.flush(&mut bytes)?;
+        store.put(vec![], bytes.clone())?;
+
+        let mut deque: BoundedDeque<u32, 2> = BoundedDeque::load(store.clone(), &mut &bytes[..])?;
+        let values: Vec<u32> = deque.iter()?.map(|v| *v.unwrap()).collect();
+        assert_eq!(values, vec![3, 4]);
+
+        deque.push(5)?;
+        let mut bytes = vec![];
+        deque.flush(&mut bytes)?;
+        let deque: BoundedDeque<u32, 2> = BoundedDeque::load(store, &mut &bytes[..])?;
+        let values: Vec<u32> = deque.iter()?.map(|v| *v.unwrap()).collect();
+        assert_eq!(values, vec![4, 5]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn describe_delegates_to_deque() {
+        let desc = BoundedDeque::<u32, 2>::describe();
+        match (desc.children(), Deque::<u32>::describe().children()) {
+            (Children::Dynamic(child), Children::Dynamic(inner)) => {
+                assert_eq!(child.key_desc().type_name, inner.key_desc().type_name);
+                assert_eq!(child.value_desc().type_name, inner.value_desc().type_name);
+                assert_eq!(
+                    child.apply_query_bytes(vec![1]),
+                    inner.apply_query_bytes(vec![1])
+                );
+            }
+            _ => panic!("Incorrect children"),
+        }
+    }
+}
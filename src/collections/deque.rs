@@ -12,6 +12,7 @@ use crate::query::FieldQuery;
 use crate::state::State;
 use crate::store::Store;
 use crate::Result;
+use std::ops::{Bound, RangeBounds};
 
 /// A double-ended queue implementation backed by a [Map].
 ///
@@ -264,10 +265,38 @@ impl<T: State> Deque<T> {
 }
 
 impl<'a, T: State> Deque<T> {
-    /// Create an iterator over the elements of the deque.
+    /// Create an iterator over the elements of the deque, from front to back.
+    ///
+    /// Pending in-memory pushes and pops are taken into account, as with
+    /// [Map::iter].
     pub fn iter(&'a self) -> Result<Iter<'a, T>> {
+        self.range(..)
+    }
+
+    /// Create an iterator over the elements of the deque within the given
+    /// range of indices, where index 0 is the front of the deque.
+    ///
+    /// Bounds past the back of the deque are clamped, so e.g.
+    /// `deque.range(2..)` yields nothing for a deque with fewer than 3
+    /// elements.
+    pub fn range<B: RangeBounds<u64>>(&'a self, range: B) -> Result<Iter<'a, T>> {
+        let (head, tail) = (self.meta.head, self.meta.tail);
+
+        let end = match range.end_bound() {
+            Bound::Included(index) => head.saturating_add(*index).saturating_add(1),
+            Bound::Excluded(index) => head.saturating_add(*index),
+            Bound::Unbounded => tail,
+        }
+        .min(tail);
+        let start = match range.start_bound() {
+            Bound::Included(index) => head.saturating_add(*index),
+            Bound::Excluded(index) => head.saturating_add(*index).saturating_add(1),
+            Bound::Unbounded => head,
+        }
+        .min(end);
+
         Ok(Iter {
-            map_iter: self.map.iter()?,
+            map_iter: self.map.range(start..end)?,
         })
     }
 }
@@ -329,6 +358,24 @@ impl<T: State> Deque<T> {
         self.get_mut(self.len() - 1)
     }
 
+    /// Creates an iterator which removes and yields elements from the front of
+    /// the deque for as long as they satisfy the predicate.
+    ///
+    /// Elements are only removed as they are yielded, so the first element
+    /// which does not satisfy the predicate (and everything after it) is left
+    /// in the deque, as are any elements remaining if the iterator is dropped
+    /// early. Removals are retained in memory until the deque is flushed.
+    pub fn drain_while<F>(&mut self, predicate: F) -> DrainWhile<'_, T, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        DrainWhile {
+            deque: self,
+            predicate,
+            done: false,
+        }
+    }
+
     /// Swap two elements in the deque by their indices.
     pub fn swap(&mut self, i: u64, j: u64) -> Result<()> {
         let i = i + self.meta.head;
@@ -397,9 +444,50 @@ where
     }
 }
 
+/// An iterator which removes elements from the front of a [Deque] while they
+/// satisfy a predicate, created by [Deque::drain_while].
+pub struct DrainWhile<'a, T, F>
+where
+    T: State,
+    F: FnMut(&T) -> bool,
+{
+    deque: &'a mut Deque<T>,
+    predicate: F,
+    done: bool,
+}
+
+impl<'a, T, F> Iterator for DrainWhile<'a, T, F>
+where
+    T: State,
+    F: FnMut(&T) -> bool,
+{
+    type Item = Result<ReadOnly<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let matches = match self.deque.front() {
+            Ok(Some(front)) => (self.predicate)(&*front),
+            Ok(None) => false,
+            Err(err) => {
+                self.done = true;
+                return Some(Err(err));
+            }
+        };
+        if !matches {
+            self.done = true;
+            return None;
+        }
+
+        self.deque.pop_front().transpose()
+    }
+}
+
 #[allow(unused_imports)]
 mod test {
-    use super::{Deque, Map, Meta};
+    use super::{Deque, Iter, Map, Meta};
     use crate::state::State;
     use crate::store::MapStore;
     use crate::store::Store;
@@ -776,4 +864,107 @@ mod test {
         let mut iter = deque.iter().unwrap();
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn deque_drain_while() {
+        let mut deque: Deque<u32> = Deque::new();
+        for i in 1..=5 {
+            deque.push_back(i).unwrap();
+        }
+
+        let drained: Vec<u32> = deque.drain_while(|x| *x < 4).map(|x| *x.unwrap()).collect();
+        assert_eq!(drained, vec![1, 2, 3]);
+        assert_eq!(deque.len(), 2);
+
+        let mut iter = deque.iter().unwrap();
+        assert_eq!(*iter.next().unwrap().unwrap(), 4);
+        assert_eq!(*iter.next().unwrap().unwrap(), 5);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn deque_drain_while_early_return() {
+        let mut deque: Deque<u32> = Deque::new();
+        for i in 1..=5 {
+            deque.push_back(i).unwrap();
+        }
+
+        assert_eq!(deque.drain_while(|x| *x > 1).count(), 0);
+        assert_eq!(deque.len(), 5);
+
+        let mut drain = deque.drain_while(|_| true);
+        assert_eq!(*drain.next().unwrap().unwrap(), 1);
+        assert_eq!(*drain.next().unwrap().unwrap(), 2);
+        drop(drain);
+        assert_eq!(deque.len(), 3);
+        assert_eq!(*deque.front().unwrap().unwrap(), 3);
+
+        assert_eq!(deque.drain_while(|_| true).count(), 3);
+        assert!(deque.is_empty());
+        assert!(deque.drain_while(|_| true).next().is_none());
+    }
+
+    #[test]
+    fn deque_drain_while_store() -> crate::Result<()> {
+        let mut store = Store::with_map_store().sub(&[123]);
+        let mut deque: Deque<u32> = Deque::new();
+        deque.attach(store.clone())?;
+        for i in 1..=5 {
+            deque.push_back(i)?;
+        }
+
+        use crate::store::Write;
+        let mut bytes = vec![];
+        deque.flush(&mut bytes)?;
+        store.put(vec![], bytes.clone()).unwrap();
+
+        let mut deque: Deque<u32> = Deque::load(store.clone(), &mut &bytes[..])?;
+        assert_eq!(deque.drain_while(|x| *x <= 2).count(), 2);
+
+        let mut bytes = vec![];
+        deque.flush(&mut bytes)?;
+        let deque: Deque<u32> = Deque::load(store, &mut &bytes[..])?;
+        assert_eq!(deque.len(), 3);
+        assert_eq!(*deque.front()?.unwrap(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn deque_iter_range_unflushed() -> crate::Result<()> {
+        use crate::store::Write;
+
+        let mut store = Store::with_map_store();
+        let mut deque: Deque<u32> = Deque::with_store(store.clone())?;
+        for i in 1..=3 {
+            deque.push_back(i)?;
+        }
+        let mut bytes = vec![];
+        deque.flush(&mut bytes)?;
+        store.put(vec![], bytes.clone())?;
+
+        // 2 and 3 are persisted, the rest are only in memory
+        let mut deque: Deque<u32> = Deque::load(store, &mut &bytes[..])?;
+        deque.pop_front()?;
+        deque.push_back(4)?;
+        deque.push_back(5)?;
+        deque.push_front(0)?;
+
+        let collect = |iter: Iter<u32>| -> crate::Result<Vec<u32>> {
+            iter.map(|value| Ok(*value?)).collect()
+        };
+        assert_eq!(collect(deque.iter()?)?, vec![0, 2, 3, 4, 5]);
+        assert_eq!(collect(deque.range(1..3)?)?, vec![2, 3]);
+        assert_eq!(collect(deque.range(2..=3)?)?, vec![3, 4]);
+        assert_eq!(collect(deque.range(..2)?)?, vec![0, 2]);
+        assert_eq!(collect(deque.range(3..)?)?, vec![4, 5]);
+        assert_eq!(collect(deque.range(3..100)?)?, vec![4, 5]);
+        assert!(collect(deque.range(5..)?)?.is_empty());
+        assert!(collect(deque.range(4..2)?)?.is_empty());
+
+        let rev: Vec<u32> = deque.range(1..4)?.rev().map(|v| *v.unwrap()).collect();
+        assert_eq!(rev, vec![4, 3, 2]);
+
+        Ok(())
+    }
 }
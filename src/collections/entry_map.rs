@@ -8,7 +8,7 @@ use super::map::ReadOnly;
 use crate::describe::Describe;
 use crate::encoding::{Decode, Encode, Terminated};
 use crate::migrate::Migrate;
-use std::ops::RangeBounds;
+use std::ops::{Bound, RangeBounds};
 
 use super::{Entry, Next};
 use crate::call::FieldCall;
@@ -176,6 +176,11 @@ where
     }
 
     /// Create an iterator over the entries within a given range.
+    ///
+    /// Entries are ordered by the encoding of their keys, so for composite
+    /// keys the range is over the key fields in declaration order, e.g.
+    /// `..(t, [0; 20])` seeks to all entries whose first key field is below
+    /// `t`.
     pub fn range<B: RangeBounds<T::Key>>(&'a self, range: B) -> Result<Iter<'a, T>> {
         Ok(Iter {
             map_iter: self.map.range(range)?,
@@ -183,6 +188,137 @@ where
     }
 }
 
+impl<T: Entry> EntryMap<T>
+where
+    T::Key: Next + Decode + Encode + Terminated + Clone,
+    T::Value: State + Clone,
+{
+    /// Calls `op` with a mutable reference to each entry within the given
+    /// range, in key order, writing back any changes to the entry.
+    ///
+    /// If `op` changes an entry's key, the entry is moved to its new key
+    /// (replacing any entry already at that key). Entries are read before any
+    /// are written back, so entries moved by `op` are not visited again.
+    pub fn range_mut<B, F>(&mut self, range: B, mut op: F) -> Result<()>
+    where
+        B: RangeBounds<T::Key>,
+        F: FnMut(&mut T) -> Result<()>,
+    {
+        let entries = self
+            .map
+            .range(range)?
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok(((*key).clone(), (*value).clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for (key, value) in entries {
+            let mut entry = T::from_entry((key.clone(), value));
+            op(&mut entry)?;
+
+            let (new_key, new_value) = entry.into_entry();
+            if new_key.encode()? != key.encode()? {
+                self.map.remove(key)?;
+            }
+            self.map.insert(new_key, new_value)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Entry> EntryMap<T>
+where
+    T::Key: Next + Decode + Encode + Terminated + Clone,
+    T::Value: State + Clone,
+{
+    /// Creates an iterator which removes and yields all entries in key order.
+    ///
+    /// Entries are only removed as they are yielded, so any entries remaining
+    /// if the iterator is dropped early are left in the map. Removals are
+    /// retained in memory until the map is flushed.
+    pub fn drain(&mut self) -> DrainWhile<'_, T, fn(&T) -> bool> {
+        self.drain_while((|_| true) as fn(&T) -> bool)
+    }
+
+    /// Creates an iterator which removes and yields entries in key order for as
+    /// long as they satisfy the predicate.
+    ///
+    /// The first entry which does not satisfy the predicate (and everything
+    /// after it) is left in the map.
+    pub fn drain_while<F>(&mut self, predicate: F) -> DrainWhile<'_, T, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        DrainWhile {
+            map: &mut self.map,
+            start: Some(Bound::Unbounded),
+            predicate,
+        }
+    }
+}
+
+/// An iterator which removes entries from an [EntryMap] in key order, created
+/// by [EntryMap::drain] or [EntryMap::drain_while].
+pub struct DrainWhile<'a, T: Entry, F>
+where
+    T::Key: Next + Decode + Encode + Terminated + Clone + 'static,
+    T::Value: State + Clone,
+    F: FnMut(&T) -> bool,
+{
+    map: &'a mut Map<T::Key, T::Value>,
+    // the key to resume from, which skips the entries already removed in
+    // memory rather than iterating over them on each call
+    start: Option<Bound<T::Key>>,
+    predicate: F,
+}
+
+impl<'a, T: Entry, F> DrainWhile<'a, T, F>
+where
+    T::Key: Next + Decode + Encode + Terminated + Clone + 'static,
+    T::Value: State + Clone,
+    F: FnMut(&T) -> bool,
+{
+    fn next_entry(&mut self) -> Result<Option<ReadOnly<T>>> {
+        let start = match self.start.take() {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+
+        let (key, value) = match self.map.range((start, Bound::Unbounded))?.next() {
+            Some(entry) => {
+                let (key, value) = entry?;
+                ((*key).clone(), (*value).clone())
+            }
+            None => return Ok(None),
+        };
+
+        let entry = T::from_entry((key.clone(), value));
+        if !(self.predicate)(&entry) {
+            return Ok(None);
+        }
+
+        self.start = key.next().map(Bound::Included);
+        self.map.remove(key)?;
+
+        Ok(Some(ReadOnly::new(entry)))
+    }
+}
+
+impl<'a, T: Entry, F> Iterator for DrainWhile<'a, T, F>
+where
+    T::Key: Next + Decode + Encode + Terminated + Clone + 'static,
+    T::Value: State + Clone,
+    F: FnMut(&T) -> bool,
+{
+    type Item = Result<ReadOnly<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
 /// An iterator over the entries of an [EntryMap].
 pub struct Iter<'a, T: Entry>
 where
@@ -614,4 +750,173 @@ mod tests {
 
         assert!(result);
     }
+
+    #[test]
+    fn drain() {
+        let (store, mut entry_map) = setup();
+
+        entry_map.insert(MapEntry { key: 12, value: 24 }).unwrap();
+        entry_map.insert(MapEntry { key: 13, value: 26 }).unwrap();
+        let mut buf = vec![];
+        entry_map.flush(&mut buf).unwrap();
+
+        let mut entry_map: EntryMap<MapEntry> = EntryMap::with_store(store.clone()).unwrap();
+        entry_map.insert(MapEntry { key: 14, value: 28 }).unwrap();
+
+        let drained: Vec<MapEntry> = entry_map
+            .drain()
+            .map(|entry| entry.unwrap().into_inner())
+            .collect();
+        assert_eq!(
+            drained,
+            vec![
+                MapEntry { key: 12, value: 24 },
+                MapEntry { key: 13, value: 26 },
+                MapEntry { key: 14, value: 28 },
+            ]
+        );
+        assert!(entry_map.iter().unwrap().next().is_none());
+
+        entry_map.flush(&mut buf).unwrap();
+        let entry_map: EntryMap<MapEntry> = EntryMap::with_store(store).unwrap();
+        assert!(entry_map.iter().unwrap().next().is_none());
+    }
+
+    #[test]
+    fn drain_while_early_return() {
+        let (_store, mut entry_map) = setup();
+
+        entry_map.insert(MapEntry { key: 12, value: 24 }).unwrap();
+        entry_map.insert(MapEntry { key: 13, value: 26 }).unwrap();
+        entry_map.insert(MapEntry { key: 14, value: 28 }).unwrap();
+
+        let drained: Vec<MapEntry> = entry_map
+            .drain_while(|entry| entry.key < 13)
+            .map(|entry| entry.unwrap().into_inner())
+            .collect();
+        assert_eq!(drained, vec![MapEntry { key: 12, value: 24 }]);
+
+        let remaining: Vec<MapEntry> = entry_map
+            .iter()
+            .unwrap()
+            .map(|entry| entry.unwrap().into_inner())
+            .collect();
+        assert_eq!(
+            remaining,
+            vec![
+                MapEntry { key: 13, value: 26 },
+                MapEntry { key: 14, value: 28 },
+            ]
+        );
+    }
+
+    fn multi_key_entries() -> Vec<MultiKeyMapEntry> {
+        [
+            (0, 0, 1),
+            (0, 1, 0),
+            (0, 1, 5),
+            (1, 0, 0),
+            (1, 0, 1),
+            (2, 0, 0),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (key_1, key_2, key_3))| MultiKeyMapEntry {
+            key_1,
+            key_2,
+            key_3,
+            value: i as u32,
+        })
+        .collect()
+    }
+
+    fn range_keys<B: RangeBounds<(u32, u8, u16)>>(
+        entry_map: &EntryMap<MultiKeyMapEntry>,
+        range: B,
+    ) -> Vec<(u32, u8, u16)> {
+        entry_map
+            .range(range)
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (entry.key_1, entry.key_2, entry.key_3)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn range_multi_key() {
+        let (store, mut entry_map) = setup();
+        for entry in multi_key_entries() {
+            entry_map.insert(entry).unwrap();
+        }
+        let mut buf = vec![];
+        entry_map.flush(&mut buf).unwrap();
+        let entry_map: EntryMap<MultiKeyMapEntry> = EntryMap::with_store(store).unwrap();
+
+        // all entries with a first key field below 1
+        assert_eq!(
+            range_keys(&entry_map, ..(1, 0, 0)),
+            vec![(0, 0, 1), (0, 1, 0), (0, 1, 5)]
+        );
+        assert_eq!(
+            range_keys(&entry_map, ..=(1, 0, 0)),
+            vec![(0, 0, 1), (0, 1, 0), (0, 1, 5), (1, 0, 0)]
+        );
+        assert_eq!(
+            range_keys(&entry_map, (0, 1, 0)..(1, 0, 1)),
+            vec![(0, 1, 0), (0, 1, 5), (1, 0, 0)]
+        );
+        assert_eq!(
+            range_keys(&entry_map, (0, 1, 1)..=(1, 0, 1)),
+            vec![(0, 1, 5), (1, 0, 0), (1, 0, 1)]
+        );
+        assert_eq!(
+            range_keys(&entry_map, (1, 0, 1)..),
+            vec![(1, 0, 1), (2, 0, 0)]
+        );
+        assert!(range_keys(&entry_map, (0, 2, 0)..(1, 0, 0)).is_empty());
+    }
+
+    #[test]
+    fn range_mut_multi_key() {
+        let (store, mut entry_map) = setup();
+        for entry in multi_key_entries() {
+            entry_map.insert(entry).unwrap();
+        }
+
+        entry_map
+            .range_mut((0, 1, 0)..=(1, 0, 0), |entry| {
+                entry.value += 10;
+                if entry.key_3 == 5 {
+                    entry.key_1 = 3;
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        let mut buf = vec![];
+        entry_map.flush(&mut buf).unwrap();
+        let entry_map: EntryMap<MultiKeyMapEntry> = EntryMap::with_store(store).unwrap();
+
+        let entries: Vec<(u32, u8, u16, u32)> = entry_map
+            .iter()
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (entry.key_1, entry.key_2, entry.key_3, entry.value)
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (0, 0, 1, 0),
+                (0, 1, 0, 11),
+                (1, 0, 0, 13),
+                (1, 0, 1, 4),
+                (2, 0, 0, 5),
+                (3, 1, 5, 12),
+            ]
+        );
+    }
 }
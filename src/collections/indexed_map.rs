@@ -0,0 +1,298 @@
+//! A map which maintains a secondary index over its values
+use super::map::{Map, Ref};
+use crate::call::FieldCall;
+use crate::describe::{Builder, Describe, Descriptor, KeyOp};
+use crate::encoding::{Decode, Encode, Terminated};
+use crate::migrate::Migrate;
+use crate::query::FieldQuery;
+use crate::state::State;
+use crate::store::Store;
+use crate::Result;
+
+/// Derives the secondary index key of an [IndexedMap] entry from its value.
+///
+/// Since the index is iterated in the order of its encoded keys, the encoding
+/// of the index key determines the iteration order, e.g. an index key of
+/// `u64::MAX - power` iterates from the highest to the lowest power.
+pub trait IndexKey<V> {
+    /// Returns the index key for the given value.
+    fn index_key(value: &V) -> Self;
+}
+
+/// A [Map] which also maintains an ordered secondary index of its entries,
+/// derived from each value by [IndexKey].
+///
+/// The index is stored as a `Map<(I, K), ()>`, so entries with equal index
+/// keys are ordered by their primary key. Inserting and removing entries
+/// through the [IndexedMap] keeps the index consistent with the primary map -
+/// values must not be mutated in a way which changes their index key other
+/// than by re-inserting them.
+#[derive(FieldQuery, FieldCall)]
+pub struct IndexedMap<K, V, I> {
+    map: Map<K, V>,
+    index: Map<(I, K), ()>,
+}
+
+impl<K, V, I> std::fmt::Debug for IndexedMap<K, V, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IndexedMap").finish()
+    }
+}
+
+impl<K, V, I> Terminated for IndexedMap<K, V, I> {}
+
+impl<K, V, I> Default for IndexedMap<K, V, I> {
+    fn default() -> Self {
+        IndexedMap {
+            map: Map::default(),
+            index: Map::default(),
+        }
+    }
+}
+
+impl<K, V, I> IndexedMap<K, V, I> {
+    /// Create a new, empty [IndexedMap].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K, V, I> State for IndexedMap<K, V, I>
+where
+    K: Encode + Terminated + 'static,
+    V: State,
+    I: Encode + Terminated + 'static,
+{
+    fn attach(&mut self, store: Store) -> Result<()> {
+        self.map.attach(store.sub(&[0]))?;
+        self.index.attach(store.sub(&[1]))
+    }
+
+    fn flush<W: std::io::Write>(self, out: &mut W) -> Result<()> {
+        self.map.flush(out)?;
+        self.index.flush(out)
+    }
+
+    fn load(store: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(IndexedMap {
+            map: Map::load(store.sub(&[0]), bytes)?,
+            index: Map::load(store.sub(&[1]), bytes)?,
+        })
+    }
+
+    fn field_keyop(field_name: &str) -> Option<KeyOp> {
+        match field_name {
+            "map" => Some(KeyOp::Append(vec![0])),
+            "index" => Some(KeyOp::Append(vec![1])),
+            _ => None,
+        }
+    }
+}
+
+impl<K, V, I> Migrate for IndexedMap<K, V, I>
+where
+    Self: State,
+    Map<K, V>: Migrate,
+    Map<(I, K), ()>: Migrate,
+{
+    fn migrate(src: Store, dest: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(IndexedMap {
+            map: Map::migrate(src.sub(&[0]), dest.sub(&[0]), bytes)?,
+            index: Map::migrate(src.sub(&[1]), dest.sub(&[1]), bytes)?,
+        })
+    }
+}
+
+impl<K, V, I> Describe for IndexedMap<K, V, I>
+where
+    Self: State,
+    Map<K, V>: Describe,
+    Map<(I, K), ()>: Describe,
+{
+    fn describe() -> Descriptor {
+        Builder::new::<Self>()
+            .named_child_from_state::<Self, Map<K, V>>("map", |value| {
+                value
+                    .downcast_ref::<Self>()
+                    .map(|value| &value.map as &dyn std::any::Any)
+            })
+            .named_child_from_state::<Self, Map<(I, K), ()>>("index", |value| {
+                value
+                    .downcast_ref::<Self>()
+                    .map(|value| &value.index as &dyn std::any::Any)
+            })
+            .build()
+    }
+}
+
+impl<K, V, I> IndexedMap<K, V, I>
+where
+    K: Encode + Decode + Terminated + Clone + Send + Sync + 'static,
+    V: State,
+    I: IndexKey<V> + Encode + Decode + Terminated + Clone + Send + Sync + 'static,
+{
+    /// Create a new, empty [IndexedMap] with the given backing [Store].
+    pub fn with_store(store: Store) -> Result<Self> {
+        Self::load(store, &mut &[][..])
+    }
+
+    /// Returns a [Ref] to the value at the given key, or `None` if the key
+    /// has no value.
+    pub fn get(&self, key: K) -> Result<Option<Ref<V>>> {
+        self.map.get(key)
+    }
+
+    /// Returns `true` if the map contains a value for the given key.
+    pub fn contains_key(&self, key: K) -> Result<bool> {
+        self.map.contains_key(key)
+    }
+
+    /// Inserts a value at the given key, replacing any previous value and
+    /// moving the entry to its new position in the index.
+    pub fn insert(&mut self, key: K, value: V) -> Result<()> {
+        self.remove_index_entry(key.clone())?;
+        self.index.insert((I::index_key(&value), key.clone()), ())?;
+        self.map.insert(key, value)
+    }
+
+    /// Removes the value at the given key, if any, along with its index entry.
+    pub fn remove(&mut self, key: K) -> Result<Option<V>> {
+        self.remove_index_entry(key.clone())?;
+        Ok(self.map.remove(key)?.map(|value| value.into_inner()))
+    }
+
+    fn remove_index_entry(&mut self, key: K) -> Result<()> {
+        let index_key = match self.map.get(key.clone())? {
+            Some(value) => I::index_key(&value),
+            None => return Ok(()),
+        };
+        self.index.remove((index_key, key))?;
+
+        Ok(())
+    }
+
+    /// Create an iterator over the entries of the map, in primary key order.
+    pub fn iter(&self) -> Result<super::map::Iter<K, V>> {
+        self.map.iter()
+    }
+
+    /// Create an iterator over the `(index key, primary key)` pairs of the
+    /// index, in index key order.
+    pub fn iter_index(&self) -> Result<impl Iterator<Item = Result<(I, K)>> + '_> {
+        Ok(self
+            .index
+            .iter()?
+            .map(|entry| entry.map(|(key, _)| (*key).clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::describe::Children;
+
+    #[derive(Clone, Debug, PartialEq, Encode, Decode)]
+    struct InvertedPower(u64);
+
+    impl Terminated for InvertedPower {}
+
+    impl IndexKey<u64> for InvertedPower {
+        fn index_key(power: &u64) -> Self {
+            InvertedPower(u64::MAX - power)
+        }
+    }
+
+    type PowerMap = IndexedMap<u32, u64, InvertedPower>;
+
+    impl IndexKey<u64> for u64 {
+        fn index_key(value: &u64) -> Self {
+            *value
+        }
+    }
+
+    fn by_power(map: &PowerMap) -> Result<Vec<(u64, u32)>> {
+        map.iter_index()?
+            .map(|entry| {
+                let (InvertedPower(inverted), key) = entry?;
+                assert_eq!(*map.get(key)?.unwrap(), u64::MAX - inverted);
+                Ok((u64::MAX - inverted, key))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn insert_update_remove() -> Result<()> {
+        let mut map = PowerMap::with_store(Store::with_map_store())?;
+        map.insert(1, 10)?;
+        map.insert(2, 30)?;
+        map.insert(3, 20)?;
+        assert_eq!(by_power(&map)?, vec![(30, 2), (20, 3), (10, 1)]);
+
+        map.insert(1, 40)?;
+        map.insert(2, 20)?;
+        assert_eq!(by_power(&map)?, vec![(40, 1), (20, 2), (20, 3)]);
+
+        assert_eq!(map.remove(3)?, Some(20));
+        assert_eq!(map.remove(3)?, None);
+        assert!(!map.contains_key(3)?);
+        assert_eq!(by_power(&map)?, vec![(40, 1), (20, 2)]);
+
+        let keys: Vec<u32> = map
+            .iter()?
+            .map(|entry| Ok(*entry?.0))
+            .collect::<Result<_>>()?;
+        assert_eq!(keys, vec![1, 2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn flush_load() -> Result<()> {
+        let store = Store::with_map_store();
+        let mut map = PowerMap::with_store(store.clone())?;
+        map.insert(1, 10)?;
+        map.insert(2, 30)?;
+        map.flush(&mut Vec::<u8>::new())?;
+
+        let mut map = PowerMap::load(store.clone(), &mut &[][..])?;
+        map.insert(1, 50)?;
+        map.remove(2)?;
+        map.insert(3, 5)?;
+        map.flush(&mut Vec::<u8>::new())?;
+
+        let map = PowerMap::load(store, &mut &[][..])?;
+        assert_eq!(by_power(&map)?, vec![(50, 1), (5, 3)]);
+
+        Ok(())
+    }
+    #[test]
+    fn describe_map_and_index() {
+        let desc = IndexedMap::<u32, u64, u64>::describe();
+        let children = match desc.children() {
+            Children::Named(children) => children,
+            _ => panic!("Incorrect children"),
+        };
+
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].name, "map");
+        assert_eq!(children[0].store_key, KeyOp::Append(vec![0]));
+        assert!(children[0].access.is_some());
+        match children[0].desc.children() {
+            Children::Dynamic(child) => {
+                assert_eq!(child.key_desc().type_name, "u32");
+                assert_eq!(child.value_desc().type_name, "u64");
+            }
+            _ => panic!("Incorrect children"),
+        }
+
+        assert_eq!(children[1].name, "index");
+        assert_eq!(children[1].store_key, KeyOp::Append(vec![1]));
+        match children[1].desc.children() {
+            Children::Dynamic(child) => {
+                assert_eq!(child.key_desc().type_name, "(u64, u32)");
+                assert_eq!(child.value_desc().type_name, "()");
+            }
+            _ => panic!("Incorrect children"),
+        }
+    }
+}
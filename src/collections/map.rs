@@ -1,7 +1,7 @@
 //! A map collection backed by a store
 use std::cmp::Ordering;
 use std::collections::btree_map::Entry::{Occupied, Vacant};
-use std::collections::{btree_map, BTreeMap};
+use std::collections::{btree_map, BTreeMap, BTreeSet};
 use std::iter::Peekable;
 use std::marker::PhantomData;
 use std::ops::{Bound, Deref, DerefMut, RangeBounds};
@@ -13,7 +13,7 @@ use crate::orga;
 use crate::query::{FieldQuery, Query};
 use crate::state::State;
 use crate::store::*;
-use crate::{Error, Result};
+use crate::{gas, Error, Result};
 use ed::*;
 use serde::Serialize;
 
@@ -197,6 +197,7 @@ where
     /// may have been stored at that key.
     pub fn insert(&mut self, key: K, mut value: V) -> Result<()> {
         let map_key = MapKey::<K>::new(key)?;
+        Self::charge_write(&map_key)?;
 
         let substore = self.store.sub(map_key.inner_bytes.as_slice());
         value.attach(substore)?;
@@ -205,6 +206,14 @@ where
         Ok(())
     }
 
+    /// Charges the [Gas](crate::gas::Gas) context for writing the child at the
+    /// given key. Children are only written to the store when the map is
+    /// flushed, which is not metered, so the write is charged when the child is
+    /// inserted, removed, or borrowed mutably.
+    fn charge_write(map_key: &MapKey<K>) -> Result<()> {
+        gas::charge_op(gas::WRITE_COST, map_key.inner_bytes.len())
+    }
+
     /// Gets a reference to the value in the map for the given key, or `None` if
     /// the key has no value.
     ///
@@ -331,9 +340,61 @@ where
         Ok(self.entry(key)?.into())
     }
 
+    /// Gets mutable references to the values for two distinct keys at once,
+    /// e.g. to move an amount from one entry to another. Each reference is
+    /// `None` if its key has no value. Returns an error if the keys are equal.
+    ///
+    /// Both values are retained in memory until the map is flushed, even if
+    /// they are not mutated.
+    pub fn get_two_mut(&mut self, a: K, b: K) -> Result<(Option<&mut V>, Option<&mut V>)> {
+        let key_a = MapKey::<K>::new(a)?;
+        let key_b = MapKey::<K>::new(b)?;
+        if key_a == key_b {
+            return Err(Error::App(
+                "Cannot get two references to the same key".into(),
+            ));
+        }
+        Self::charge_write(&key_a)?;
+        Self::charge_write(&key_b)?;
+
+        for key in [&key_a, &key_b] {
+            if !self.children.contains_key(key) {
+                if let Some(value) = self.get_from_store(&key.inner)? {
+                    self.children.insert(key.clone(), Some(value));
+                }
+            }
+        }
+
+        let swapped = key_a > key_b;
+        let (lo, hi) = if swapped {
+            (&key_b, &key_a)
+        } else {
+            (&key_a, &key_b)
+        };
+        let lo_present = self.children.contains_key(lo);
+        let hi_present = self.children.contains_key(hi);
+
+        // the first and last entries of the range are distinct entries, so
+        // both may be borrowed mutably at once
+        let mut range = self
+            .children
+            .range_mut((Bound::Included(lo), Bound::Included(hi)));
+        let lo_value = if lo_present { range.next() } else { None };
+        let hi_value = if hi_present { range.next_back() } else { None };
+        let lo_value = lo_value.and_then(|(_, value)| value.as_mut());
+        let hi_value = hi_value.and_then(|(_, value)| value.as_mut());
+
+        Ok(if swapped {
+            (hi_value, lo_value)
+        } else {
+            (lo_value, hi_value)
+        })
+    }
+
     /// Returns a mutable reference to the key/value entry for the given key.
     pub fn entry(&mut self, key: K) -> Result<Entry<K, V>> {
         let map_key = MapKey::<K>::new(key)?;
+        Self::charge_write(&map_key)?;
         Ok(if self.children.contains_key(&map_key) {
             // value is already retained in memory (was modified)
             let entry = match self.children.entry(map_key) {
@@ -358,9 +419,23 @@ where
         })
     }
 
+    /// Removes all entries from the map, including any changes not yet
+    /// flushed.
+    ///
+    /// Persisted entries and their descendants are deleted from the backing
+    /// store directly by key range, without decoding any values. The key at
+    /// the map's own prefix (e.g. the parent's entry for a nested map) is
+    /// left untouched.
+    pub fn clear(&mut self) -> Result<()> {
+        self.children.clear();
+        self.store
+            .remove_range((Bound::Excluded(vec![]), Bound::Unbounded))
+    }
+
     /// Removes the value at the given key, if any.
     pub fn remove(&mut self, key: K) -> Result<Option<ReadOnly<V>>> {
         let map_key = MapKey::<K>::new(key)?;
+        Self::charge_write(&map_key)?;
         if self.children.contains_key(&map_key) {
             let result = self.children.remove(&map_key).unwrap();
             self.children.insert(map_key, None);
@@ -378,6 +453,7 @@ where
 
     fn remove_raw(&mut self, k: K) -> Result<Option<V>> {
         let map_key = MapKey::<K>::new(k)?;
+        Self::charge_write(&map_key)?;
         if self.children.contains_key(&map_key) {
             let result = self.children.remove(&map_key).unwrap();
             self.children.insert(map_key, None);
@@ -415,8 +491,59 @@ where
         self.range(..)
     }
 
+    /// Returns the number of entries in the map.
+    ///
+    /// No count is stored, so this walks every entry in the backing store and
+    /// costs O(n) in the number of entries. Values are not decoded, and
+    /// pending in-memory insertions and removals are taken into account. Use
+    /// [Map::is_empty] when only checking for the presence of any entry.
+    pub fn len(&self) -> Result<u64> {
+        let mut len = self.children.values().filter(|v| v.is_some()).count() as u64;
+        for key in self.unmodified_store_keys()? {
+            key?;
+            len += 1;
+        }
+
+        Ok(len)
+    }
+
+    /// Returns `true` if the map contains no entries.
+    ///
+    /// This stops at the first entry found rather than walking the whole map,
+    /// only skipping entries in the backing store which have pending
+    /// removals.
+    pub fn is_empty(&self) -> Result<bool> {
+        if self.children.values().any(Option::is_some) {
+            return Ok(false);
+        }
+
+        Ok(self.unmodified_store_keys()?.next().transpose()?.is_none())
+    }
+
+    /// Iterates over the encoded keys in the backing store which do not have
+    /// pending in-memory changes.
+    fn unmodified_store_keys(&self) -> Result<impl Iterator<Item = Result<Vec<u8>>> + '_> {
+        let modified: BTreeSet<&[u8]> = self
+            .children
+            .keys()
+            .map(|key| key.inner_bytes.as_slice())
+            .collect();
+
+        Ok(
+            StoreNextIter::<Store, K>::new(&self.store, ..)?.filter_map(move |entry| match entry {
+                Ok((key, _)) if modified.contains(key.as_slice()) => None,
+                Ok((key, _)) => Some(Ok(key)),
+                Err(err) => Some(Err(err)),
+            }),
+        )
+    }
+
     /// Create an iterator over all KV pairs in the map within the given key
     /// range.
+    ///
+    /// Entries are yielded in ascending order of their encoded keys. The
+    /// iterator is double-ended, so `.rev()` yields them in descending order,
+    /// merging pending in-memory changes with the backing store the same way.
     pub fn range<B: RangeBounds<K>>(&'a self, range: B) -> Result<Iter<'a, K, V>> {
         let map_start = range
             .start_bound()
@@ -438,6 +565,75 @@ where
             store_iter,
         })
     }
+
+    /// Returns the entry with the smallest key in the map, or `None` if the map
+    /// is empty.
+    ///
+    /// Pending in-memory insertions and removals take precedence over the
+    /// backing store.
+    pub fn first(&self) -> Result<Option<(Ref<K>, Ref<V>)>> {
+        self.edge_entry(true)
+    }
+
+    /// Returns the entry with the largest key in the map, or `None` if the map
+    /// is empty.
+    ///
+    /// Pending in-memory insertions and removals take precedence over the
+    /// backing store.
+    pub fn last(&self) -> Result<Option<(Ref<K>, Ref<V>)>> {
+        self.edge_entry(false)
+    }
+
+    /// Walks the in-memory children from the given end of the map, only reading
+    /// past backing store entries which are shadowed by a pending removal.
+    fn edge_entry(&self, forward: bool) -> Result<Option<(Ref<K>, Ref<V>)>> {
+        let mut store_iter = StoreNextIter::<Store, K>::new(&self.store, ..)?;
+        let mut store_next = || {
+            let entry = if forward {
+                store_iter.next()
+            } else {
+                store_iter.next_back()
+            };
+            entry.transpose()
+        };
+
+        let children: Box<dyn Iterator<Item = (&MapKey<K>, &Option<V>)> + '_> = if forward {
+            Box::new(self.children.iter())
+        } else {
+            Box::new(self.children.iter().rev())
+        };
+
+        let mut store_entry = store_next()?;
+        for (key, value) in children {
+            let key_cmp = store_entry
+                .as_ref()
+                .map(|(store_key, _)| key.inner_bytes.cmp(store_key));
+
+            // the store entry comes before this child, so it is unmodified
+            if (forward && key_cmp == Some(Ordering::Greater))
+                || (!forward && key_cmp == Some(Ordering::Less))
+            {
+                break;
+            }
+
+            if let Some(value) = value {
+                return Ok(Some((Ref::Borrowed(&key.inner), Ref::Borrowed(value))));
+            }
+
+            // the child is a pending removal, skip the store entry it shadows
+            if key_cmp == Some(Ordering::Equal) {
+                store_entry = store_next()?;
+            }
+        }
+
+        store_entry
+            .map(|(key_bytes, value_bytes)| {
+                let key = Decode::decode(key_bytes.as_slice())?;
+                let value = V::load(self.store.sub(key_bytes.as_slice()), &mut &value_bytes[..])?;
+                Ok((Ref::Owned(key), Ref::Owned(value)))
+            })
+            .transpose()
+    }
 }
 
 fn encode_bound<K: Encode>(bound: Bound<&K>) -> Result<Bound<Vec<u8>>> {
@@ -453,6 +649,35 @@ where
     K: Encode + Terminated + 'static,
     V: State,
 {
+    /// Returns a snapshot of the map, e.g. for speculatively executing changes
+    /// which may be discarded.
+    ///
+    /// The snapshot is backed by an in-memory copy of the map's entries in its
+    /// backing store, so later writes to this map are not visible in the
+    /// snapshot, and writes to the snapshot never reach this map's store.
+    /// Copying reads every entry of the map, so this is O(n).
+    ///
+    /// Returns an error if the map has changes which have not been flushed,
+    /// since they can not be copied without flushing the map.
+    pub fn snapshot(&self) -> Result<Self> {
+        if !self.children.is_empty() {
+            return Err(Error::Store(
+                "Can not snapshot a map with unflushed changes".into(),
+            ));
+        }
+
+        let mut copy = Store::with_map_store();
+        for entry in self.store.range(..) {
+            let (key, value) = entry?;
+            copy.put(key, value)?;
+        }
+
+        Ok(Map {
+            store: copy,
+            children: BTreeMap::new(),
+        })
+    }
+
     /// Removes all values with the given prefix from the key/value store.
     /// Iterates until reaching the first key that does not have the given
     /// prefix, or the end of the store.
@@ -684,41 +909,21 @@ struct StoreNextIter<'a, S: Default + Read, K: Decode> {
     _phantom: PhantomData<K>,
 }
 
-// TODO: dedupe this with the same code in Store
-fn increment_bytes(mut bytes: Vec<u8>) -> Vec<u8> {
-    for byte in bytes.iter_mut().rev() {
-        if *byte == 255 {
-            *byte = 0;
-        } else {
-            *byte += 1;
-            return bytes;
-        }
-    }
-
-    bytes.push(0);
-    if bytes.len() > 1 {
-        bytes[0] += 1;
-    }
-
-    bytes
-}
-
-fn decrement_bytes(mut bytes: Vec<u8>) -> Option<Vec<u8>> {
-    for byte in bytes.iter_mut().rev() {
-        if *byte == 0 {
-            *byte = 255;
-        } else {
-            *byte -= 1;
-            return Some(bytes);
+/// Returns the smallest key which is greater than `key` and every key it is a
+/// prefix of, or `None` if there is no such key.
+///
+/// Map keys may have encodings of different lengths, so the successor of a key
+/// must be shorter than it rather than an increment of the same length (e.g.
+/// the key `[1]` comes between `[0, 255]` and `[1, 0]`).
+fn prefix_successor(mut key: Vec<u8>) -> Option<Vec<u8>> {
+    while let Some(byte) = key.pop() {
+        if byte < 255 {
+            key.push(byte + 1);
+            return Some(key);
         }
     }
 
-    if bytes.is_empty() {
-        bytes.pop();
-        Some(bytes)
-    } else {
-        None
-    }
+    None
 }
 
 impl<'a, S: Default + Read, K: Decode> StoreNextIter<'a, S, K> {
@@ -802,7 +1007,11 @@ impl<'a, S: Default + Read, K: Decode> Iterator for StoreNextIter<'a, S, K> {
 
     fn next(&mut self) -> Option<Self::Item> {
         Some(self.peek()?.map(|(key, value)| {
-            self.next_key = Bound::Included(increment_bytes(key.clone()));
+            // skip past the keys of the entry's descendants
+            match prefix_successor(key.clone()) {
+                Some(next_key) => self.next_key = Bound::Included(next_key),
+                None => self.end_key = Bound::Excluded(vec![]),
+            }
             (key, value)
         }))
     }
@@ -811,9 +1020,9 @@ impl<'a, S: Default + Read, K: Decode> Iterator for StoreNextIter<'a, S, K> {
 impl<'a, S: Default + Read, K: Decode> DoubleEndedIterator for StoreNextIter<'a, S, K> {
     fn next_back(&mut self) -> Option<Self::Item> {
         Some(self.peek_back()?.map(|(key, value)| {
-            self.end_key = decrement_bytes(key.clone())
-                .map(Bound::Included)
-                .unwrap_or(Bound::Excluded(vec![]));
+            // descendant keys are greater than the entry's key, so excluding it
+            // is enough to move past the entry
+            self.end_key = Bound::Excluded(key.clone());
             (key, value)
         }))
     }
@@ -1165,6 +1374,31 @@ mod tests {
         assert_eq!(store.get(&enc(1)).unwrap().unwrap(), enc(3));
     }
 
+    #[test]
+    fn get_two_mut() {
+        let (store, mut map) = setup();
+        map.insert(1, 10).unwrap();
+        map.insert(2, 20).unwrap();
+        map.flush(&mut vec![]).unwrap();
+
+        let mut map: Map<u32, u32> = Map::load(store.clone(), &mut &[][..]).unwrap();
+        map.insert(3, 30).unwrap();
+        let (a, b) = map.get_two_mut(2, 1).unwrap();
+        *a.unwrap() -= 5;
+        *b.unwrap() += 5;
+        let (a, b) = map.get_two_mut(3, 4).unwrap();
+        *a.unwrap() += 1;
+        assert!(b.is_none());
+        assert!(map.get_two_mut(1, 1).is_err());
+
+        assert_eq!(store.get(&enc(1)).unwrap().unwrap(), enc(10));
+        map.flush(&mut vec![]).unwrap();
+        assert_eq!(store.get(&enc(1)).unwrap().unwrap(), enc(15));
+        assert_eq!(store.get(&enc(2)).unwrap().unwrap(), enc(15));
+        assert_eq!(store.get(&enc(3)).unwrap().unwrap(), enc(31));
+        assert!(store.get(&enc(4)).unwrap().is_none());
+    }
+
     #[test]
     fn mem_unmodified() {
         let (store, mut map) = setup();
@@ -1816,6 +2050,164 @@ mod tests {
         assert!(iter.next().is_none());
     }
 
+    fn collect_entries<'a, K: Clone + 'a>(
+        iter: impl Iterator<Item = Result<(Ref<'a, K>, Ref<'a, u32>)>>,
+    ) -> Vec<(K, u32)> {
+        iter.map(|entry| {
+            let (key, value) = entry.unwrap();
+            ((*key).clone(), *value)
+        })
+        .collect()
+    }
+
+    /// Collects the entries of a range of the map, iterating both forward and
+    /// in reverse. The reversed entries are returned in ascending order.
+    fn collect_both_ways<K, B>(map: &Map<K, u32>, range: B) -> (Vec<(K, u32)>, Vec<(K, u32)>)
+    where
+        K: Encode + Decode + Terminated + Clone + 'static,
+        B: RangeBounds<K> + Clone,
+    {
+        let forward = collect_entries(map.range(range.clone()).unwrap());
+        let mut reverse = collect_entries(map.range(range).unwrap().rev());
+        reverse.reverse();
+
+        (forward, reverse)
+    }
+
+    #[test]
+    fn map_range_rev_interleaved() {
+        let (store, mut map) = setup();
+        for i in (0..20).step_by(2) {
+            map.insert(i, i * 10).unwrap();
+        }
+        let mut bytes = vec![];
+        map.flush(&mut bytes).unwrap();
+
+        // pending inserts, updates and removals over the persisted entries
+        let mut map: Map<u32, u32> = Map::with_store(store).unwrap();
+        let mut expected: BTreeMap<u32, u32> = (0..20).step_by(2).map(|i| (i, i * 10)).collect();
+        for i in (1..20).step_by(4) {
+            map.insert(i, i * 10).unwrap();
+            expected.insert(i, i * 10);
+        }
+        map.insert(6, 7).unwrap();
+        expected.insert(6, 7);
+        for i in [4, 5, 12, 18] {
+            map.remove(i).unwrap();
+            expected.remove(&i);
+        }
+
+        for (start, end) in [(0, 20), (3, 15), (4, 12), (5, 6), (12, 13), (13, 13)] {
+            let expected: Vec<_> = expected.range(start..end).map(|(k, v)| (*k, *v)).collect();
+            let (forward, reverse) = collect_both_ways(&map, start..end);
+            assert_eq!(forward, expected);
+            assert_eq!(reverse, expected);
+        }
+
+        let all: Vec<_> = expected.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(collect_both_ways(&map, ..), (all.clone(), all));
+
+        let expected_tail: Vec<_> = expected.range(9..).map(|(k, v)| (*k, *v)).collect();
+        let (forward, reverse) = collect_both_ways(&map, 9..);
+        assert_eq!(forward, expected_tail);
+        assert_eq!(reverse, expected_tail);
+
+        let expected_head: Vec<_> = expected.range(..=9).map(|(k, v)| (*k, *v)).collect();
+        let (forward, reverse) = collect_both_ways(&map, ..=9);
+        assert_eq!(forward, expected_head);
+        assert_eq!(reverse, expected_head);
+
+        // iterating from both ends yields each entry once
+        let mut iter = map.range(3..15).unwrap();
+        let mut front = vec![];
+        let mut back = vec![];
+        loop {
+            match iter.next() {
+                Some(entry) => front.push(*entry.unwrap().0),
+                None => break,
+            }
+            match iter.next_back() {
+                Some(entry) => back.push(*entry.unwrap().0),
+                None => break,
+            }
+        }
+        back.reverse();
+        front.extend(back);
+        assert_eq!(
+            front,
+            expected.range(3..15).map(|(k, _)| *k).collect::<Vec<_>>()
+        );
+    }
+
+    /// A map key whose encoding length depends on its variant.
+    #[derive(Clone, Debug, PartialEq)]
+    enum VarKey {
+        Long(u16),
+        Short,
+    }
+
+    impl Encode for VarKey {
+        fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> ed::Result<()> {
+            match self {
+                VarKey::Long(n) => {
+                    dest.write_all(&[0])?;
+                    n.encode_into(dest)
+                }
+                VarKey::Short => Ok(dest.write_all(&[1])?),
+            }
+        }
+
+        fn encoding_length(&self) -> ed::Result<usize> {
+            Ok(match self {
+                VarKey::Long(_) => 3,
+                VarKey::Short => 1,
+            })
+        }
+    }
+
+    impl Decode for VarKey {
+        fn decode<R: std::io::Read>(mut input: R) -> ed::Result<Self> {
+            match u8::decode(&mut input)? {
+                0 => Ok(VarKey::Long(u16::decode(input)?)),
+                1 => Ok(VarKey::Short),
+                byte => Err(ed::Error::UnexpectedByte(byte)),
+            }
+        }
+    }
+
+    impl Terminated for VarKey {}
+
+    #[test]
+    fn map_range_rev_variable_length_keys() {
+        let store = mapstore();
+        let mut map: Map<VarKey, u32> = Default::default();
+        map.attach(store.clone()).unwrap();
+        map.insert(VarKey::Long(1), 1).unwrap();
+        map.insert(VarKey::Long(0xff00), 2).unwrap();
+        map.insert(VarKey::Short, 4).unwrap();
+        let mut bytes = vec![];
+        map.flush(&mut bytes).unwrap();
+
+        // `Short` sorts after every `Long` key, despite its shorter encoding
+        let mut map: Map<VarKey, u32> = Map::with_store(store.clone()).unwrap();
+        map.insert(VarKey::Long(0xffff), 3).unwrap();
+
+        let expected = vec![
+            (VarKey::Long(1), 1),
+            (VarKey::Long(0xff00), 2),
+            (VarKey::Long(0xffff), 3),
+            (VarKey::Short, 4),
+        ];
+        assert_eq!(collect_both_ways(&map, ..), (expected.clone(), expected));
+
+        let mut bytes = vec![];
+        map.flush(&mut bytes).unwrap();
+        let map: Map<VarKey, u32> = Map::with_store(store).unwrap();
+        let expected = vec![(VarKey::Long(0xff00), 2), (VarKey::Long(0xffff), 3)];
+        let range = VarKey::Long(2)..VarKey::Short;
+        assert_eq!(collect_both_ways(&map, range), (expected.clone(), expected));
+    }
+
     #[test]
     fn map_range_map_only_unbounded() {
         let (_store, mut map) = setup();
@@ -2031,6 +2423,69 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn len() {
+        let (mut store, mut map) = setup();
+        assert_eq!(map.len().unwrap(), 0);
+        assert!(map.is_empty().unwrap());
+
+        store.put(enc(1), enc(10)).unwrap();
+        store.put(enc(2), enc(20)).unwrap();
+        assert_eq!(map.len().unwrap(), 2);
+        assert!(!map.is_empty().unwrap());
+
+        map.insert(2, 21).unwrap();
+        map.insert(3, 30).unwrap();
+        assert_eq!(map.len().unwrap(), 3);
+
+        map.remove(1).unwrap();
+        map.remove(2).unwrap();
+        assert_eq!(map.len().unwrap(), 1);
+
+        map.remove(3).unwrap();
+        assert_eq!(map.len().unwrap(), 0);
+        assert!(map.is_empty().unwrap());
+
+        map.insert(4, 40).unwrap();
+        map.flush(&mut vec![]).unwrap();
+        let map: Map<u32, u32> = Map::with_store(store).unwrap();
+        assert_eq!(map.len().unwrap(), 1);
+    }
+
+    #[test]
+    fn len_map_of_map() {
+        let (store, mut map) = {
+            let store = mapstore();
+            let mut map: Map<u32, Map<u32, u32>> = Default::default();
+            map.attach(store.clone()).unwrap();
+            (store, map)
+        };
+
+        map.entry(1)
+            .unwrap()
+            .or_insert_default()
+            .unwrap()
+            .insert(1, 1)
+            .unwrap();
+        map.entry(1)
+            .unwrap()
+            .or_insert_default()
+            .unwrap()
+            .insert(2, 2)
+            .unwrap();
+        map.entry(2)
+            .unwrap()
+            .or_insert_default()
+            .unwrap()
+            .insert(1, 1)
+            .unwrap();
+        map.flush(&mut vec![]).unwrap();
+
+        let map: Map<u32, Map<u32, u32>> = Map::with_store(store).unwrap();
+        assert_eq!(map.len().unwrap(), 2);
+        assert_eq!(map.get(1).unwrap().unwrap().len().unwrap(), 2);
+    }
+
     #[test]
     fn map_of_map() {
         let store = mapstore();
@@ -2407,4 +2862,122 @@ mod tests {
         let expected: Vec<(u32, u32)> = vec![(12, 26), (13, 24)];
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn clear() {
+        let mut store = mapstore();
+        store.put(vec![6], vec![1]).unwrap();
+
+        let mut map: Map<u32, Map<u32, u32>> = Default::default();
+        map.attach(store.sub(&[5])).unwrap();
+        for i in 0..50 {
+            let mut inner = map.entry(i).unwrap().or_insert_default().unwrap();
+            inner.insert(i, i).unwrap();
+            inner.insert(i + 1, i).unwrap();
+        }
+        map.flush(&mut vec![]).unwrap();
+
+        let mut map: Map<u32, Map<u32, u32>> = Map::with_store(store.sub(&[5])).unwrap();
+        for i in 50..100 {
+            map.entry(i).unwrap().or_insert_default().unwrap();
+        }
+        map.get_mut(10).unwrap().unwrap().insert(100, 100).unwrap();
+
+        map.clear().unwrap();
+        assert!(map.iter().unwrap().next().is_none());
+        assert!(map.get(10).unwrap().is_none());
+
+        map.insert(7, Map::new()).unwrap();
+        map.flush(&mut vec![]).unwrap();
+
+        let keys: Vec<_> = store
+            .sub(&[5])
+            .into_iter(..)
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        assert_eq!(keys, vec![vec![0, 0, 0, 7]]);
+        assert_eq!(store.get(&[6]).unwrap(), Some(vec![1]));
+    }
+
+    #[test]
+    fn first_last() {
+        let store = mapstore();
+        let mut map: Map<u32, u32> = Map::with_store(store.clone()).unwrap();
+        assert!(map.first().unwrap().is_none());
+        assert!(map.last().unwrap().is_none());
+
+        for i in 10..20 {
+            map.insert(i, i * 2).unwrap();
+        }
+        let edges = |map: &Map<u32, u32>| {
+            let first = map.first().unwrap().map(|(k, v)| (*k, *v));
+            let last = map.last().unwrap().map(|(k, v)| (*k, *v));
+            (first, last)
+        };
+        assert_eq!(edges(&map), (Some((10, 20)), Some((19, 38))));
+        map.flush(&mut vec![]).unwrap();
+
+        let mut map: Map<u32, u32> = Map::with_store(store.clone()).unwrap();
+        assert_eq!(edges(&map), (Some((10, 20)), Some((19, 38))));
+
+        map.remove(10).unwrap();
+        map.remove(11).unwrap();
+        map.remove(19).unwrap();
+        assert_eq!(edges(&map), (Some((12, 24)), Some((18, 36))));
+
+        map.insert(11, 100).unwrap();
+        map.insert(25, 200).unwrap();
+        assert_eq!(edges(&map), (Some((11, 100)), Some((25, 200))));
+
+        map.insert(5, 300).unwrap();
+        map.remove(5).unwrap();
+        map.remove(25).unwrap();
+        map.insert(18, 400).unwrap();
+        assert_eq!(edges(&map), (Some((11, 100)), Some((18, 400))));
+        map.flush(&mut vec![]).unwrap();
+
+        let mut map: Map<u32, u32> = Map::with_store(store).unwrap();
+        assert_eq!(edges(&map), (Some((11, 100)), Some((18, 400))));
+
+        for i in 11..19 {
+            map.remove(i).unwrap();
+        }
+        assert_eq!(edges(&map), (None, None));
+    }
+
+    #[test]
+    fn snapshot() {
+        let (mut store, mut map) = setup();
+        map.insert(1, 10).unwrap();
+        assert!(map.snapshot().is_err());
+        map.flush(&mut vec![]).unwrap();
+        store.put(enc(2), enc(20)).unwrap();
+
+        let mut map: Map<u32, u32> = Map::with_store(store.clone()).unwrap();
+        let mut snapshot = map.snapshot().unwrap();
+        assert_eq!(*snapshot.get(1).unwrap().unwrap(), 10);
+
+        // later writes to the map are not visible in the snapshot
+        map.insert(4, 40).unwrap();
+        map.remove(2).unwrap();
+        map.flush(&mut vec![]).unwrap();
+        assert_eq!(store.get(&enc(4)).unwrap(), Some(enc(40)));
+        assert!(snapshot.get(4).unwrap().is_none());
+        assert_eq!(*snapshot.get(2).unwrap().unwrap(), 20);
+
+        snapshot.remove(1).unwrap();
+        *snapshot.get_mut(2).unwrap().unwrap() = 200;
+        snapshot.insert(3, 30).unwrap();
+        let snapshot_store = snapshot.store.clone();
+        snapshot.flush(&mut vec![]).unwrap();
+
+        let snapshot: Map<u32, u32> = Map::with_store(snapshot_store).unwrap();
+        assert!(snapshot.get(1).unwrap().is_none());
+        assert_eq!(*snapshot.get(2).unwrap().unwrap(), 200);
+        assert_eq!(*snapshot.get(3).unwrap().unwrap(), 30);
+
+        assert_eq!(store.get(&enc(1)).unwrap(), Some(enc(10)));
+        assert!(store.get(&enc(2)).unwrap().is_none());
+        assert!(store.get(&enc(3)).unwrap().is_none());
+    }
 }
@@ -3,13 +3,21 @@ use crate::state::State;
 
 pub use crate::macros::{Entry, Next};
 
+pub mod bounded_deque;
 pub mod deque;
 pub mod entry_map;
+pub mod indexed_map;
 pub mod map;
+pub mod set;
+pub mod value;
 
+pub use bounded_deque::BoundedDeque;
 pub use deque::Deque;
 pub use entry_map::EntryMap;
+pub use indexed_map::{IndexKey, IndexedMap};
 pub use map::Map;
+pub use set::Set;
+pub use value::Value;
 
 pub use map::{ChildMut, Ref};
 
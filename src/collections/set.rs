@@ -0,0 +1,234 @@
+//! A set collection backed by a store
+use serde::Serialize;
+
+use super::map::{Map, Ref};
+use crate::call::FieldCall;
+use crate::describe::{Builder, Describe, Descriptor};
+use crate::encoding::{Decode, Encode, Terminated};
+use crate::migrate::Migrate;
+use crate::orga;
+use crate::query::FieldQuery;
+use crate::state::State;
+use crate::store::Store;
+use crate::Result;
+
+/// A collection of unique items, stored as the keys of a `Map<T, ()>`.
+///
+/// This has the same storage layout as a `Map<T, ()>` (the unit value encodes
+/// to zero bytes), so state written by a `Map<T, ()>` can be loaded as a
+/// `Set<T>` without migrating any data.
+///
+/// Like [Map], changes are retained in memory until the call to
+/// `State::flush`.
+#[derive(FieldQuery, FieldCall)]
+pub struct Set<T> {
+    inner: Map<T, ()>,
+}
+
+impl<T> std::fmt::Debug for Set<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Set").finish()
+    }
+}
+
+impl<T> Terminated for Set<T> {}
+
+impl<T> Default for Set<T> {
+    fn default() -> Self {
+        Set {
+            inner: Map::default(),
+        }
+    }
+}
+
+impl<T> From<Map<T, ()>> for Set<T> {
+    fn from(inner: Map<T, ()>) -> Self {
+        Set { inner }
+    }
+}
+
+impl<T> Set<T> {
+    /// Create a new, empty [Set].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T: Encode + Terminated + 'static> State for Set<T> {
+    fn attach(&mut self, store: Store) -> Result<()> {
+        self.inner.attach(store)
+    }
+
+    fn flush<W: std::io::Write>(self, out: &mut W) -> Result<()> {
+        self.inner.flush(out)
+    }
+
+    fn load(store: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Set {
+            inner: Map::load(store, bytes)?,
+        })
+    }
+}
+
+impl<T> Migrate for Set<T>
+where
+    Self: State,
+    Map<T, ()>: Migrate,
+{
+    fn migrate(src: Store, dest: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Set {
+            inner: Map::migrate(src, dest, bytes)?,
+        })
+    }
+}
+
+impl<T> Describe for Set<T>
+where
+    T: Encode + Terminated + Clone + 'static + Describe,
+{
+    fn describe() -> Descriptor {
+        Builder::new::<Self>()
+            .dynamic_child::<T, ()>(|mut query_bytes| {
+                query_bytes.extend_from_slice(&[128]);
+                query_bytes
+            })
+            .build()
+    }
+}
+
+#[orga]
+impl<T> Set<T>
+where
+    T: Encode + Decode + Terminated + Clone + Send + Sync + 'static,
+{
+    /// Create a new, empty [Set] with the given backing [Store].
+    pub fn with_store(store: Store) -> Result<Self> {
+        Ok(Set {
+            inner: Map::with_store(store)?,
+        })
+    }
+
+    /// Returns `true` if the set contains the given item.
+    #[query]
+    pub fn contains(&self, item: T) -> Result<bool> {
+        self.inner.contains_key(item)
+    }
+
+    /// Adds an item to the set. Inserting an item which is already present
+    /// has no effect.
+    pub fn insert(&mut self, item: T) -> Result<()> {
+        self.inner.insert(item, ())
+    }
+
+    /// Removes an item from the set, returning `true` if it was present.
+    pub fn remove(&mut self, item: T) -> Result<bool> {
+        Ok(self.inner.remove(item)?.is_some())
+    }
+
+    /// Returns the number of items in the set.
+    pub fn len(&self) -> Result<u64> {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the set contains no items.
+    pub fn is_empty(&self) -> Result<bool> {
+        self.inner.is_empty()
+    }
+
+    /// Create an iterator over the items of the set, in encoded key order.
+    pub fn iter(&self) -> Result<impl Iterator<Item = Result<Ref<T>>> + '_> {
+        Ok(self.inner.iter()?.map(|entry| entry.map(|(item, _)| item)))
+    }
+}
+
+impl<T> Serialize for Set<T>
+where
+    T: Serialize + Encode + Decode + Terminated + Clone + Send + Sync + 'static,
+{
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::{Error, SerializeSeq};
+        let mut seq = serializer.serialize_seq(None)?;
+        for item in self.iter().map_err(Error::custom)? {
+            let item = item.map_err(Error::custom)?;
+            seq.serialize_element(&*item)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::describe::Children;
+
+    #[test]
+    fn insert_contains_remove() -> Result<()> {
+        let mut set: Set<u32> = Set::with_store(Store::with_map_store())?;
+        assert!(set.is_empty()?);
+
+        set.insert(3)?;
+        set.insert(1)?;
+        set.insert(3)?;
+        assert!(set.contains(1)?);
+        assert!(set.contains(3)?);
+        assert!(!set.contains(2)?);
+        assert_eq!(set.len()?, 2);
+
+        assert!(set.remove(3)?);
+        assert!(!set.remove(3)?);
+        assert!(!set.contains(3)?);
+
+        let items: Vec<u32> = set.iter()?.map(|item| Ok(*item?)).collect::<Result<_>>()?;
+        assert_eq!(items, vec![1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_map_unit_data() -> Result<()> {
+        let store = Store::with_map_store();
+        let mut map: Map<u32, ()> = Map::with_store(store.clone())?;
+        map.insert(2, ())?;
+        map.insert(1, ())?;
+        map.flush(&mut Vec::<u8>::new())?;
+
+        let mut set: Set<u32> = Set::load(store.clone(), &mut &[][..])?;
+        assert!(set.contains(1)?);
+        assert!(set.contains(2)?);
+        set.insert(5)?;
+        set.remove(1)?;
+        set.flush(&mut Vec::<u8>::new())?;
+
+        let map: Map<u32, ()> = Map::load(store, &mut &[][..])?;
+        assert!(!map.contains_key(1)?);
+        assert!(map.contains_key(2)?);
+        assert!(map.contains_key(5)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn serialize() -> Result<()> {
+        let mut set: Set<u32> = Set::with_store(Store::with_map_store())?;
+        set.insert(2)?;
+        set.insert(1)?;
+        assert_eq!(serde_json::to_string(&set).unwrap(), "[1,2]");
+
+        Ok(())
+    }
+
+    #[test]
+    fn describe_items_as_dynamic_children() {
+        let desc = Set::<u32>::describe();
+        match desc.children() {
+            Children::Dynamic(child) => {
+                assert_eq!(child.key_desc().type_name, "u32");
+                assert_eq!(child.value_desc().type_name, "()");
+            }
+            _ => panic!("Incorrect children"),
+        }
+    }
+}
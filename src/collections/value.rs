@@ -0,0 +1,209 @@
+//! A single-value collection backed by a store
+use serde::Serialize;
+
+use super::map::{ChildMut, Map, Ref};
+use crate::call::FieldCall;
+use crate::describe::{Builder, Describe, Descriptor};
+use crate::encoding::Terminated;
+use crate::migrate::Migrate;
+use crate::orga;
+use crate::query::FieldQuery;
+use crate::state::State;
+use crate::store::Store;
+use crate::{Error, Result};
+
+/// A collection which stores exactly one value at its own prefix in the
+/// backing store.
+///
+/// This has the same storage layout as a `Map<(), T>` (the unit key encodes to
+/// zero bytes), so state written by a `Map<(), T>` can be loaded as a
+/// `Value<T>` without migrating any data.
+///
+/// Like [Map], the value is loaded lazily and changes are retained in memory
+/// until the call to `State::flush`.
+#[derive(FieldQuery, FieldCall)]
+pub struct Value<T> {
+    inner: Map<(), T>,
+}
+
+impl<T> std::fmt::Debug for Value<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Value").finish()
+    }
+}
+
+impl<T> Terminated for Value<T> {}
+
+impl<T> Default for Value<T> {
+    fn default() -> Self {
+        Value {
+            inner: Map::default(),
+        }
+    }
+}
+
+impl<T> From<Map<(), T>> for Value<T> {
+    fn from(inner: Map<(), T>) -> Self {
+        Value { inner }
+    }
+}
+
+impl<T: State> State for Value<T> {
+    fn attach(&mut self, store: Store) -> Result<()> {
+        self.inner.attach(store)
+    }
+
+    fn flush<W: std::io::Write>(self, out: &mut W) -> Result<()> {
+        self.inner.flush(out)
+    }
+
+    fn load(store: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Value {
+            inner: Map::load(store, bytes)?,
+        })
+    }
+}
+
+impl<T: State + Migrate> Migrate for Value<T> {
+    fn migrate(src: Store, dest: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Value {
+            inner: Map::migrate(src, dest, bytes)?,
+        })
+    }
+}
+
+impl<T: State + Describe> Describe for Value<T> {
+    fn describe() -> Descriptor {
+        Builder::new::<Self>()
+            .dynamic_child::<(), T>(|mut query_bytes| {
+                query_bytes.extend_from_slice(&[128]);
+                query_bytes
+            })
+            .build()
+    }
+}
+
+impl<T: State> Value<T> {
+    /// Create a new [Value] containing the given value.
+    pub fn new(value: T) -> Result<Self> {
+        let mut inner = Map::new();
+        inner.insert((), value)?;
+        Ok(Value { inner })
+    }
+
+    /// Create a new, empty [Value] with the given backing [Store].
+    pub fn with_store(store: Store) -> Result<Self> {
+        Ok(Value {
+            inner: Map::with_store(store)?,
+        })
+    }
+
+    fn unset_error() -> Error {
+        Error::Store("Value has not been set".into())
+    }
+}
+
+#[orga]
+impl<T: State> Value<T> {
+    /// Returns a [Ref] to the stored value, or an error if no value has been
+    /// set.
+    #[query]
+    pub fn get(&self) -> Result<Ref<T>> {
+        self.try_get()?.ok_or_else(Self::unset_error)
+    }
+
+    /// Returns a [Ref] to the stored value, or `None` if no value has been
+    /// set.
+    pub fn try_get(&self) -> Result<Option<Ref<T>>> {
+        self.inner.get(())
+    }
+
+    /// Returns `true` if a value has been set.
+    pub fn is_set(&self) -> Result<bool> {
+        self.inner.contains_key(())
+    }
+
+    /// Sets the stored value, replacing any previous value.
+    pub fn set(&mut self, value: T) -> Result<()> {
+        self.inner.insert((), value)
+    }
+
+    /// Gets a mutable reference to the stored value, or an error if no value
+    /// has been set.
+    ///
+    /// If the value is mutated, it will be retained in memory until the
+    /// [Value] is flushed.
+    pub fn get_mut(&mut self) -> Result<ChildMut<(), T>> {
+        self.inner.get_mut(())?.ok_or_else(Self::unset_error)
+    }
+}
+
+impl<T: State + Serialize> Serialize for Value<T> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+        match self.try_get().map_err(Error::custom)? {
+            Some(value) => serializer.serialize_some(&*value),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{MapStore, Read, Shared};
+
+    fn store() -> Store {
+        Store::new(Shared::new(MapStore::new()).into())
+    }
+
+    #[test]
+    fn set_get() -> Result<()> {
+        let mut value: Value<u32> = Value::with_store(store())?;
+        assert!(!value.is_set()?);
+        assert!(value.get().is_err());
+        assert!(value.get_mut().is_err());
+
+        value.set(12)?;
+        assert!(value.is_set()?);
+        assert_eq!(*value.get()?, 12);
+
+        *value.get_mut()? = 34;
+        assert_eq!(*value.get()?, 34);
+
+        Ok(())
+    }
+
+    #[test]
+    fn flush_load() -> Result<()> {
+        let store = store();
+        let mut value: Value<u32> = Value::with_store(store.clone())?;
+        value.set(12)?;
+
+        let mut bytes: Vec<u8> = vec![];
+        value.flush(&mut bytes)?;
+        assert!(bytes.is_empty());
+        assert!(store.get(&[])?.is_some());
+
+        let value: Value<u32> = Value::load(store, &mut bytes.as_slice())?;
+        assert_eq!(*value.get()?, 12);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_map_unit_data() -> Result<()> {
+        let store = store();
+        let mut map: Map<(), u32> = Map::with_store(store.clone())?;
+        map.insert((), 12)?;
+        map.flush(&mut Vec::<u8>::new())?;
+
+        let value: Value<u32> = Value::load(store, &mut &[][..])?;
+        assert_eq!(*value.get()?, 12);
+
+        Ok(())
+    }
+}
@@ -15,7 +15,8 @@ use crate::{
     Error, Result,
 };
 use ed::Terminated;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
     any::{Any, TypeId},
     fmt::{Debug, Display},
@@ -47,10 +48,24 @@ pub struct Descriptor {
     pub type_name: String,
     /// The state version of this type.
     pub state_version: u32,
+    /// An explicit identifier for the type's encoding, included in the
+    /// [layout hash](Descriptor::layout_hash). Types whose encoding is not
+    /// described by their children, e.g. primitives, should set one with
+    /// [Builder::layout_id].
+    pub layout_id: Option<String>,
     /// The child descriptors of the type.
     children: Children,
     /// The function used to load the type from a [Store].
     pub load: Option<LoadFn>,
+    /// The function used to load the type from a [Store] and serialize it as
+    /// JSON.
+    pub to_json: Option<ToJsonFn>,
+    /// The function used to deserialize the type from JSON and encode it.
+    pub encode_json: Option<EncodeJsonFn>,
+    /// The function used to load the type from a [Store] as a boxed [Any].
+    pub load_any: Option<LoadAnyFn>,
+    /// The function used to serialize a loaded value of the type as JSON.
+    pub any_to_json: Option<AnyToJsonFn>,
     /// A meta-descriptor.
     pub meta: Option<Box<Self>>,
 }
@@ -71,6 +86,75 @@ impl Descriptor {
         &self.children
     }
 
+    /// Returns a hash of the descriptor tree's layout: the layout identifiers,
+    /// state versions, child names, and store key operations of this
+    /// descriptor and all of its descendants.
+    ///
+    /// The hash is deterministic, so it can be recorded and compared against
+    /// in CI or at startup to detect changes to a type's state layout which
+    /// would require a migration. Type names are not included, so moving or
+    /// renaming a type does not change the hash.
+    pub fn layout_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        self.hash_layout(&mut hasher);
+        hasher.finalize().into()
+    }
+
+    fn hash_layout(&self, hasher: &mut Sha256) {
+        match &self.layout_id {
+            None => hasher.update([0]),
+            Some(id) => {
+                hasher.update([1]);
+                hash_bytes(hasher, id.as_bytes());
+            }
+        }
+        hasher.update(self.state_version.to_be_bytes());
+
+        match &self.children {
+            Children::None => hasher.update([0]),
+            Children::Named(children) => {
+                hasher.update([1]);
+                hasher.update((children.len() as u64).to_be_bytes());
+                for child in children {
+                    hash_bytes(hasher, child.name.as_bytes());
+                    let (op, bytes) = match &child.store_key {
+                        KeyOp::Append(bytes) => (0, bytes),
+                        KeyOp::Absolute(bytes) => (1, bytes),
+                    };
+                    hasher.update([op]);
+                    hash_bytes(hasher, bytes);
+                    child.desc.hash_layout(hasher);
+                }
+            }
+            Children::Dynamic(child) => {
+                hasher.update([2]);
+                child.key_desc.hash_layout(hasher);
+                child.value_desc.hash_layout(hasher);
+            }
+            Children::Variants(variants) => {
+                hasher.update([3]);
+                hasher.update((variants.len() as u64).to_be_bytes());
+                for variant in variants {
+                    hash_bytes(hasher, variant.name.as_bytes());
+                    hasher.update([variant.discriminant]);
+                    hasher.update((variant.fields.len() as u64).to_be_bytes());
+                    for field in &variant.fields {
+                        hash_bytes(hasher, field.name.as_bytes());
+                        field.desc.hash_layout(hasher);
+                    }
+                }
+            }
+        }
+
+        match &self.meta {
+            None => hasher.update([0]),
+            Some(meta) => {
+                hasher.update([1]);
+                meta.hash_layout(hasher);
+            }
+        }
+    }
+
     // pub fn kv_descs(self) -> impl Iterator<Item = DynamicChild> {
     //     let (own, named) = match self.children {
     //         Children::None => (vec![], vec![]),
@@ -82,10 +166,339 @@ impl Descriptor {
     // }
 }
 
+/// Returns a layout identifier for a generic type named `name` which wraps `T`,
+/// e.g. `Vec<T>`.
+fn wrapper_layout_id<T: Describe>(name: &str) -> String {
+    format!("{}<{}>", name, hex::encode(T::describe().layout_hash()))
+}
+
+/// Hashes length-prefixed bytes, so adjacent values in the layout hash can not
+/// be confused for each other.
+fn hash_bytes(hasher: &mut Sha256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_be_bytes());
+    hasher.update(bytes);
+}
+
+/// Returns a JSON Schema document describing the state tree of the given
+/// [Descriptor], e.g. for generating types for a frontend.
+///
+/// Types with named children (such as structs) are described as objects with
+/// a property per child, and types with dynamic children (such as maps) as
+/// objects whose additional properties are described by the value
+/// descriptor. Primitive leaves are described by their JSON type, and any
+/// other leaf only by its title.
+///
+/// Enums are described by a `oneOf` with an object schema per variant, whose
+/// `x-orga-discriminant` keyword is the byte the variant is encoded with.
+///
+/// Each schema's `title` is the Rust type name, and the orga-specific
+/// `x-orga-state-version`, `x-orga-store-key` (for named children), and
+/// `x-orga-key` (for dynamic children) keywords describe the store layout.
+pub fn to_json_schema(descriptor: &Descriptor) -> serde_json::Value {
+    let mut schema = json_schema(descriptor);
+    schema.as_object_mut().unwrap().insert(
+        "$schema".to_string(),
+        "https://json-schema.org/draft/2020-12/schema".into(),
+    );
+    schema
+}
+
+fn json_schema(descriptor: &Descriptor) -> serde_json::Value {
+    use serde_json::{json, Map, Value};
+
+    let mut schema = Map::new();
+    schema.insert("title".to_string(), descriptor.type_name.clone().into());
+    schema.insert(
+        "x-orga-state-version".to_string(),
+        descriptor.state_version.into(),
+    );
+
+    match descriptor.children() {
+        Children::None => {
+            let leaf = match descriptor.type_name.as_str() {
+                "bool" => json!({ "type": "boolean" }),
+                "alloc::string::String" => json!({ "type": "string" }),
+                "()" => json!({ "type": "null" }),
+                "u8" | "u16" | "u32" | "u64" | "u128" => {
+                    json!({ "type": "integer", "minimum": 0 })
+                }
+                "i8" | "i16" | "i32" | "i64" | "i128" => json!({ "type": "integer" }),
+                _ => json!({}),
+            };
+            if let Value::Object(leaf) = leaf {
+                schema.extend(leaf);
+            }
+        }
+        Children::Named(children) => {
+            let mut properties = Map::new();
+            for child in children {
+                let mut child_schema = json_schema(&child.desc);
+                child_schema.as_object_mut().unwrap().insert(
+                    "x-orga-store-key".to_string(),
+                    serde_json::to_value(&child.store_key).unwrap(),
+                );
+                properties.insert(child.name.clone(), child_schema);
+            }
+            let required: Vec<_> = children.iter().map(|c| c.name.clone()).collect();
+
+            schema.insert("type".to_string(), "object".into());
+            schema.insert("properties".to_string(), properties.into());
+            schema.insert("required".to_string(), required.into());
+        }
+        Children::Dynamic(child) => {
+            schema.insert("type".to_string(), "object".into());
+            schema.insert("x-orga-key".to_string(), json_schema(child.key_desc()));
+            schema.insert(
+                "additionalProperties".to_string(),
+                json_schema(child.value_desc()),
+            );
+        }
+        Children::Variants(variants) => {
+            let variants: Vec<_> = variants
+                .iter()
+                .map(|variant| {
+                    let mut properties = Map::new();
+                    for field in &variant.fields {
+                        properties.insert(field.name.clone(), json_schema(&field.desc));
+                    }
+                    let required: Vec<_> = variant.fields.iter().map(|f| f.name.clone()).collect();
+
+                    json!({
+                        "title": variant.name,
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                        "x-orga-discriminant": variant.discriminant,
+                    })
+                })
+                .collect();
+            schema.insert("oneOf".to_string(), variants.into());
+        }
+    }
+
+    Value::Object(schema)
+}
+
 /// A function used to load an instance of this value from a [Store] and encoded
 /// bytes.
 pub type LoadFn = fn(Store, &mut &[u8]) -> Result<()>;
 
+/// A function used to load an instance of this value from a [Store] and encoded
+/// bytes, then serialize it as JSON. Returns `None` if the type does not
+/// implement [Serialize].
+pub type ToJsonFn = fn(Store, &mut &[u8]) -> Result<Option<serde_json::Value>>;
+
+/// A function used to deserialize an instance of this value from JSON, then
+/// encode it. Returns `None` if the type does not implement [Deserialize] and
+/// [Encode].
+pub type EncodeJsonFn = fn(serde_json::Value) -> Result<Option<Vec<u8>>>;
+
+/// A function used to load an instance of this type from a [Store], returning
+/// it as a boxed [Any].
+pub type LoadAnyFn = fn(Store, &mut &[u8]) -> Result<Box<dyn Any>>;
+
+/// A function used to serialize a loaded instance of this type as JSON. Returns
+/// `None` if the value is not of this type or the type does not implement
+/// [Serialize].
+pub type AnyToJsonFn = fn(&dyn Any) -> Result<Option<serde_json::Value>>;
+
+/// A function used to access a named child of a loaded value. Returns `None`
+/// if the value is not of the parent type.
+pub type AccessFn = fn(&dyn Any) -> Option<&dyn Any>;
+
+/// A value at a position within a path being resolved by [resolve_path].
+enum Resolved<'a> {
+    /// The encoded bytes of the value, which has not been loaded yet.
+    Bytes(Vec<u8>),
+    /// The loaded value, e.g. a field of its loaded parent.
+    Loaded(&'a dyn Any),
+    /// A value whose dynamic children are read directly from the store, so it
+    /// does not need to be loaded itself.
+    Entries,
+}
+
+impl<'a> Resolved<'a> {
+    fn into_json(self, descriptor: &Descriptor, store: &Store) -> Result<serde_json::Value> {
+        if contains_collection(descriptor) {
+            return Err(Error::Query(format!(
+                "Can not serialize {} since it contains a collection",
+                descriptor.type_name
+            )));
+        }
+
+        let json = match self {
+            Resolved::Bytes(bytes) => descriptor
+                .to_json
+                .map(|to_json| to_json(store.clone(), &mut bytes.as_slice()))
+                .transpose()?
+                .flatten(),
+            Resolved::Loaded(value) => descriptor
+                .any_to_json
+                .map(|any_to_json| any_to_json(value))
+                .transpose()?
+                .flatten(),
+            Resolved::Entries => {
+                return Err(Error::Query(format!(
+                    "Can not serialize {} without its bytes",
+                    descriptor.type_name
+                )))
+            }
+        };
+
+        json.ok_or_else(|| {
+            Error::Query(format!(
+                "{} can not be serialized as JSON",
+                descriptor.type_name
+            ))
+        })
+    }
+}
+
+/// Returns whether the described type has dynamic children, or has a
+/// descendant which does. Serializing such a value reads every entry of the
+/// collection, so [resolve_path] refuses to.
+fn contains_collection(descriptor: &Descriptor) -> bool {
+    match descriptor.children() {
+        Children::None => false,
+        Children::Named(children) => children
+            .iter()
+            .any(|child| contains_collection(&child.desc)),
+        Children::Dynamic(_) => true,
+        Children::Variants(variants) => variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .any(|field| contains_collection(&field.desc)),
+    }
+}
+
+/// Resolves a path into the state tree of the given [Descriptor], returning
+/// the value at the end of the path as JSON.
+///
+/// `store` and `state_bytes` are the store and encoded bytes the described
+/// value is loaded from. Each segment of the path is either the name of a
+/// named child as a JSON string, or a key of a dynamic child (e.g. a
+/// [Map](crate::collections::Map) key) as the JSON of the key type. Keys which
+/// are not present resolve to `null`.
+///
+/// Dynamic children are read directly from the store, without loading the rest
+/// of their parent. Named children are accessed on their loaded parent, so only
+/// the value at the end of the path is serialized, and it must implement
+/// [Serialize]. Values which contain a collection are never serialized as a
+/// whole, since that would read every entry of the collection; the path must
+/// instead resolve to an entry within it.
+pub fn resolve_path(
+    descriptor: &Descriptor,
+    store: Store,
+    state_bytes: &[u8],
+    path: &[serde_json::Value],
+) -> Result<serde_json::Value> {
+    resolve_path_from(
+        descriptor,
+        store,
+        Resolved::Bytes(state_bytes.to_vec()),
+        path,
+    )
+}
+
+/// Resolves a path into the state tree of an already-loaded value. See
+/// [resolve_path] for details.
+pub(crate) fn resolve_loaded_path(
+    descriptor: &Descriptor,
+    store: Store,
+    value: &dyn Any,
+    path: &[serde_json::Value],
+) -> Result<serde_json::Value> {
+    let resolved = match descriptor.children() {
+        Children::Dynamic(_) if !path.is_empty() => Resolved::Entries,
+        _ => Resolved::Loaded(value),
+    };
+
+    resolve_path_from(descriptor, store, resolved, path)
+}
+
+fn resolve_path_from(
+    descriptor: &Descriptor,
+    store: Store,
+    resolved: Resolved,
+    path: &[serde_json::Value],
+) -> Result<serde_json::Value> {
+    let (segment, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return resolved.into_json(descriptor, &store),
+    };
+
+    match descriptor.children() {
+        Children::Named(children) => {
+            let name = segment
+                .as_str()
+                .ok_or_else(|| Error::Query(format!("Expected a field name, got {}", segment)))?;
+            let child = children
+                .iter()
+                .find(|child| child.name == name)
+                .ok_or_else(|| {
+                    Error::Query(format!("{} has no field {}", descriptor.type_name, name))
+                })?;
+            let child_store = child.store_key.apply(&store);
+
+            if !rest.is_empty() && matches!(child.desc.children(), Children::Dynamic(_)) {
+                return resolve_path_from(&child.desc, child_store, Resolved::Entries, rest);
+            }
+
+            let not_accessible = || {
+                Error::Query(format!(
+                    "Field {} of {} can not be accessed",
+                    name, descriptor.type_name
+                ))
+            };
+            let access = child.access.ok_or_else(not_accessible)?;
+            let parent = match resolved {
+                Resolved::Bytes(bytes) => {
+                    let load_any = descriptor.load_any.ok_or_else(not_accessible)?;
+                    load_any(store, &mut bytes.as_slice())?
+                }
+                Resolved::Loaded(value) => {
+                    let value = access(value).ok_or_else(not_accessible)?;
+                    return resolve_path_from(
+                        &child.desc,
+                        child_store,
+                        Resolved::Loaded(value),
+                        rest,
+                    );
+                }
+                Resolved::Entries => return Err(not_accessible()),
+            };
+            let value = access(parent.as_ref()).ok_or_else(not_accessible)?;
+
+            resolve_path_from(&child.desc, child_store, Resolved::Loaded(value), rest)
+        }
+        Children::Dynamic(child) => {
+            let key_desc = child.key_desc();
+            let key = key_desc
+                .encode_json
+                .map(|encode_json| encode_json(segment.clone()))
+                .transpose()?
+                .flatten()
+                .ok_or_else(|| {
+                    Error::Query(format!(
+                        "{} can not be deserialized from JSON",
+                        key_desc.type_name
+                    ))
+                })?;
+
+            let resolved = match store.get(&key)? {
+                Some(bytes) => Resolved::Bytes(bytes),
+                None => return Ok(serde_json::Value::Null),
+            };
+
+            resolve_path_from(child.value_desc(), store.sub(&key), resolved, rest)
+        }
+        _ => Err(Error::Query(format!(
+            "Can not resolve {} in {}",
+            segment, descriptor.type_name
+        ))),
+    }
+}
+
 /// A function used to modify the encoded bytes of a query when this type is
 /// used as a dynamic child.
 ///
@@ -103,6 +516,30 @@ pub enum Children {
     Named(Vec<NamedChild>),
     /// Dynamic children.
     Dynamic(DynamicChild),
+    /// The variants of an enum. Enum values are encoded inline, so unlike
+    /// other children, the fields of each variant have no store key.
+    Variants(Vec<Variant>),
+}
+
+/// A variant of an enum [Descriptor].
+#[derive(Clone, Debug)]
+pub struct Variant {
+    /// The name of the variant.
+    pub name: String,
+    /// The byte which the variant is encoded with, i.e. its index within the
+    /// enum.
+    pub discriminant: u8,
+    /// The fields of the variant, in encoding order.
+    pub fields: Vec<VariantField>,
+}
+
+/// A field of an enum [Variant].
+#[derive(Clone, Debug)]
+pub struct VariantField {
+    /// The name of the field, or its index for tuple variants.
+    pub name: String,
+    /// The field descriptor.
+    pub desc: Descriptor,
 }
 
 // #[wasm_bindgen(getter_with_clone, inspectable)]
@@ -116,6 +553,9 @@ pub struct NamedChild {
     /// The key operation to be applied to the parent when traversing into this
     /// child.
     pub store_key: KeyOp,
+    /// The function used to access the child on its loaded parent, if
+    /// available.
+    pub access: Option<AccessFn>,
 }
 
 // #[wasm_bindgen(inspectable)]
@@ -318,6 +758,25 @@ impl<T> MaybeToJson for ToJsonWrapper<T> {
     }
 }
 
+trait MaybeFromJson {
+    fn maybe_encode_json(value: serde_json::Value) -> Result<Option<Vec<u8>>>;
+}
+
+struct FromJsonWrapper<T>(std::marker::PhantomData<T>);
+
+impl<T> MaybeFromJson for FromJsonWrapper<T> {
+    default fn maybe_encode_json(_value: serde_json::Value) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
+
+impl<T: DeserializeOwned + Encode> MaybeFromJson for FromJsonWrapper<T> {
+    fn maybe_encode_json(value: serde_json::Value) -> Result<Option<Vec<u8>>> {
+        let value: T = serde_json::from_value(value)?;
+        Ok(Some(value.encode()?))
+    }
+}
+
 impl<T: Serialize> MaybeToJson for ToJsonWrapper<T> {
     fn maybe_to_json(&self) -> Result<Option<serde_json::Value>> {
         Ok(Some(serde_json::to_value(&self.0)?))
@@ -387,7 +846,7 @@ macro_rules! primitive_impl {
     ($ty:ty) => {
         impl Describe for $ty {
             fn describe() -> Descriptor {
-                Builder::new::<Self>().build()
+                Builder::new::<Self>().layout_id(stringify!($ty)).build()
             }
         }
     };
@@ -405,10 +864,11 @@ primitive_impl!(i64);
 primitive_impl!(i128);
 primitive_impl!(bool);
 primitive_impl!(());
+primitive_impl!(String);
 
 impl<T: 'static> Describe for std::marker::PhantomData<T> {
     fn describe() -> Descriptor {
-        Builder::new::<Self>().build()
+        Builder::new::<Self>().layout_id("PhantomData").build()
     }
 }
 
@@ -429,7 +889,9 @@ where
 {
     fn describe() -> Descriptor {
         // TODO: add child descriptors
-        Builder::new::<Self>().build()
+        Builder::new::<Self>()
+            .layout_id(format!("{}; {}", wrapper_layout_id::<T>("Array"), N))
+            .build()
     }
 }
 
@@ -439,7 +901,27 @@ where
 {
     fn describe() -> Descriptor {
         // TODO: add child descriptors
-        Builder::new::<Self>().build()
+        Builder::new::<Self>()
+            .layout_id(wrapper_layout_id::<T>("Vec"))
+            .build()
+    }
+}
+
+impl<K, V> Describe for std::collections::BTreeMap<K, V>
+where
+    Self: State,
+{
+    fn describe() -> Descriptor {
+        Builder::new::<Self>().layout_id("BTreeMap").build()
+    }
+}
+
+impl<K, V> Describe for std::collections::HashMap<K, V>
+where
+    Self: State,
+{
+    fn describe() -> Descriptor {
+        Builder::new::<Self>().layout_id("HashMap").build()
     }
 }
 
@@ -450,6 +932,7 @@ where
     fn describe() -> Descriptor {
         Builder::new::<Self>()
             // .named_child::<T>("inner", &[], |v| Builder::maybe_access(v, Self::take))
+            .layout_id(wrapper_layout_id::<T>("Option"))
             .build()
     }
 }
@@ -484,6 +967,210 @@ tuple_impl!(A, B, C, D, E, F, G, H, I; J; 0, 1, 2, 3, 4, 5, 6, 7, 8; 9);
 tuple_impl!(A, B, C, D, E, F, G, H, I, J; K; 0, 1, 2, 3, 4, 5, 6, 7, 8, 9; 10);
 tuple_impl!(A, B, C, D, E, F, G, H, I, J, K; L; 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10; 11);
 
+#[cfg(test)]
+mod layout_tests {
+    use super::*;
+    use crate::collections::Map;
+    use crate::orga;
+
+    fn layout(fields: &[(&'static str, u8)]) -> Descriptor {
+        fields
+            .iter()
+            .fold(Builder::new::<u32>(), |builder, (name, key)| {
+                builder.named_child::<u64>(name, &[*key])
+            })
+            .build()
+    }
+
+    #[orga]
+    struct App {
+        pub foo: u32,
+        pub bar: bool,
+        pub map: Map<u32, String>,
+    }
+
+    #[derive(Encode, Decode, Describe)]
+    enum Shape {
+        Circle(u32),
+        Rect { width: u32, height: u64 },
+    }
+
+    impl State for Shape {
+        fn attach(&mut self, _store: Store) -> Result<()> {
+            Ok(())
+        }
+
+        fn flush<W: std::io::Write>(self, out: &mut W) -> Result<()> {
+            Ok(self.encode_into(out)?)
+        }
+
+        fn load(_store: Store, bytes: &mut &[u8]) -> Result<Self> {
+            Ok(Self::decode(bytes)?)
+        }
+    }
+
+    #[test]
+    fn enum_variants() {
+        let desc = Shape::describe();
+        let variants = match desc.children() {
+            Children::Variants(variants) => variants,
+            _ => panic!("Incorrect children"),
+        };
+        assert_eq!(variants.len(), 2);
+
+        assert_eq!(variants[0].name, "Circle");
+        assert_eq!(variants[0].discriminant, 0);
+        assert_eq!(variants[0].fields.len(), 1);
+        assert_eq!(variants[0].fields[0].name, "0");
+        assert_eq!(variants[0].fields[0].desc.type_name, "u32");
+
+        assert_eq!(variants[1].name, "Rect");
+        assert_eq!(variants[1].discriminant, 1);
+        let fields: Vec<_> = variants[1]
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.desc.type_name.as_str()))
+            .collect();
+        assert_eq!(fields, vec![("width", "u32"), ("height", "u64")]);
+
+        let bytes = Shape::Rect {
+            width: 1,
+            height: 2,
+        }
+        .encode()
+        .unwrap();
+        assert_eq!(bytes[0], variants[1].discriminant);
+
+        let schema = to_json_schema(&desc);
+        assert_eq!(schema["oneOf"][1]["title"], "Rect");
+        assert_eq!(schema["oneOf"][1]["x-orga-discriminant"], 1);
+        assert_eq!(schema["oneOf"][1]["properties"]["height"]["title"], "u64");
+    }
+
+    #[test]
+    fn resolve_app_path() -> Result<()> {
+        use serde_json::json;
+
+        let store = Store::with_map_store();
+        let mut app = App::default();
+        app.attach(store.clone())?;
+        app.foo = 5;
+        app.bar = true;
+        app.map.insert(3, "three".to_string())?;
+        let mut bytes = vec![];
+        app.flush(&mut bytes)?;
+
+        let desc = App::describe();
+        let resolve = |path: &[serde_json::Value]| resolve_path(&desc, store.clone(), &bytes, path);
+        assert_eq!(resolve(&[json!("foo")])?, json!(5));
+        assert_eq!(resolve(&[json!("bar")])?, json!(true));
+        assert_eq!(resolve(&[json!("map"), json!(3)])?, json!("three"));
+        assert_eq!(resolve(&[json!("map"), json!(4)])?, json!(null));
+        assert!(resolve(&[json!("map")]).is_err());
+        assert!(resolve(&[]).is_err());
+        assert!(resolve(&[json!("baz")]).is_err());
+        assert!(resolve(&[json!("map"), json!("x")]).is_err());
+        assert!(resolve(&[json!("foo"), json!(0)]).is_err());
+
+        let plugin =
+            crate::plugins::QueryPlugin::<App>::load(store.clone(), &mut bytes.as_slice())?;
+        assert_eq!(plugin.resolve_path(&[json!("foo")])?, json!(5));
+        assert_eq!(
+            plugin.resolve_path(&[json!("map"), json!(3)])?,
+            json!("three")
+        );
+
+        #[cfg(feature = "abci")]
+        {
+            use crate::abci::AbciQuery;
+            use crate::plugins::query::STATE_QUERY_PATH;
+            use tendermint_proto::v0_34::abci::RequestQuery;
+
+            let query = |path: &str| {
+                plugin.abci_query(&RequestQuery {
+                    path: format!("{}{}", STATE_QUERY_PATH, path),
+                    ..Default::default()
+                })
+            };
+            let res = query(r#"["map",3]"#)?;
+            assert_eq!(res.code, 0);
+            assert_eq!(res.value, br#""three""#.to_vec());
+            assert_eq!(query(r#"["foo"]"#)?.value, b"5".to_vec());
+            assert!(query(r#"["map"]"#).is_err());
+            assert!(query("[]").is_err());
+            assert!(query(r#"["baz"]"#).is_err());
+            assert!(query("foo").is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn app_json_schema() {
+        let schema = to_json_schema(&App::describe());
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"], serde_json::json!(["foo", "bar", "map"]));
+
+        let properties = &schema["properties"];
+        assert_eq!(properties["foo"]["type"], "integer");
+        assert_eq!(properties["foo"]["title"], "u32");
+        assert_eq!(properties["foo"]["x-orga-store-key"]["Append"][0], 0);
+        assert_eq!(properties["bar"]["type"], "boolean");
+
+        let map = &properties["map"];
+        assert_eq!(map["type"], "object");
+        assert_eq!(map["x-orga-store-key"]["Append"][0], 2);
+        assert_eq!(map["x-orga-key"]["title"], "u32");
+        assert_eq!(map["additionalProperties"]["type"], "string");
+    }
+
+    #[test]
+    fn layout_hash() {
+        let desc = layout(&[("a", 0), ("b", 1)]);
+        assert_eq!(
+            desc.layout_hash(),
+            layout(&[("a", 0), ("b", 1)]).layout_hash()
+        );
+        assert_eq!(
+            hex::encode(desc.layout_hash()),
+            "951255e99eefed913c71b6146fe8585ed5803f1bddd0a49274656347ceb3f3eb"
+        );
+
+        // adding a field
+        assert_ne!(
+            desc.layout_hash(),
+            layout(&[("a", 0), ("b", 1), ("c", 2)]).layout_hash()
+        );
+        // renaming a field
+        assert_ne!(
+            desc.layout_hash(),
+            layout(&[("a", 0), ("c", 1)]).layout_hash()
+        );
+        // changing a field's store key
+        assert_ne!(
+            desc.layout_hash(),
+            layout(&[("a", 0), ("b", 2)]).layout_hash()
+        );
+        // changing a field's type
+        let retyped = Builder::new::<u32>()
+            .named_child::<u64>("a", &[0])
+            .named_child::<u32>("b", &[1])
+            .build();
+        assert_ne!(desc.layout_hash(), retyped.layout_hash());
+
+        // the type's name is not part of the layout
+        let renamed = Builder::new::<bool>()
+            .named_child::<u64>("a", &[0])
+            .named_child::<u64>("b", &[1])
+            .build();
+        assert_eq!(desc.layout_hash(), renamed.layout_hash());
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use serde::{Deserialize, Serialize};
@@ -1,9 +1,10 @@
 use crate::state::State;
-use std::any::{type_name, TypeId};
+use std::any::{type_name, Any, TypeId};
 
 use super::{
-    ApplyQueryBytesFn, Children, Describe, Descriptor, DynamicChild, Inspect, KeyOp, LoadFn,
-    NamedChild,
+    AccessFn, AnyToJsonFn, ApplyQueryBytesFn, Children, Describe, Descriptor, DynamicChild,
+    EncodeJsonFn, FromJsonWrapper, Inspect, KeyOp, LoadAnyFn, LoadFn, MaybeFromJson, NamedChild,
+    ToJsonFn, Variant, VariantField,
 };
 
 /// A builder for creating a [Descriptor].
@@ -11,7 +12,12 @@ pub struct Builder {
     type_id: TypeId,
     type_name: String,
     state_version: u32,
+    layout_id: Option<String>,
     load: LoadFn,
+    to_json: ToJsonFn,
+    encode_json: EncodeJsonFn,
+    load_any: LoadAnyFn,
+    any_to_json: AnyToJsonFn,
     children: Option<Children>,
     meta: Option<Box<Descriptor>>,
 }
@@ -23,10 +29,18 @@ impl Builder {
             type_id: TypeId::of::<T>(),
             type_name: type_name::<T>().to_string(),
             state_version: 0, // TODO
+            layout_id: None,
             load: |store, bytes| {
                 T::load(store, bytes)?;
                 Ok(())
             },
+            to_json: |store, bytes| T::load(store, bytes)?.maybe_to_json(),
+            encode_json: FromJsonWrapper::<T>::maybe_encode_json,
+            load_any: |store, bytes| Ok(Box::new(T::load(store, bytes)?) as Box<dyn Any>),
+            any_to_json: |value| match value.downcast_ref::<T>() {
+                Some(value) => value.maybe_to_json(),
+                None => Ok(None),
+            },
             // meta: Some(Box::new(<u8 as Describe>::describe())),
             meta: None,
             children: None,
@@ -34,11 +48,21 @@ impl Builder {
     }
 
     /// Add a [NamedChild] to the descriptor with a given key operation.
-    pub fn named_child_keyop<T: Describe>(mut self, name: &'static str, keyop: KeyOp) -> Self {
+    pub fn named_child_keyop<T: Describe>(self, name: &'static str, keyop: KeyOp) -> Self {
+        self.add_named_child::<T>(name, keyop, None)
+    }
+
+    fn add_named_child<T: Describe>(
+        mut self,
+        name: &'static str,
+        keyop: KeyOp,
+        access: Option<AccessFn>,
+    ) -> Self {
         let child = NamedChild {
             name: name.to_string(),
             store_key: keyop,
             desc: T::describe(),
+            access,
         };
 
         match self.children {
@@ -57,18 +81,28 @@ impl Builder {
     }
 
     /// Add a [NamedChild] to the descriptor using the [KeyOp] defined by the
-    /// type's implementation of [State::field_keyop] for that field.
+    /// type's implementation of [State::field_keyop] for that field, and the
+    /// given [AccessFn] to access the field on a loaded value.
     pub fn named_child_from_state<T: State + Describe, U: Describe>(
         self,
         name: &'static str,
+        access: AccessFn,
     ) -> Self {
         if let Some(keyop) = T::field_keyop(name) {
-            self.named_child_keyop::<U>(name, keyop)
+            self.add_named_child::<U>(name, keyop, Some(access))
         } else {
             self
         }
     }
 
+    /// Sets the descriptor's [layout identifier](Descriptor::layout_id).
+    pub fn layout_id(self, id: impl Into<String>) -> Self {
+        Builder {
+            layout_id: Some(id.into()),
+            ..self
+        }
+    }
+
     /// Sets the [Meta] for the descriptor to the descriptor of another type
     /// `T`.
     pub fn meta<T: Describe>(self) -> Self {
@@ -98,13 +132,53 @@ impl Builder {
         self
     }
 
+    /// Add a [Variant] with no fields to the descriptor, e.g. for an enum.
+    /// Fields can then be added to it with [Builder::variant_field].
+    pub fn variant(mut self, name: &'static str, discriminant: u8) -> Self {
+        let variant = Variant {
+            name: name.to_string(),
+            discriminant,
+            fields: vec![],
+        };
+
+        match self.children {
+            None => self.children = Some(Children::Variants(vec![variant])),
+            Some(Children::Variants(ref mut variants)) => variants.push(variant),
+            Some(_) => panic!("Cannot add variant"),
+        };
+
+        self
+    }
+
+    /// Add a field to the most recently added [Variant].
+    pub fn variant_field<T: Describe>(mut self, name: &'static str) -> Self {
+        let field = VariantField {
+            name: name.to_string(),
+            desc: T::describe(),
+        };
+
+        match self.children {
+            Some(Children::Variants(ref mut variants)) if !variants.is_empty() => {
+                variants.last_mut().unwrap().fields.push(field)
+            }
+            _ => panic!("Cannot add variant field without a variant"),
+        };
+
+        self
+    }
+
     /// Builds the descriptor.
     pub fn build(self) -> Descriptor {
         Descriptor {
             type_id: self.type_id,
             type_name: self.type_name,
             state_version: self.state_version,
+            layout_id: self.layout_id,
             load: Some(self.load),
+            to_json: Some(self.to_json),
+            encode_json: Some(self.encode_json),
+            load_any: Some(self.load_any),
+            any_to_json: Some(self.any_to_json),
             children: self.children.unwrap_or_default(),
             meta: self.meta,
         }
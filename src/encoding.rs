@@ -11,8 +11,13 @@ pub mod encoder;
 use derive_more::{Deref, DerefMut, Into};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, HashMap},
     convert::{TryFrom, TryInto},
+    hash::Hash,
+    io::Read,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     str::FromStr,
+    time::Duration,
 };
 
 #[derive(
@@ -362,6 +367,403 @@ where
     }
 }
 
+/// Canonical encoding for [HashMap]: a `u64` entry count followed by the
+/// entries, sorted by their encoded key bytes so that equal maps always encode
+/// identically regardless of their internal iteration order. Decoding rejects
+/// encoded keys which are not strictly increasing, so each map has exactly one
+/// valid encoding.
+impl<K, V> Encode for Adapter<HashMap<K, V>>
+where
+    K: Encode + Terminated,
+    V: Encode + Terminated,
+{
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+        (self.0.len() as u64).encode_into(dest)?;
+
+        let mut entries = self
+            .0
+            .iter()
+            .map(|(key, value)| Ok((key.encode()?, value)))
+            .collect::<Result<Vec<_>>>()?;
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (key_bytes, value) in entries {
+            dest.write_all(&key_bytes)?;
+            value.encode_into(dest)?;
+        }
+
+        Ok(())
+    }
+
+    fn encoding_length(&self) -> Result<usize> {
+        let mut len = 0u64.encoding_length()?;
+        for (key, value) in self.0.iter() {
+            len += key.encoding_length()? + value.encoding_length()?;
+        }
+
+        Ok(len)
+    }
+}
+
+impl<K, V> Decode for Adapter<HashMap<K, V>>
+where
+    K: Encode + Decode + Terminated + Eq + Hash,
+    V: Decode + Terminated,
+{
+    fn decode<R: std::io::Read>(mut input: R) -> Result<Self> {
+        let len = u64::decode(&mut input)?;
+
+        let mut map = HashMap::new();
+        let mut last_key_bytes: Option<Vec<u8>> = None;
+        for _ in 0..len {
+            let key = K::decode(&mut input)?;
+            let key_bytes = key.encode()?;
+            if last_key_bytes.is_some_and(|last| last >= key_bytes) {
+                return Err(Error::UnexpectedByte(5));
+            }
+            let value = V::decode(&mut input)?;
+            map.insert(key, value);
+            last_key_bytes = Some(key_bytes);
+        }
+
+        Ok(Adapter(map))
+    }
+}
+
+impl<K, V> Terminated for Adapter<HashMap<K, V>>
+where
+    K: Terminated,
+    V: Terminated,
+{
+}
+
+/// Canonical encoding for [BTreeMap]: a `u64` entry count followed by the
+/// entries in the map's natural key order. Decoding rejects keys which are not
+/// strictly increasing, so each map has exactly one valid encoding.
+impl<K, V> Encode for Adapter<BTreeMap<K, V>>
+where
+    K: Encode + Terminated,
+    V: Encode + Terminated,
+{
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+        (self.0.len() as u64).encode_into(dest)?;
+
+        for (key, value) in self.0.iter() {
+            key.encode_into(dest)?;
+            value.encode_into(dest)?;
+        }
+
+        Ok(())
+    }
+
+    fn encoding_length(&self) -> Result<usize> {
+        let mut len = 0u64.encoding_length()?;
+        for (key, value) in self.0.iter() {
+            len += key.encoding_length()? + value.encoding_length()?;
+        }
+
+        Ok(len)
+    }
+}
+
+impl<K, V> Decode for Adapter<BTreeMap<K, V>>
+where
+    K: Decode + Terminated + Ord,
+    V: Decode + Terminated,
+{
+    fn decode<R: std::io::Read>(mut input: R) -> Result<Self> {
+        let len = u64::decode(&mut input)?;
+
+        let mut map = BTreeMap::new();
+        for _ in 0..len {
+            let key = K::decode(&mut input)?;
+            let value = V::decode(&mut input)?;
+            if map.last_key_value().is_some_and(|(last, _)| *last >= key) {
+                return Err(Error::UnexpectedByte(5));
+            }
+            map.insert(key, value);
+        }
+
+        Ok(Adapter(map))
+    }
+}
+
+impl<K, V> Terminated for Adapter<BTreeMap<K, V>>
+where
+    K: Terminated,
+    V: Terminated,
+{
+}
+
+/// Length-prefixed encoding for [String]: the byte length of the string as an
+/// unsigned LEB128 varint, followed by its UTF-8 bytes.
+///
+/// Orphan rules prevent implementing [Encode] and [Decode] for [String]
+/// directly, so this is exposed through [Adapter]. The `State` impl for
+/// [String] uses the same encoding.
+impl Encode for Adapter<String> {
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+        let mut len = self.0.len() as u64;
+        loop {
+            let byte = (len & 0x7f) as u8;
+            len >>= 7;
+            if len == 0 {
+                dest.write_all(&[byte])?;
+                break;
+            }
+            dest.write_all(&[byte | 0x80])?;
+        }
+        dest.write_all(self.0.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn encoding_length(&self) -> Result<usize> {
+        let len = self.0.len();
+        let prefix_len = (usize::BITS - len.leading_zeros()).div_ceil(7).max(1) as usize;
+
+        Ok(prefix_len + len)
+    }
+}
+
+impl Decode for Adapter<String> {
+    fn decode<R: std::io::Read>(mut input: R) -> Result<Self> {
+        let mut len = 0u64;
+        for i in 0.. {
+            let byte = u8::decode(&mut input)?;
+            // reject overflowing and non-canonical (zero-padded) prefixes
+            if (i == 9 && byte > 1) || (i > 0 && byte == 0) {
+                return Err(Error::UnexpectedByte(byte));
+            }
+            len |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        // read through `take` so a malicious length can not cause a large
+        // allocation without the bytes being present
+        let mut bytes = vec![];
+        input.take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(Error::IOError(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+
+        String::from_utf8(bytes)
+            .map(Adapter)
+            .map_err(|e| Error::IOError(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+    }
+}
+
+impl Terminated for Adapter<String> {}
+
+macro_rules! float_impl {
+    ($type:ty, $nan_bits:expr) => {
+        /// Fixed-size encoding for floats: the big-endian bytes of the value's
+        /// bit pattern, as returned by `to_bits`. All NaN values are encoded as
+        /// a single canonical NaN, and decoding rejects any other NaN bit
+        /// pattern, so each value has exactly one valid encoding.
+        ///
+        /// Unlike the integer encodings, the byte order of encoded floats does
+        /// not match their numeric order (e.g. negative values sort after
+        /// positive ones), so they are not suitable for use in keys.
+        impl Encode for Adapter<$type> {
+            fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+                let bits = if self.0.is_nan() {
+                    $nan_bits
+                } else {
+                    self.0.to_bits()
+                };
+                dest.write_all(&bits.to_be_bytes())?;
+                Ok(())
+            }
+
+            fn encoding_length(&self) -> Result<usize> {
+                Ok(std::mem::size_of::<$type>())
+            }
+        }
+
+        impl Decode for Adapter<$type> {
+            fn decode<R: std::io::Read>(mut input: R) -> Result<Self> {
+                let mut bytes = [0; std::mem::size_of::<$type>()];
+                input.read_exact(&mut bytes)?;
+
+                let value = <$type>::from_be_bytes(bytes);
+                if value.is_nan() && value.to_bits() != $nan_bits {
+                    return Err(Error::UnexpectedByte(bytes[0]));
+                }
+
+                Ok(Adapter(value))
+            }
+        }
+
+        impl Terminated for Adapter<$type> {}
+    };
+}
+
+float_impl!(f32, 0x7fc0_0000u32);
+float_impl!(f64, 0x7ff8_0000_0000_0000u64);
+
+/// Fixed-size encoding for [char]: its Unicode scalar value as a big-endian
+/// `u32`. Decoding rejects surrogates and values above `char::MAX`.
+impl Encode for Adapter<char> {
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+        (self.0 as u32).encode_into(dest)
+    }
+
+    fn encoding_length(&self) -> Result<usize> {
+        Ok(std::mem::size_of::<u32>())
+    }
+}
+
+impl Decode for Adapter<char> {
+    fn decode<R: std::io::Read>(input: R) -> Result<Self> {
+        let code_point = u32::decode(input)?;
+        char::from_u32(code_point).map(Adapter).ok_or_else(|| {
+            Error::IOError(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid char code point {:#x}", code_point),
+            ))
+        })
+    }
+}
+
+impl Terminated for Adapter<char> {}
+
+/// Fixed-size encoding for [Duration]: the whole seconds as a `u64`, followed
+/// by the subsecond nanoseconds as a `u32`. Decoding rejects nanosecond values
+/// of a second or more, so each duration has exactly one valid encoding.
+impl Encode for Adapter<Duration> {
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+        self.0.as_secs().encode_into(dest)?;
+        self.0.subsec_nanos().encode_into(dest)
+    }
+
+    fn encoding_length(&self) -> Result<usize> {
+        Ok(std::mem::size_of::<u64>() + std::mem::size_of::<u32>())
+    }
+}
+
+impl Decode for Adapter<Duration> {
+    fn decode<R: std::io::Read>(mut input: R) -> Result<Self> {
+        let secs = u64::decode(&mut input)?;
+        let nanos = u32::decode(&mut input)?;
+        if nanos >= 1_000_000_000 {
+            return Err(Error::IOError(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid duration nanoseconds {}", nanos),
+            )));
+        }
+
+        Ok(Adapter(Duration::new(secs, nanos)))
+    }
+}
+
+impl Terminated for Adapter<Duration> {}
+
+/// Fixed-size encoding for [Ipv4Addr]: its 4 octets.
+impl Encode for Adapter<Ipv4Addr> {
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+        dest.write_all(&self.0.octets())?;
+        Ok(())
+    }
+
+    fn encoding_length(&self) -> Result<usize> {
+        Ok(4)
+    }
+}
+
+impl Decode for Adapter<Ipv4Addr> {
+    fn decode<R: std::io::Read>(input: R) -> Result<Self> {
+        Ok(Adapter(<[u8; 4]>::decode(input)?.into()))
+    }
+}
+
+impl Terminated for Adapter<Ipv4Addr> {}
+
+/// Fixed-size encoding for [Ipv6Addr]: its 16 octets.
+impl Encode for Adapter<Ipv6Addr> {
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+        dest.write_all(&self.0.octets())?;
+        Ok(())
+    }
+
+    fn encoding_length(&self) -> Result<usize> {
+        Ok(16)
+    }
+}
+
+impl Decode for Adapter<Ipv6Addr> {
+    fn decode<R: std::io::Read>(input: R) -> Result<Self> {
+        Ok(Adapter(<[u8; 16]>::decode(input)?.into()))
+    }
+}
+
+impl Terminated for Adapter<Ipv6Addr> {}
+
+/// Encoding for [IpAddr]: a family tag byte (0 for IPv4, 1 for IPv6) followed
+/// by the address's octets.
+impl Encode for Adapter<IpAddr> {
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+        match self.0 {
+            IpAddr::V4(addr) => {
+                0u8.encode_into(dest)?;
+                Adapter(addr).encode_into(dest)
+            }
+            IpAddr::V6(addr) => {
+                1u8.encode_into(dest)?;
+                Adapter(addr).encode_into(dest)
+            }
+        }
+    }
+
+    fn encoding_length(&self) -> Result<usize> {
+        match self.0 {
+            IpAddr::V4(addr) => Ok(1 + Adapter(addr).encoding_length()?),
+            IpAddr::V6(addr) => Ok(1 + Adapter(addr).encoding_length()?),
+        }
+    }
+}
+
+impl Decode for Adapter<IpAddr> {
+    fn decode<R: std::io::Read>(mut input: R) -> Result<Self> {
+        match u8::decode(&mut input)? {
+            0 => Ok(Adapter(Adapter::<Ipv4Addr>::decode(input)?.0.into())),
+            1 => Ok(Adapter(Adapter::<Ipv6Addr>::decode(input)?.0.into())),
+            tag => Err(Error::UnexpectedByte(tag)),
+        }
+    }
+}
+
+impl Terminated for Adapter<IpAddr> {}
+
+/// Encoding for [SocketAddr]: the encoding of its [IpAddr], followed by the
+/// port as a `u16`.
+///
+/// The flow info and scope ID of IPv6 socket addresses are not encoded, and
+/// are always zero when decoded.
+impl Encode for Adapter<SocketAddr> {
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+        Adapter(self.0.ip()).encode_into(dest)?;
+        self.0.port().encode_into(dest)
+    }
+
+    fn encoding_length(&self) -> Result<usize> {
+        Ok(Adapter(self.0.ip()).encoding_length()? + 2)
+    }
+}
+
+impl Decode for Adapter<SocketAddr> {
+    fn decode<R: std::io::Read>(mut input: R) -> Result<Self> {
+        let ip = Adapter::<IpAddr>::decode(&mut input)?.0;
+        let port = u16::decode(&mut input)?;
+
+        Ok(Adapter(SocketAddr::new(ip, port)))
+    }
+}
+
+impl Terminated for Adapter<SocketAddr> {}
+
 #[derive(Clone, Debug, Deref, Serialize, Default)]
 #[serde(transparent)]
 pub struct ByteTerminatedString<const B: u8, T: FromStr + ToString = String>(pub T);
@@ -370,7 +772,9 @@ impl<const B: u8, T: FromStr + ToString + 'static> Migrate for ByteTerminatedStr
 
 impl<const B: u8, T: FromStr + ToString + 'static> Describe for ByteTerminatedString<B, T> {
     fn describe() -> crate::describe::Descriptor {
-        crate::describe::Builder::new::<Self>().build()
+        crate::describe::Builder::new::<Self>()
+            .layout_id(format!("ByteTerminatedString<{}>", B))
+            .build()
     }
 }
 
@@ -442,7 +846,9 @@ pub struct EofTerminatedString<T: FromStr + ToString = String>(pub T);
 
 impl<T: FromStr + ToString + 'static> Describe for EofTerminatedString<T> {
     fn describe() -> crate::describe::Descriptor {
-        crate::describe::Builder::new::<Self>().build()
+        crate::describe::Builder::new::<Self>()
+            .layout_id("EofTerminatedString")
+            .build()
     }
 }
 
@@ -507,7 +913,9 @@ pub struct FixedString<const S: &'static str>;
 
 impl<const S: &'static str> Describe for FixedString<S> {
     fn describe() -> crate::describe::Descriptor {
-        crate::describe::Builder::new::<Self>().build()
+        crate::describe::Builder::new::<Self>()
+            .layout_id(format!("FixedString<{:?}>", S))
+            .build()
     }
 }
 
@@ -557,6 +965,425 @@ where
     }
 }
 
+/// A reader which returns an error if more than a maximum number of bytes are
+/// read from the inner reader.
+///
+/// Unlike [std::io::Take], reaching the limit is an error rather than an EOF,
+/// so decoders which read until EOF can not silently truncate their input.
+pub struct LimitedReader<R> {
+    inner: R,
+    remaining: usize,
+}
+
+impl<R: std::io::Read> LimitedReader<R> {
+    /// Create a new reader which will read at most `max_bytes` bytes from
+    /// `inner`.
+    pub fn new(inner: R, max_bytes: usize) -> Self {
+        Self {
+            inner,
+            remaining: max_bytes,
+        }
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.remaining == 0 {
+            // only error if the inner reader actually has more data
+            let mut probe = [0];
+            return match self.inner.read(&mut probe)? {
+                0 => Ok(0),
+                _ => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Decode length limit exceeded",
+                )),
+            };
+        }
+
+        let max = buf.len().min(self.remaining);
+        let n = self.inner.read(&mut buf[..max])?;
+        self.remaining -= n;
+
+        Ok(n)
+    }
+}
+
+/// Decodes a value from `input`, returning an error rather than continuing to
+/// read (and allocate) once more than `max_bytes` bytes have been consumed.
+pub fn decode_with_limit<T: Decode, R: std::io::Read>(input: R, max_bytes: usize) -> Result<T> {
+    T::decode(LimitedReader::new(input, max_bytes))
+}
+
+/// A wrapper which limits the encoding of the inner value to at most `N`
+/// bytes.
+///
+/// Decoding returns an error as soon as more than `N` bytes would be read, so
+/// e.g. a `LengthLimited<Vec<T>, N>` can be used to decode untrusted input
+/// without unbounded allocation.
+#[derive(Clone, Debug, Deref, DerefMut, Serialize, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct LengthLimited<T, const N: usize>(pub T);
+
+impl<T: Describe + 'static, const N: usize> Describe for LengthLimited<T, N>
+where
+    Self: State,
+{
+    fn describe() -> crate::describe::Descriptor {
+        // the encoding is the same as the inner value's, so its layout and
+        // children are described by `T`, while values are still loaded with
+        // the length limit applied
+        let own = crate::describe::Builder::new::<Self>().build();
+        let mut desc = T::describe();
+        desc.type_id = own.type_id;
+        desc.type_name = own.type_name;
+        desc.load = own.load;
+        desc.to_json = own.to_json;
+        desc.encode_json = own.encode_json;
+        desc.load_any = own.load_any;
+        desc.any_to_json = own.any_to_json;
+
+        desc
+    }
+}
+
+impl<T: 'static, const N: usize> Migrate for LengthLimited<T, N> where Self: State {}
+
+impl<T: Encode, const N: usize> Encode for LengthLimited<T, N> {
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> ed::Result<()> {
+        if self.0.encoding_length()? > N {
+            return Err(ed::Error::UnexpectedByte(0));
+        }
+        self.0.encode_into(dest)
+    }
+
+    fn encoding_length(&self) -> ed::Result<usize> {
+        self.0.encoding_length()
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for LengthLimited<T, N> {
+    fn decode<R: std::io::Read>(input: R) -> ed::Result<Self> {
+        Ok(Self(decode_with_limit(input, N)?))
+    }
+}
+
+impl<T: Terminated, const N: usize> Terminated for LengthLimited<T, N> {}
+
+impl<T: 'static, const N: usize> State for LengthLimited<T, N>
+where
+    Self: Encode + Decode,
+{
+    fn attach(&mut self, _store: crate::store::Store) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn flush<W: std::io::Write>(self, out: &mut W) -> crate::Result<()> {
+        self.encode_into(out)?;
+        Ok(())
+    }
+
+    fn load(_store: crate::store::Store, bytes: &mut &[u8]) -> crate::Result<Self> {
+        Ok(Self::decode(bytes)?)
+    }
+}
+
+impl<T, const N: usize> LengthLimited<T, N> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// A wrapper which run-length encodes the runs of zero bytes in the inner byte
+/// array, for large values which are mostly zeros (e.g. bitmaps or sparse
+/// accumulators).
+///
+/// The encoding is a big-endian `u32` total length, followed by segments of a
+/// `u8` count of zero bytes, a `u8` count of literal bytes, and the literal
+/// bytes themselves. Segments are built greedily so every value has exactly one
+/// encoding, and decoding rejects any other (non-canonical) form.
+#[derive(Clone, Debug, Deref, DerefMut, Serialize, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Sparse<T>(pub T);
+
+impl<T> Sparse<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+fn sparse_segments(bytes: &[u8]) -> Vec<(usize, &[u8])> {
+    let mut segments = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let zeros = bytes[i..]
+            .iter()
+            .take(u8::MAX as usize)
+            .take_while(|b| **b == 0)
+            .count();
+        i += zeros;
+
+        let literal_len = if zeros == u8::MAX as usize {
+            0
+        } else {
+            bytes[i..]
+                .iter()
+                .take(u8::MAX as usize)
+                .take_while(|b| **b != 0)
+                .count()
+        };
+        segments.push((zeros, &bytes[i..i + literal_len]));
+        i += literal_len;
+    }
+
+    segments
+}
+
+impl<T: AsRef<[u8]>> Encode for Sparse<T> {
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> ed::Result<()> {
+        let bytes = self.0.as_ref();
+        let len: u32 = bytes
+            .len()
+            .try_into()
+            .map_err(|_| ed::Error::UnexpectedByte(0))?;
+        len.encode_into(dest)?;
+
+        for (zeros, literal) in sparse_segments(bytes) {
+            dest.write_all(&[zeros as u8, literal.len() as u8])?;
+            dest.write_all(literal)?;
+        }
+
+        Ok(())
+    }
+
+    fn encoding_length(&self) -> ed::Result<usize> {
+        let segments = sparse_segments(self.0.as_ref());
+        Ok(4 + segments
+            .iter()
+            .map(|(_, literal)| 2 + literal.len())
+            .sum::<usize>())
+    }
+}
+
+impl<T: AsRef<[u8]> + TryFrom<Vec<u8>>> Decode for Sparse<T> {
+    fn decode<R: std::io::Read>(mut input: R) -> ed::Result<Self> {
+        let len = u32::decode(&mut input)?;
+        let mut encoded = len.encode()?;
+        let len = len as usize;
+
+        // the output only grows as segments are read, so the allocation is
+        // bounded by the input actually consumed (at most 255 bytes per 2 byte
+        // segment header) rather than by the length header
+        let mut bytes = vec![];
+        while bytes.len() < len {
+            let mut header = [0; 2];
+            input.read_exact(&mut header)?;
+            encoded.extend_from_slice(&header);
+            let [zeros, literal_len] = header.map(usize::from);
+            if zeros + literal_len == 0 || bytes.len() + zeros + literal_len > len {
+                return Err(ed::Error::UnexpectedByte(header[0]));
+            }
+
+            bytes.resize(bytes.len() + zeros, 0);
+            let start = bytes.len();
+            bytes.resize(start + literal_len, 0);
+            input.read_exact(&mut bytes[start..])?;
+            encoded.extend_from_slice(&bytes[start..]);
+        }
+
+        let value = Sparse(T::try_from(bytes).map_err(|_| ed::Error::UnexpectedByte(0))?);
+        if value.encode()? != encoded {
+            // the segments were not split the way the encoder would split
+            // them, so the input is not canonical
+            return Err(ed::Error::UnexpectedByte(0));
+        }
+
+        Ok(value)
+    }
+}
+
+impl<T> Terminated for Sparse<T> {}
+
+impl<T: 'static> Describe for Sparse<T> {
+    fn describe() -> crate::describe::Descriptor {
+        crate::describe::Builder::new::<Self>()
+            .layout_id("Sparse")
+            .build()
+    }
+}
+
+impl<T: 'static> Migrate for Sparse<T> where Self: State {}
+
+impl<T: 'static> State for Sparse<T>
+where
+    Self: Encode + Decode,
+{
+    fn attach(&mut self, _store: crate::store::Store) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn flush<W: std::io::Write>(self, out: &mut W) -> crate::Result<()> {
+        self.encode_into(out)?;
+        Ok(())
+    }
+
+    fn load(_store: crate::store::Store, bytes: &mut &[u8]) -> crate::Result<Self> {
+        Ok(Self::decode(bytes)?)
+    }
+}
+
+/// Integer types which can be encoded with an explicit byte order by [LeInt]
+/// and [BeInt].
+pub trait FixedInt: Copy + 'static {
+    /// The fixed-size byte array representation of the integer.
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Default;
+
+    /// Returns the little-endian bytes of the integer.
+    fn into_le_bytes(self) -> Self::Bytes;
+    /// Returns the big-endian bytes of the integer.
+    fn into_be_bytes(self) -> Self::Bytes;
+    /// Creates the integer from its little-endian bytes.
+    fn from_le_array(bytes: Self::Bytes) -> Self;
+    /// Creates the integer from its big-endian bytes.
+    fn from_be_array(bytes: Self::Bytes) -> Self;
+}
+
+macro_rules! fixed_int_impl {
+    ($type:ty) => {
+        impl FixedInt for $type {
+            type Bytes = [u8; std::mem::size_of::<$type>()];
+
+            fn into_le_bytes(self) -> Self::Bytes {
+                self.to_le_bytes()
+            }
+
+            fn into_be_bytes(self) -> Self::Bytes {
+                self.to_be_bytes()
+            }
+
+            fn from_le_array(bytes: Self::Bytes) -> Self {
+                Self::from_le_bytes(bytes)
+            }
+
+            fn from_be_array(bytes: Self::Bytes) -> Self {
+                Self::from_be_bytes(bytes)
+            }
+        }
+    };
+}
+
+fixed_int_impl!(u8);
+fixed_int_impl!(u16);
+fixed_int_impl!(u32);
+fixed_int_impl!(u64);
+fixed_int_impl!(u128);
+fixed_int_impl!(i8);
+fixed_int_impl!(i16);
+fixed_int_impl!(i32);
+fixed_int_impl!(i64);
+fixed_int_impl!(i128);
+
+macro_rules! endian_int {
+    ($name:ident, $to:ident, $from:ident, $doc:literal) => {
+        #[doc = $doc]
+        ///
+        /// Signed integers are encoded as plain two's complement, so this is
+        /// intended for fields of interop formats rather than for keys which
+        /// rely on the default encoding's ordering.
+        #[derive(
+            Clone,
+            Copy,
+            Debug,
+            Default,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            Deref,
+            DerefMut,
+            Serialize,
+            Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name<T>(pub T);
+
+        impl<T: FixedInt> Encode for $name<T> {
+            fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> Result<()> {
+                dest.write_all(self.0.$to().as_ref())?;
+                Ok(())
+            }
+
+            fn encoding_length(&self) -> Result<usize> {
+                Ok(std::mem::size_of::<T::Bytes>())
+            }
+        }
+
+        impl<T: FixedInt> Decode for $name<T> {
+            fn decode<R: std::io::Read>(mut input: R) -> Result<Self> {
+                let mut bytes = T::Bytes::default();
+                input.read_exact(bytes.as_mut())?;
+                Ok(Self(T::$from(bytes)))
+            }
+        }
+
+        impl<T: FixedInt> Terminated for $name<T> {}
+
+        impl<T: FixedInt> State for $name<T> {
+            fn attach(&mut self, _store: crate::store::Store) -> crate::Result<()> {
+                Ok(())
+            }
+
+            fn flush<W: std::io::Write>(self, out: &mut W) -> crate::Result<()> {
+                self.encode_into(out)?;
+                Ok(())
+            }
+
+            fn load(_store: crate::store::Store, bytes: &mut &[u8]) -> crate::Result<Self> {
+                Ok(Self::decode(bytes)?)
+            }
+        }
+
+        impl<T: FixedInt> Migrate for $name<T> {}
+
+        impl<T: FixedInt> Describe for $name<T> {
+            fn describe() -> crate::describe::Descriptor {
+                crate::describe::Builder::new::<Self>()
+                    .layout_id(stringify!($name))
+                    .build()
+            }
+        }
+
+        impl<T> From<T> for $name<T> {
+            fn from(value: T) -> Self {
+                Self(value)
+            }
+        }
+
+        impl<T> $name<T> {
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+    };
+}
+
+endian_int!(
+    LeInt,
+    into_le_bytes,
+    from_le_array,
+    "An integer which is always encoded in little-endian byte order."
+);
+endian_int!(
+    BeInt,
+    into_be_bytes,
+    from_be_array,
+    "An integer which is always encoded in big-endian byte order."
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -612,6 +1439,59 @@ mod tests {
         assert_eq!(*decoded, *value);
     }
 
+    #[test]
+    fn hash_map_canonical_encoding() {
+        let mut a = HashMap::new();
+        let mut b = HashMap::new();
+        for i in 0..100u32 {
+            a.insert(i, i as u64 * 2);
+            b.insert(99 - i, (99 - i) as u64 * 2);
+        }
+
+        let a_bytes = Adapter(a.clone()).encode().unwrap();
+        let b_bytes = Adapter(b).encode().unwrap();
+        assert_eq!(a_bytes, b_bytes);
+        assert_eq!(a_bytes.len(), Adapter(a.clone()).encoding_length().unwrap());
+        assert_eq!(&a_bytes[..8], &100u64.to_be_bytes());
+        assert_eq!(&a_bytes[8..12], &0u32.to_be_bytes());
+
+        let decoded = Adapter::<HashMap<u32, u64>>::decode(a_bytes.as_slice()).unwrap();
+        assert_eq!(decoded.0, a);
+
+        // out-of-order and duplicate keys are not canonical
+        let mut bytes = 2u64.encode().unwrap();
+        bytes.extend((1u32, 0u64, 0u32, 0u64).encode().unwrap());
+        assert!(Adapter::<HashMap<u32, u64>>::decode(bytes.as_slice()).is_err());
+        let mut bytes = 2u64.encode().unwrap();
+        bytes.extend((1u32, 0u64, 1u32, 0u64).encode().unwrap());
+        assert!(Adapter::<HashMap<u32, u64>>::decode(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn btree_map_encoding() {
+        let mut map = BTreeMap::new();
+        for i in (0..10u32).rev() {
+            map.insert(i, i as u64 * 2);
+        }
+
+        let bytes = Adapter(map.clone()).encode().unwrap();
+        assert_eq!(bytes.len(), Adapter(map.clone()).encoding_length().unwrap());
+        assert_eq!(&bytes[..8], &10u64.to_be_bytes());
+        assert_eq!(&bytes[8..12], &0u32.to_be_bytes());
+        assert_eq!(&bytes[20..24], &1u32.to_be_bytes());
+
+        let decoded = Adapter::<BTreeMap<u32, u64>>::decode(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.0, map);
+
+        // out-of-order and duplicate keys are not canonical
+        let mut bytes = 2u64.encode().unwrap();
+        bytes.extend((1u32, 0u64, 0u32, 0u64).encode().unwrap());
+        assert!(Adapter::<BTreeMap<u32, u64>>::decode(bytes.as_slice()).is_err());
+        let mut bytes = 2u64.encode().unwrap();
+        bytes.extend((1u32, 0u64, 1u32, 0u64).encode().unwrap());
+        assert!(Adapter::<BTreeMap<u32, u64>>::decode(bytes.as_slice()).is_err());
+    }
+
     #[test]
     fn string_roundtrip() -> crate::Result<()> {
         let value = "hello";
@@ -623,4 +1503,308 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn decode_with_limit() {
+        let bytes = vec![1u8; 16];
+
+        let decoded: Vec<u8> = super::decode_with_limit(bytes.as_slice(), 16).unwrap();
+        assert_eq!(decoded, bytes);
+        assert!(super::decode_with_limit::<Vec<u8>, _>(bytes.as_slice(), 15).is_err());
+
+        // terminated values which fit within the limit do not read past it
+        let mut input = bytes.as_slice();
+        let value: u32 = super::decode_with_limit(&mut input, 4).unwrap();
+        assert_eq!(value, 0x01010101);
+        assert_eq!(input.len(), 12);
+    }
+
+    #[test]
+    fn length_limited() {
+        let value: LengthLimited<Vec<u32>, 8> = LengthLimited(vec![1, 2]);
+        let bytes = value.encode().unwrap();
+        let decoded = LengthLimited::<Vec<u32>, 8>::decode(bytes.as_slice()).unwrap();
+        assert_eq!(decoded, value);
+
+        let bytes = vec![1, 2, 3].encode().unwrap();
+        assert!(LengthLimited::<Vec<u32>, 8>::decode(bytes.as_slice()).is_err());
+        assert!(LengthLimited::<Vec<u32>, 8>(vec![1, 2, 3])
+            .encode()
+            .is_err());
+
+        let desc = LengthLimited::<Vec<u32>, 8>::describe();
+        assert_eq!(desc.layout_hash(), Vec::<u32>::describe().layout_hash());
+        assert_eq!(
+            desc.type_id,
+            std::any::TypeId::of::<LengthLimited<Vec<u32>, 8>>()
+        );
+    }
+
+    #[test]
+    fn explicit_endianness() {
+        let le = LeInt(0x0102_0304u32);
+        let be = BeInt(0x0102_0304u32);
+        let le_bytes = le.encode().unwrap();
+        let be_bytes = be.encode().unwrap();
+        assert_eq!(le_bytes, vec![4, 3, 2, 1]);
+        assert_eq!(be_bytes, vec![1, 2, 3, 4]);
+        assert_eq!(be_bytes, 0x0102_0304u32.encode().unwrap());
+        assert_eq!(le.encoding_length().unwrap(), 4);
+
+        assert_eq!(LeInt::<u32>::decode(le_bytes.as_slice()).unwrap(), le);
+        assert_eq!(BeInt::<u32>::decode(be_bytes.as_slice()).unwrap(), be);
+
+        let bytes = LeInt(-2i16).encode().unwrap();
+        assert_eq!(bytes, vec![0xfe, 0xff]);
+        assert_eq!(*LeInt::<i16>::decode(bytes.as_slice()).unwrap(), -2);
+        assert!(LeInt::<u64>::decode(&[1, 2, 3][..]).is_err());
+
+        let mut bytes = vec![];
+        (LeInt(1u16), BeInt(1u16)).encode_into(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn string_adapter_roundtrip() {
+        for value in ["", "hello", "héllo wörld 🦀", &"a".repeat(300)] {
+            let bytes = Adapter(value.to_string()).encode().unwrap();
+            assert_eq!(
+                bytes.len(),
+                Adapter(value.to_string()).encoding_length().unwrap()
+            );
+            assert_eq!(&bytes[bytes.len() - value.len()..], value.as_bytes());
+
+            let decoded = Adapter::<String>::decode(bytes.as_slice()).unwrap();
+            assert_eq!(decoded.0, value);
+        }
+
+        assert_eq!(Adapter(String::new()).encode().unwrap(), vec![0]);
+        assert_eq!(
+            &Adapter("a".repeat(300)).encode().unwrap()[..2],
+            &[0xac, 0x02]
+        );
+
+        // non-canonical prefix, truncated input and invalid utf-8
+        assert!(Adapter::<String>::decode(&[0x80, 0x00][..]).is_err());
+        assert!(Adapter::<String>::decode(&[3, b'a', b'b'][..]).is_err());
+        assert!(Adapter::<String>::decode(&[2, 0xc3, 0x28][..]).is_err());
+    }
+
+    #[test]
+    fn float_roundtrip() {
+        for value in [0.0, -0.0, 1.5, -2.25, f64::MIN_POSITIVE, f64::INFINITY] {
+            let bytes = Adapter(value).encode().unwrap();
+            assert_eq!(bytes, value.to_bits().to_be_bytes());
+            let decoded = Adapter::<f64>::decode(bytes.as_slice()).unwrap().0;
+            assert_eq!(decoded.to_bits(), value.to_bits());
+        }
+
+        for value in [0.0, -0.0, f32::INFINITY, f32::NEG_INFINITY] {
+            let bytes = Adapter(value).encode().unwrap();
+            assert_eq!(bytes.len(), Adapter(value).encoding_length().unwrap());
+            let decoded = Adapter::<f32>::decode(bytes.as_slice()).unwrap().0;
+            assert_eq!(decoded.to_bits(), value.to_bits());
+        }
+        assert_ne!(
+            Adapter(0.0f32).encode().unwrap(),
+            Adapter(-0.0f32).encode().unwrap()
+        );
+        assert_eq!(
+            Adapter(f64::NEG_INFINITY).encode().unwrap(),
+            vec![0xff, 0xf0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn float_nan_canonicalization() {
+        let nans = [
+            f64::NAN,
+            -f64::NAN,
+            f64::from_bits(0x7ff0_0000_0000_0001),
+            f64::from_bits(0xfff8_0000_0000_1234),
+        ];
+        for value in nans {
+            assert!(value.is_nan());
+            let bytes = Adapter(value).encode().unwrap();
+            assert_eq!(bytes, vec![0x7f, 0xf8, 0, 0, 0, 0, 0, 0]);
+            assert!(Adapter::<f64>::decode(bytes.as_slice()).unwrap().0.is_nan());
+        }
+
+        let bytes = Adapter(f32::from_bits(0xffc0_0001)).encode().unwrap();
+        assert_eq!(bytes, vec![0x7f, 0xc0, 0, 0]);
+
+        // non-canonical NaNs and truncated input are rejected
+        assert!(Adapter::<f64>::decode(&0x7ff0_0000_0000_0001u64.to_be_bytes()[..]).is_err());
+        assert!(Adapter::<f32>::decode(&0xffc0_0000u32.to_be_bytes()[..]).is_err());
+        assert!(Adapter::<f32>::decode(&[0x7f, 0xc0][..]).is_err());
+    }
+
+    #[test]
+    fn char_roundtrip() {
+        for value in ['\0', 'a', 'é', '\u{d7ff}', '\u{e000}', '🦀', char::MAX] {
+            let bytes = Adapter(value).encode().unwrap();
+            assert_eq!(bytes, (value as u32).to_be_bytes());
+            assert_eq!(bytes.len(), Adapter(value).encoding_length().unwrap());
+            assert_eq!(Adapter::<char>::decode(bytes.as_slice()).unwrap().0, value);
+        }
+        assert_eq!(
+            Adapter(char::MAX).encode().unwrap(),
+            vec![0, 0x10, 0xff, 0xff]
+        );
+
+        // surrogates and out-of-range code points are rejected
+        for code_point in [0xd800u32, 0xdfff, 0x11_0000, u32::MAX] {
+            let bytes = code_point.to_be_bytes();
+            assert!(Adapter::<char>::decode(&bytes[..]).is_err());
+        }
+    }
+
+    #[test]
+    fn duration_roundtrip() {
+        let values = [
+            Duration::ZERO,
+            Duration::from_nanos(1),
+            Duration::new(5, 999_999_999),
+            Duration::MAX,
+        ];
+        for value in values {
+            let bytes = Adapter(value).encode().unwrap();
+            assert_eq!(bytes.len(), 12);
+            assert_eq!(bytes.len(), Adapter(value).encoding_length().unwrap());
+            assert_eq!(
+                Adapter::<Duration>::decode(bytes.as_slice()).unwrap().0,
+                value
+            );
+        }
+        assert_eq!(
+            Adapter(Duration::new(1, 2)).encode().unwrap(),
+            vec![0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2]
+        );
+
+        // nanoseconds must be less than a second
+        let bytes = (1u64, 1_000_000_000u32).encode().unwrap();
+        assert!(Adapter::<Duration>::decode(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn ip_addr_roundtrip() {
+        let v4s = [
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::LOCALHOST,
+            Ipv4Addr::new(192, 168, 1, 20),
+        ];
+        for addr in v4s {
+            let bytes = Adapter(addr).encode().unwrap();
+            assert_eq!(bytes, addr.octets());
+            assert_eq!(
+                Adapter::<Ipv4Addr>::decode(bytes.as_slice()).unwrap().0,
+                addr
+            );
+
+            let bytes = Adapter(IpAddr::V4(addr)).encode().unwrap();
+            assert_eq!(bytes[0], 0);
+            assert_eq!(bytes.len(), 5);
+            assert_eq!(
+                Adapter::<IpAddr>::decode(bytes.as_slice()).unwrap().0,
+                IpAddr::V4(addr)
+            );
+        }
+
+        let v6s = [
+            Ipv6Addr::UNSPECIFIED,
+            Ipv6Addr::LOCALHOST,
+            "2001:db8::ff00:42:8329".parse().unwrap(),
+        ];
+        for addr in v6s {
+            let bytes = Adapter(addr).encode().unwrap();
+            assert_eq!(bytes, addr.octets());
+            assert_eq!(
+                Adapter::<Ipv6Addr>::decode(bytes.as_slice()).unwrap().0,
+                addr
+            );
+
+            let bytes = Adapter(IpAddr::V6(addr)).encode().unwrap();
+            assert_eq!(bytes[0], 1);
+            assert_eq!(bytes.len(), 17);
+            assert_eq!(
+                bytes.len(),
+                Adapter(IpAddr::V6(addr)).encoding_length().unwrap()
+            );
+            assert_eq!(
+                Adapter::<IpAddr>::decode(bytes.as_slice()).unwrap().0,
+                IpAddr::V6(addr)
+            );
+        }
+
+        assert_eq!(
+            Adapter(IpAddr::V4(Ipv4Addr::LOCALHOST)).encode().unwrap(),
+            vec![0, 127, 0, 0, 1]
+        );
+        // unknown family tags and truncated addresses are rejected
+        assert!(Adapter::<IpAddr>::decode(&[2, 127, 0, 0, 1][..]).is_err());
+        assert!(Adapter::<IpAddr>::decode(&[1, 127, 0, 0, 1][..]).is_err());
+    }
+
+    #[test]
+    fn socket_addr_roundtrip() {
+        let addrs: [SocketAddr; 4] = [
+            "0.0.0.0:0".parse().unwrap(),
+            "127.0.0.1:26656".parse().unwrap(),
+            "[::]:80".parse().unwrap(),
+            "[::1]:65535".parse().unwrap(),
+        ];
+        for addr in addrs {
+            let bytes = Adapter(addr).encode().unwrap();
+            assert_eq!(bytes.len(), Adapter(addr).encoding_length().unwrap());
+            assert_eq!(&bytes[bytes.len() - 2..], &addr.port().to_be_bytes());
+            assert_eq!(
+                Adapter::<SocketAddr>::decode(bytes.as_slice()).unwrap().0,
+                addr
+            );
+        }
+
+        assert_eq!(
+            Adapter(addrs[1]).encode().unwrap(),
+            vec![0, 127, 0, 0, 1, 0x68, 0x20]
+        );
+        assert!(Adapter::<SocketAddr>::decode(&[0, 127, 0, 0, 1, 0x68][..]).is_err());
+    }
+
+    #[test]
+    fn sparse_roundtrip() {
+        let mut raw = [0u8; 1024];
+        raw[3] = 1;
+        raw[500..504].copy_from_slice(&[1, 2, 0, 3]);
+        raw[1023] = 9;
+
+        let bytes = Sparse(raw).encode().unwrap();
+        assert_eq!(bytes.len(), Sparse(raw).encoding_length().unwrap());
+        assert!(bytes.len() < raw.len() / 20);
+
+        let decoded = Sparse::<[u8; 1024]>::decode(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.0, raw);
+
+        let empty = Sparse(Vec::<u8>::new()).encode().unwrap();
+        assert_eq!(empty, vec![0, 0, 0, 0]);
+        assert_eq!(
+            Sparse::<Vec<u8>>::decode(empty.as_slice()).unwrap().0,
+            vec![]
+        );
+
+        // non-canonical segments, overlong segments and wrong array length
+        assert!(Sparse::<Vec<u8>>::decode(&[0, 0, 0, 2, 1, 0, 1, 0][..]).is_err());
+        assert!(Sparse::<Vec<u8>>::decode(&[0, 0, 0, 1, 2, 0][..]).is_err());
+        assert!(Sparse::<Vec<u8>>::decode(&[0, 0, 0, 1, 0, 0, 1, 0][..]).is_err());
+        assert!(Sparse::<[u8; 4]>::decode(&[0, 0, 0, 1, 1, 0][..]).is_err());
+
+        // non-canonical segments with the canonical encoding length
+        assert_eq!(
+            Sparse(vec![1, 0, 0, 1]).encode().unwrap(),
+            vec![0, 0, 0, 4, 0, 1, 1, 2, 1, 1]
+        );
+        assert!(Sparse::<Vec<u8>>::decode(&[0, 0, 0, 4, 0, 4, 1, 0, 0, 1][..]).is_err());
+
+        // a large length header without the input to back it
+        assert!(Sparse::<Vec<u8>>::decode(&[255, 255, 255, 255, 255, 0][..]).is_err());
+    }
 }
@@ -43,6 +43,8 @@ pub enum Error {
     Migrate(String),
     #[error("Nonce Error: {0}")]
     Nonce(String),
+    #[error("Out of Gas Error: Gas limit exceeded")]
+    OutOfGas,
     #[error("Overflow Error")]
     Overflow,
     #[error("Parse Int Error: {0}")]
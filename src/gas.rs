@@ -0,0 +1,219 @@
+//! Deterministic gas metering for state operations.
+use crate::context::Context;
+use crate::{Error, Result};
+
+/// The gas charged for each read from a [Store](crate::store::Store) (`get`,
+/// `get_next`, and `get_prev` calls), including each step of an iteration.
+pub const READ_COST: u64 = 100;
+/// The gas charged for each `put` or `delete` call.
+pub const WRITE_COST: u64 = 500;
+/// The gas charged per key and value byte read or written.
+pub const BYTE_COST: u64 = 10;
+
+/// Context which meters the gas consumed by state operations.
+///
+/// While a `Gas` context is present, every read and write through a
+/// [Store](crate::store::Store) charges against it, and fails with
+/// [Error::OutOfGas] once the limit is exceeded. Charges only depend on the
+/// number of operations and the sizes of the keys and values involved, so gas
+/// usage is the same on every node.
+///
+/// Collections only write their changed children to the store when they are
+/// flushed, so [Map](crate::collections::Map) (and the collections built on
+/// it) instead charge [WRITE_COST] plus the key bytes when a child is
+/// inserted, removed, or borrowed mutably.
+///
+/// The context is added with a per-call limit by
+/// [FeePlugin](crate::plugins::FeePlugin), and removed by
+/// [ABCIPlugin](crate::plugins::ABCIPlugin) after each transaction. Writes made
+/// when state is flushed, after the call returns or when a
+/// [transaction](crate::state::transaction) commits, are not metered, so
+/// running out of gas never interrupts a flush.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Gas {
+    limit: u64,
+    used: u64,
+}
+
+impl Gas {
+    /// Creates a new gas meter with the given limit.
+    pub fn new(limit: u64) -> Self {
+        Self { limit, used: 0 }
+    }
+
+    /// Returns the maximum amount of gas which may be consumed.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the amount of gas consumed so far. This never exceeds the
+    /// limit.
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+
+    /// Returns the amount of gas which may still be consumed.
+    pub fn remaining(&self) -> u64 {
+        self.limit - self.used
+    }
+
+    /// Consumes the given amount of gas, returning [Error::OutOfGas] if this
+    /// exceeds the limit. Once the limit is exceeded, all gas is considered
+    /// used.
+    pub fn charge(&mut self, amount: u64) -> Result<()> {
+        if amount > self.remaining() {
+            self.used = self.limit;
+            return Err(Error::OutOfGas);
+        }
+        self.used += amount;
+
+        Ok(())
+    }
+}
+
+/// Charges the given amount of gas to the [Gas] context, if there is one.
+pub fn charge(amount: u64) -> Result<()> {
+    match Context::resolve::<Gas>() {
+        Some(gas) => gas.charge(amount),
+        None => Ok(()),
+    }
+}
+
+/// Runs `op` with the [Gas] context removed, restoring it afterwards. Used
+/// when flushing or committing state, which is never metered.
+pub fn unmetered<R>(op: impl FnOnce() -> R) -> R {
+    let gas = Context::resolve::<Gas>().cloned();
+    Context::remove::<Gas>();
+    let res = op();
+    if let Some(gas) = gas {
+        Context::add(gas);
+    }
+
+    res
+}
+
+/// Charges the gas for a store operation with the given base cost, touching
+/// the given number of key and value bytes.
+pub(crate) fn charge_op(base_cost: u64, n_bytes: usize) -> Result<()> {
+    charge(base_cost.saturating_add((n_bytes as u64).saturating_mul(BYTE_COST)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::Map;
+    use crate::state::{transaction, State};
+    use crate::store::{Read, Store, Write};
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn loop_halts_at_limit() {
+        let mut store = Store::with_map_store();
+        Context::add(Gas::new(10_000));
+
+        let mut n = 0u32;
+        let err = loop {
+            if let Err(err) = store.put(n.to_be_bytes().to_vec(), vec![0; 8]) {
+                break err;
+            }
+            n += 1;
+        };
+        let gas = Context::resolve::<Gas>().unwrap().clone();
+        Context::remove::<Gas>();
+
+        assert!(matches!(err, Error::OutOfGas));
+        // each put costs 500 + (4 + 8) * 10
+        assert_eq!(n, 16);
+        assert_eq!(gas.used(), gas.limit());
+        assert!(store.put(vec![0], vec![0]).is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn map_insert_loop_halts_at_limit() -> Result<()> {
+        let mut map: Map<u32, u64> = Map::with_store(Store::with_map_store())?;
+        Context::add(Gas::new(10_000));
+
+        let mut n = 0u32;
+        let err = loop {
+            if let Err(err) = map.insert(n, n as u64) {
+                break err;
+            }
+            n += 1;
+        };
+        let gas = Context::resolve::<Gas>().unwrap().clone();
+        Context::remove::<Gas>();
+
+        assert!(matches!(err, Error::OutOfGas));
+        // each insert costs 500 + 4 * 10
+        assert_eq!(n, 18);
+        assert_eq!(gas.used(), gas.limit());
+        assert!(map.get(n)?.is_none());
+
+        Ok(())
+    }
+
+    fn sum_entries(store: &Store) -> Result<(u64, u64)> {
+        let map: Map<u32, u64> = Map::load(store.clone(), &mut &[][..])?;
+        Context::add(Gas::new(1_000_000));
+        let sum = map
+            .iter()?
+            .map(|entry| entry.map(|(_, v)| *v))
+            .sum::<Result<u64>>();
+        let used = Context::resolve::<Gas>().unwrap().used();
+        Context::remove::<Gas>();
+
+        Ok((sum?, used))
+    }
+
+    #[test]
+    #[serial]
+    fn gas_used_is_deterministic() -> Result<()> {
+        let store = Store::with_map_store();
+        let mut map: Map<u32, u64> = Map::load(store.clone(), &mut &[][..])?;
+        for i in 0..10 {
+            map.insert(i, i as u64)?;
+        }
+        map.flush(&mut vec![])?;
+
+        let (sum, used) = sum_entries(&store)?;
+        assert_eq!(sum, 45);
+        assert!(used >= 10 * READ_COST);
+        assert_eq!(sum_entries(&store)?, (sum, used));
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn out_of_gas_in_transaction() -> Result<()> {
+        let mut store = Store::with_map_store();
+        store.put(vec![0], vec![1])?;
+        let mut value = store.clone();
+
+        // reads through the transaction's buffer are only charged once
+        Context::add(Gas::new(1_000_000));
+        store.get(&[0])?;
+        let direct = Context::resolve::<Gas>().unwrap().used();
+        transaction(&mut value, &store, |value| value.get(&[0]))?;
+        assert_eq!(Context::resolve::<Gas>().unwrap().used(), 2 * direct);
+        Context::remove::<Gas>();
+
+        Context::add(Gas::new(10_000));
+        let err = transaction(&mut value, &store, |value| {
+            for n in 0u32.. {
+                value.put(n.to_be_bytes().to_vec(), vec![0; 8])?;
+            }
+            Ok(())
+        })
+        .unwrap_err();
+        Context::remove::<Gas>();
+
+        assert!(matches!(err, Error::OutOfGas));
+        assert_eq!(value.get(&[0])?, Some(vec![1]));
+        assert!(store.get(&0u32.to_be_bytes())?.is_none());
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use ibc::clients::tendermint::types::TENDERMINT_CONSENSUS_STATE_TYPE_URL;
+use ibc::clients::tendermint::types::{client_type, TENDERMINT_CONSENSUS_STATE_TYPE_URL};
 use ibc::{
     clients::tendermint::consensus_state::ConsensusState,
     core::{
@@ -133,7 +133,8 @@ impl ClientExecutionContext for IbcContext {
         client_state_path: ClientStatePath,
         client_state: Self::ClientStateRef,
     ) -> Result<(), ContextError> {
-        self.clients
+        let mut client = self
+            .clients
             .entry(client_state_path.0.into())
             .map_err(|_| ClientError::ClientSpecific {
                 description: "Failed to store client state".to_string(),
@@ -141,7 +142,11 @@ impl ClientExecutionContext for IbcContext {
             .or_insert_default()
             .map_err(|_| ClientError::ClientSpecific {
                 description: "Failed to store client state".to_string(),
-            })?
+            })?;
+        // client states stored through this context are always Tendermint
+        // client states
+        client.set_client_type(client_type());
+        client
             .client_state
             .insert(Default::default(), client_state.into())
             .map_err(|_| ClientError::ClientSpecific {
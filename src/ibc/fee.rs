@@ -0,0 +1,670 @@
+//! ICS-29 fee middleware for relayer incentivization.
+//!
+//! Users may escrow fees against a packet they have sent, to be paid to the
+//! relayers which deliver the packet, its acknowledgement, or its timeout.
+//! Fees are paid in any denom held in the [Transfer](super::transfer::Transfer)
+//! module, and unused fees are refunded to the payer.
+//!
+//! Fees can only be escrowed on channels opened with the ICS-29 version
+//! negotiated on top of the transfer version, e.g.
+//! `{"fee_version":"ics29-1","app_version":"ics20-1"}`. On these channels,
+//! acknowledgements are wrapped to carry the address of the relayer which
+//! delivered the packet, so the counterparty chain can pay it.
+
+use ibc::core::channel::types::{
+    acknowledgement::{Acknowledgement, AcknowledgementStatus},
+    channel::{Counterparty, Order},
+    error::{ChannelError, PacketError},
+    packet::Packet,
+    Version,
+};
+use ibc::core::host::types::identifiers::{ChannelId, ConnectionId, PortId};
+use ibc::core::router::{module::Module, types::module::ModuleExtras};
+use ibc::primitives::Signer;
+use serde::{Deserialize, Serialize};
+
+use super::router::IbcRouter;
+use super::transfer::Denom;
+use super::{PortChannel, PortChannelSequence};
+use crate::coins::{Address, Amount};
+use crate::encoding::LengthVec;
+use crate::{orga, Error, Result as OrgaResult};
+
+/// The ICS-29 fee version.
+pub const FEE_VERSION: &str = "ics29-1";
+
+/// A relayer's address on the counterparty chain.
+pub type Payee = LengthVec<u8, u8>;
+
+/// Fees escrowed for relaying a packet.
+#[orga]
+#[derive(Debug, Clone)]
+pub struct PacketFee {
+    /// The denom the fees are paid in.
+    pub denom: Denom,
+    /// Paid to the relayer which delivered the packet to the counterparty
+    /// chain.
+    pub recv_fee: Amount,
+    /// Paid to the relayer which delivers the packet's acknowledgement.
+    pub ack_fee: Amount,
+    /// Paid to the relayer which delivers the packet's timeout.
+    pub timeout_fee: Amount,
+    /// The address which paid the fees, and which unused fees are refunded
+    /// to.
+    pub refund_address: Address,
+}
+
+impl PacketFee {
+    fn total(&self) -> OrgaResult<Amount> {
+        (self.recv_fee + self.ack_fee + self.timeout_fee).result()
+    }
+}
+
+/// Channel version metadata for fee-enabled channels.
+#[derive(Serialize, Deserialize)]
+struct FeeMetadata {
+    fee_version: String,
+    app_version: String,
+}
+
+/// Splits a channel version into the version of the wrapped application, and
+/// whether it enables fees.
+fn unwrap_version(version: &Version) -> (Version, bool) {
+    match serde_json::from_str::<FeeMetadata>(version.as_str()) {
+        Ok(metadata) if metadata.fee_version == FEE_VERSION => {
+            (Version::new(metadata.app_version), true)
+        }
+        _ => (version.clone(), false),
+    }
+}
+
+/// Wraps the version of the wrapped application to enable fees.
+fn wrap_version(app_version: Version) -> Version {
+    let metadata = FeeMetadata {
+        fee_version: FEE_VERSION.to_string(),
+        app_version: app_version.as_str().to_string(),
+    };
+    Version::new(serde_json::to_string(&metadata).unwrap())
+}
+
+/// An acknowledgement on a fee-enabled channel, wrapping the acknowledgement
+/// of the underlying application.
+#[derive(Serialize, Deserialize)]
+struct IncentivizedAcknowledgement {
+    #[serde(with = "base64_bytes")]
+    app_acknowledgement: Vec<u8>,
+    forward_relayer_address: String,
+    underlying_app_success: bool,
+}
+
+mod base64_bytes {
+    use base64::prelude::{Engine, BASE64_STANDARD};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        BASE64_STANDARD
+            .decode(string)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl IbcRouter {
+    /// Returns true if the channel was opened with fees enabled.
+    pub fn is_fee_enabled(&self, port_chan: PortChannel) -> OrgaResult<bool> {
+        self.fee_enabled.contains_key(port_chan)
+    }
+
+    /// Returns the fees escrowed for the given packet, if any.
+    pub fn packet_fee(&self, packet_id: PortChannelSequence) -> OrgaResult<Option<PacketFee>> {
+        Ok(self.packet_fees.get(packet_id)?.map(|fee| fee.clone()))
+    }
+
+    /// Returns the counterparty chain address registered by a relayer for the
+    /// given channel, if any.
+    pub fn counterparty_payee(
+        &self,
+        port_chan: PortChannel,
+        relayer: Address,
+    ) -> OrgaResult<Option<String>> {
+        let Some(payees) = self.counterparty_payees.get(port_chan)? else {
+            return Ok(None);
+        };
+        payees
+            .get(relayer)?
+            .map(|payee| String::try_from(payee.clone()))
+            .transpose()
+    }
+
+    /// Escrows fees for a packet sent over a fee-enabled channel, taking them
+    /// from the refund address's transfer module balance.
+    pub(super) fn pay_packet_fee(
+        &mut self,
+        packet_id: PortChannelSequence,
+        fee: PacketFee,
+    ) -> OrgaResult<()> {
+        let port_chan = PortChannel::new(packet_id.port_id()?, packet_id.channel_id()?);
+        if !self.is_fee_enabled(port_chan)? {
+            return Err(Error::Ibc("Channel does not have fees enabled".into()));
+        }
+        if self.packet_fees.contains_key(packet_id.clone())? {
+            return Err(Error::Ibc("Fee already escrowed for packet".into()));
+        }
+
+        self.transfer
+            .debit(fee.refund_address, fee.denom.clone(), fee.total()?)?;
+        self.packet_fees.insert(packet_id, fee)
+    }
+
+    /// Registers the counterparty chain address to pay fees to for packets
+    /// delivered by `relayer` on the given channel.
+    pub(super) fn register_counterparty_payee(
+        &mut self,
+        port_chan: PortChannel,
+        relayer: Address,
+        payee: Payee,
+    ) -> OrgaResult<()> {
+        if payee.is_empty() {
+            return Err(Error::Ibc("Counterparty payee must not be empty".into()));
+        }
+
+        self.counterparty_payees
+            .entry(port_chan)?
+            .or_default()?
+            .insert(relayer, payee)
+    }
+
+    /// Pays out the fees escrowed for a packet once it has been acknowledged
+    /// or has timed out, refunding the unused fees. Fees for a relayer whose
+    /// address is not known are refunded.
+    fn distribute_packet_fee(
+        &mut self,
+        packet: &Packet,
+        forward_relayer: Option<Address>,
+        relayer: Option<Address>,
+        timed_out: bool,
+    ) -> OrgaResult<()> {
+        let packet_id = PortChannelSequence::new(
+            packet.port_id_on_a.clone(),
+            packet.chan_id_on_a.clone(),
+            packet.seq_on_a,
+        );
+        let Some(fee) = self.packet_fees.remove(packet_id)? else {
+            return Ok(());
+        };
+        let fee = fee.into_inner();
+
+        let payouts = if timed_out {
+            [
+                (relayer, fee.timeout_fee),
+                (None, fee.recv_fee),
+                (None, fee.ack_fee),
+            ]
+        } else {
+            [
+                (forward_relayer, fee.recv_fee),
+                (relayer, fee.ack_fee),
+                (None, fee.timeout_fee),
+            ]
+        };
+        for (recipient, amount) in payouts {
+            let recipient = recipient.unwrap_or(fee.refund_address);
+            self.transfer.credit(recipient, fee.denom.clone(), amount)?;
+        }
+
+        Ok(())
+    }
+
+    /// Wraps the acknowledgement of a packet received on a fee-enabled channel
+    /// with the address the counterparty chain should pay the relayer at.
+    fn wrap_acknowledgement(
+        &self,
+        packet: &Packet,
+        relayer: &Signer,
+        ack: &Acknowledgement,
+    ) -> OrgaResult<Option<Acknowledgement>> {
+        let port_chan = PortChannel::new(packet.port_id_on_b.clone(), packet.chan_id_on_b.clone());
+        if !self.is_fee_enabled(port_chan.clone())? {
+            return Ok(None);
+        }
+
+        let payee = match relayer.as_ref().parse::<Address>() {
+            Ok(relayer) => self.counterparty_payee(port_chan, relayer)?,
+            Err(_) => None,
+        };
+        let underlying_app_success = serde_json::from_slice::<AcknowledgementStatus>(ack.as_ref())
+            .map_or(false, |status| status.is_successful());
+        let ack = IncentivizedAcknowledgement {
+            app_acknowledgement: ack.as_ref().to_vec(),
+            forward_relayer_address: payee.unwrap_or_else(|| relayer.to_string()),
+            underlying_app_success,
+        };
+        let bytes = serde_json::to_vec(&ack).map_err(|e| Error::Ibc(e.to_string()))?;
+
+        Ok(Some(
+            bytes
+                .try_into()
+                .map_err(|e: PacketError| Error::Ibc(e.to_string()))?,
+        ))
+    }
+
+    /// Unwraps the acknowledgement of a packet sent on a fee-enabled channel,
+    /// returning the underlying application's acknowledgement and the
+    /// address of the relayer which delivered the packet.
+    fn unwrap_acknowledgement(
+        &self,
+        packet: &Packet,
+        ack: &Acknowledgement,
+    ) -> OrgaResult<Option<(Acknowledgement, Option<Address>)>> {
+        let port_chan = PortChannel::new(packet.port_id_on_a.clone(), packet.chan_id_on_a.clone());
+        if !self.is_fee_enabled(port_chan)? {
+            return Ok(None);
+        }
+
+        let ack: IncentivizedAcknowledgement = serde_json::from_slice(ack.as_ref())
+            .map_err(|_| Error::Ibc("Expected incentivized acknowledgement".into()))?;
+        let forward_relayer = ack.forward_relayer_address.parse().ok();
+        let app_ack = ack
+            .app_acknowledgement
+            .try_into()
+            .map_err(|e: PacketError| Error::Ibc(e.to_string()))?;
+
+        Ok(Some((app_ack, forward_relayer)))
+    }
+}
+
+fn channel_error(err: Error) -> ChannelError {
+    ChannelError::AppModule {
+        description: err.to_string(),
+    }
+}
+
+fn packet_error(err: Error) -> PacketError {
+    PacketError::AppModule {
+        description: err.to_string(),
+    }
+}
+
+impl Module for IbcRouter {
+    fn on_chan_open_init_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &Version,
+    ) -> Result<Version, ChannelError> {
+        let (app_version, fee_enabled) = unwrap_version(version);
+        let version = self.transfer.on_chan_open_init_validate(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            &app_version,
+        )?;
+
+        Ok(if fee_enabled {
+            wrap_version(version)
+        } else {
+            version
+        })
+    }
+
+    fn on_chan_open_init_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        version: &Version,
+    ) -> Result<(ModuleExtras, Version), ChannelError> {
+        let (app_version, fee_enabled) = unwrap_version(version);
+        let (extras, version) = self.transfer.on_chan_open_init_execute(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            &app_version,
+        )?;
+        if !fee_enabled {
+            return Ok((extras, version));
+        }
+
+        let port_chan = PortChannel::new(port_id.clone(), channel_id.clone());
+        self.fee_enabled
+            .insert(port_chan, ())
+            .map_err(channel_error)?;
+
+        Ok((extras, wrap_version(version)))
+    }
+
+    fn on_chan_open_try_validate(
+        &self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &Version,
+    ) -> Result<Version, ChannelError> {
+        let (app_version, fee_enabled) = unwrap_version(counterparty_version);
+        let version = self.transfer.on_chan_open_try_validate(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            &app_version,
+        )?;
+
+        Ok(if fee_enabled {
+            wrap_version(version)
+        } else {
+            version
+        })
+    }
+
+    fn on_chan_open_try_execute(
+        &mut self,
+        order: Order,
+        connection_hops: &[ConnectionId],
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty: &Counterparty,
+        counterparty_version: &Version,
+    ) -> Result<(ModuleExtras, Version), ChannelError> {
+        let (app_version, fee_enabled) = unwrap_version(counterparty_version);
+        let (extras, version) = self.transfer.on_chan_open_try_execute(
+            order,
+            connection_hops,
+            port_id,
+            channel_id,
+            counterparty,
+            &app_version,
+        )?;
+        if !fee_enabled {
+            return Ok((extras, version));
+        }
+
+        let port_chan = PortChannel::new(port_id.clone(), channel_id.clone());
+        self.fee_enabled
+            .insert(port_chan, ())
+            .map_err(channel_error)?;
+
+        Ok((extras, wrap_version(version)))
+    }
+
+    fn on_chan_open_ack_validate(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        counterparty_version: &Version,
+    ) -> Result<(), ChannelError> {
+        let port_chan = PortChannel::new(port_id.clone(), channel_id.clone());
+        let (app_version, fee_enabled) = unwrap_version(counterparty_version);
+        if self.is_fee_enabled(port_chan).map_err(channel_error)? && !fee_enabled {
+            return Err(channel_error(Error::Ibc(
+                "Counterparty did not accept fee version".into(),
+            )));
+        }
+
+        self.transfer
+            .on_chan_open_ack_validate(port_id, channel_id, &app_version)
+    }
+
+    fn on_recv_packet_execute(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Acknowledgement) {
+        let (extras, ack) = self.transfer.on_recv_packet_execute(packet, relayer);
+
+        match self.wrap_acknowledgement(packet, relayer, &ack) {
+            Ok(Some(wrapped)) => (extras, wrapped),
+            Ok(None) => (extras, ack),
+            Err(err) => {
+                log::debug!("Error wrapping acknowledgement: {}", err);
+                (extras, ack)
+            }
+        }
+    }
+
+    fn on_acknowledgement_packet_validate(
+        &self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        let ack = match self
+            .unwrap_acknowledgement(packet, acknowledgement)
+            .map_err(packet_error)?
+        {
+            Some((app_ack, _)) => app_ack,
+            None => acknowledgement.clone(),
+        };
+
+        self.transfer
+            .on_acknowledgement_packet_validate(packet, &ack, relayer)
+    }
+
+    fn on_acknowledgement_packet_execute(
+        &mut self,
+        packet: &Packet,
+        acknowledgement: &Acknowledgement,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        let (ack, forward_relayer) = match self.unwrap_acknowledgement(packet, acknowledgement) {
+            Ok(Some((app_ack, forward_relayer))) => (app_ack, forward_relayer),
+            Ok(None) => (acknowledgement.clone(), None),
+            Err(err) => return (ModuleExtras::empty(), Err(packet_error(err))),
+        };
+
+        let (extras, res) = self
+            .transfer
+            .on_acknowledgement_packet_execute(packet, &ack, relayer);
+        if res.is_err() {
+            return (extras, res);
+        }
+
+        let relayer = relayer.as_ref().parse().ok();
+        let res = self
+            .distribute_packet_fee(packet, forward_relayer, relayer, false)
+            .map_err(packet_error);
+        (extras, res)
+    }
+
+    fn on_timeout_packet_validate(
+        &self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        self.transfer.on_timeout_packet_validate(packet, relayer)
+    }
+
+    fn on_timeout_packet_execute(
+        &mut self,
+        packet: &Packet,
+        relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        let (extras, res) = self.transfer.on_timeout_packet_execute(packet, relayer);
+        if res.is_err() {
+            return (extras, res);
+        }
+
+        let relayer = relayer.as_ref().parse().ok();
+        let res = self
+            .distribute_packet_fee(packet, None, relayer, true)
+            .map_err(packet_error);
+        (extras, res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+    use crate::ibc::Ibc;
+    use crate::plugins::Signer as SignerCtx;
+    use ibc::apps::transfer::types::{packet::PacketData, PrefixedCoin};
+    use ibc_proto::ibc::core::channel::v1::Packet as RawPacket;
+    use serial_test::serial;
+
+    fn packet(sequence: u64, data: Vec<u8>) -> Packet {
+        RawPacket {
+            sequence,
+            source_port: "transfer".to_string(),
+            source_channel: "channel-0".to_string(),
+            destination_port: "transfer".to_string(),
+            destination_channel: "channel-1".to_string(),
+            data,
+            timeout_height: None,
+            timeout_timestamp: 1,
+        }
+        .try_into()
+        .unwrap()
+    }
+
+    fn packet_id(sequence: u64) -> PortChannelSequence {
+        PortChannelSequence::new(PortId::transfer(), ChannelId::new(0), sequence.into())
+    }
+
+    #[test]
+    fn version_negotiation() {
+        let app_version = Version::new("ics20-1".to_string());
+        let version = wrap_version(app_version.clone());
+        assert_eq!(
+            version.as_str(),
+            r#"{"fee_version":"ics29-1","app_version":"ics20-1"}"#
+        );
+        assert_eq!(unwrap_version(&version), (app_version.clone(), true));
+        assert_eq!(unwrap_version(&app_version), (app_version, false));
+    }
+
+    #[test]
+    #[serial]
+    fn packet_fees() -> OrgaResult<()> {
+        let alice = Address::from([1; Address::LENGTH]);
+        let bob = Address::from([2; Address::LENGTH]);
+        let carol = Address::from([3; Address::LENGTH]);
+        let denom: Denom = "unom".try_into()?;
+
+        let mut ibc = Ibc::default();
+        let port_chan = PortChannel::new(PortId::transfer(), ChannelId::new(0));
+        ibc.router.fee_enabled.insert(port_chan, ())?;
+        ibc.transfer_mut()
+            .credit(alice, denom.clone(), 1000.into())?;
+        Context::add(SignerCtx {
+            signer: Some(alice),
+        });
+
+        // fees can only be escrowed for pending packets
+        assert!(ibc
+            .pay_packet_fee(packet_id(1), denom.clone(), 10.into(), 20.into(), 30.into())
+            .is_err());
+        ibc.ctx.commitments.insert(packet_id(1), vec![1])?;
+        ibc.pay_packet_fee(packet_id(1), denom.clone(), 10.into(), 20.into(), 30.into())?;
+        assert_eq!(ibc.transfer().balance(alice, denom.clone())?, 940);
+        assert!(ibc
+            .pay_packet_fee(packet_id(1), denom.clone(), 10.into(), 20.into(), 30.into())
+            .is_err());
+
+        // bob delivered the packet, carol delivers the acknowledgement
+        let ack = IncentivizedAcknowledgement {
+            app_acknowledgement: br#"{"result":"AQ=="}"#.to_vec(),
+            forward_relayer_address: bob.to_string(),
+            underlying_app_success: true,
+        };
+        let ack: Acknowledgement = serde_json::to_vec(&ack).unwrap().try_into().unwrap();
+        let (_, res) = ibc.router.on_acknowledgement_packet_execute(
+            &packet(1, vec![]),
+            &ack,
+            &carol.to_string().into(),
+        );
+        res.unwrap();
+
+        assert_eq!(ibc.transfer().balance(bob, denom.clone())?, 10);
+        assert_eq!(ibc.transfer().balance(carol, denom.clone())?, 20);
+        assert_eq!(ibc.transfer().balance(alice, denom.clone())?, 970);
+        assert!(ibc.router.packet_fee(packet_id(1))?.is_none());
+
+        // carol delivers the timeout of another packet, which refunds the
+        // transferred tokens from escrow
+        let escrow = ibc
+            .transfer()
+            .get_escrow_account(&PortId::transfer(), &ChannelId::new(0))?;
+        ibc.transfer_mut().credit(escrow, denom.clone(), 5.into())?;
+        let data = PacketData {
+            token: PrefixedCoin {
+                denom: "unom".parse()?,
+                amount: 5u64.into(),
+            },
+            sender: alice.to_string().into(),
+            receiver: "receiver".to_string().into(),
+            memo: "".to_string().into(),
+        };
+        ibc.ctx.commitments.insert(packet_id(2), vec![1])?;
+        ibc.pay_packet_fee(packet_id(2), denom.clone(), 10.into(), 20.into(), 30.into())?;
+        assert_eq!(ibc.transfer().balance(alice, denom.clone())?, 910);
+
+        let (_, res) = ibc.router.on_timeout_packet_execute(
+            &packet(2, serde_json::to_vec(&data).unwrap()),
+            &carol.to_string().into(),
+        );
+        res.unwrap();
+
+        assert_eq!(ibc.transfer().balance(bob, denom.clone())?, 10);
+        assert_eq!(ibc.transfer().balance(carol, denom.clone())?, 50);
+        assert_eq!(ibc.transfer().balance(alice, denom.clone())?, 945);
+        assert!(ibc.router.packet_fee(packet_id(2))?.is_none());
+
+        Context::remove::<SignerCtx>();
+
+        Ok(())
+    }
+
+    #[test]
+    fn wrapped_acknowledgement() -> OrgaResult<()> {
+        let bob = Address::from([2; Address::LENGTH]);
+        let mut ibc = Ibc::default();
+        let app_ack: Acknowledgement = br#"{"result":"AQ=="}"#.to_vec().try_into().unwrap();
+
+        // acknowledgements are only wrapped on fee-enabled channels
+        let packet = packet(1, vec![]);
+        let relayer = bob.to_string().into();
+        assert!(ibc
+            .router
+            .wrap_acknowledgement(&packet, &relayer, &app_ack)?
+            .is_none());
+
+        let port_chan = PortChannel::new(PortId::transfer(), ChannelId::new(1));
+        ibc.router.fee_enabled.insert(port_chan.clone(), ())?;
+        let ack = ibc
+            .router
+            .wrap_acknowledgement(&packet, &relayer, &app_ack)?
+            .unwrap();
+        let ack: IncentivizedAcknowledgement = serde_json::from_slice(ack.as_ref()).unwrap();
+        assert_eq!(ack.forward_relayer_address, bob.to_string());
+        assert_eq!(ack.app_acknowledgement, app_ack.as_ref());
+        assert!(ack.underlying_app_success);
+
+        // relayers are paid at their registered counterparty payee
+        ibc.router
+            .register_counterparty_payee(port_chan, bob, "cosmos1payee".try_into()?)?;
+        let ack = ibc
+            .router
+            .wrap_acknowledgement(&packet, &relayer, &app_ack)?
+            .unwrap();
+        let ack: IncentivizedAcknowledgement = serde_json::from_slice(ack.as_ref()).unwrap();
+        assert_eq!(ack.forward_relayer_address, "cosmos1payee");
+
+        Ok(())
+    }
+}
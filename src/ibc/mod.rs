@@ -33,12 +33,12 @@ use ibc_rs::core::host::types::path::{
 };
 use serde::Serialize;
 
-use crate::coins::Address;
+use crate::coins::{Address, Amount};
 use crate::collections::{Deque, Map};
 use crate::context::GetContext;
 use crate::describe::{Describe, Descriptor};
 use crate::encoding::{
-    Adapter, ByteTerminatedString, Decode, Encode, EofTerminatedString, FixedString,
+    Adapter, ByteTerminatedString, Decode, Encode, EofTerminatedString, FixedString, LeInt,
 };
 use crate::migrate::{Migrate, MigrateInto};
 use crate::plugins::Signer;
@@ -51,7 +51,8 @@ pub use ibc::primitives::Timestamp as IbcTimestamp;
 
 mod impls;
 pub mod transfer;
-use transfer::{Transfer, TransferInfo};
+use fee::{PacketFee, Payee};
+use transfer::{Denom, Transfer, TransferInfo};
 #[cfg(feature = "abci")]
 mod service;
 #[cfg(feature = "abci")]
@@ -59,6 +60,7 @@ pub use service::{start_grpc, GrpcOpts};
 
 pub use self::messages::{IbcMessage, IbcTx, RawIbcTx};
 mod client_contexts;
+pub mod fee;
 mod messages;
 mod query;
 mod router;
@@ -153,7 +155,7 @@ impl Ibc {
         let message: MsgTransfer = message.inner;
         let sender_addr: Address = message.packet_data.sender.clone().try_into()?;
 
-        if self.signer()? != sender_addr {
+        if !self.signer()?.ct_eq(&sender_addr) {
             return Err(crate::Error::Ibc(
                 "Transfers must be signed by the sender".into(),
             ));
@@ -164,6 +166,54 @@ impl Ibc {
         Ok(())
     }
 
+    /// Call for escrowing ICS-29 relayer fees for a packet which has been sent
+    /// but not yet acknowledged or timed out. The fees are taken from the
+    /// signer's transfer module balance, and unused fees are refunded to the
+    /// signer.
+    #[call]
+    pub fn pay_packet_fee(
+        &mut self,
+        packet_id: PortChannelSequence,
+        denom: Denom,
+        recv_fee: Amount,
+        ack_fee: Amount,
+        timeout_fee: Amount,
+    ) -> crate::Result<()> {
+        let signer = self.signer()?;
+        if !self.ctx.commitments.contains_key(packet_id.clone())? {
+            return Err(Error::Ibc("Packet is not pending".to_string()));
+        }
+
+        self.router.pay_packet_fee(
+            packet_id,
+            PacketFee {
+                denom,
+                recv_fee,
+                ack_fee,
+                timeout_fee,
+                refund_address: signer,
+            },
+        )
+    }
+
+    /// Call for a relayer to register its address on the counterparty chain,
+    /// where it will be paid ICS-29 fees for packets it delivers to this chain
+    /// over the given channel.
+    #[call]
+    pub fn register_counterparty_payee(
+        &mut self,
+        port_chan: PortChannel,
+        payee: Payee,
+    ) -> crate::Result<()> {
+        let signer = self.signer()?;
+        if !self.ctx.channel_ends.contains_key(port_chan.clone())? {
+            return Err(Error::Ibc("Channel not found".to_string()));
+        }
+
+        self.router
+            .register_counterparty_payee(port_chan, signer, payee)
+    }
+
     /// Execute one [IbcMessage].
     ///
     /// Returns info about a transfer if the message triggered one.
@@ -188,16 +238,16 @@ impl Ibc {
         };
 
         if let Some(msg) = maybe_client_update {
-            match Header::try_from(msg.client_message) {
-                Ok(header) => {
-                    let mut client = self
-                        .ctx
-                        .clients
-                        .get_mut(msg.client_id.into())?
-                        .ok_or_else(|| Error::Ibc("Expected client".to_string()))?;
-                    client.last_header = Some(header.into())
+            let mut client = self
+                .ctx
+                .clients
+                .get_mut(msg.client_id.into())?
+                .ok_or_else(|| Error::Ibc("Expected client".to_string()))?;
+            if client.is_tendermint()? {
+                match Header::try_from(msg.client_message) {
+                    Ok(header) => client.last_header = Some(header.into()),
+                    Err(err) => log::debug!("Error decoding header: {}", err),
                 }
-                Err(err) => log::debug!("Error decoding header: {}", err),
             }
         }
 
@@ -249,19 +299,30 @@ pub struct Client {
     #[state(prefix(b"connections/"))]
     pub connections: Map<ConnectionIdKey, ()>,
 
-    // TODO: support headers for non-tendermint clients
     /// The last header received from the chain. Not required by IBC, but useful
-    /// to keep around since it is provided during client updates.
+    /// to keep around since it is provided during client updates. Only
+    /// recorded for Tendermint clients.
     pub last_header: Option<WrappedHeader>,
 
     client_type: EofTerminatedString,
 }
 
 impl Client {
-    /// The [ClientType] of this client. Currently, only "07-tendermint" clients
-    /// are supported. (ics-02) (ics-07)
+    /// The [ClientType] of this client. (ics-02)
+    ///
+    /// Clients which were stored without a client type are "07-tendermint"
+    /// clients. (ics-07)
     pub fn client_type(&self) -> crate::Result<ClientType> {
-        Ok(client_type())
+        if self.client_type.0.is_empty() {
+            return Ok(client_type());
+        }
+
+        ClientType::new(self.client_type.0.as_str()).map_err(|e| Error::Ibc(e.to_string()))
+    }
+
+    /// Returns true if this is a Tendermint ("07-tendermint") client.
+    pub fn is_tendermint(&self) -> crate::Result<bool> {
+        Ok(self.client_type()? == client_type())
     }
 
     /// Set the client type.
@@ -269,7 +330,8 @@ impl Client {
         self.client_type = client_type.into();
     }
 
-    /// Returns the last header as a [Header]
+    /// Returns the last header as a [Header]. Errors for clients other than
+    /// Tendermint clients, which have no header recorded.
     pub fn last_header(&self) -> crate::Result<Header> {
         Ok(self
             .last_header
@@ -305,7 +367,9 @@ impl Migrate for WrappedTimestamp {}
 
 impl Describe for WrappedTimestamp {
     fn describe() -> Descriptor {
-        crate::describe::Builder::new::<Self>().build()
+        crate::describe::Builder::new::<Self>()
+            .layout_id("WrappedTimestamp")
+            .build()
     }
 }
 
@@ -354,15 +418,13 @@ impl Encode for Adapter<IbcSigner> {
 
 impl Decode for Adapter<IbcSigner> {
     fn decode<R: std::io::Read>(mut input: R) -> ed::Result<Self> {
-        let mut len_buf = [0; 4];
-        input.read_exact(&mut len_buf)?;
-        let len: u32 = borsh::BorshDeserialize::deserialize(&mut len_buf.as_slice())
-            .map_err(|_| ed::Error::UnexpectedByte(40))?;
+        // borsh strings are prefixed with their length as a little-endian u32
+        let len = LeInt::<u32>::decode(&mut input)?;
 
-        let mut buf = vec![0; len as usize];
+        let mut buf = vec![0; *len as usize];
 
         input.read_exact(&mut buf)?;
-        let bytes = [len_buf.to_vec(), buf].concat();
+        let bytes = [len.encode()?, buf].concat();
 
         Ok(Self(
             borsh::BorshDeserialize::deserialize(&mut bytes.as_slice())
@@ -480,7 +542,9 @@ impl Migrate for PortChannel {
 
 impl Describe for PortChannel {
     fn describe() -> Descriptor {
-        crate::describe::Builder::new::<Self>().build()
+        crate::describe::Builder::new::<Self>()
+            .layout_id("PortChannel")
+            .build()
     }
 }
 
@@ -566,7 +630,9 @@ impl Migrate for PortChannelSequence {
 
 impl Describe for PortChannelSequence {
     fn describe() -> Descriptor {
-        crate::describe::Builder::new::<Self>().build()
+        crate::describe::Builder::new::<Self>()
+            .layout_id("PortChannelSequence")
+            .build()
     }
 }
 
@@ -662,6 +728,9 @@ macro_rules! protobuf_newtype {
 
         impl Decode for $newtype {
             fn decode<R: std::io::Read>(mut input: R) -> ed::Result<Self> {
+                // no length limit is applied here: calls are already limited
+                // as a whole when transactions are decoded, and stored client
+                // or consensus states may be larger than a call
                 let mut buf = vec![];
                 input.read_to_end(&mut buf)?;
                 let inner = $proto::<$raw>::decode(buf.as_slice())
@@ -716,7 +785,9 @@ macro_rules! protobuf_newtype {
 
         impl Describe for $newtype {
             fn describe() -> Descriptor {
-                crate::describe::Builder::new::<Self>().build()
+                crate::describe::Builder::new::<Self>()
+                    .layout_id(stringify!($newtype))
+                    .build()
             }
         }
 
@@ -840,7 +911,9 @@ impl Migrate for WrappedConsensusState {
 
 impl Describe for WrappedConsensusState {
     fn describe() -> Descriptor {
-        crate::describe::Builder::new::<Self>().build()
+        crate::describe::Builder::new::<Self>()
+            .layout_id("WrappedConsensusState")
+            .build()
     }
 }
 
@@ -1214,4 +1287,25 @@ mod tests {
 
         assert_state(&store);
     }
+
+    #[test]
+    fn client_types() -> crate::Result<()> {
+        let mut clients: Map<ClientIdKey, Client> = Default::default();
+
+        let mut client = Client::default();
+        assert_eq!(client.client_type()?, client_type());
+        assert!(client.is_tendermint()?);
+
+        let solomachine = ClientType::new("06-solomachine").unwrap();
+        client.set_client_type(solomachine.clone());
+        let client_id = ClientId::new("06-solomachine", 0).unwrap();
+        clients.insert(client_id.clone().into(), client)?;
+
+        let client = clients.get(client_id.into())?.unwrap();
+        assert_eq!(client.client_type()?, solomachine);
+        assert!(!client.is_tendermint()?);
+        assert!(client.last_header().is_err());
+
+        Ok(())
+    }
 }
@@ -9,20 +9,42 @@ use ibc::{
     },
 };
 
+use super::fee::{PacketFee, Payee};
 use super::transfer::Transfer;
+use super::{PortChannel, PortChannelSequence};
+use crate::coins::Address;
+use crate::collections::Map;
 
+/// Routes packets to the transfer module, wrapped in the ICS-29 fee
+/// middleware.
 #[orga]
 pub struct IbcRouter {
     pub transfer: Transfer,
+
+    /// Channels which were opened with ICS-29 fees enabled.
+    pub(super) fee_enabled: Map<PortChannel, ()>,
+
+    /// Relayer fees escrowed for sent packets.
+    pub(super) packet_fees: Map<PortChannelSequence, PacketFee>,
+
+    /// Counterparty chain addresses registered by relayers for each channel,
+    /// to be paid fees for packets they deliver to this chain.
+    pub(super) counterparty_payees: Map<PortChannel, Map<Address, Payee>>,
+}
+
+impl std::fmt::Debug for IbcRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IbcRouter").finish()
+    }
 }
 
 impl Router for IbcRouter {
     fn get_route(&self, module_id: &ModuleId) -> Option<&dyn Module> {
-        (Borrow::<str>::borrow(module_id) == MODULE_ID_STR).then_some(&self.transfer as _)
+        (Borrow::<str>::borrow(module_id) == MODULE_ID_STR).then_some(self as _)
     }
 
     fn get_route_mut(&mut self, module_id: &ModuleId) -> Option<&mut dyn Module> {
-        (Borrow::<str>::borrow(module_id) == MODULE_ID_STR).then_some(&mut self.transfer as _)
+        (Borrow::<str>::borrow(module_id) == MODULE_ID_STR).then_some(self as _)
     }
 
     fn lookup_module(&self, port_id: &PortId) -> Option<ModuleId> {
@@ -4,7 +4,7 @@ use std::time::Duration;
 use async_process::{Child, Command, Stdio};
 use ibc::applications::transfer::context::TokenTransferExecutionContext;
 use orga::abci::{BeginBlock, Node};
-use orga::call::build_call;
+use orga::call::{build_call, Item};
 use orga::client::wallet::{DerivedKey, Unsigned};
 use orga::client::AppClient;
 use orga::coins::{Accounts, Address, Amount, Coin, Symbol};
@@ -134,6 +134,7 @@ pub fn spawn_node() {
             orga::abci::DefaultConfig {
                 seeds: None,
                 timeout_commit: None,
+                ..Default::default()
             },
         )
         .tendermint_flags(vec![
@@ -153,7 +154,12 @@ async fn start_grpc_server() {
     local
         .run_until(async move {
             start_grpc(
-                || client().sub(|app| app.ibc),
+                || {
+                    client().sub(
+                        |app| app.ibc,
+                        |call| Item::Field(IbcAppFieldCall::Ibc(call)),
+                    )
+                },
                 &GrpcOpts {
                     host: "127.0.0.1".to_string(),
                     port: 9001,
@@ -281,7 +287,12 @@ async fn ibc_app() -> Result<()> {
         .run_until(async move {
             // tokio::task::spawn_local(async move {
             start_grpc(
-                || client().sub(|app| app.ibc),
+                || {
+                    client().sub(
+                        |app| app.ibc,
+                        |call| Item::Field(IbcAppFieldCall::Ibc(call)),
+                    )
+                },
                 &GrpcOpts {
                     host: "127.0.0.1".to_string(),
                     port: 9001,
@@ -1,7 +1,7 @@
 //! ICS-20 Fungible Token Transfer module
 
 use crate::{
-    coins::{Address, Amount, Coin, Symbol},
+    coins::{address_prefix, Address, Amount, Coin, Symbol},
     collections::Map,
     describe::{Builder, Describe},
     encoding::LengthVec,
@@ -36,7 +36,7 @@ use ibc::{
     },
     primitives::Signer,
 };
-const ACCOUNT_PREFIX: &str = "nomic"; // TODO: configurable prefix
+use serde::Serialize;
 impl From<TokenTransferError> for crate::Error {
     fn from(err: TokenTransferError) -> Self {
         crate::Error::Ibc(err.to_string())
@@ -49,6 +49,10 @@ pub struct Transfer {
     /// Maps of account balances for each denom
     pub accounts: Map<Denom, Map<Address, Amount>>,
 
+    /// Full denoms of received vouchers, indexed by the hash used in their
+    /// `ibc/<hash>` denom.
+    denom_traces: Map<DenomHash, Denom>,
+
     #[state(skip)]
     #[serde(skip)]
     incoming_transfer: Option<TransferInfo>,
@@ -75,6 +79,35 @@ impl Transfer {
             .unwrap_or_default())
     }
 
+    /// Adds to the balance of an address for the provided [Denom].
+    pub(crate) fn credit(
+        &mut self,
+        address: Address,
+        denom: Denom,
+        amount: Amount,
+    ) -> crate::Result<()> {
+        let mut denom_balances = self.accounts.entry(denom)?.or_default()?;
+        let mut balance = denom_balances.entry(address)?.or_default()?;
+        *balance = (*balance + amount).result()?;
+
+        Ok(())
+    }
+
+    /// Subtracts from the balance of an address for the provided [Denom],
+    /// erroring if the balance is insufficient.
+    pub(crate) fn debit(
+        &mut self,
+        address: Address,
+        denom: Denom,
+        amount: Amount,
+    ) -> crate::Result<()> {
+        let mut denom_balances = self.accounts.entry(denom)?.or_default()?;
+        let mut balance = denom_balances.entry(address)?.or_default()?;
+        *balance = (*balance - amount).result()?;
+
+        Ok(())
+    }
+
     /// Returns the balance of an address for the provided [Symbol].
     pub fn symbol_balance<S: Symbol>(&self, address: Address) -> crate::Result<Amount> {
         let denom = S::NAME.try_into()?;
@@ -89,7 +122,7 @@ impl Transfer {
         channel_id: &ChannelId,
     ) -> Result<Address, TokenTransferError> {
         let account_id = AccountId::new(
-            ACCOUNT_PREFIX,
+            &address_prefix(),
             &cosmos_adr028_escrow_address(port_id, channel_id),
         )
         .map_err(|_| TokenTransferError::ParseAccountFailure)?;
@@ -100,6 +133,65 @@ impl Transfer {
     }
 }
 
+#[orga]
+impl Transfer {
+    /// Returns the trace of a hashed voucher denom of the form `ibc/<hash>`,
+    /// or `None` if no voucher with this denom has been received.
+    #[query]
+    pub fn denom_trace(&self, denom: Denom) -> crate::Result<Option<DenomTrace>> {
+        let denom: String = denom.try_into()?;
+        let hash = denom
+            .strip_prefix("ibc/")
+            .and_then(|hash| hex::decode(hash).ok())
+            .and_then(|hash| DenomHash::try_from(hash).ok())
+            .ok_or_else(|| crate::Error::Ibc(format!("Invalid hashed denom: {}", denom)))?;
+
+        let Some(full_denom) = self.denom_traces.get(hash)? else {
+            return Ok(None);
+        };
+        let full_denom: PrefixedDenom = String::try_from(full_denom.clone())?.parse()?;
+
+        Ok(Some(DenomTrace {
+            path: full_denom.trace_path.to_string(),
+            base_denom: full_denom.base_denom.to_string(),
+        }))
+    }
+
+    /// Records the trace of a received voucher denom, so it can be resolved
+    /// from its hash.
+    fn record_denom_trace(&mut self, denom: &PrefixedDenom) -> crate::Result<()> {
+        if denom.trace_path.is_empty() {
+            return Ok(());
+        }
+
+        let hash = denom_hash(denom);
+        if !self.denom_traces.contains_key(hash)? {
+            self.denom_traces.insert(hash, denom.clone().try_into()?)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The SHA-256 hash of a full denom, as used in `ibc/<hash>` voucher denoms.
+type DenomHash = [u8; 32];
+
+/// Returns the hash of the given full denom.
+fn denom_hash(denom: &PrefixedDenom) -> DenomHash {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(denom.to_string().as_bytes()).into()
+}
+
+/// The origin of a voucher denom (ICS-20).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DenomTrace {
+    /// The ports and channels the tokens were transferred through, e.g.
+    /// `transfer/channel-0`.
+    pub path: String,
+    /// The denom of the tokens on their origin chain.
+    pub base_denom: String,
+}
+
 impl TokenTransferValidationContext for Transfer {
     type AccountId = Address;
 
@@ -154,7 +246,8 @@ impl TokenTransferValidationContext for Transfer {
     }
 }
 
-type Denom = LengthVec<u8, u8>;
+/// A full denom, e.g. `transfer/channel-0/uatom`.
+pub type Denom = LengthVec<u8, u8>;
 
 impl TryFrom<PrefixedDenom> for Denom {
     type Error = crate::Error;
@@ -207,6 +300,8 @@ impl TokenTransferExecutionContext for Transfer {
         account: &Self::AccountId,
         coin: &PrefixedCoin,
     ) -> Result<(), TokenTransferError> {
+        self.record_denom_trace(&coin.denom)?;
+
         let denom: Denom = coin.denom.clone().try_into()?;
         let amount: Amount = coin.amount.try_into()?;
 
@@ -531,3 +626,51 @@ impl State for TransferInfo {
 }
 
 impl crate::encoding::Terminated for TransferInfo {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn denom_trace() -> crate::Result<()> {
+        let mut transfer = Transfer::default();
+        let hashed: Denom =
+            "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2".try_into()?;
+        assert_eq!(transfer.denom_trace(hashed.clone())?, None);
+
+        let coin = PrefixedCoin {
+            denom: "transfer/channel-0/uatom".parse()?,
+            amount: 100u64.into(),
+        };
+        transfer.mint_coins_execute(&Address::NULL, &coin)?;
+        transfer.mint_coins_execute(&Address::NULL, &coin)?;
+
+        assert_eq!(
+            transfer.denom_trace(hashed)?,
+            Some(DenomTrace {
+                path: "transfer/channel-0".to_string(),
+                base_denom: "uatom".to_string(),
+            })
+        );
+        assert_eq!(
+            transfer.balance(Address::NULL, "transfer/channel-0/uatom".try_into()?)?,
+            200
+        );
+
+        // native denoms have no trace
+        let coin = PrefixedCoin {
+            denom: "unom".parse()?,
+            amount: 100u64.into(),
+        };
+        transfer.mint_coins_execute(&Address::NULL, &coin)?;
+        assert!(transfer
+            .denom_traces
+            .get(denom_hash(&coin.denom))?
+            .is_none());
+
+        assert!(transfer.denom_trace("uatom".try_into()?).is_err());
+        assert!(transfer.denom_trace("ibc/1234".try_into()?).is_err());
+
+        Ok(())
+    }
+}
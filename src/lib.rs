@@ -30,6 +30,8 @@ pub mod collections;
 
 pub mod describe;
 
+pub mod gas;
+
 /// Traits for deterministic encoding and decoding.
 ///
 /// This module is actually just a re-export of the [ed](https://docs.rs/ed)
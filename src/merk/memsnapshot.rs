@@ -5,23 +5,24 @@ use crate::{
 };
 
 use super::MerkStore;
+use std::sync::Arc;
 
 /// A KV store backed by a [merk::snapshot::StaticSnapshot].
 ///
 /// This is used to provide a read-only view into the Merk tree at a given point
-/// in time.
+/// in time. The snapshot is shared with the [MerkStore], which defers dropping
+/// it until every [MemSnapshot] referencing it has been dropped.
 pub struct MemSnapshot {
-    snapshot: merk::snapshot::StaticSnapshot,
+    snapshot: Arc<merk::snapshot::StaticSnapshot>,
     merk_store: Shared<MerkStore>,
 }
 
 impl MemSnapshot {
     /// Create a new [MemSnapshot] from a [merk::snapshot::StaticSnapshot].
     pub fn new(
-        mut snapshot: merk::snapshot::StaticSnapshot,
+        snapshot: Arc<merk::snapshot::StaticSnapshot>,
         merk_store: Shared<MerkStore>,
     ) -> Self {
-        snapshot.should_drop = true;
         Self {
             snapshot,
             merk_store,
@@ -32,6 +33,8 @@ impl MemSnapshot {
     pub fn use_snapshot<R, F: FnOnce(&merk::Snapshot) -> R>(&self, f: F) -> R {
         let store = self.merk_store.borrow();
         let db = store.merk().db();
+        // SAFETY: `self.merk_store` keeps the DB open, and the store does not
+        // drop the snapshot while this reference to it is alive.
         let ss = unsafe { self.snapshot.with_db(db) };
         f(&ss)
     }
@@ -216,12 +216,34 @@ impl Snapshots {
             .collect::<Vec<_>>();
 
         for ss_height in remove_heights {
-            self.snapshots.remove(&ss_height);
+            self.remove(ss_height)?;
+        }
 
-            let path = self.path(ss_height);
-            if path.exists() {
-                std::fs::remove_dir_all(path)?;
-            }
+        Ok(())
+    }
+
+    /// Removes all snapshots of heights before the given height.
+    pub fn prune_before(&mut self, height: u64) -> Result<()> {
+        let remove_heights = self
+            .snapshots
+            .range(..height)
+            .map(|(ss_height, _)| *ss_height)
+            .collect::<Vec<_>>();
+
+        for ss_height in remove_heights {
+            self.remove(ss_height)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the snapshot at the given height, deleting it from disk.
+    fn remove(&mut self, height: u64) -> Result<()> {
+        self.snapshots.remove(&height);
+
+        let path = self.path(height);
+        if path.exists() {
+            std::fs::remove_dir_all(path)?;
         }
 
         Ok(())
@@ -5,7 +5,9 @@ use crate::error::{Error, Result};
 use crate::store::*;
 use merk::snapshot::StaticSnapshot;
 use merk::{restore::Restorer, tree::Tree, BatchEntry, Merk, Op};
+use std::ops::Bound;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::{collections::BTreeMap, convert::TryInto};
 use tendermint_proto::v0_34::abci::{self, *};
 
@@ -26,7 +28,9 @@ pub struct MerkStore {
     snapshots: snapshot::Snapshots,
     restorer: Option<Restorer>,
     target_snapshot: Option<Snapshot>,
-    mem_snapshots: BTreeMap<u64, StaticSnapshot>,
+    mem_snapshots: BTreeMap<u64, Arc<StaticSnapshot>>,
+    released_snapshots: Vec<Arc<StaticSnapshot>>,
+    retain_heights: Option<u64>,
 }
 
 impl MerkStore {
@@ -49,6 +53,8 @@ impl MerkStore {
             target_snapshot: None,
             restorer: None,
             mem_snapshots: BTreeMap::new(),
+            released_snapshots: vec![],
+            retain_heights: None,
         }
     }
 
@@ -68,9 +74,19 @@ impl MerkStore {
             target_snapshot: None,
             restorer: None,
             mem_snapshots: BTreeMap::new(),
+            released_snapshots: vec![],
+            retain_heights: None,
         }
     }
 
+    /// Retains only the snapshots of the given number of most recent heights,
+    /// pruning older ones with [MerkStore::prune_before] on each commit.
+    pub fn with_retain_heights(mut self, heights: u64) -> Self {
+        self.retain_heights = Some(heights);
+
+        self
+    }
+
     fn load_snapshots<P: AsRef<Path>>(path: P) -> snapshot::Snapshots {
         snapshot::Snapshots::load(path.as_ref())
             .expect("Failed to load snapshots")
@@ -147,8 +163,85 @@ impl MerkStore {
         self.merk.unwrap()
     }
 
-    pub(crate) fn mem_snapshots(&self) -> &BTreeMap<u64, StaticSnapshot> {
-        &self.mem_snapshots
+    /// Returns a read-only snapshot of the state as of the given committed
+    /// height, along with that height. A height of 0 resolves to the latest
+    /// committed height.
+    ///
+    /// Only a window of recent heights is retained in memory, so this returns
+    /// an error if the height is outside of that window. The returned snapshot
+    /// stays readable until it is dropped, even if its height is pruned.
+    pub fn snapshot_at(&self, height: u64) -> Result<(u64, Arc<StaticSnapshot>)> {
+        let entry = if height == 0 {
+            self.mem_snapshots.last_key_value()
+        } else {
+            self.mem_snapshots.get_key_value(&height)
+        };
+
+        if let Some((height, snapshot)) = entry {
+            return Ok((*height, snapshot.clone()));
+        }
+
+        let (first, last) = match (
+            self.mem_snapshots.first_key_value(),
+            self.mem_snapshots.last_key_value(),
+        ) {
+            (Some((first, _)), Some((last, _))) => (*first, *last),
+            _ => {
+                return Err(Error::Query(format!(
+                    "Cannot query for height {}: no heights are available",
+                    height
+                )))
+            }
+        };
+
+        Err(Error::Query(format!(
+            "Cannot query for height {}: only heights {} to {} are available",
+            height, first, last
+        )))
+    }
+
+    /// Removes the retained snapshots of heights before the given height, to
+    /// bound the disk usage of a long-running node. This includes both the
+    /// in-memory snapshots used to query recent heights and the on-disk
+    /// snapshots offered to peers for state sync.
+    ///
+    /// Snapshots of the given height and later are kept, so this is typically
+    /// called periodically with the current height minus the number of
+    /// heights to retain.
+    pub fn prune_before(&mut self, height: u64) -> Result<()> {
+        while let Some(entry) = self.mem_snapshots.first_entry() {
+            if *entry.key() >= height {
+                break;
+            }
+            let ss = entry.remove();
+            self.release_snapshot(ss);
+        }
+
+        self.snapshots.prune_before(height)
+    }
+
+    /// Releases an in-memory snapshot which has been removed from the
+    /// retained window. The underlying RocksDB snapshot is dropped as soon as
+    /// no [MemSnapshot](super::memsnapshot::MemSnapshot) still holds it, which
+    /// may be deferred to a later call.
+    fn release_snapshot(&mut self, snapshot: Arc<StaticSnapshot>) {
+        self.released_snapshots.push(snapshot);
+        self.drop_unused_snapshots();
+    }
+
+    /// Drops the released snapshots which are no longer held by any reader.
+    fn drop_unused_snapshots(&mut self) {
+        let db = self.merk.as_ref().unwrap().db();
+        for ss in std::mem::take(&mut self.released_snapshots) {
+            match Arc::try_unwrap(ss) {
+                // SAFETY: the snapshot was taken from this store's DB, which is
+                // still open. It is no longer in `mem_snapshots` and this was
+                // the last reference to it, so no reader can still use it and
+                // no new reader can obtain it.
+                Ok(ss) => unsafe { ss.drop(db) },
+                Err(ss) => self.released_snapshots.push(ss),
+            }
+        }
     }
 }
 
@@ -174,12 +267,53 @@ impl Read for MerkStore {
         }
     }
 
+    /// Gets the next entry after `start`, including writes which have not yet
+    /// been written to the underlying `Merk` store, so iteration sees the same
+    /// version of the data as `get`.
     fn get_next(&self, start: &[u8]) -> Result<Option<KV>> {
-        get_next(self.merk().raw_iter(), start)
+        let map = self.map.as_ref().unwrap();
+        let pending = map
+            .range::<[u8], _>((Bound::Excluded(start), Bound::Unbounded))
+            .find_map(|(key, value)| Some((key.clone(), value.clone()?)));
+
+        // skip committed entries which have been overwritten or deleted
+        let mut key = start.to_vec();
+        let committed = loop {
+            match get_next(self.merk().raw_iter(), &key)? {
+                Some((next_key, _)) if map.contains_key(&next_key) => key = next_key,
+                entry => break entry,
+            }
+        };
+
+        Ok(match (pending, committed) {
+            (Some(pending), Some(committed)) if committed.0 < pending.0 => Some(committed),
+            (pending, committed) => pending.or(committed),
+        })
     }
 
+    /// Gets the previous entry before `end`, including writes which have not
+    /// yet been written to the underlying `Merk` store.
     fn get_prev(&self, end: Option<&[u8]>) -> Result<Option<KV>> {
-        get_prev(self.merk().raw_iter(), end)
+        let map = self.map.as_ref().unwrap();
+        let upper = end.map_or(Bound::Unbounded, Bound::Excluded);
+        let pending = map
+            .range::<[u8], _>((Bound::Unbounded, upper))
+            .rev()
+            .find_map(|(key, value)| Some((key.clone(), value.clone()?)));
+
+        // skip committed entries which have been overwritten or deleted
+        let mut key = end.map(|end| end.to_vec());
+        let committed = loop {
+            match get_prev(self.merk().raw_iter(), key.as_deref())? {
+                Some((prev_key, _)) if map.contains_key(&prev_key) => key = Some(prev_key),
+                entry => break entry,
+            }
+        };
+
+        Ok(match (pending, committed) {
+            (Some(pending), Some(committed)) if committed.0 > pending.0 => Some(committed),
+            (pending, committed) => pending.or(committed),
+        })
     }
 }
 
@@ -310,13 +444,17 @@ impl ABCIStore for MerkStore {
         }
 
         let snapshot = self.merk().snapshot()?.staticize();
-        self.mem_snapshots.insert(height, snapshot);
+        self.mem_snapshots.insert(height, Arc::new(snapshot));
 
         // TODO: parameterize
         while self.mem_snapshots.len() > 20 {
-            let ss = self.mem_snapshots.pop_first().unwrap();
-            let db = self.merk().db();
-            unsafe { ss.1.drop(db) };
+            let (_, ss) = self.mem_snapshots.pop_first().unwrap();
+            self.release_snapshot(ss);
+        }
+        self.drop_unused_snapshots();
+
+        if let Some(retain_heights) = self.retain_heights {
+            self.prune_before((height + 1).saturating_sub(retain_heights))?;
         }
 
         Ok(())
@@ -408,3 +546,174 @@ fn read_u64(bytes: &[u8]) -> u64 {
     array.copy_from_slice(bytes);
     u64::from_be_bytes(array)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merk::memsnapshot::MemSnapshot;
+    use tempfile::TempDir;
+
+    fn temp_merk_store() -> MerkStore {
+        let temp_dir = TempDir::new().unwrap();
+        MerkStore::new(temp_dir.path())
+    }
+
+    #[test]
+    fn iteration_includes_pending_writes() -> Result<()> {
+        let mut store = temp_merk_store();
+        store.put(vec![1], vec![1])?;
+        store.put(vec![3], vec![3])?;
+        store.put(vec![5], vec![5])?;
+        store.write(vec![])?;
+
+        store.put(vec![2], vec![2])?;
+        store.delete(&[3])?;
+        store.put(vec![5], vec![50])?;
+
+        assert_eq!(store.get_next(&[1])?, Some((vec![2], vec![2])));
+        assert_eq!(store.get_next(&[2])?, Some((vec![5], vec![50])));
+        assert_eq!(store.get_next(&[5])?, None);
+        assert_eq!(store.get_prev(None)?, Some((vec![5], vec![50])));
+        assert_eq!(store.get_prev(Some(&[5]))?, Some((vec![2], vec![2])));
+        assert_eq!(store.get_prev(Some(&[2]))?, Some((vec![1], vec![1])));
+
+        Ok(())
+    }
+
+    fn commit_at(store: &mut MerkStore, height: i64) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap();
+        store.commit(tendermint_proto::v0_34::types::Header {
+            height,
+            time: Some(tendermint_proto::google::protobuf::Timestamp {
+                seconds: now.as_secs() as i64,
+                nanos: 0,
+            }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn snapshot_at_height() -> Result<()> {
+        let mut store = temp_merk_store();
+        assert!(store.snapshot_at(0).is_err());
+
+        for height in 1..=25 {
+            store.put(vec![1], vec![height as u8])?;
+            commit_at(&mut store, height)?;
+        }
+        let store = Shared::new(store);
+
+        let (height, snapshot) = store.borrow().snapshot_at(0)?;
+        assert_eq!(height, 25);
+        let snapshot = MemSnapshot::new(snapshot, store.clone());
+        assert_eq!(snapshot.get(&[1])?, Some(vec![25]));
+
+        let (height, snapshot) = store.borrow().snapshot_at(10)?;
+        assert_eq!(height, 10);
+        let snapshot = MemSnapshot::new(snapshot, store.clone());
+        assert_eq!(snapshot.get(&[1])?, Some(vec![10]));
+
+        let err = store.borrow().snapshot_at(3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Query Error: Cannot query for height 3: only heights 6 to 25 are available"
+        );
+        assert!(store.borrow().snapshot_at(26).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn prune_before() -> Result<()> {
+        let mut store = temp_merk_store();
+        for height in [1000, 2000, 3000, 4000] {
+            store.put(vec![1], vec![(height / 1000) as u8])?;
+            commit_at(&mut store, height)?;
+
+            let path = store.snapshots.path(height as u64);
+            let checkpoint = store.merk().checkpoint(&path)?;
+            store.snapshots.create(height as u64, checkpoint)?;
+        }
+
+        store.prune_before(3000)?;
+
+        for height in [1000, 2000] {
+            assert!(store.snapshots.get(height).is_none());
+            assert!(!store.snapshots.path(height).exists());
+            assert!(store.snapshot_at(height).is_err());
+        }
+        assert_eq!(store.snapshots.get(3000).unwrap().get(&[1])?, Some(vec![3]));
+        assert_eq!(store.snapshots.get(4000).unwrap().get(&[1])?, Some(vec![4]));
+
+        let store = Shared::new(store);
+        let (height, snapshot) = store.borrow().snapshot_at(3000)?;
+        assert_eq!(height, 3000);
+        let snapshot = MemSnapshot::new(snapshot, store.clone());
+        assert_eq!(snapshot.get(&[1])?, Some(vec![3]));
+
+        // snapshots held by readers are only dropped once the reader is done
+        store.borrow_mut().prune_before(4000)?;
+        assert!(store.borrow().snapshot_at(3000).is_err());
+        assert_eq!(store.borrow().released_snapshots.len(), 1);
+        assert_eq!(snapshot.get(&[1])?, Some(vec![3]));
+
+        drop(snapshot);
+        store.borrow_mut().prune_before(4000)?;
+        assert!(store.borrow().released_snapshots.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn retain_heights() -> Result<()> {
+        let mut store = temp_merk_store().with_retain_heights(2000);
+        for height in [1000, 2000, 3000, 4000] {
+            store.put(vec![1], vec![(height / 1000) as u8])?;
+            commit_at(&mut store, height)?;
+
+            let path = store.snapshots.path(height as u64);
+            let checkpoint = store.merk().checkpoint(&path)?;
+            store.snapshots.create(height as u64, checkpoint)?;
+        }
+
+        for height in [1000, 2000] {
+            assert!(store.snapshots.get(height).is_none());
+            assert!(store.snapshot_at(height).is_err());
+        }
+        for height in [3000, 4000] {
+            assert!(store.snapshots.get(height).is_some());
+            assert!(store.snapshot_at(height).is_ok());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_iteration_ignores_later_writes() -> Result<()> {
+        let mut store = temp_merk_store();
+        store.put(vec![1], vec![1])?;
+        store.put(vec![5], vec![5])?;
+        store.write(vec![])?;
+        let snapshot = store.merk().snapshot()?.staticize();
+
+        store.put(vec![3], vec![3])?;
+        store.put(vec![7], vec![7])?;
+        store.delete(&[5])?;
+        store.write(vec![])?;
+
+        let store = Shared::new(store);
+        let snapshot = MemSnapshot::new(snapshot, store.clone());
+
+        assert_eq!(snapshot.get_next(&[1])?, Some((vec![5], vec![5])));
+        assert_eq!(snapshot.get_next(&[5])?, None);
+        assert_eq!(snapshot.get_prev(None)?, Some((vec![5], vec![5])));
+        assert_eq!(snapshot.get_prev(Some(&[5]))?, Some((vec![1], vec![1])));
+
+        assert_eq!(store.get_next(&[1])?, Some((vec![3], vec![3])));
+        assert_eq!(store.get_prev(None)?, Some((vec![7], vec![7])));
+
+        Ok(())
+    }
+}
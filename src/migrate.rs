@@ -4,7 +4,7 @@ pub use crate::macros::Migrate;
 use crate::{
     encoding::{Decode, Terminated},
     state::State,
-    store::Store,
+    store::{Read, Store, Write},
     Error, Result,
 };
 use std::{cell::RefCell, marker::PhantomData, rc::Rc};
@@ -22,6 +22,39 @@ pub trait Migrate: State {
 
 /// Create a migrated instance of this type from a loaded value of a previous
 /// version.
+///
+/// For versioned `#[orga]` structs, this can be derived by annotating fields
+/// of the new version with `#[migrate(..)]` attributes instead of being
+/// written by hand. If any field has such an attribute, the [Migrate] derive
+/// implements [MigrateFrom] for the previous version, populating each field as
+/// follows:
+///
+/// - `#[migrate(from = "old_name")]` migrates the previous version's `old_name`
+///   field, e.g. for a renamed field.
+/// - `#[migrate(skip)]` uses the default value, e.g. for a newly added field.
+/// - Any other field migrates the previous version's field of the same name.
+///
+/// A field's `#[migrate(..)]` attribute only applies to the first version
+/// containing the field, so later versions which only add other fields can
+/// still implement [MigrateFrom] by hand.
+///
+/// Fields are converted with [MigrateInto], so their types may change between
+/// versions as long as a [MigrateFrom] implementation exists. Fields of the
+/// previous version which are not used are dropped.
+///
+/// ```ignore
+/// #[orga(version = 1)]
+/// struct Foo {
+///     #[orga(version(V0))]
+///     old_name: u32,
+///     #[orga(version(V1))]
+///     #[migrate(from = "old_name")]
+///     new_name: u64,
+///     #[orga(version(V1))]
+///     #[migrate(skip)]
+///     added: u8,
+/// }
+/// ```
 pub trait MigrateFrom<T>: State {
     /// Migrate from the previous version instance.
     fn migrate_from(value: T) -> Result<Self>;
@@ -49,6 +82,26 @@ impl<T: From<U> + State, U> MigrateFrom<U> for T {
     }
 }
 
+/// Migrate the state tree stored at `key` in `src` to the same key in `dest`,
+/// returning the migrated value loaded from `dest`.
+///
+/// The encoded root value is read from `key` itself, and the migrated root
+/// value is written back to `key` in `dest` along with all of its descendant
+/// keys, so an entire nested module can be migrated in one call.
+pub fn migrate_subtree<T: Migrate>(src: Store, dest: Store, key: &[u8]) -> Result<T> {
+    let src = src.sub(key);
+    let mut dest = dest.sub(key);
+
+    let src_bytes = src.get(&[])?.unwrap_or_default();
+    let value = T::migrate(src, dest.clone(), &mut src_bytes.as_slice())?;
+
+    let mut bytes = vec![];
+    value.flush(&mut bytes)?;
+    dest.put(vec![], bytes.clone())?;
+
+    T::load(dest, &mut bytes.as_slice())
+}
+
 macro_rules! migrate_impl {
     ($type:ty) => {
         impl Migrate for $type {}
@@ -67,6 +120,7 @@ migrate_impl!(i64);
 migrate_impl!(i128);
 migrate_impl!(bool);
 migrate_impl!(());
+migrate_impl!(String);
 
 impl<T: Migrate> Migrate for Option<T> {
     #[inline]
@@ -115,6 +169,10 @@ impl<T: Migrate + Terminated> Migrate for Vec<T> {
     }
 }
 
+impl<K, V> Migrate for std::collections::BTreeMap<K, V> where Self: State {}
+
+impl<K, V> Migrate for std::collections::HashMap<K, V> where Self: State {}
+
 impl<T: Migrate> Migrate for RefCell<T> {
     #[inline]
     fn migrate(src: Store, dest: Store, bytes: &mut &[u8]) -> Result<Self> {
@@ -278,12 +336,93 @@ mod tests {
         }
     }
 
+    #[orga(version = 1)]
+    struct Renamed {
+        #[orga(version(V0))]
+        old_name: u32,
+        #[orga(version(V1))]
+        #[migrate(from = "old_name")]
+        new_name: u64,
+
+        #[orga(version(V0))]
+        removed: u16,
+
+        map: Map<u32, u32>,
+
+        #[orga(version(V1))]
+        #[migrate(skip)]
+        added: u8,
+    }
+
+    #[orga(version = 2)]
+    struct Extended {
+        a: u32,
+        #[orga(version(V1, V2))]
+        #[migrate(skip)]
+        b: u32,
+        #[orga(version(V2))]
+        c: u32,
+    }
+
+    impl MigrateFrom<ExtendedV1> for ExtendedV2 {
+        fn migrate_from(value: ExtendedV1) -> Result<Self> {
+            Ok(Self {
+                a: value.a,
+                b: value.b,
+                c: value.b + 1,
+            })
+        }
+    }
+
     #[orga(version = 1)]
     struct WithGeneric<T> {
         a: u32,
         b: T,
     }
 
+    #[orga]
+    struct Nested {
+        count: u32,
+        maps: Map<u32, Map<u32, u32>>,
+    }
+
+    #[test]
+    fn subtree_migration() -> Result<()> {
+        let mut src = Store::new(BackingStore::MapStore(Shared::new(MapStore::new())));
+        let dest = Store::new(BackingStore::MapStore(Shared::new(MapStore::new())));
+
+        let mut nested = Nested {
+            count: 3,
+            ..Default::default()
+        };
+        nested.attach(src.sub(&[7]))?;
+        for i in 0..3 {
+            let mut map = nested.maps.entry(i)?.or_insert_default()?;
+            map.insert(i * 10, i)?;
+            map.insert(i * 10 + 1, i + 1)?;
+        }
+        let mut bytes = vec![];
+        nested.flush(&mut bytes)?;
+        src.put(vec![7], bytes)?;
+        src.put(vec![8], vec![1])?;
+
+        let src_entries: Vec<_> = src.sub(&[7]).into_iter(..).collect::<Result<_>>()?;
+        assert_eq!(src_entries.len(), 10);
+
+        let nested: Nested = migrate_subtree(src.clone(), dest.clone(), &[7])?;
+        assert_eq!(nested.count, 3);
+        assert_eq!(*nested.maps.get(2)?.unwrap().get(21)?.unwrap(), 3);
+
+        let dest_entries: Vec<_> = dest.clone().into_iter(..).collect::<Result<_>>()?;
+        let expected: Vec<_> = src_entries
+            .into_iter()
+            .map(|(key, value)| ([&[7], key.as_slice()].concat(), value))
+            .collect();
+        assert_eq!(dest_entries, expected);
+
+        Ok(())
+    }
+
     impl<T: State> MigrateFrom<WithGenericV0<T>> for WithGenericV1<T> {
         fn migrate_from(value: WithGenericV0<T>) -> Result<Self> {
             Ok(Self {
@@ -412,4 +551,47 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn derived_migrate_from() -> Result<()> {
+        let mut store = Store::new(BackingStore::MapStore(Shared::new(MapStore::new())));
+
+        let mut renamed = RenamedV0 {
+            old_name: 7,
+            removed: 3,
+            ..Default::default()
+        };
+        renamed.attach(store.clone())?;
+        renamed.map.insert(1, 2)?;
+        let mut bytes = vec![];
+        renamed.flush(&mut bytes)?;
+        assert_eq!(bytes, vec![0, 0, 0, 0, 7, 0, 3]);
+        store.put(vec![], bytes.clone())?;
+
+        let mut renamed = RenamedV1::migrate(store.clone(), store.clone(), &mut bytes.as_slice())?;
+        assert_eq!(renamed.new_name, 7);
+        assert_eq!(renamed.added, 0);
+        assert_eq!(*renamed.map.get(1)?.unwrap(), 2);
+
+        let mut bytes = vec![];
+        renamed.attach(store.clone())?;
+        renamed.flush(&mut bytes)?;
+        assert_eq!(bytes, vec![1, 0, 0, 0, 0, 0, 0, 0, 7, 0]);
+        assert_eq!(store.get(&[1, 0, 0, 0, 1])?.unwrap(), vec![0, 0, 0, 2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn derived_migrate_from_only_for_added_fields() -> Result<()> {
+        let store = Store::new(BackingStore::MapStore(Shared::new(MapStore::new())));
+        let bytes = vec![0, 0, 0, 0, 7];
+
+        let extended = Extended::migrate(store.clone(), store, &mut bytes.as_slice())?;
+        assert_eq!(extended.a, 7);
+        assert_eq!(extended.b, 0);
+        assert_eq!(extended.c, 1);
+
+        Ok(())
+    }
 }
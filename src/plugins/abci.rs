@@ -1,9 +1,10 @@
-use crate::abci::{prost::Adapter, AbciQuery, App};
+use crate::abci::{prost::Adapter, AbciQuery, App, CheckTx as _};
 use crate::call::Call;
-use crate::collections::{Entry, EntryMap, Map};
+use crate::collections::{Entry, EntryMap, Map, Value};
 use crate::context::Context;
 use crate::describe::Describe;
 use crate::encoding::{Decode, Encode};
+use crate::gas::Gas;
 use crate::migrate::Migrate;
 use crate::query::Query;
 use crate::state::State;
@@ -15,7 +16,7 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::rc::Rc;
 use tendermint_proto::google::protobuf::Timestamp;
-use tendermint_proto::v0_34::abci::Event;
+use tendermint_proto::v0_34::abci::{Event, EventAttribute};
 use tendermint_proto::v0_34::abci::{Evidence, LastCommitInfo, RequestQuery, ResponseQuery};
 use tendermint_proto::v0_34::abci::{
     RequestBeginBlock, RequestEndBlock, RequestInitChain, ValidatorUpdate,
@@ -86,6 +87,10 @@ pub struct ABCIPlugin<T> {
     cons_key_by_op_addr: Rc<RefCell<Option<OperatorMap>>>,
     #[serde(skip)]
     pub(crate) logs: Option<Vec<String>>,
+    #[serde(skip)]
+    pub(crate) gas: Option<Gas>,
+    #[serde(skip)]
+    last_block_time: Value<Adapter<Timestamp>>,
 }
 
 impl<T: Migrate> Migrate for ABCIPlugin<T> {
@@ -102,6 +107,8 @@ impl<T: Migrate> Migrate for ABCIPlugin<T> {
             events: None,
             time: None,
             logs: None,
+            gas: None,
+            last_block_time: State::load(src.sub(&[4]), bytes)?,
         })
     }
 }
@@ -117,6 +124,8 @@ impl<T: Default> Default for ABCIPlugin<T> {
             current_vp: Rc::new(RefCell::new(Some(Default::default()))),
             cons_key_by_op_addr: Rc::new(RefCell::new(Some(Default::default()))),
             logs: None,
+            gas: None,
+            last_block_time: Value::default(),
         }
     }
 }
@@ -341,12 +350,63 @@ impl Events {
         self.events.push(event);
     }
 
+    /// Emit an event of the given type, returning an [EventBuilder] to add its
+    /// attributes, e.g.
+    /// `events.emit("unbond").attr("validator", address).index_all()`.
+    pub fn emit(&mut self, ty: impl Into<String>) -> EventBuilder {
+        self.events.push(Event {
+            r#type: ty.into(),
+            attributes: vec![],
+        });
+
+        EventBuilder {
+            event: self.events.last_mut().unwrap(),
+        }
+    }
+
     /// Read the events that have been emitted during the current ABCI call.
     pub fn events(&self) -> &[Event] {
         &self.events
     }
 }
 
+/// Adds attributes to an event emitted with [Events::emit].
+///
+/// The event has already been emitted, so the builder modifies it in place and
+/// does not need to be finished.
+pub struct EventBuilder<'a> {
+    event: &'a mut Event,
+}
+
+impl<'a> EventBuilder<'a> {
+    /// Add an attribute which is not indexed.
+    pub fn attr(self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.push_attr(key.into(), value.to_string(), false)
+    }
+
+    /// Add an indexed attribute.
+    pub fn indexed_attr(self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.push_attr(key.into(), value.to_string(), true)
+    }
+
+    /// Mark all of the attributes added so far as indexed.
+    pub fn index_all(self) -> Self {
+        for attr in self.event.attributes.iter_mut() {
+            attr.index = true;
+        }
+        self
+    }
+
+    fn push_attr(self, key: String, value: String, index: bool) -> Self {
+        self.event.attributes.push(EventAttribute {
+            key: key.into(),
+            value: value.into(),
+            index,
+        });
+        self
+    }
+}
+
 /// A context for emitting log messages via ABCI responses.
 #[derive(Default)]
 pub struct Logs {
@@ -399,9 +459,8 @@ impl<T: App> Call for ABCIPlugin<T> {
 
     fn call(&mut self, call: Self::Call) -> Result<()> {
         use ABCICall::*;
-        let validators = Validators::new(self.current_vp.clone(), self.cons_key_by_op_addr.clone());
         let context_remover = ContextRemover;
-        Context::add(validators);
+        Context::add(self.validators());
         let create_time_ctx = |time: &Option<Timestamp>| {
             if let Some(timestamp) = time {
                 Context::add(Time {
@@ -420,11 +479,12 @@ impl<T: App> Call for ABCIPlugin<T> {
                 self.inner.init_chain(&ctx)?;
             }
             BeginBlock(req) => {
+                let ctx: BeginBlockCtx = req.into_inner().into();
+                self.check_block_time(ctx.header.time.as_ref())?;
                 Context::add(Events::default());
                 Context::add(Logs::default());
                 self.events.replace(vec![]);
                 self.logs.replace(vec![]);
-                let ctx: BeginBlockCtx = req.into_inner().into();
                 self.time = ctx.header.clone().time;
                 create_time_ctx(&self.time);
                 let res = self.inner.begin_block(&ctx);
@@ -462,6 +522,8 @@ impl<T: App> Call for ABCIPlugin<T> {
                 self.events.replace(vec![]);
                 self.logs.replace(vec![]);
                 let res = self.inner.call(inner_call);
+                self.gas = Context::resolve::<Gas>().cloned();
+                Context::remove::<Gas>();
                 if res.is_ok() {
                     self.events
                         .replace(Context::resolve::<Events>().unwrap().events.clone());
@@ -477,7 +539,9 @@ impl<T: App> Call for ABCIPlugin<T> {
                 Context::add(Logs::default());
                 self.events.replace(vec![]);
                 self.logs.replace(vec![]);
-                let res = self.inner.call(inner_call);
+                let res = self.inner.check_tx(inner_call);
+                self.gas = Context::resolve::<Gas>().cloned();
+                Context::remove::<Gas>();
                 if res.is_ok() {
                     self.events
                         .replace(Context::resolve::<Events>().unwrap().events.clone());
@@ -514,11 +578,13 @@ impl<T: App> Call for ABCIPlugin<T> {
     }
 }
 
-struct ContextRemover;
+pub(crate) struct ContextRemover;
 
 impl ContextRemover {
     fn remove(&self) {
         Context::remove::<Validators>();
+        Context::remove::<Gas>();
+        Context::remove::<Time>();
     }
 }
 
@@ -529,6 +595,27 @@ impl Drop for ContextRemover {
 }
 
 impl<T: App> ABCIPlugin<T> {
+    /// Ensures block time does not decrease relative to the previous block,
+    /// then records it for the next check. Consumers of the [Time] context,
+    /// e.g. staking queues, rely on time never going backward.
+    fn check_block_time(&mut self, time: Option<&Timestamp>) -> Result<()> {
+        let time = match time {
+            Some(time) => time,
+            None => return Ok(()),
+        };
+
+        if let Some(last) = self.last_block_time.try_get()? {
+            if (time.seconds, time.nanos) < (last.seconds, last.nanos) {
+                return Err(Error::App(format!(
+                    "Block time {}.{:09} is before previous block time {}.{:09}",
+                    time.seconds, time.nanos, last.seconds, last.nanos,
+                )));
+            }
+        }
+
+        self.last_block_time.set(Adapter(time.clone()))
+    }
+
     fn build_updates(&mut self) -> Result<()> {
         let mut update_keys = vec![];
         let mut update_map = HashMap::new();
@@ -550,10 +637,34 @@ impl<T: App> ABCIPlugin<T> {
     }
 }
 
+impl<T> ABCIPlugin<T> {
+    /// Returns a [Validators] context for the current validator set.
+    pub(crate) fn validators(&self) -> Validators {
+        Validators::new(self.current_vp.clone(), self.cons_key_by_op_addr.clone())
+    }
+
+    /// Adds the [Validators] context, and the [Time] context of the last block
+    /// if there is one, so that queries which depend on them can run outside
+    /// of block processing. Both contexts are removed when the returned value
+    /// is dropped.
+    pub(crate) fn add_query_context(&self) -> Result<ContextRemover> {
+        Context::add(self.validators());
+        if let Some(time) = self.last_block_time.try_get()? {
+            Context::add(Time {
+                seconds: time.seconds,
+                nanos: time.nanos,
+            });
+        }
+
+        Ok(ContextRemover)
+    }
+}
+
 impl<T: Query> Query for ABCIPlugin<T> {
     type Query = T::Query;
 
     fn query(&self, query: Self::Query) -> Result<()> {
+        let _context_remover = self.add_query_context()?;
         self.inner.query(query)
     }
 }
@@ -565,7 +676,8 @@ impl<T: State> State for ABCIPlugin<T> {
         self.current_vp.borrow_mut().attach(store.sub(&[2]))?;
         self.cons_key_by_op_addr
             .borrow_mut()
-            .attach(store.sub(&[3]))
+            .attach(store.sub(&[3]))?;
+        self.last_block_time.attach(store.sub(&[4]))
     }
 
     fn flush<W: std::io::Write>(self, out: &mut W) -> Result<()> {
@@ -575,7 +687,8 @@ impl<T: State> State for ABCIPlugin<T> {
         self.inner.flush(out)?;
         self.updates.flush(out)?;
         self.current_vp.take().flush(&mut vec![])?;
-        self.cons_key_by_op_addr.take().flush(&mut vec![])
+        self.cons_key_by_op_addr.take().flush(&mut vec![])?;
+        self.last_block_time.flush(&mut vec![])
     }
 
     fn load(store: Store, bytes: &mut &[u8]) -> Result<Self> {
@@ -590,6 +703,8 @@ impl<T: State> State for ABCIPlugin<T> {
             events: None,
             time: None,
             logs: None,
+            gas: None,
+            last_block_time: loader.load_child::<Self, _>()?,
         })
     }
 
@@ -599,6 +714,7 @@ impl<T: State> State for ABCIPlugin<T> {
             "updates" => Some(crate::describe::KeyOp::Append(vec![1])),
             "current_vp" => Some(crate::describe::KeyOp::Append(vec![2])),
             "cons_key_by_op_addr" => Some(crate::describe::KeyOp::Append(vec![3])),
+            "last_block_time" => Some(crate::describe::KeyOp::Append(vec![4])),
             _ => None,
         }
     }
@@ -622,3 +738,271 @@ where
         self.inner.abci_query(req)
     }
 }
+
+#[cfg(feature = "abci-plus")]
+impl<T> crate::abci::ExtendVote for ABCIPlugin<T>
+where
+    T: State + crate::abci::ExtendVote,
+{
+    fn extend_vote(
+        &mut self,
+        req: &crate::abci::messages_plus::RequestExtendVote,
+    ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+        self.inner.extend_vote(req)
+    }
+}
+
+#[cfg(feature = "abci-plus")]
+impl<T> crate::abci::VerifyVoteExtension for ABCIPlugin<T>
+where
+    T: State + crate::abci::VerifyVoteExtension,
+{
+    fn verify_vote_extension(
+        &self,
+        req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+    ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+        self.inner.verify_vote_extension(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abci::{BeginBlock, CheckTx};
+    use crate::orga;
+    use crate::plugins::{
+        sdk_compat, ConvertSdkTx, DefaultPlugins, NonceCall, PaidCall, PayableCall, SigType,
+        SignerCall, TimeoutHeightCall,
+    };
+    use crate::store::{Read, Write};
+    use serial_test::serial;
+
+    #[orga]
+    struct Counter {
+        count: u32,
+    }
+
+    impl BeginBlock for Counter {
+        fn begin_block(&mut self, _ctx: &BeginBlockCtx) -> Result<()> {
+            self.count += 1;
+            Ok(())
+        }
+    }
+
+    fn begin_block(store: &Store, seconds: i64, nanos: i32) -> Result<u32> {
+        let mut store = store.clone();
+        let mut app = match store.get(&[])? {
+            Some(bytes) => ABCIPlugin::<Counter>::load(store.clone(), &mut bytes.as_slice())?,
+            None => {
+                let mut app = ABCIPlugin::<Counter>::default();
+                app.attach(store.clone())?;
+                app
+            }
+        };
+
+        let req = RequestBeginBlock {
+            header: Some(Header {
+                time: Some(Timestamp { seconds, nanos }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let res = app.call(req.into());
+        let count = app.inner.count;
+
+        let mut bytes = vec![];
+        app.flush(&mut bytes)?;
+        store.put(vec![], bytes)?;
+
+        res.map(|_| count)
+    }
+
+    #[test]
+    #[serial]
+    fn block_time_non_decreasing() -> Result<()> {
+        let store = Store::with_map_store();
+
+        assert_eq!(begin_block(&store, 10, 0)?, 1);
+        assert_eq!(begin_block(&store, 10, 0)?, 2);
+        assert_eq!(begin_block(&store, 10, 5)?, 3);
+        assert_eq!(begin_block(&store, 15, 0)?, 4);
+
+        let err = begin_block(&store, 12, 0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "App Error: Block time 12.000000000 is before previous block time 15.000000000"
+        );
+        begin_block(&store, 14, 999_999_999).unwrap_err();
+
+        assert_eq!(begin_block(&store, 15, 0)?, 5);
+        assert_eq!(begin_block(&store, 20, 0)?, 6);
+
+        // the block time does not leak into calls outside of the block
+        assert!(Context::resolve::<Time>().is_none());
+
+        Ok(())
+    }
+
+    #[orga(skip(Call))]
+    struct Checked {
+        count: u32,
+    }
+
+    #[derive(Debug, Encode, Decode)]
+    enum CheckedCall {
+        Pay,
+        Increment(u32),
+    }
+
+    impl Call for Checked {
+        type Call = CheckedCall;
+
+        fn call(&mut self, call: Self::Call) -> Result<()> {
+            match call {
+                CheckedCall::Pay => crate::plugins::disable_fee(),
+                CheckedCall::Increment(n) => self.count += n,
+            }
+            Ok(())
+        }
+    }
+
+    impl CheckTx for Checked {
+        fn check_tx(&mut self, call: Self::Call) -> Result<()> {
+            match call {
+                CheckedCall::Increment(0) => {
+                    Err(Error::App("Increment must be non-zero".to_string()))
+                }
+                CheckedCall::Increment(_) => Ok(()),
+                call => self.call(call),
+            }
+        }
+    }
+
+    impl ConvertSdkTx for Checked {
+        type Output = PaidCall<CheckedCall>;
+
+        fn convert(&self, _msg: &crate::plugins::sdk_compat::sdk::Tx) -> Result<Self::Output> {
+            unimplemented!()
+        }
+    }
+
+    #[orga]
+    #[derive(Clone, Debug)]
+    struct Simp {}
+    impl crate::coins::Symbol for Simp {
+        const INDEX: u8 = 12;
+        const NAME: &'static str = "SIMP";
+    }
+
+    type CheckedApp = ABCIPlugin<DefaultPlugins<Simp, Checked>>;
+
+    fn checked_tx(n: u32) -> Result<sdk_compat::Call<SignerCall>> {
+        let call = NonceCall {
+            nonce: None,
+            inner_call: TimeoutHeightCall {
+                timeout_height: 0,
+                inner_call: PayableCall::Paid(PaidCall {
+                    payer: CheckedCall::Pay,
+                    paid: CheckedCall::Increment(n),
+                }),
+            },
+        };
+
+        Ok(sdk_compat::Call::Native(SignerCall {
+            signature: None,
+            pubkey: None,
+            sigtype: SigType::Native,
+            memo: None,
+            call_bytes: [b"foo".to_vec(), call.encode()?].concat(),
+        }))
+    }
+
+    fn checked_count(app: &CheckedApp) -> u32 {
+        app.inner
+            .inner
+            .borrow()
+            .inner
+            .inner
+            .inner
+            .inner
+            .inner
+            .inner
+            .inner
+            .count
+    }
+
+    #[test]
+    #[serial]
+    fn check_tx_hook() -> Result<()> {
+        let mut app = CheckedApp::default();
+        app.attach(Store::with_map_store())?;
+        app.inner.inner.borrow_mut().inner.inner.chain_id = b"foo".to_vec().try_into()?;
+
+        let err = app.call(ABCICall::CheckTx(checked_tx(0)?)).unwrap_err();
+        assert_eq!(err.to_string(), "App Error: Increment must be non-zero");
+        assert_eq!(checked_count(&app), 0);
+
+        // valid calls pass the check without being executed
+        app.call(ABCICall::CheckTx(checked_tx(2)?))?;
+        assert_eq!(checked_count(&app), 0);
+
+        // the check only runs in CheckTx, so the same call is accepted when
+        // delivered
+        app.call(ABCICall::DeliverTx(checked_tx(0)?))?;
+        assert_eq!(checked_count(&app), 0);
+        app.call(ABCICall::DeliverTx(checked_tx(2)?))?;
+        assert_eq!(checked_count(&app), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn event_builder() {
+        let mut events = Events::default();
+        events
+            .emit("unbond")
+            .attr("validator", "val")
+            .attr("amount", format!("{}{}", 100, "unom"))
+            .index_all();
+        events
+            .emit("message")
+            .indexed_attr("sender", 123)
+            .attr("memo", "");
+
+        assert_eq!(
+            events.events(),
+            &[
+                Event {
+                    r#type: "unbond".to_string(),
+                    attributes: vec![
+                        EventAttribute {
+                            key: "validator".into(),
+                            value: "val".into(),
+                            index: true,
+                        },
+                        EventAttribute {
+                            key: "amount".into(),
+                            value: "100unom".into(),
+                            index: true,
+                        },
+                    ],
+                },
+                Event {
+                    r#type: "message".to_string(),
+                    attributes: vec![
+                        EventAttribute {
+                            key: "sender".into(),
+                            value: "123".into(),
+                            index: true,
+                        },
+                        EventAttribute {
+                            key: "memo".into(),
+                            value: "".into(),
+                            index: false,
+                        },
+                    ],
+                },
+            ]
+        );
+    }
+}
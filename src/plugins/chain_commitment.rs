@@ -64,6 +64,15 @@ impl<T: CallTrait> CallTrait for ChainCommitmentPlugin<T> {
     type Call = Vec<u8>;
 
     fn call(&mut self, call: Self::Call) -> Result<()> {
+        let inner_call = self.inner_call(call)?;
+        self.inner.call(inner_call)
+    }
+}
+
+impl<T: CallTrait> ChainCommitmentPlugin<T> {
+    /// Checks the call's chain ID commitment and sets the [ChainId] context,
+    /// returning the decoded inner call.
+    fn inner_call(&self, call: Vec<u8>) -> Result<T::Call> {
         if self.chain_id.len() == 0 {
             return Err(Error::App("Chain ID not set".into()));
         }
@@ -83,7 +92,7 @@ impl<T: CallTrait> CallTrait for ChainCommitmentPlugin<T> {
 
         let inner_call = Decode::decode(&call[self.chain_id.len()..])?;
         Context::add(ChainId(String::from_utf8(self.chain_id.to_vec()).unwrap()));
-        self.inner.call(inner_call)
+        Ok(inner_call)
     }
 }
 
@@ -129,9 +138,16 @@ impl<T: Migrate> MigrateFrom<ChainCommitmentPluginV0<T>> for ChainCommitmentPlug
 mod abci {
     use super::super::{BeginBlockCtx, EndBlockCtx, InitChainCtx};
     use super::*;
-    use crate::abci::{BeginBlock, EndBlock, InitChain};
+    use crate::abci::{BeginBlock, CheckTx, EndBlock, InitChain};
     use crate::state::State;
 
+    impl<T: CallTrait> CheckTx for ChainCommitmentPlugin<T> {
+        fn check_tx(&mut self, call: Self::Call) -> Result<()> {
+            let inner_call = self.inner_call(call)?;
+            self.inner.check_tx(inner_call)
+        }
+    }
+
     impl<T> BeginBlock for ChainCommitmentPlugin<T>
     where
         T: BeginBlock + State,
@@ -177,4 +193,30 @@ mod abci {
             self.inner.abci_query(request)
         }
     }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::ExtendVote for ChainCommitmentPlugin<T>
+    where
+        T: crate::abci::ExtendVote + State,
+    {
+        fn extend_vote(
+            &mut self,
+            req: &crate::abci::messages_plus::RequestExtendVote,
+        ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+            self.inner.extend_vote(req)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::VerifyVoteExtension for ChainCommitmentPlugin<T>
+    where
+        T: crate::abci::VerifyVoteExtension + State,
+    {
+        fn verify_vote_extension(
+            &self,
+            req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+            self.inner.verify_vote_extension(req)
+        }
+    }
 }
@@ -0,0 +1,327 @@
+//! Congestion-priced fee deduction for calls.
+use orga_macros::orga;
+
+use super::sdk_compat::{sdk::Tx as SdkTx, ConvertSdkTx};
+use super::Paid;
+use crate::call::Call;
+use crate::coins::{Amount, Coin, Decimal, Symbol};
+use crate::context::GetContext;
+use crate::encoding::Encode;
+use crate::state::State;
+use crate::{Error, Result};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// A plugin which charges an EIP-1559-style base fee per unit of gas, which
+/// rises and falls with block fullness.
+///
+/// The gas used by a call is the encoded length of its `paid` call in bytes.
+/// At least `base_fee * gas` units of symbol `S` must be paid into the [Paid]
+/// context by the `payer` call, and are burned before running the `paid` call.
+///
+/// At the end of each block the base fee moves toward the level where blocks
+/// use `target_fullness` of `max_block_gas`, changing by at most `max_change`
+/// per block and never falling below `min_base_fee`. Since the base fee is
+/// adjusted multiplicatively, `min_base_fee` should be non-zero.
+#[orga(skip(Call))]
+pub struct DynamicFeePlugin<S, T> {
+    #[state(skip)]
+    _symbol: PhantomData<S>,
+    /// The current fee per unit of gas.
+    base_fee: Decimal,
+    /// The lowest the base fee can be adjusted to.
+    pub min_base_fee: Decimal,
+    /// The gas used by a completely full block.
+    pub max_block_gas: u64,
+    /// The fraction of `max_block_gas` each block is targeted to use.
+    pub target_fullness: Decimal,
+    /// The maximum fraction the base fee may change by in a single block.
+    pub max_change: Decimal,
+    /// The gas used so far in the current block.
+    block_gas: u64,
+    /// The inner value.
+    pub inner: T,
+}
+
+#[orga]
+impl<S: Symbol, T: State> DynamicFeePlugin<S, T> {
+    /// Returns the current fee per unit of gas.
+    #[query]
+    pub fn base_fee(&self) -> Result<Decimal> {
+        Ok(self.base_fee)
+    }
+
+    /// Returns the minimum fee for a call using the given amount of gas at the
+    /// current base fee.
+    #[query]
+    pub fn min_fee(&self, gas: u64) -> Result<Amount> {
+        let fee = (self.base_fee * Decimal::from(gas))?;
+        Decimal::from(fee.value.ceil()).amount()
+    }
+
+    /// Sets the base fee, e.g. when initializing the chain.
+    pub fn set_base_fee(&mut self, base_fee: Decimal) {
+        self.base_fee = base_fee;
+    }
+
+    /// Adjusts the base fee based on the gas used in the current block, then
+    /// resets the block's gas usage.
+    fn adjust_base_fee(&mut self) -> Result<()> {
+        let gas_used = Decimal::from(self.block_gas.min(self.max_block_gas));
+        self.block_gas = 0;
+
+        let target = (Decimal::from(self.max_block_gas) * self.target_fullness)?;
+        if target == Decimal::zero() {
+            return Ok(());
+        }
+
+        let fullness_delta = ((gas_used - target)? / target)?;
+        let change = (fullness_delta * self.max_change)?
+            .min(self.max_change)
+            .max((Decimal::zero() - self.max_change)?);
+
+        let base_fee = (self.base_fee * (Decimal::one() + change)?)?;
+        self.base_fee = base_fee.max(self.min_base_fee);
+
+        Ok(())
+    }
+}
+
+impl<S: Symbol, T: Call + State> Call for DynamicFeePlugin<S, T> {
+    type Call = T::Call;
+
+    fn call(&mut self, call: Self::Call) -> Result<()> {
+        let running_payer = self
+            .context::<Paid>()
+            .ok_or_else(|| Error::Coins("Minimum fee not paid".into()))?
+            .running_payer;
+
+        if !running_payer {
+            let gas = call.encoding_length()? as u64;
+            let fee = self.min_fee(gas)?;
+
+            let paid = self
+                .context::<Paid>()
+                .ok_or_else(|| Error::Coins("Minimum fee not paid".into()))?;
+            if !paid.fee_disabled {
+                if paid.balance::<S>()? < fee {
+                    return Err(Error::Coins(format!(
+                        "Offered fee is below the base fee of {} for {} gas",
+                        fee, gas
+                    )));
+                }
+                let fee_payment: Coin<S> = paid.take(fee)?;
+                fee_payment.burn();
+            }
+
+            self.block_gas = self.block_gas.saturating_add(gas);
+        }
+
+        self.inner.call(call)
+    }
+}
+
+impl<S, T: ConvertSdkTx> ConvertSdkTx for DynamicFeePlugin<S, T> {
+    type Output = T::Output;
+
+    fn convert(&self, sdk_tx: &SdkTx) -> Result<T::Output> {
+        self.inner.convert(sdk_tx)
+    }
+}
+
+impl<S, T> Deref for DynamicFeePlugin<S, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<S, T> DerefMut for DynamicFeePlugin<S, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+// TODO: Remove dependency on ABCI for this otherwise-pure plugin.
+#[cfg(feature = "abci")]
+mod abci {
+    use super::super::{BeginBlockCtx, EndBlockCtx, InitChainCtx};
+    use super::*;
+    use crate::abci::{BeginBlock, EndBlock, InitChain};
+
+    impl<S, T> BeginBlock for DynamicFeePlugin<S, T>
+    where
+        S: Symbol,
+        T: BeginBlock + State,
+    {
+        fn begin_block(&mut self, ctx: &BeginBlockCtx) -> Result<()> {
+            self.inner.begin_block(ctx)
+        }
+    }
+
+    impl<S, T> EndBlock for DynamicFeePlugin<S, T>
+    where
+        S: Symbol,
+        T: EndBlock + State,
+    {
+        fn end_block(&mut self, ctx: &EndBlockCtx) -> Result<()> {
+            self.adjust_base_fee()?;
+            self.inner.end_block(ctx)
+        }
+    }
+
+    impl<S, T> InitChain for DynamicFeePlugin<S, T>
+    where
+        S: Symbol,
+        T: InitChain + State + Call,
+    {
+        fn init_chain(&mut self, ctx: &InitChainCtx) -> Result<()> {
+            self.inner.init_chain(ctx)
+        }
+    }
+
+    impl<S, T> crate::abci::AbciQuery for DynamicFeePlugin<S, T>
+    where
+        S: Symbol,
+        T: crate::abci::AbciQuery + State + Call,
+    {
+        fn abci_query(
+            &self,
+            request: &tendermint_proto::v0_34::abci::RequestQuery,
+        ) -> Result<tendermint_proto::v0_34::abci::ResponseQuery> {
+            self.inner.abci_query(request)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<S, T> crate::abci::ExtendVote for DynamicFeePlugin<S, T>
+    where
+        S: Symbol,
+        T: crate::abci::ExtendVote + State + Call,
+    {
+        fn extend_vote(
+            &mut self,
+            req: &crate::abci::messages_plus::RequestExtendVote,
+        ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+            self.inner.extend_vote(req)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<S, T> crate::abci::VerifyVoteExtension for DynamicFeePlugin<S, T>
+    where
+        S: Symbol,
+        T: crate::abci::VerifyVoteExtension + State + Call,
+    {
+        fn verify_vote_extension(
+            &self,
+            req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+            self.inner.verify_vote_extension(req)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "abci"))]
+mod tests {
+    use super::*;
+    use crate::abci::EndBlock;
+    use crate::context::Context;
+    use crate::encoding::Decode;
+    use crate::orga;
+    use rust_decimal_macros::dec;
+
+    #[orga]
+    #[derive(Debug, Clone)]
+    struct Simp;
+    impl Symbol for Simp {
+        const INDEX: u8 = 0;
+        const NAME: &'static str = "SIMP";
+    }
+
+    #[derive(State, Encode, Decode, Default)]
+    struct Counter {
+        pub count: u64,
+    }
+
+    #[derive(Debug, Encode, Decode)]
+    enum CounterCall {
+        Increment([u8; 99]),
+    }
+
+    impl Call for Counter {
+        type Call = CounterCall;
+
+        fn call(&mut self, _call: Self::Call) -> Result<()> {
+            self.count += 1;
+            Ok(())
+        }
+    }
+
+    /// The gas used by each [CounterCall].
+    const GAS: u64 = 100;
+
+    fn run_block(plugin: &mut DynamicFeePlugin<Simp, Counter>, calls: usize) -> Result<()> {
+        for _ in 0..calls {
+            let fee = plugin.min_fee(GAS)?;
+            Context::resolve::<Paid>().unwrap().give::<Simp, _>(fee)?;
+            plugin.call(CounterCall::Increment([0; 99]))?;
+        }
+
+        plugin.end_block(&Default::default())
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn base_fee_converges() -> Result<()> {
+        let mut plugin: DynamicFeePlugin<Simp, Counter> = DynamicFeePlugin {
+            min_base_fee: 1.into(),
+            max_block_gas: 1_000,
+            target_fullness: dec!(0.5).into(),
+            max_change: dec!(0.125).into(),
+            ..Default::default()
+        };
+        plugin.set_base_fee(10.into());
+        Context::add(Paid::default());
+
+        // full blocks raise the base fee by the maximum change
+        run_block(&mut plugin, 10)?;
+        assert_eq!(plugin.base_fee()?, Decimal::from(dec!(11.25)));
+        run_block(&mut plugin, 10)?;
+        assert_eq!(plugin.base_fee()?, Decimal::from(dec!(12.65625)));
+        assert_eq!(plugin.min_fee(GAS)?, 1266);
+
+        // blocks above the target raise it proportionally
+        run_block(&mut plugin, 7)?;
+        assert_eq!(plugin.base_fee()?, Decimal::from(dec!(13.2890625)));
+
+        // blocks at the target leave it unchanged
+        for _ in 0..3 {
+            run_block(&mut plugin, 5)?;
+            assert_eq!(plugin.base_fee()?, Decimal::from(dec!(13.2890625)));
+        }
+
+        // blocks below the target lower it, down to the minimum
+        let mut last = plugin.base_fee()?;
+        for _ in 0..30 {
+            run_block(&mut plugin, 1)?;
+            let base_fee = plugin.base_fee()?;
+            assert!(base_fee < last || base_fee == Decimal::one());
+            last = base_fee;
+        }
+        assert_eq!(plugin.base_fee()?, Decimal::one());
+        assert_eq!(plugin.inner.count, 72);
+
+        // calls offering less than the base fee are rejected
+        Context::resolve::<Paid>()
+            .unwrap()
+            .give::<Simp, _>(GAS - 1)?;
+        assert!(plugin.call(CounterCall::Increment([0; 99])).is_err());
+        assert_eq!(plugin.inner.count, 72);
+
+        Context::remove::<Paid>();
+
+        Ok(())
+    }
+}
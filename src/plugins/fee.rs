@@ -6,6 +6,7 @@ use super::Paid;
 use crate::call::Call;
 use crate::coins::{Coin, Symbol};
 use crate::context::{Context, GetContext};
+use crate::gas::Gas;
 
 use crate::query::Query;
 use crate::state::State;
@@ -17,8 +18,15 @@ use std::ops::{Deref, DerefMut};
 // TODO: This should be configurable, part of the fee plugin's state.
 pub const MIN_FEE: u64 = 10_000;
 
+/// Maximum gas which may be consumed by the `paid` calls of a transaction.
+// TODO: This should be configurable, part of the fee plugin's state.
+pub const GAS_LIMIT: u64 = 10_000_000;
+
 /// A plugin which requires that at least `MIN_FEE` units of symbol `S` are paid
 /// into the [Paid] context by the `payer` call before running the `paid` call.
+///
+/// The `paid` call is metered by a [Gas] context limited to `GAS_LIMIT`, which
+/// is shared by all `paid` calls of a batch.
 #[orga(skip(Call, Query))]
 pub struct FeePlugin<S, T> {
     #[state(skip)]
@@ -40,6 +48,15 @@ impl<S: Symbol, T: Call + State> Call for FeePlugin<S, T> {
     type Call = T::Call;
 
     fn call(&mut self, call: Self::Call) -> Result<()> {
+        self.pay_fee()?;
+        self.inner.call(call)
+    }
+}
+
+impl<S: Symbol, T: Call + State> FeePlugin<S, T> {
+    /// Burns the minimum fee from the [Paid] context, and adds the [Gas]
+    /// context for the call.
+    fn pay_fee(&mut self) -> Result<()> {
         let paid = self
             .context::<Paid>()
             .ok_or_else(|| Error::Coins("Minimum fee not paid".into()))?;
@@ -49,7 +66,11 @@ impl<S: Symbol, T: Call + State> Call for FeePlugin<S, T> {
             fee_payment.burn();
         }
 
-        self.inner.call(call)
+        if !paid.running_payer && Context::resolve::<Gas>().is_none() {
+            Context::add(Gas::new(GAS_LIMIT));
+        }
+
+        Ok(())
     }
 }
 
@@ -88,7 +109,14 @@ impl<S, T> DerefMut for FeePlugin<S, T> {
 mod abci {
     use super::super::{BeginBlockCtx, EndBlockCtx, InitChainCtx};
     use super::*;
-    use crate::abci::{BeginBlock, EndBlock, InitChain};
+    use crate::abci::{BeginBlock, CheckTx, EndBlock, InitChain};
+
+    impl<S: Symbol, T: Call + State> CheckTx for FeePlugin<S, T> {
+        fn check_tx(&mut self, call: Self::Call) -> Result<()> {
+            self.pay_fee()?;
+            self.inner.check_tx(call)
+        }
+    }
 
     impl<S, T> BeginBlock for FeePlugin<S, T>
     where
@@ -132,4 +160,32 @@ mod abci {
             self.inner.abci_query(request)
         }
     }
+
+    #[cfg(feature = "abci-plus")]
+    impl<S, T> crate::abci::ExtendVote for FeePlugin<S, T>
+    where
+        S: Symbol,
+        T: crate::abci::ExtendVote + State + Call,
+    {
+        fn extend_vote(
+            &mut self,
+            req: &crate::abci::messages_plus::RequestExtendVote,
+        ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+            self.inner.extend_vote(req)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<S, T> crate::abci::VerifyVoteExtension for FeePlugin<S, T>
+    where
+        S: Symbol,
+        T: crate::abci::VerifyVoteExtension + State + Call,
+    {
+        fn verify_vote_extension(
+            &self,
+            req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+            self.inner.verify_vote_extension(req)
+        }
+    }
 }
@@ -7,6 +7,9 @@ pub use signer::*;
 mod nonce;
 pub use nonce::*;
 
+mod timeout_height;
+pub use timeout_height::*;
+
 mod abci;
 pub use abci::*;
 
@@ -16,6 +19,9 @@ pub use payable::*;
 mod fee;
 pub use fee::*;
 
+mod dynamic_fee;
+pub use dynamic_fee::*;
+
 pub mod chain_commitment;
 pub use chain_commitment::{ChainCommitmentPlugin, ChainId};
 
@@ -42,6 +48,7 @@ pub type DefaultPlugins<S, T> = type_chain! {
     SignerPlugin<_>,
     ChainCommitmentPlugin<_>,
     NoncePlugin<_>,
+    TimeoutHeightPlugin<_>,
     PayablePlugin<_>,
     FeePlugin<S, _>,
     T
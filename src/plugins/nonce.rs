@@ -84,6 +84,18 @@ where
     type Call = NonceCall<T::Call>;
 
     fn call(&mut self, call: Self::Call) -> Result<()> {
+        self.check_nonce(call.nonce)?;
+        self.inner.call(call.inner_call)
+    }
+}
+
+impl<T> NoncePlugin<T>
+where
+    T: Call + State,
+{
+    /// Checks the call's nonce against the signer's last nonce, and records
+    /// it if it is valid.
+    fn check_nonce(&mut self, nonce: Option<u64>) -> Result<()> {
         let signer = match self.context::<Signer>() {
             Some(signer) => signer,
             None => {
@@ -93,7 +105,7 @@ where
             }
         };
 
-        match (signer.signer, call.nonce) {
+        match (signer.signer, nonce) {
             // Happy paths:
             (Some(pub_key), Some(nonce)) => {
                 let mut expected_nonce = self.map.entry(pub_key)?.or_default()?;
@@ -114,9 +126,9 @@ where
                 }
 
                 *expected_nonce = nonce;
-                self.inner.call(call.inner_call)
+                Ok(())
             }
-            (None, None) => self.inner.call(call.inner_call),
+            (None, None) => Ok(()),
 
             // Unhappy paths:
             (Some(_), None) => Err(Error::Nonce("Signed calls must include a nonce".into())),
@@ -132,7 +144,14 @@ where
 mod abci {
     use super::super::{BeginBlockCtx, EndBlockCtx, InitChainCtx};
     use super::*;
-    use crate::abci::{BeginBlock, EndBlock, InitChain};
+    use crate::abci::{BeginBlock, CheckTx, EndBlock, InitChain};
+
+    impl<T: Call + State> CheckTx for NoncePlugin<T> {
+        fn check_tx(&mut self, call: Self::Call) -> Result<()> {
+            self.check_nonce(call.nonce)?;
+            self.inner.check_tx(call.inner_call)
+        }
+    }
 
     impl<T> BeginBlock for NoncePlugin<T>
     where
@@ -172,6 +191,32 @@ mod abci {
             self.inner.abci_query(request)
         }
     }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::ExtendVote for NoncePlugin<T>
+    where
+        T: crate::abci::ExtendVote + State + Call,
+    {
+        fn extend_vote(
+            &mut self,
+            req: &crate::abci::messages_plus::RequestExtendVote,
+        ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+            self.inner.extend_vote(req)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::VerifyVoteExtension for NoncePlugin<T>
+    where
+        T: crate::abci::VerifyVoteExtension + State + Call,
+    {
+        fn verify_vote_extension(
+            &self,
+            req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+            self.inner.verify_vote_extension(req)
+        }
+    }
 }
 
 #[cfg(test)]
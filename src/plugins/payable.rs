@@ -8,19 +8,33 @@ use crate::context::{Context, GetContext};
 
 use crate::encoding::{Decode, Encode};
 
-use crate::state::State;
+use crate::state::{transaction, State};
+use crate::store::Store;
 use crate::{Error, Result};
 use std::collections::HashMap;
 use std::convert::TryInto;
 
 const MAX_SUBCALL_LEN: u32 = 200_000;
+const MAX_BATCH_LEN: u32 = 64;
 
 /// A plugin which allows a pair of calls to be issued together, with the first
 /// call (`payer` call) loading funds into the [Paid] context for use in the
 /// second call (`paid` call).
+///
+/// The `paid` call runs in a scoped transaction: if it fails, its writes are
+/// rolled back while the writes of the `payer` call (e.g. fee payment) are
+/// still committed.
 #[orga(skip(Call))]
+#[state(allow_prefix_overlap)]
 pub struct PayablePlugin<T> {
+    /// The store the inner value is attached to, used to run the `paid` call
+    /// in a transaction. This shares the inner value's prefix and flushes no
+    /// bytes, so the plugin's storage layout is unchanged.
+    #[state(prefix(0))]
+    #[serde(skip)]
+    store: Store,
     /// The inner value.
+    #[state(prefix(0))]
     pub inner: T,
 }
 
@@ -85,8 +99,8 @@ impl Paid {
 /// A two-part call, where the `payer` call may load funds into the [Paid]
 /// context for use in the `paid` call.
 ///
-/// This two-call system will be replaced by a more flexible "multi-call" system
-/// in the future.
+/// To run several `paid` calls atomically with a single `payer` call, use a
+/// [BatchCall].
 #[derive(Debug)]
 pub struct PaidCall<T> {
     /// The `payer` call, which may load funds into the [Paid] context (e.g. to
@@ -97,87 +111,171 @@ pub struct PaidCall<T> {
     pub paid: T,
 }
 
+/// Writes a call prefixed with its encoded length.
+fn encode_subcall<T: Encode, W: std::io::Write>(call: &T, dest: &mut W) -> ed::Result<()> {
+    let bytes = call.encode()?;
+    let len: u32 = bytes
+        .len()
+        .try_into()
+        .map_err(|_| ed::Error::UnexpectedByte(0))?;
+    dest.write_all(&len.encode()?)?;
+    dest.write_all(&bytes)?;
+
+    Ok(())
+}
+
+/// Reads a call prefixed with its encoded length.
+fn decode_subcall<T: Decode, R: std::io::Read>(mut reader: R) -> ed::Result<T> {
+    let len = u32::decode(&mut reader)?;
+    if len > MAX_SUBCALL_LEN {
+        return Err(ed::Error::UnexpectedByte(32));
+    }
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes)?;
+
+    T::decode(&mut bytes.as_slice())
+}
+
 impl<T: Encode + std::fmt::Debug> Encode for PaidCall<T> {
     fn encoding_length(&self) -> ed::Result<usize> {
         Ok(self.payer.encoding_length()? + self.paid.encoding_length()? + 8)
     }
     fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> ed::Result<()> {
-        let payer_call_bytes = self.payer.encode()?;
-        let payer_call_len: u32 = payer_call_bytes
-            .len()
-            .try_into()
-            .map_err(|_| ed::Error::UnexpectedByte(0))?;
-        let paid_call_bytes = self.paid.encode()?;
-        let paid_call_len: u32 = paid_call_bytes
+        encode_subcall(&self.payer, dest)?;
+        encode_subcall(&self.paid, dest)?;
+
+        Ok(())
+    }
+}
+
+impl<T: Decode + std::fmt::Debug> Decode for PaidCall<T> {
+    fn decode<R: std::io::Read>(mut reader: R) -> ed::Result<Self> {
+        Ok(Self {
+            payer: decode_subcall(&mut reader)?,
+            paid: decode_subcall(&mut reader)?,
+        })
+    }
+}
+
+/// A call made up of a `payer` call and a list of `paid` calls, which are run
+/// in order as a single atomic batch.
+///
+/// As with [PaidCall], funds loaded into the [Paid] context by the `payer` call
+/// are shared by all of the `paid` calls. If any `paid` call fails, the writes
+/// of the whole batch are rolled back while the writes of the `payer` call are
+/// still committed.
+#[derive(Debug)]
+pub struct BatchCall<T> {
+    /// The `payer` call, which may load funds into the [Paid] context.
+    pub payer: T,
+    /// The `paid` calls, which are run in order.
+    pub paid: Vec<T>,
+}
+
+impl<T: Encode + std::fmt::Debug> Encode for BatchCall<T> {
+    fn encoding_length(&self) -> ed::Result<usize> {
+        let mut len = self.payer.encoding_length()? + 8;
+        for call in self.paid.iter() {
+            len += call.encoding_length()? + 4;
+        }
+
+        Ok(len)
+    }
+
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> ed::Result<()> {
+        let batch_len: u32 = self
+            .paid
             .len()
             .try_into()
             .map_err(|_| ed::Error::UnexpectedByte(0))?;
 
-        dest.write_all(&payer_call_len.encode()?)?;
-        dest.write_all(&payer_call_bytes)?;
-        dest.write_all(&paid_call_len.encode()?)?;
-        dest.write_all(&paid_call_bytes)?;
+        encode_subcall(&self.payer, dest)?;
+        dest.write_all(&batch_len.encode()?)?;
+        for call in self.paid.iter() {
+            encode_subcall(call, dest)?;
+        }
 
         Ok(())
     }
 }
 
-impl<T: Decode + std::fmt::Debug> Decode for PaidCall<T> {
+impl<T: Decode + std::fmt::Debug> Decode for BatchCall<T> {
     fn decode<R: std::io::Read>(mut reader: R) -> ed::Result<Self> {
-        let payer_call_len = u32::decode(&mut reader)?;
-        if payer_call_len > MAX_SUBCALL_LEN {
+        let payer = decode_subcall(&mut reader)?;
+        let batch_len = u32::decode(&mut reader)?;
+        if batch_len > MAX_BATCH_LEN {
             return Err(ed::Error::UnexpectedByte(32));
         }
-        let mut payer_call_bytes = vec![0u8; payer_call_len as usize];
-        reader.read_exact(&mut payer_call_bytes)?;
-        let paid_call_len = u32::decode(&mut reader)?;
-        if payer_call_len > MAX_SUBCALL_LEN {
-            return Err(ed::Error::UnexpectedByte(32));
-        }
-        let mut paid_call_bytes = vec![0u8; paid_call_len as usize];
-        reader.read_exact(&mut paid_call_bytes)?;
-        Ok(Self {
-            payer: T::decode(&mut payer_call_bytes.as_slice())?,
-            paid: T::decode(&mut paid_call_bytes.as_slice())?,
-        })
+        let paid = (0..batch_len)
+            .map(|_| decode_subcall(&mut reader))
+            .collect::<ed::Result<_>>()?;
+
+        Ok(Self { payer, paid })
     }
 }
 
-/// A payable call, which may be either a [PaidCall] or the inner type's call
-/// (unpaid).
+/// A payable call, which may be either a [PaidCall], the inner type's call
+/// (unpaid), or a [BatchCall].
 #[derive(Debug, Encode, Decode)]
 pub enum PayableCall<T> {
     /// A paid call.
     Paid(PaidCall<T>),
     /// An unpaid call, passed through to the inner value.
     Unpaid(T),
+    /// A paid batch of calls, which succeed or fail together.
+    Batch(BatchCall<T>),
 }
 
 impl<T> Call for PayablePlugin<T>
 where
-    T: Call + State,
+    T: Call + State + Default,
 {
     type Call = PayableCall<T::Call>;
 
     fn call(&mut self, call: Self::Call) -> Result<()> {
+        self.call_with(call, T::call)
+    }
+}
+
+impl<T> PayablePlugin<T>
+where
+    T: Call + State + Default,
+{
+    /// Runs the call, passing the unpaid or paid calls to `dispatch`.
+    fn call_with(
+        &mut self,
+        call: PayableCall<T::Call>,
+        dispatch: fn(&mut T, T::Call) -> Result<()>,
+    ) -> Result<()> {
         Context::remove::<Paid>();
         match call {
-            PayableCall::Unpaid(call) => self.inner.call(call),
-            PayableCall::Paid(calls) => {
-                let ctx = Paid {
-                    running_payer: true,
-                    ..Default::default()
-                };
-                Context::add(ctx);
-                self.inner.call(calls.payer)?;
-
-                let ctx = self.context::<Paid>().unwrap();
-                ctx.running_payer = false;
-                self.inner.call(calls.paid)?;
-                Ok(())
-            }
+            PayableCall::Unpaid(call) => dispatch(&mut self.inner, call),
+            PayableCall::Paid(calls) => self.call_paid(calls.payer, vec![calls.paid], dispatch),
+            PayableCall::Batch(calls) => self.call_paid(calls.payer, calls.paid, dispatch),
         }
     }
+
+    /// Runs the `payer` call, then passes the `paid` calls in order to
+    /// `dispatch` in a single transaction.
+    fn call_paid(
+        &mut self,
+        payer: T::Call,
+        paid: Vec<T::Call>,
+        dispatch: fn(&mut T, T::Call) -> Result<()>,
+    ) -> Result<()> {
+        let ctx = Paid {
+            running_payer: true,
+            ..Default::default()
+        };
+        Context::add(ctx);
+        self.inner.call(payer)?;
+
+        let ctx = self.context::<Paid>().unwrap();
+        ctx.running_payer = false;
+        transaction(&mut self.inner, &self.store, |inner| {
+            paid.into_iter().try_for_each(|call| dispatch(inner, call))
+        })
+    }
 }
 
 impl<T> ConvertSdkTx for PayablePlugin<T>
@@ -196,7 +294,16 @@ where
 mod abci {
     use super::super::*;
     use super::*;
-    use crate::abci::{BeginBlock, EndBlock, InitChain};
+    use crate::abci::{BeginBlock, CheckTx, EndBlock, InitChain};
+
+    /// The `payer` call is always run in full, so that fees are paid in
+    /// `CheckTx`, while the `paid` calls are passed to the inner value's
+    /// [CheckTx] implementation.
+    impl<T: Call + State + Default> CheckTx for PayablePlugin<T> {
+        fn check_tx(&mut self, call: Self::Call) -> Result<()> {
+            self.call_with(call, T::check_tx)
+        }
+    }
 
     impl<T> BeginBlock for PayablePlugin<T>
     where
@@ -236,4 +343,172 @@ mod abci {
             self.inner.abci_query(request)
         }
     }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::ExtendVote for PayablePlugin<T>
+    where
+        T: crate::abci::ExtendVote + State + Call,
+    {
+        fn extend_vote(
+            &mut self,
+            req: &crate::abci::messages_plus::RequestExtendVote,
+        ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+            self.inner.extend_vote(req)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::VerifyVoteExtension for PayablePlugin<T>
+    where
+        T: crate::abci::VerifyVoteExtension + State + Call,
+    {
+        fn verify_vote_extension(
+            &self,
+            req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+            self.inner.verify_vote_extension(req)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::Map;
+    use serial_test::serial;
+
+    #[derive(State, Default)]
+    struct Counter {
+        count: u64,
+        writes: Map<u8, u64>,
+    }
+
+    #[derive(Debug, Encode, Decode)]
+    enum CounterCall {
+        Write(u8),
+        WriteAndFail(u8),
+    }
+
+    impl Call for Counter {
+        type Call = CounterCall;
+
+        fn call(&mut self, call: Self::Call) -> Result<()> {
+            self.count += 1;
+            match call {
+                CounterCall::Write(key) => self.writes.insert(key, self.count),
+                CounterCall::WriteAndFail(key) => {
+                    self.writes.insert(key, self.count)?;
+                    Err(Error::App("Paid call failed".into()))
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn failed_paid_call_rolls_back() -> Result<()> {
+        let store = Store::with_map_store();
+        let mut plugin: PayablePlugin<Counter> = PayablePlugin::default();
+        plugin.attach(store.clone())?;
+
+        let res = plugin.call(PayableCall::Paid(PaidCall {
+            payer: CounterCall::Write(0),
+            paid: CounterCall::WriteAndFail(1),
+        }));
+        assert!(res.is_err());
+        assert_eq!(plugin.inner.count, 1);
+        assert_eq!(*plugin.inner.writes.get(0)?.unwrap(), 1);
+        assert!(plugin.inner.writes.get(1)?.is_none());
+
+        plugin.call(PayableCall::Paid(PaidCall {
+            payer: CounterCall::Write(2),
+            paid: CounterCall::Write(3),
+        }))?;
+        assert_eq!(plugin.inner.count, 3);
+
+        let mut bytes = vec![];
+        plugin.flush(&mut bytes)?;
+        let plugin: PayablePlugin<Counter> = PayablePlugin::load(store, &mut bytes.as_slice())?;
+        assert_eq!(plugin.inner.count, 3);
+        assert_eq!(*plugin.inner.writes.get(0)?.unwrap(), 1);
+        assert!(plugin.inner.writes.get(1)?.is_none());
+        assert_eq!(*plugin.inner.writes.get(2)?.unwrap(), 2);
+        assert_eq!(*plugin.inner.writes.get(3)?.unwrap(), 3);
+
+        Context::remove::<Paid>();
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn failed_batch_call_rolls_back() -> Result<()> {
+        let store = Store::with_map_store();
+        let mut plugin: PayablePlugin<Counter> = PayablePlugin::default();
+        plugin.attach(store.clone())?;
+
+        let res = plugin.call(PayableCall::Batch(BatchCall {
+            payer: CounterCall::Write(0),
+            paid: vec![
+                CounterCall::Write(1),
+                CounterCall::Write(2),
+                CounterCall::WriteAndFail(3),
+            ],
+        }));
+        assert!(res.is_err());
+        assert_eq!(plugin.inner.count, 1);
+        assert_eq!(*plugin.inner.writes.get(0)?.unwrap(), 1);
+        assert!(plugin.inner.writes.get(1)?.is_none());
+        assert!(plugin.inner.writes.get(2)?.is_none());
+        assert!(plugin.inner.writes.get(3)?.is_none());
+
+        plugin.call(PayableCall::Batch(BatchCall {
+            payer: CounterCall::Write(4),
+            paid: vec![CounterCall::Write(5), CounterCall::Write(6)],
+        }))?;
+        assert_eq!(plugin.inner.count, 4);
+
+        let mut bytes = vec![];
+        plugin.flush(&mut bytes)?;
+        let plugin: PayablePlugin<Counter> = PayablePlugin::load(store, &mut bytes.as_slice())?;
+        assert_eq!(plugin.inner.count, 4);
+        assert!(plugin.inner.writes.get(1)?.is_none());
+        assert_eq!(*plugin.inner.writes.get(4)?.unwrap(), 2);
+        assert_eq!(*plugin.inner.writes.get(5)?.unwrap(), 3);
+        assert_eq!(*plugin.inner.writes.get(6)?.unwrap(), 4);
+
+        Context::remove::<Paid>();
+
+        Ok(())
+    }
+
+    #[test]
+    fn batch_call_encoding() -> Result<()> {
+        let call = BatchCall {
+            payer: CounterCall::Write(0),
+            paid: vec![CounterCall::Write(1), CounterCall::WriteAndFail(2)],
+        };
+        let bytes = call.encode()?;
+        assert_eq!(bytes.len(), call.encoding_length()?);
+        assert_eq!(
+            bytes,
+            vec![0, 0, 0, 2, 0, 0, 0, 0, 0, 2, 0, 0, 0, 2, 0, 1, 0, 0, 0, 2, 1, 2]
+        );
+
+        let decoded = BatchCall::<CounterCall>::decode(bytes.as_slice())?;
+        assert!(matches!(decoded.payer, CounterCall::Write(0)));
+        assert!(matches!(
+            decoded.paid.as_slice(),
+            [CounterCall::Write(1), CounterCall::WriteAndFail(2)]
+        ));
+
+        // batches longer than the maximum are rejected
+        let call = BatchCall {
+            payer: CounterCall::Write(0),
+            paid: (0..=MAX_BATCH_LEN).map(|_| CounterCall::Write(1)).collect(),
+        };
+        assert!(BatchCall::<CounterCall>::decode(call.encode()?.as_slice()).is_err());
+
+        Ok(())
+    }
 }
@@ -1,6 +1,6 @@
 //! Low-level query operations.
 use crate::call::Call;
-use crate::describe::Describe;
+use crate::describe::{resolve_loaded_path, Describe};
 use crate::encoding::{Decode, Encode};
 use crate::migrate::Migrate;
 use crate::orga;
@@ -57,6 +57,11 @@ impl<T: Call> Call for QueryPlugin<T> {
     }
 }
 
+/// Prefixes ABCI query paths which resolve a path into the state of the inner
+/// value with [QueryPlugin::resolve_path]. The rest of the query path is the
+/// JSON array of path segments, e.g. `/state/["map",3]`.
+pub const STATE_QUERY_PATH: &str = "/state/";
+
 /// The query type for [QueryPlugin].
 #[derive(Clone, Encode, Decode, Educe)]
 #[educe(Debug)]
@@ -112,6 +117,21 @@ impl<T: State + Describe> Describe for QueryPlugin<T> {
     }
 }
 
+impl<T: State + Describe + 'static> QueryPlugin<T> {
+    /// Resolves a path into the state tree of the inner value, returning the
+    /// value at the end of the path as JSON. See
+    /// [resolve_path](crate::describe::resolve_path) for the format of the
+    /// path.
+    pub fn resolve_path(&self, path: &[serde_json::Value]) -> Result<serde_json::Value> {
+        resolve_loaded_path(
+            &T::describe(),
+            self.store.clone(),
+            &*self.inner.borrow(),
+            path,
+        )
+    }
+}
+
 // TODO: Remove dependency on ABCI for this otherwise-pure plugin.
 #[cfg(feature = "abci")]
 mod abci {
@@ -119,8 +139,16 @@ mod abci {
 
     use super::super::{BeginBlockCtx, EndBlockCtx, InitChainCtx};
     use super::*;
-    use crate::abci::{BeginBlock, EndBlock, InitChain};
+    use crate::abci::{AbciQuery, BeginBlock, CheckTx, EndBlock, InitChain};
     use crate::state::State;
+    use crate::Error;
+    use tendermint_proto::v0_34::abci::{RequestQuery, ResponseQuery};
+
+    impl<T: Call> CheckTx for QueryPlugin<T> {
+        fn check_tx(&mut self, call: Self::Call) -> Result<()> {
+            self.inner.get_mut().check_tx(call)
+        }
+    }
 
     impl<T> BeginBlock for QueryPlugin<T>
     where
@@ -149,17 +177,66 @@ mod abci {
         }
     }
 
-    impl<T> crate::abci::AbciQuery for QueryPlugin<T>
+    impl<T> AbciQuery for QueryPlugin<T>
     where
-        T: crate::abci::AbciQuery + State + Call,
+        T: AbciQuery + State + Call,
     {
-        fn abci_query(
-            &self,
-            request: &tendermint_proto::v0_34::abci::RequestQuery,
-        ) -> Result<tendermint_proto::v0_34::abci::ResponseQuery> {
+        default fn abci_query(&self, request: &RequestQuery) -> Result<ResponseQuery> {
             self.inner.borrow().deref().abci_query(request)
         }
     }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::ExtendVote for QueryPlugin<T>
+    where
+        T: crate::abci::ExtendVote + State + Call,
+    {
+        fn extend_vote(
+            &mut self,
+            req: &crate::abci::messages_plus::RequestExtendVote,
+        ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+            self.inner.borrow_mut().deref_mut().extend_vote(req)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::VerifyVoteExtension for QueryPlugin<T>
+    where
+        T: crate::abci::VerifyVoteExtension + State + Call,
+    {
+        fn verify_vote_extension(
+            &self,
+            req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+            self.inner.borrow().deref().verify_vote_extension(req)
+        }
+    }
+
+    /// Queries with paths beginning with [STATE_QUERY_PATH] respond with the
+    /// JSON resolved by [QueryPlugin::resolve_path]. Other queries are passed
+    /// along to the inner value.
+    impl<T> AbciQuery for QueryPlugin<T>
+    where
+        T: AbciQuery + State + Call + Describe + 'static,
+    {
+        fn abci_query(&self, request: &RequestQuery) -> Result<ResponseQuery> {
+            let path = match request.path.strip_prefix(STATE_QUERY_PATH) {
+                Some(path) => path,
+                None => return self.inner.borrow().deref().abci_query(request),
+            };
+
+            let path: Vec<serde_json::Value> = serde_json::from_str(path)
+                .map_err(|err| Error::Query(format!("Invalid state path: {}", err)))?;
+            let value = self.resolve_path(&path)?;
+
+            Ok(ResponseQuery {
+                code: 0,
+                height: request.height,
+                value: value.to_string().into_bytes().into(),
+                ..Default::default()
+            })
+        }
+    }
 }
 
 #[cfg(test)]
@@ -167,7 +244,7 @@ mod tests {
     use super::*;
     use crate::call::build_call;
     use crate::call::FieldCall;
-    use crate::query::FieldQuery;
+    use crate::query::{FieldQuery, Item};
     use crate::state::State;
 
     #[derive(State, FieldCall, Default, Debug)]
@@ -246,6 +323,23 @@ mod tests {
         }
     }
 
+    #[derive(State, Default, FieldQuery)]
+    pub struct Primitives {
+        pub balance: i64,
+        pub name: String,
+        pub id: [u8; 4],
+    }
+
+    #[test]
+    fn primitive_field_query() -> Result<()> {
+        let prims = Primitives::default();
+        prims.query(Item::Field(PrimitivesFieldQuery::Balance(())))?;
+        prims.query(Item::Field(PrimitivesFieldQuery::Name(())))?;
+        prims.query(Item::Field(PrimitivesFieldQuery::Id(())))?;
+
+        Ok(())
+    }
+
     #[test]
     fn call_sim() -> Result<()> {
         let mut bloop = Bloop::default();
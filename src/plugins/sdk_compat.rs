@@ -4,7 +4,8 @@ use orga_macros::orga;
 use crate::call::Call as CallTrait;
 use crate::coins::{Address, Symbol};
 
-use crate::encoding::{Decode, Encode};
+use crate::encoding::{Decode, Encode, LimitedReader};
+use std::io::Read;
 
 use crate::migrate::MigrateFrom;
 use crate::state::State;
@@ -63,13 +64,9 @@ impl<T: Encode> Encode for Call<T> {
 }
 
 impl<T: Decode> Decode for Call<T> {
-    fn decode<R: std::io::Read>(mut reader: R) -> ed::Result<Self> {
+    fn decode<R: std::io::Read>(reader: R) -> ed::Result<Self> {
         let mut bytes = vec![];
-        reader.read_to_end(&mut bytes)?;
-
-        if bytes.len() > MAX_CALL_SIZE {
-            return Err(ed::Error::UnexpectedByte(0));
-        }
+        LimitedReader::new(reader, MAX_CALL_SIZE).read_to_end(&mut bytes)?;
 
         match bytes.first() {
             Some(&NATIVE_CALL_FLAG) => {
@@ -90,7 +87,7 @@ impl<T: Decode> Decode for Call<T> {
 
 pub mod sdk {
     //! Cosmos SDK types.
-    use super::{Address, Decode, Encode, Error, Result, MAX_CALL_SIZE};
+    use super::{Address, Decode, Encode, Error, LimitedReader, Read, Result, MAX_CALL_SIZE};
     use cosmrs::proto::cosmos::tx::v1beta1::Tx as ProtoTx;
     use prost::Message;
     use serde::{Deserialize, Serialize};
@@ -140,11 +137,11 @@ pub mod sdk {
     }
 
     impl Decode for Tx {
-        fn decode<R: std::io::Read>(mut reader: R) -> ed::Result<Self> {
+        fn decode<R: std::io::Read>(reader: R) -> ed::Result<Self> {
             let mut bytes = Vec::with_capacity(MAX_CALL_SIZE);
-            reader.read_to_end(&mut bytes)?;
+            LimitedReader::new(reader, MAX_CALL_SIZE).read_to_end(&mut bytes)?;
 
-            if bytes.len() > MAX_CALL_SIZE || bytes.is_empty() {
+            if bytes.is_empty() {
                 return Err(ed::Error::UnexpectedByte(0));
             }
 
@@ -276,6 +273,25 @@ pub mod sdk {
             Ok(sig_arr)
         }
 
+        /// Returns the memo of this transaction.
+        pub fn memo(&self) -> &str {
+            match self {
+                Tx::Amino(tx) => tx.memo.as_str(),
+                Tx::Protobuf(tx) => tx.body.memo.as_str(),
+            }
+        }
+
+        /// Returns the timeout height of this transaction, or zero if it has
+        /// none.
+        ///
+        /// Amino transactions do not include a timeout height.
+        pub fn timeout_height(&self) -> u64 {
+            match self {
+                Tx::Amino(_) => 0,
+                Tx::Protobuf(tx) => tx.body.timeout_height.value(),
+            }
+        }
+
         /// Returns the signature type of the sender of this transaction if it
         /// is an Amino transaction.
         ///
@@ -423,15 +439,24 @@ where
     type Call = Call<T::Call>;
 
     fn call(&mut self, call: Self::Call) -> Result<()> {
-        let call = match call {
-            Call::Native(call) => call,
-            Call::Sdk(tx) => self.inner.convert(&tx)?,
-        };
-
+        let call = self.native_call(call)?;
         self.inner.call(call)
     }
 }
 
+impl<S, T> SdkCompatPlugin<S, T>
+where
+    T: CallTrait + ConvertSdkTx<Output = T::Call>,
+{
+    /// Converts the call to the inner value's native call.
+    fn native_call(&self, call: Call<T::Call>) -> Result<T::Call> {
+        match call {
+            Call::Native(call) => Ok(call),
+            Call::Sdk(tx) => self.inner.convert(&tx),
+        }
+    }
+}
+
 impl<S: 'static, T: State> MigrateFrom<SdkCompatPluginV0<S, T>> for SdkCompatPluginV1<S, T> {
     fn migrate_from(_value: SdkCompatPluginV0<S, T>) -> Result<Self> {
         unreachable!()
@@ -442,7 +467,17 @@ impl<S: 'static, T: State> MigrateFrom<SdkCompatPluginV0<S, T>> for SdkCompatPlu
 mod abci {
     use super::super::*;
     use super::*;
-    use crate::abci::{BeginBlock, EndBlock, InitChain};
+    use crate::abci::{BeginBlock, CheckTx, EndBlock, InitChain};
+
+    impl<S: Symbol, T> CheckTx for SdkCompatPlugin<S, T>
+    where
+        T: CallTrait + State + ConvertSdkTx<Output = T::Call>,
+    {
+        fn check_tx(&mut self, call: Self::Call) -> Result<()> {
+            let call = self.native_call(call)?;
+            self.inner.check_tx(call)
+        }
+    }
 
     impl<S, T: State> BeginBlock for SdkCompatPlugin<S, T>
     where
@@ -482,4 +517,30 @@ mod abci {
             self.inner.abci_query(request)
         }
     }
+
+    #[cfg(feature = "abci-plus")]
+    impl<S, T> crate::abci::ExtendVote for SdkCompatPlugin<S, T>
+    where
+        T: crate::abci::ExtendVote + State + CallTrait,
+    {
+        fn extend_vote(
+            &mut self,
+            req: &crate::abci::messages_plus::RequestExtendVote,
+        ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+            self.inner.extend_vote(req)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<S, T> crate::abci::VerifyVoteExtension for SdkCompatPlugin<S, T>
+    where
+        T: crate::abci::VerifyVoteExtension + State + CallTrait,
+    {
+        fn verify_vote_extension(
+            &self,
+            req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+            self.inner.verify_vote_extension(req)
+        }
+    }
 }
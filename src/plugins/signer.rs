@@ -6,17 +6,17 @@ use super::{
 use crate::coins::Address;
 use crate::context::{Context, GetContext};
 
-use crate::encoding::{Decode, Encode};
+use crate::encoding::{Decode, Encode, LengthVec};
 use crate::orga;
 
 use crate::call::Call;
 use crate::state::State;
 use crate::{Error, Result};
 
-use secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1, SecretKey};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId, Signature};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
 use serde::Serialize;
 use std::ops::Deref;
-use tendermint_proto::v0_34::abci::{Event, EventAttribute};
 
 /// A plugin for enforcing valid signatures for calls.
 #[orga(skip(Call))]
@@ -32,9 +32,45 @@ pub struct Signer {
     pub signer: Option<Address>,
 }
 
+impl Signer {
+    /// Returns true if the current call was signed by the given address. The
+    /// addresses are compared in constant time.
+    pub fn is_signer(&self, address: Address) -> bool {
+        self.signer
+            .map(|signer| signer.ct_eq(&address))
+            .unwrap_or(false)
+    }
+}
+
+/// The maximum length of a call's memo, in bytes.
+pub const MAX_MEMO_LENGTH: usize = 256;
+
+/// Prepended to the signed bytes of calls which include a memo, so they can not
+/// be confused with the signed bytes of calls without one.
+const MEMO_SIGN_PREFIX: &[u8] = b"orga/memo";
+
+/// Prepended to the signed bytes of [SigType::Tagged] calls, followed by the
+/// encoded [KeyType], so the key type can not be swapped.
+const KEY_TYPE_SIGN_PREFIX: &[u8] = b"orga/keytype";
+
+/// A context for reading the memo attached to the current call.
+pub struct Memo {
+    /// The memo, if any.
+    pub memo: Option<String>,
+}
+
+/// Prefixes the encoding of a [SignerCall] which includes a memo. Calls without
+/// a memo keep the original encoding, which always begins with the `0` or `1`
+/// tag of the optional signature, so the two can not be confused.
+const MEMO_CALL_TAG: u8 = 2;
+
 /// A call which may claim to have been signed by the provided public key, which
 /// is verified by the implementation of [Call] for [SignerPlugin].
-#[derive(Debug, Encode, Decode)]
+///
+/// Calls without a memo are encoded as their signature, public key, signature
+/// type and call bytes. Calls with a memo are encoded as [MEMO_CALL_TAG] and
+/// the memo, followed by the same fields.
+#[derive(Debug)]
 pub struct SignerCall {
     /// The 64-byte signature.
     pub signature: Option<[u8; 64]>,
@@ -42,28 +78,133 @@ pub struct SignerCall {
     pub pubkey: Option<[u8; 33]>,
     /// The signature type.
     pub sigtype: SigType,
+    /// An optional free-form memo of at most [MAX_MEMO_LENGTH] bytes of UTF-8,
+    /// which is covered by the signature.
+    pub memo: Option<LengthVec<u16, u8>>,
     /// The raw call bytes. Decoded and executed following signature
     /// verification.
     pub call_bytes: Vec<u8>,
 }
 
+impl Encode for SignerCall {
+    fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> ed::Result<()> {
+        if let Some(memo) = &self.memo {
+            MEMO_CALL_TAG.encode_into(dest)?;
+            memo.encode_into(dest)?;
+        }
+        self.signature.encode_into(dest)?;
+        self.pubkey.encode_into(dest)?;
+        self.sigtype.encode_into(dest)?;
+        self.call_bytes.encode_into(dest)
+    }
+
+    fn encoding_length(&self) -> ed::Result<usize> {
+        let memo_len = match &self.memo {
+            Some(memo) => MEMO_CALL_TAG.encoding_length()? + memo.encoding_length()?,
+            None => 0,
+        };
+
+        Ok(memo_len
+            + self.signature.encoding_length()?
+            + self.pubkey.encoding_length()?
+            + self.sigtype.encoding_length()?
+            + self.call_bytes.encoding_length()?)
+    }
+}
+
+impl Decode for SignerCall {
+    fn decode<R: std::io::Read>(mut input: R) -> ed::Result<Self> {
+        use std::io::Read;
+
+        let tag = u8::decode(&mut input)?;
+        let (memo, signature) = if tag == MEMO_CALL_TAG {
+            let memo = Decode::decode(&mut input)?;
+            (Some(memo), Decode::decode(&mut input)?)
+        } else {
+            (None, Decode::decode([tag].as_slice().chain(&mut input))?)
+        };
+
+        Ok(SignerCall {
+            signature,
+            pubkey: Decode::decode(&mut input)?,
+            sigtype: Decode::decode(&mut input)?,
+            memo,
+            call_bytes: Decode::decode(&mut input)?,
+        })
+    }
+}
+
 impl SignerCall {
     /// Returns the address of the signer, based on the [SigType] and provided
     /// public key.
     pub fn address(&self) -> Result<Address> {
+        if let (SigType::Eth(recovery_id), None, Some(signature)) =
+            (&self.sigtype, self.pubkey, self.signature)
+        {
+            let pubkey = recover_eth_pubkey(&self.sign_bytes()?, &signature, *recovery_id)?;
+            return Ok(eth_address(&pubkey));
+        }
+
         let pubkey_bytes = self
             .pubkey
             .ok_or_else(|| Error::Signer("No pubkey specified".to_string()))?;
-        match &self.sigtype {
-            SigType::EthPersonalSign(_) => {
-                let pubkey = PublicKey::from_slice(pubkey_bytes.as_slice())?;
-                let pubkey_bytes = pubkey.serialize_uncompressed();
-                let mut eth_pubkey = [0; 64];
-                eth_pubkey.copy_from_slice(&pubkey_bytes[1..]);
-                Ok(Address::from_pubkey_eth(eth_pubkey))
+        let pubkey = PublicKey::from_slice(pubkey_bytes.as_slice())?;
+        Ok(self.sigtype.key_type().address(&pubkey))
+    }
+
+    /// Returns the bytes to be signed for a [SigType::Native],
+    /// [SigType::Adr36], [SigType::Eth] or [SigType::Tagged] call.
+    ///
+    /// Calls without a memo sign their call bytes directly. Calls with a memo
+    /// sign a prefix, the encoded memo, then the call bytes. Tagged calls
+    /// additionally sign a prefix and their encoded [KeyType] before these.
+    pub fn sign_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        if let SigType::Tagged(key_type) = &self.sigtype {
+            bytes.extend_from_slice(KEY_TYPE_SIGN_PREFIX);
+            key_type.encode_into(&mut bytes)?;
+        }
+
+        if let Some(memo) = &self.memo {
+            bytes.extend_from_slice(MEMO_SIGN_PREFIX);
+            memo.encode_into(&mut bytes)?;
+        }
+        bytes.extend_from_slice(&self.call_bytes);
+
+        Ok(bytes)
+    }
+
+    /// Returns the call's memo, checking that it is valid UTF-8 and does not
+    /// exceed [MAX_MEMO_LENGTH].
+    pub fn memo(&self) -> Result<Option<String>> {
+        let memo = match &self.sigtype {
+            SigType::Sdk(tx) | SigType::EthPersonalSign(tx) => {
+                if self.memo.is_some() {
+                    return Err(Error::Signer(
+                        "Memo must be set in the SDK transaction".into(),
+                    ));
+                }
+                Some(tx.memo().to_string()).filter(|memo| !memo.is_empty())
             }
-            _ => Ok(Address::from_pubkey(pubkey_bytes)),
+            _ => self
+                .memo
+                .clone()
+                .map(String::try_from)
+                .transpose()
+                .map_err(|_| Error::Signer("Memo is not valid UTF-8".into()))?,
+        };
+
+        if memo
+            .as_ref()
+            .map_or(false, |memo| memo.len() > MAX_MEMO_LENGTH)
+        {
+            return Err(Error::Signer(format!(
+                "Memo exceeds maximum length of {} bytes",
+                MAX_MEMO_LENGTH
+            )));
         }
+
+        Ok(memo)
     }
 }
 
@@ -80,6 +221,88 @@ pub enum SigType {
     /// Ethereum personal sign.
     #[skip]
     EthPersonalSign(Box<sdk_compat::sdk::Tx>),
+    /// Ethereum `personal_sign` (EIP-191) signature over the call's sign
+    /// bytes, with the given recovery id. The recovery id may be 0 or 1, or
+    /// Ethereum's `v` value of 27 or 28.
+    ///
+    /// The public key may be omitted, in which case it is recovered from the
+    /// signature.
+    Eth(u8),
+    /// ECDSA (secp256k1) signature over the call's sign bytes, which include
+    /// the given [KeyType] selecting how the signer's address is derived.
+    Tagged(KeyType),
+}
+
+impl SigType {
+    /// Returns the type of key which signs calls with this signature type.
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            SigType::Native | SigType::Adr36 | SigType::Sdk(_) => KeyType::Secp256k1,
+            SigType::EthPersonalSign(_) | SigType::Eth(_) => KeyType::EthSecp256k1,
+            SigType::Tagged(key_type) => *key_type,
+        }
+    }
+}
+
+/// The type of key which signed a call, which determines how the signer's
+/// address is derived from its public key.
+///
+/// New key types (e.g. ed25519) may be added as new variants, so the encoding
+/// of existing variants does not change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+pub enum KeyType {
+    /// A Cosmos-style secp256k1 key, with an address derived by
+    /// [Address::from_pubkey].
+    Secp256k1,
+    /// An Ethereum-style secp256k1 key, with an address derived by
+    /// [Address::from_pubkey_eth].
+    EthSecp256k1,
+}
+
+impl KeyType {
+    /// Returns the address for the given public key.
+    pub fn address(&self, pubkey: &PublicKey) -> Address {
+        match self {
+            KeyType::Secp256k1 => Address::from_pubkey(pubkey.serialize()),
+            KeyType::EthSecp256k1 => eth_address(pubkey),
+        }
+    }
+}
+
+/// Returns the digest signed by Ethereum's `personal_sign` (EIP-191): the
+/// Keccak-256 hash of a prefix, the message's length in decimal, then the
+/// message.
+pub fn eth_personal_sign_digest(msg: &[u8]) -> [u8; 32] {
+    use sha3::{Digest, Keccak256};
+    let mut hasher = Keccak256::new();
+    hasher.update(b"\x19Ethereum Signed Message:\n");
+    hasher.update(msg.len().to_string().as_bytes());
+    hasher.update(msg);
+    hasher.finalize().into()
+}
+
+/// Recovers the public key which created the given `personal_sign` signature
+/// over `msg`. The recovery id may be 0 or 1, or Ethereum's `v` value of 27 or
+/// 28.
+pub fn recover_eth_pubkey(msg: &[u8], signature: &[u8; 64], recovery_id: u8) -> Result<PublicKey> {
+    let recovery_id = match recovery_id {
+        0 | 1 => recovery_id,
+        27 | 28 => recovery_id - 27,
+        _ => return Err(Error::Signer("Invalid recovery id".into())),
+    };
+    let recovery_id = RecoveryId::from_i32(recovery_id as i32)?;
+    let signature = RecoverableSignature::from_compact(signature, recovery_id)?;
+    let msg = Message::from_digest_slice(&eth_personal_sign_digest(msg))?;
+
+    Ok(Secp256k1::verification_only().recover_ecdsa(&msg, &signature)?)
+}
+
+/// Returns the Ethereum-style address for the given public key.
+fn eth_address(pubkey: &PublicKey) -> Address {
+    let pubkey_bytes = pubkey.serialize_uncompressed();
+    let mut eth_pubkey = [0; 64];
+    eth_pubkey.copy_from_slice(&pubkey_bytes[1..]);
+    Address::from_pubkey_eth(eth_pubkey)
 }
 
 #[derive(Serialize)]
@@ -155,46 +378,27 @@ where
                 let secp = Secp256k1::verification_only();
                 let pubkey = PublicKey::from_slice(pubkey_bytes.as_slice())?;
 
-                let (msg, addr) = match &call.sigtype {
-                    SigType::Native => {
-                        let addr = Address::from_pubkey(*pubkey_bytes);
-                        let msg = Message::from_hashed_data::<sha256::Hash>(&call.call_bytes);
-                        (msg, addr)
+                let addr = call.sigtype.key_type().address(&pubkey);
+                let msg = match &call.sigtype {
+                    SigType::Native | SigType::Tagged(_) => {
+                        let bytes = call.sign_bytes()?;
+                        Message::from_hashed_data::<sha256::Hash>(bytes.as_slice())
                     }
                     SigType::Adr36 => {
-                        let addr = Address::from_pubkey(*pubkey_bytes);
-                        let bytes = adr36_bytes(call.call_bytes.as_slice(), addr)?;
-                        let msg = Message::from_hashed_data::<sha256::Hash>(bytes.as_slice());
-                        (msg, addr)
+                        let bytes = adr36_bytes(call.sign_bytes()?.as_slice(), addr)?;
+                        Message::from_hashed_data::<sha256::Hash>(bytes.as_slice())
                     }
                     SigType::Sdk(tx) => {
-                        let addr = Address::from_pubkey(*pubkey_bytes);
                         let bytes = self.sdk_sign_bytes(tx, addr)?;
-                        let msg = Message::from_hashed_data::<sha256::Hash>(bytes.as_slice());
-                        (msg, addr)
+                        Message::from_hashed_data::<sha256::Hash>(bytes.as_slice())
                     }
                     SigType::EthPersonalSign(tx) => {
-                        let pubkey_bytes = pubkey.serialize_uncompressed();
-                        let mut eth_pubkey = [0; 64];
-                        eth_pubkey.copy_from_slice(&pubkey_bytes[1..]);
-                        let addr = Address::from_pubkey_eth(eth_pubkey);
-
-                        let prefix = b"\x19Ethereum Signed Message:\n";
-                        let mut sdk_bytes = self.sdk_sign_bytes(tx, addr)?;
-                        let mut len_bytes = sdk_bytes.len().to_string().as_bytes().to_vec();
-
-                        let mut bytes = prefix.to_vec();
-                        bytes.append(&mut len_bytes);
-                        bytes.append(&mut sdk_bytes);
-
-                        use sha3::{Digest, Keccak256};
-                        let mut hasher = Keccak256::new();
-                        hasher.update(&bytes);
-                        let hash = hasher.finalize();
-
-                        let msg = Message::from_digest_slice(&hash)?;
-
-                        (msg, addr)
+                        let bytes = self.sdk_sign_bytes(tx, addr)?;
+                        Message::from_digest_slice(&eth_personal_sign_digest(&bytes))?
+                    }
+                    SigType::Eth(_) => {
+                        let bytes = call.sign_bytes()?;
+                        Message::from_digest_slice(&eth_personal_sign_digest(&bytes))?
                     }
                 };
 
@@ -204,6 +408,7 @@ where
 
                 Ok(Some(addr))
             }
+            (None, Some(_)) if matches!(call.sigtype, SigType::Eth(_)) => call.address().map(Some),
             (None, None) => Ok(None),
             _ => Err(Error::Signer("Malformed transaction".into())),
         }
@@ -217,30 +422,37 @@ where
     type Call = SignerCall;
 
     fn call(&mut self, call: Self::Call) -> Result<()> {
+        let inner_call = self.verify_call(call)?;
+        self.inner.call(inner_call)
+    }
+}
+
+impl<T: Call + State> SignerPlugin<T>
+where
+    T: GetNonce,
+{
+    /// Verifies the call's signature and sets the [Signer] and [Memo]
+    /// contexts, returning the decoded inner call.
+    fn verify_call(&mut self, call: SignerCall) -> Result<T::Call> {
         Context::remove::<Signer>();
+        Context::remove::<Memo>();
         let signer_ctx = Signer {
             signer: self.verify(&call)?,
         };
+        let memo_ctx = Memo { memo: call.memo()? };
 
         if let Some(signer) = signer_ctx.signer {
             let ev_ctx: &mut Events = self
                 .context::<Events>()
                 .ok_or_else(|| Error::Coins("No Events context available".into()))?;
 
-            ev_ctx.add(Event {
-                r#type: "message".to_string(),
-                attributes: vec![EventAttribute {
-                    key: "sender".into(),
-                    value: signer.to_string().into(),
-                    index: true,
-                }],
-            });
+            ev_ctx.emit("message").indexed_attr("sender", signer);
         }
 
         Context::add(signer_ctx);
+        Context::add(memo_ctx);
 
-        let inner_call = Decode::decode(call.call_bytes.as_slice())?;
-        self.inner.call(inner_call)
+        Ok(Decode::decode(call.call_bytes.as_slice())?)
     }
 }
 
@@ -260,6 +472,7 @@ pub(crate) fn sdk_to_signercall(sdk_tx: &SdkTx) -> Result<SignerCall> {
         signature: Some(signature),
         pubkey: Some(pubkey),
         sigtype,
+        memo: None,
         call_bytes: vec![],
     })
 }
@@ -491,7 +704,14 @@ pub fn load_keypair() -> Result<KeyPair> {
 mod abci {
     use super::super::{BeginBlockCtx, EndBlockCtx, InitChainCtx};
     use super::*;
-    use crate::abci::{BeginBlock, EndBlock, InitChain};
+    use crate::abci::{BeginBlock, CheckTx, EndBlock, InitChain};
+
+    impl<T: Call + State + GetNonce> CheckTx for SignerPlugin<T> {
+        fn check_tx(&mut self, call: Self::Call) -> Result<()> {
+            let inner_call = self.verify_call(call)?;
+            self.inner.check_tx(inner_call)
+        }
+    }
 
     impl<T> BeginBlock for SignerPlugin<T>
     where
@@ -531,6 +751,32 @@ mod abci {
             self.inner.abci_query(request)
         }
     }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::ExtendVote for SignerPlugin<T>
+    where
+        T: crate::abci::ExtendVote + State + Call,
+    {
+        fn extend_vote(
+            &mut self,
+            req: &crate::abci::messages_plus::RequestExtendVote,
+        ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+            self.inner.extend_vote(req)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::VerifyVoteExtension for SignerPlugin<T>
+    where
+        T: crate::abci::VerifyVoteExtension + State + Call,
+    {
+        fn verify_vote_extension(
+            &self,
+            req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+            self.inner.verify_vote_extension(req)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -538,6 +784,7 @@ mod tests {
 
     use super::*;
     use crate::call::Call;
+    use crate::client::wallet::{DerivedKey, EthWallet, Wallet};
     use crate::coins::{Address, Symbol};
     use crate::migrate::Migrate;
     use crate::plugins::{sdk_compat, ConvertSdkTx, SdkCompatPlugin};
@@ -617,4 +864,213 @@ mod tests {
         );
         Context::remove::<ChainId>();
     }
+
+    fn counter_plugin() -> SignerPlugin<Counter> {
+        SignerPlugin {
+            inner: Counter {
+                count: 0,
+                last_signer: Address::NULL,
+            },
+        }
+    }
+
+    fn signed_increment(memo: Option<&str>) -> Result<SignerCall> {
+        let call = <Counter as Call>::Call::Method(CounterMethodCall::Increment());
+        DerivedKey::new(b"alice")?.sign_with_memo(&call.encode()?, memo)
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn memo() -> Result<()> {
+        let mut state = counter_plugin();
+        Context::add(Events::default());
+
+        state.call(signed_increment(None)?)?;
+        assert_eq!(state.inner.count, 1);
+        assert_eq!(state.inner.last_signer, DerivedKey::address_for(b"alice")?);
+        assert_eq!(Context::resolve::<Memo>().unwrap().memo, None);
+
+        state.call(signed_increment(Some("hello"))?)?;
+        assert_eq!(state.inner.count, 2);
+        assert_eq!(
+            Context::resolve::<Memo>().unwrap().memo.as_deref(),
+            Some("hello")
+        );
+
+        // altering the memo invalidates the signature
+        let mut call = signed_increment(Some("hello"))?;
+        call.memo = Some("goodbye".try_into()?);
+        assert!(state.call(call).is_err());
+
+        // so does adding or removing one
+        let mut call = signed_increment(None)?;
+        call.memo = Some("hello".try_into()?);
+        assert!(state.call(call).is_err());
+        let mut call = signed_increment(Some("hello"))?;
+        call.memo = None;
+        assert!(state.call(call).is_err());
+        assert_eq!(state.inner.count, 2);
+
+        // memos over the maximum length are rejected
+        let memo = "x".repeat(MAX_MEMO_LENGTH + 1);
+        assert!(state.call(signed_increment(Some(&memo))?).is_err());
+        let memo = "x".repeat(MAX_MEMO_LENGTH);
+        state.call(signed_increment(Some(&memo))?)?;
+        assert_eq!(state.inner.count, 3);
+
+        Context::remove::<Memo>();
+        Context::remove::<Signer>();
+        Context::remove::<Events>();
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn memo_encoding() -> Result<()> {
+        let mut state = counter_plugin();
+        Context::add(Events::default());
+
+        // calls in the encoding from before memos were added still verify
+        let call = signed_increment(None)?;
+        let mut bytes = vec![];
+        call.signature.encode_into(&mut bytes)?;
+        call.pubkey.encode_into(&mut bytes)?;
+        call.sigtype.encode_into(&mut bytes)?;
+        bytes.extend_from_slice(&call.call_bytes);
+        assert_eq!(call.encode()?, bytes);
+        state.call(SignerCall::decode(bytes.as_slice())?)?;
+        assert_eq!(state.inner.count, 1);
+
+        let call = signed_increment(Some("hello"))?;
+        let bytes = call.encode()?;
+        assert_eq!(bytes[0], MEMO_CALL_TAG);
+        assert_eq!(bytes.len(), call.encoding_length()?);
+        state.call(SignerCall::decode(bytes.as_slice())?)?;
+        assert_eq!(state.inner.count, 2);
+        assert_eq!(
+            Context::resolve::<Memo>().unwrap().memo.as_deref(),
+            Some("hello")
+        );
+
+        Context::remove::<Memo>();
+        Context::remove::<Signer>();
+        Context::remove::<Events>();
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn tagged_key_types() -> Result<()> {
+        let mut state = counter_plugin();
+        Context::add(Events::default());
+
+        let key = DerivedKey::new(b"alice")?;
+        let call = <Counter as Call>::Call::Method(CounterMethodCall::Increment());
+        let call_bytes = call.encode()?;
+
+        let signed = key.sign_tagged(&call_bytes, KeyType::Secp256k1)?;
+        assert_eq!(signed.address()?, key.address());
+        state.call(signed)?;
+        assert_eq!(state.inner.last_signer, key.address());
+        assert_eq!(
+            Context::resolve::<Signer>().unwrap().signer,
+            Some(key.address())
+        );
+
+        let eth_addr = eth_address(&key.pubkey());
+        assert_ne!(eth_addr, key.address());
+        let signed = key.sign_tagged(&call_bytes, KeyType::EthSecp256k1)?;
+        assert_eq!(signed.address()?, eth_addr);
+        state.call(signed)?;
+        assert_eq!(state.inner.last_signer, eth_addr);
+        assert_eq!(state.inner.count, 2);
+
+        // the key type is covered by the signature
+        let mut signed = key.sign_tagged(&call_bytes, KeyType::Secp256k1)?;
+        signed.sigtype = SigType::Tagged(KeyType::EthSecp256k1);
+        assert!(state.call(signed).is_err());
+        let mut signed = key.sign_tagged(&call_bytes, KeyType::EthSecp256k1)?;
+        signed.sigtype = SigType::Native;
+        assert!(state.call(signed).is_err());
+        assert_eq!(state.inner.count, 2);
+
+        Context::remove::<Signer>();
+        Context::remove::<Memo>();
+        Context::remove::<Events>();
+
+        Ok(())
+    }
+
+    /// The secret key used in the web3.js documentation.
+    const ETH_PRIVKEY: [u8; 32] =
+        hex_literal::hex!("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318");
+
+    #[test]
+    fn eth_recovery() -> Result<()> {
+        let expected: Address =
+            hex_literal::hex!("2c7536e3605d9c16a7a3d7b1898e529396a65c23").into();
+
+        // signature of "Some data" from the web3.js documentation
+        let sig = hex_literal::hex!("b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c");
+        let mut signature = [0; 64];
+        signature.copy_from_slice(&sig[..64]);
+        assert_eq!(
+            eth_personal_sign_digest(b"Some data"),
+            hex_literal::hex!("1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655")
+        );
+
+        let pubkey = recover_eth_pubkey(b"Some data", &signature, sig[64])?;
+        assert_eq!(eth_address(&pubkey), expected);
+        let pubkey = recover_eth_pubkey(b"Some data", &signature, sig[64] - 27)?;
+        assert_eq!(eth_address(&pubkey), expected);
+        assert!(recover_eth_pubkey(b"Some data", &signature, 2).is_err());
+
+        // the wallet produces the same signature
+        let wallet = EthWallet::from_secret_key(SecretKey::from_slice(&ETH_PRIVKEY)?);
+        assert_eq!(wallet.address(), expected);
+        let call = wallet.sign(b"Some data")?;
+        assert_eq!(call.signature, Some(signature));
+        assert!(matches!(call.sigtype, SigType::Eth(28)));
+        assert_eq!(call.address()?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn eth_wallet() -> Result<()> {
+        let mut state = counter_plugin();
+        Context::add(Events::default());
+
+        let wallet = EthWallet::from_secret_key(SecretKey::from_slice(&ETH_PRIVKEY)?);
+        let call = <Counter as Call>::Call::Method(CounterMethodCall::Increment());
+        state.call(wallet.sign(&call.encode()?)?)?;
+        assert_eq!(state.inner.count, 1);
+        assert_eq!(state.inner.last_signer, wallet.address());
+
+        // a wrong recovery id recovers a different signer
+        let mut signed = wallet.sign(&call.encode()?)?;
+        let SigType::Eth(v) = signed.sigtype else {
+            unreachable!()
+        };
+        signed.sigtype = SigType::Eth(if v == 27 { 28 } else { 27 });
+        assert_ne!(signed.address().ok(), Some(wallet.address()));
+
+        // a provided pubkey must match the signature
+        let mut signed = wallet.sign(&call.encode()?)?;
+        signed.pubkey = Some(DerivedKey::new(b"alice")?.pubkey().serialize());
+        assert!(state.call(signed).is_err());
+        let mut signed = wallet.sign(&call.encode()?)?;
+        signed.pubkey = Some(wallet.pubkey().serialize());
+        state.call(signed)?;
+        assert_eq!(state.inner.last_signer, wallet.address());
+
+        Context::remove::<Signer>();
+        Context::remove::<Memo>();
+        Context::remove::<Events>();
+
+        Ok(())
+    }
 }
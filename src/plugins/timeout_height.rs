@@ -0,0 +1,282 @@
+//! Expiry of calls after a block height.
+use orga_macros::orga;
+
+use super::{sdk_compat::sdk::Tx as SdkTx, ConvertSdkTx, GetNonce};
+use crate::call::Call;
+use crate::encoding::{Decode, Encode};
+use crate::state::State;
+use crate::{Error, Result};
+
+/// A plugin which rejects calls included after their timeout height, bounding
+/// how long a signed call can be replayed.
+///
+/// Calls must include a timeout height (`u64`), and are rejected if the current
+/// block height is greater than it. A timeout height of zero means the call
+/// never expires.
+///
+/// This plugin should be placed inside the [super::SignerPlugin] (e.g. next to
+/// the [super::NoncePlugin]) so that the timeout height is covered by the
+/// call's signature.
+#[orga(skip(Call))]
+pub struct TimeoutHeightPlugin<T> {
+    /// The height of the current block, updated at the beginning of each
+    /// block.
+    height: u64,
+    /// The inner value.
+    pub inner: T,
+}
+
+impl<T: State> TimeoutHeightPlugin<T> {
+    /// Returns the height of the current block.
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Returns an error if a call with the given timeout height can not be
+    /// included in a block of the given height.
+    fn check_timeout(timeout_height: u64, height: u64) -> Result<()> {
+        if timeout_height != 0 && height > timeout_height {
+            return Err(Error::App(format!(
+                "Call timed out at height {}, current height is {}",
+                timeout_height, height,
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// A call which expires after a block height.
+#[derive(Debug, Encode, Decode)]
+pub struct TimeoutHeightCall<T> {
+    /// The last block height the call may be included in, or zero for no
+    /// timeout.
+    pub timeout_height: u64,
+    /// The inner call.
+    pub inner_call: T,
+}
+
+impl<T> Call for TimeoutHeightPlugin<T>
+where
+    T: Call + State,
+{
+    type Call = TimeoutHeightCall<T::Call>;
+
+    fn call(&mut self, call: Self::Call) -> Result<()> {
+        Self::check_timeout(call.timeout_height, self.height)?;
+        self.inner.call(call.inner_call)
+    }
+}
+
+impl<T> GetNonce for TimeoutHeightPlugin<T>
+where
+    T: GetNonce,
+{
+    fn nonce(&self, address: crate::coins::Address) -> Result<u64> {
+        self.inner.nonce(address)
+    }
+}
+
+impl<T> ConvertSdkTx for TimeoutHeightPlugin<T>
+where
+    T: State + ConvertSdkTx<Output = T::Call> + Call,
+{
+    type Output = TimeoutHeightCall<T::Call>;
+
+    fn convert(&self, sdk_tx: &SdkTx) -> Result<TimeoutHeightCall<T::Call>> {
+        Ok(TimeoutHeightCall {
+            timeout_height: sdk_tx.timeout_height(),
+            inner_call: self.inner.convert(sdk_tx)?,
+        })
+    }
+}
+
+// TODO: Remove dependency on ABCI for this otherwise-pure plugin.
+#[cfg(feature = "abci")]
+mod abci {
+    use super::super::{BeginBlockCtx, EndBlockCtx, InitChainCtx};
+    use super::*;
+    use crate::abci::{BeginBlock, CheckTx, EndBlock, InitChain};
+
+    /// Calls in the mempool can at the earliest be included in the next block,
+    /// so they are checked against its height rather than the current one.
+    impl<T: Call + State> CheckTx for TimeoutHeightPlugin<T> {
+        fn check_tx(&mut self, call: Self::Call) -> Result<()> {
+            Self::check_timeout(call.timeout_height, self.height + 1)?;
+            self.inner.check_tx(call.inner_call)
+        }
+    }
+
+    impl<T> BeginBlock for TimeoutHeightPlugin<T>
+    where
+        T: BeginBlock + State,
+    {
+        fn begin_block(&mut self, ctx: &BeginBlockCtx) -> Result<()> {
+            self.height = ctx.height;
+            self.inner.begin_block(ctx)
+        }
+    }
+
+    impl<T> EndBlock for TimeoutHeightPlugin<T>
+    where
+        T: EndBlock + State,
+    {
+        fn end_block(&mut self, ctx: &EndBlockCtx) -> Result<()> {
+            self.inner.end_block(ctx)
+        }
+    }
+
+    impl<T> InitChain for TimeoutHeightPlugin<T>
+    where
+        T: InitChain + State + Call,
+    {
+        fn init_chain(&mut self, ctx: &InitChainCtx) -> Result<()> {
+            self.inner.init_chain(ctx)
+        }
+    }
+
+    impl<T> crate::abci::AbciQuery for TimeoutHeightPlugin<T>
+    where
+        T: crate::abci::AbciQuery + State + Call,
+    {
+        fn abci_query(
+            &self,
+            request: &tendermint_proto::v0_34::abci::RequestQuery,
+        ) -> Result<tendermint_proto::v0_34::abci::ResponseQuery> {
+            self.inner.abci_query(request)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::ExtendVote for TimeoutHeightPlugin<T>
+    where
+        T: crate::abci::ExtendVote + State + Call,
+    {
+        fn extend_vote(
+            &mut self,
+            req: &crate::abci::messages_plus::RequestExtendVote,
+        ) -> Result<crate::abci::messages_plus::ResponseExtendVote> {
+            self.inner.extend_vote(req)
+        }
+    }
+
+    #[cfg(feature = "abci-plus")]
+    impl<T> crate::abci::VerifyVoteExtension for TimeoutHeightPlugin<T>
+    where
+        T: crate::abci::VerifyVoteExtension + State + Call,
+    {
+        fn verify_vote_extension(
+            &self,
+            req: &crate::abci::messages_plus::RequestVerifyVoteExtension,
+        ) -> Result<crate::abci::messages_plus::ResponseVerifyVoteExtension> {
+            self.inner.verify_vote_extension(req)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::wallet::{DerivedKey, Wallet};
+    use crate::coins::Address;
+    use crate::context::Context;
+    use crate::plugins::{Events, Memo, Signer, SignerPlugin};
+
+    #[derive(State, Encode, Decode, Default)]
+    struct Counter {
+        pub count: u64,
+    }
+
+    #[derive(Debug, Encode, Decode)]
+    enum CounterCall {
+        Increment,
+    }
+
+    impl Call for Counter {
+        type Call = CounterCall;
+
+        fn call(&mut self, _call: Self::Call) -> Result<()> {
+            self.count += 1;
+            Ok(())
+        }
+    }
+
+    impl GetNonce for Counter {
+        fn nonce(&self, _address: Address) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    fn call(timeout_height: u64) -> TimeoutHeightCall<CounterCall> {
+        TimeoutHeightCall {
+            timeout_height,
+            inner_call: CounterCall::Increment,
+        }
+    }
+
+    #[test]
+    fn timeout_height() {
+        let mut state: TimeoutHeightPlugin<Counter> = Default::default();
+        state.height = 100;
+
+        // No timeout
+        state.call(call(0)).unwrap();
+        // Future timeout
+        state.call(call(101)).unwrap();
+        // Timeout at the current height
+        state.call(call(100)).unwrap();
+        assert_eq!(state.inner.count, 3);
+
+        // Past timeout
+        assert!(state.call(call(99)).is_err());
+        assert!(state.call(call(1)).is_err());
+        assert_eq!(state.inner.count, 3);
+    }
+
+    #[cfg(feature = "abci")]
+    #[test]
+    fn check_tx_uses_next_height() {
+        use crate::abci::CheckTx;
+
+        let mut state: TimeoutHeightPlugin<Counter> = Default::default();
+        state.height = 100;
+
+        state.check_tx(call(0)).unwrap();
+        state.check_tx(call(101)).unwrap();
+        assert_eq!(state.inner.count, 2);
+
+        // a call timing out at the current height can never be included
+        assert!(state.check_tx(call(100)).is_err());
+        assert_eq!(state.inner.count, 2);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn timeout_height_is_signed() -> Result<()> {
+        let mut state = SignerPlugin {
+            inner: TimeoutHeightPlugin {
+                height: 100,
+                inner: Counter::default(),
+            },
+        };
+        Context::add(Events::default());
+
+        let key = DerivedKey::new(b"alice")?;
+        state.call(key.sign(&call(100).encode()?)?)?;
+        assert_eq!(state.inner.inner.count, 1);
+
+        // extending the timeout height of an expired call invalidates the
+        // signature
+        let mut signed = key.sign(&call(99).encode()?)?;
+        signed.call_bytes = call(200).encode()?;
+        let err = state.call(signed).unwrap_err();
+        assert!(!err.to_string().contains("timed out"));
+        assert_eq!(state.inner.inner.count, 1);
+
+        Context::remove::<Signer>();
+        Context::remove::<Memo>();
+        Context::remove::<Events>();
+
+        Ok(())
+    }
+}
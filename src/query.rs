@@ -79,6 +79,9 @@ noop_impl!(i16);
 noop_impl!(i32);
 noop_impl!(i64);
 noop_impl!(i128);
+noop_impl!(usize);
+noop_impl!(isize);
+noop_impl!(String);
 
 impl<T> Query for Vec<T> {
     type Query = ();
@@ -1,4 +1,4 @@
-use super::State;
+use super::{State, Transform};
 use crate::compat_mode;
 use crate::Result;
 
@@ -40,6 +40,20 @@ where
         Ok(self)
     }
 
+    /// Flush a child, passing its bytes through the [Transform] `C` before
+    /// writing them.
+    pub fn flush_child_transformed<C, U>(self, value: U) -> Result<Self>
+    where
+        C: Transform,
+        U: State,
+    {
+        let mut bytes = vec![];
+        value.flush(&mut bytes)?;
+        self.out.write_all(&C::encode(bytes)?)?;
+
+        Ok(self)
+    }
+
     /// No-op for skipped children.
     pub fn flush_skipped_child<T>(self, _value: T) -> Result<Self> {
         Ok(self)
@@ -50,6 +64,13 @@ where
         self.flush_child(value)
     }
 
+    /// Writes the discriminant byte of an enum's active variant.
+    pub fn discriminant(self, discriminant: u8) -> Result<Self> {
+        self.out.write_all(&[discriminant])?;
+
+        Ok(self)
+    }
+
     /// Writes the version byte.
     pub fn version(self, version: u8) -> Result<Self> {
         if !compat_mode() {
@@ -4,12 +4,13 @@ use crate::compat_mode;
 use crate::store::Store;
 use crate::{Error, Result};
 
-use super::State;
+use super::{State, Transform};
 
 /// A helper for loading children in [State] implementations, used by the
 /// derive macro.
 pub struct Loader<'a, 'b> {
     version: u8,
+    version_read: bool,
     field_count: u8,
     store: Store,
     bytes: &'a mut &'b [u8],
@@ -21,6 +22,7 @@ impl<'a, 'b> Loader<'a, 'b> {
         Self {
             field_count: 0,
             version,
+            version_read: false,
             store,
             bytes,
         }
@@ -32,7 +34,34 @@ impl<'a, 'b> Loader<'a, 'b> {
     where
         U: State,
     {
-        if !compat_mode() && self.field_count == 0 {
+        self.maybe_read_version::<T>()?;
+
+        let res = U::load(self.store.sub(&[self.field_count]), self.bytes);
+
+        self.field_count += 1;
+
+        res
+    }
+
+    /// Loads a child whose bytes were written through the [Transform] `C`,
+    /// reading the version byte if it is the first child.
+    pub fn load_child_transformed<T, C, U>(&mut self) -> Result<U>
+    where
+        C: Transform,
+        U: State,
+    {
+        self.maybe_read_version::<T>()?;
+
+        let bytes = C::decode(self.bytes)?;
+        let res = U::load(self.store.sub(&[self.field_count]), &mut bytes.as_slice());
+
+        self.field_count += 1;
+
+        res
+    }
+
+    fn maybe_read_version<T>(&mut self) -> Result<()> {
+        if !compat_mode() && !self.version_read {
             if self.bytes.is_empty() {
                 return Err(Error::State("Unexpected EOF".to_string()));
             }
@@ -46,13 +75,25 @@ impl<'a, 'b> Loader<'a, 'b> {
                 )));
             }
             *self.bytes = &self.bytes[1..];
+            self.version_read = true;
         }
 
-        let res = U::load(self.store.sub(&[self.field_count]), self.bytes);
+        Ok(())
+    }
 
-        self.field_count += 1;
+    /// Reads the discriminant byte of an enum, reading the version byte first.
+    /// Children loaded afterwards use sub-stores of the discriminant's prefix.
+    pub fn load_discriminant<T>(&mut self) -> Result<u8> {
+        self.maybe_read_version::<T>()?;
 
-        res
+        let discriminant = *self
+            .bytes
+            .first()
+            .ok_or_else(|| Error::State("Unexpected EOF".to_string()))?;
+        *self.bytes = &self.bytes[1..];
+        self.store = self.store.sub(&[discriminant]);
+
+        Ok(discriminant)
     }
 
     /// Loads a child using the [State] implementation of `T`, then converts it
@@ -1,5 +1,5 @@
 use crate::describe::KeyOp;
-use crate::encoding::{Decode, Encode};
+use crate::encoding::{Adapter, Decode, Encode};
 use crate::store::Store;
 use crate::{Error, Result};
 use ed::Terminated;
@@ -11,8 +11,12 @@ mod flush;
 pub use flush::Flusher;
 mod load;
 pub use load::Loader;
+mod transaction;
+pub use transaction::transaction;
 
 use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
 use std::marker::PhantomData;
 use std::rc::Rc;
 
@@ -57,6 +61,20 @@ pub trait State: Sized + 'static {
     }
 }
 
+/// A reversible transformation of a field's flushed bytes, applied by the
+/// `#[state(transform = "...")]` field attribute. This allows a field to be
+/// stored in a different form (e.g. compressed) without changing its in-memory
+/// type.
+pub trait Transform {
+    /// Transforms the bytes written by the field's [State::flush].
+    fn encode(bytes: Vec<u8>) -> Result<Vec<u8>>;
+
+    /// Reads transformed bytes from the front of `bytes`, advancing the slice
+    /// past them, and returns the original bytes to be passed to the field's
+    /// [State::load].
+    fn decode(bytes: &mut &[u8]) -> Result<Vec<u8>>;
+}
+
 macro_rules! state_impl {
     ($type:ty) => {
         impl State for $type {
@@ -96,6 +114,30 @@ state_impl!(i128);
 state_impl!(bool);
 state_impl!(());
 
+/// Strings are stored inline, prefixed by their byte length as a varint, using
+/// the same encoding as `Adapter<String>`.
+impl State for String {
+    #[inline]
+    fn attach(&mut self, _: Store) -> Result<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn flush<W: std::io::Write>(self, out: &mut W) -> Result<()> {
+        Ok(Adapter(self).encode_into(out)?)
+    }
+
+    #[inline]
+    fn load(_store: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Adapter::<String>::decode(bytes)?.0)
+    }
+
+    #[inline]
+    fn field_keyop(_field_name: &str) -> Option<KeyOp> {
+        Some(KeyOp::Append(vec![]))
+    }
+}
+
 /// Returns the varint encoding of a number `n` with a maximum value `max`.
 pub fn varint(n: usize, max: usize) -> Vec<u8> {
     if max < u8::MAX as usize {
@@ -109,6 +151,52 @@ pub fn varint(n: usize, max: usize) -> Vec<u8> {
     }
 }
 
+/// In-memory maps are stored inline rather than as store children, using the
+/// canonical encodings of `Adapter<BTreeMap>` and `Adapter<HashMap>`. This is
+/// only suitable for small maps since the whole map is loaded and flushed at
+/// once - use [crate::collections::Map] for larger data.
+impl<K, V> State for BTreeMap<K, V>
+where
+    K: Encode + Decode + Terminated + Ord + 'static,
+    V: Encode + Decode + Terminated + 'static,
+{
+    #[inline]
+    fn attach(&mut self, _: Store) -> Result<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn flush<W: std::io::Write>(self, out: &mut W) -> Result<()> {
+        Ok(Adapter(self).encode_into(out)?)
+    }
+
+    #[inline]
+    fn load(_store: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Adapter::<Self>::decode(bytes)?.0)
+    }
+}
+
+impl<K, V> State for HashMap<K, V>
+where
+    K: Encode + Decode + Terminated + Eq + Hash + 'static,
+    V: Encode + Decode + Terminated + 'static,
+{
+    #[inline]
+    fn attach(&mut self, _: Store) -> Result<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn flush<W: std::io::Write>(self, out: &mut W) -> Result<()> {
+        Ok(Adapter(self).encode_into(out)?)
+    }
+
+    #[inline]
+    fn load(_store: Store, bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Adapter::<Self>::decode(bytes)?.0)
+    }
+}
+
 impl<T: State> State for Option<T> {
     #[inline]
     fn attach(&mut self, store: Store) -> Result<()> {
@@ -315,8 +403,10 @@ impl<S> !Simple for Store<S> {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collections::Map;
+    use crate::migrate::Migrate;
     use crate::orga;
-    use crate::store::Store;
+    use crate::store::{Read, Store};
 
     #[orga(channels(Alpha, Beta))]
     pub struct ExplicitPrefixes {
@@ -340,6 +430,53 @@ mod tests {
         pub fn _foo(&self) {}
     }
 
+    struct XorCodec;
+
+    impl Transform for XorCodec {
+        fn encode(bytes: Vec<u8>) -> Result<Vec<u8>> {
+            Ok(bytes.into_iter().map(|b| b ^ 0xff).collect())
+        }
+
+        fn decode(bytes: &mut &[u8]) -> Result<Vec<u8>> {
+            let decoded = bytes.iter().map(|b| b ^ 0xff).collect();
+            *bytes = &[];
+            Ok(decoded)
+        }
+    }
+
+    #[orga]
+    pub struct Transformed {
+        a: u32,
+
+        #[state(transform = "XorCodec")]
+        data: Vec<u8>,
+    }
+
+    #[derive(State, Default)]
+    struct Pair(Store, Map<u32, u32>);
+
+    #[test]
+    fn tuple_struct_prefixes() -> Result<()> {
+        let store = Store::with_map_store();
+        let mut value = Pair::default();
+        value.attach(store.clone())?;
+        assert_eq!(value.0.prefix(), &[0]);
+
+        value.1.insert(5, 6)?;
+        let mut bytes = vec![];
+        value.flush(&mut bytes)?;
+        assert_eq!(store.get(&[1, 0, 0, 0, 5])?, Some(vec![0, 0, 0, 6]));
+
+        assert_eq!(Pair::field_keyop("0"), Some(KeyOp::Append(vec![0])));
+        assert_eq!(Pair::field_keyop("1"), Some(KeyOp::Append(vec![1])));
+        assert_eq!(Pair::field_keyop("2"), None);
+
+        let value = Pair::load(store, &mut bytes.as_slice())?;
+        assert_eq!(value.1.get(5)?.map(|v| *v), Some(6));
+
+        Ok(())
+    }
+
     #[test]
     fn explicit_prefixes() -> Result<()> {
         let store = Store::default();
@@ -350,4 +487,150 @@ mod tests {
         value.0._foo();
         Ok(())
     }
+
+    #[test]
+    fn transformed_field() -> Result<()> {
+        let value = Transformed {
+            a: 1,
+            data: vec![1, 2, 3],
+        };
+        let mut bytes = vec![];
+        value.flush(&mut bytes)?;
+        assert_eq!(bytes, vec![0, 0, 0, 0, 1, 0xfe, 0xfd, 0xfc]);
+        assert_ne!(&bytes[5..], &[1, 2, 3]);
+
+        let value = Transformed::load(Store::default(), &mut bytes.as_slice())?;
+        assert_eq!(value.a, 1);
+        assert_eq!(value.data, vec![1, 2, 3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_transformed_field() -> Result<()> {
+        let value = Transformed {
+            a: 1,
+            data: vec![1, 2, 3],
+        };
+        let mut bytes = vec![];
+        value.flush(&mut bytes)?;
+
+        let value =
+            Transformed::migrate(Store::default(), Store::default(), &mut bytes.as_slice())?;
+        assert_eq!(value.a, 1);
+        assert_eq!(value.data, vec![1, 2, 3]);
+
+        Ok(())
+    }
+
+    #[orga]
+    pub struct Named {
+        id: u32,
+        name: String,
+    }
+
+    #[test]
+    fn string_state() -> Result<()> {
+        for name in ["", "héllo wörld 🦀"] {
+            let value = Named {
+                id: 1,
+                name: name.to_string(),
+            };
+            let mut bytes = vec![];
+            value.flush(&mut bytes)?;
+            assert_eq!(bytes[5] as usize, name.len());
+            assert_eq!(&bytes[6..], name.as_bytes());
+
+            let value = Named::load(Store::default(), &mut bytes.as_slice())?;
+            assert_eq!(value.id, 1);
+            assert_eq!(value.name, name);
+        }
+
+        Ok(())
+    }
+
+    #[orga]
+    struct Lookups {
+        sorted: BTreeMap<u32, u64>,
+        hashed: HashMap<u32, u64>,
+    }
+
+    #[test]
+    fn map_state() -> Result<()> {
+        let mut a = Lookups::default();
+        let mut b = Lookups::default();
+        for i in 0..50u32 {
+            a.sorted.insert(i, i as u64);
+            a.hashed.insert(i, i as u64);
+            b.sorted.insert(49 - i, (49 - i) as u64);
+            b.hashed.insert(49 - i, (49 - i) as u64);
+        }
+
+        let mut a_bytes = vec![];
+        a.flush(&mut a_bytes)?;
+        let mut b_bytes = vec![];
+        b.flush(&mut b_bytes)?;
+        assert_eq!(a_bytes, b_bytes);
+
+        let value = Lookups::load(Store::default(), &mut a_bytes.as_slice())?;
+        assert_eq!(value.sorted.len(), 50);
+        assert_eq!(value.hashed.get(&7), Some(&7));
+
+        Ok(())
+    }
+
+    #[derive(State)]
+    enum Shape {
+        Empty,
+        Pair(u32, u64),
+        Named { id: u32, entries: Map<u32, u32> },
+    }
+
+    fn round_trip(store: &Store, mut value: Shape, expected: &[u8]) -> Result<Shape> {
+        value.attach(store.clone())?;
+        if let Shape::Named { entries, .. } = &mut value {
+            entries.insert(7, 9)?;
+        }
+
+        let mut bytes = vec![];
+        value.flush(&mut bytes)?;
+        assert_eq!(bytes, expected);
+
+        Shape::load(store.clone(), &mut bytes.as_slice())
+    }
+
+    #[test]
+    fn enum_state() -> Result<()> {
+        let store = Store::with_map_store();
+
+        let value = round_trip(&store, Shape::Empty, &[0, 0])?;
+        assert!(matches!(value, Shape::Empty));
+
+        let value = round_trip(
+            &store,
+            Shape::Pair(1, 2),
+            &[0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2],
+        )?;
+        assert!(matches!(value, Shape::Pair(1, 2)));
+
+        let value = Shape::Named {
+            id: 3,
+            entries: Default::default(),
+        };
+        let value = round_trip(&store, value, &[0, 2, 0, 0, 0, 3])?;
+        let Shape::Named { id, entries } = value else {
+            panic!("Expected Named variant");
+        };
+        assert_eq!(id, 3);
+        assert_eq!(*entries.get(7)?.unwrap(), 9);
+        // fields are stored under the discriminant's prefix
+        assert_eq!(
+            store.sub(&[2, 1]).get(&[0, 0, 0, 7])?,
+            Some(vec![0, 0, 0, 9])
+        );
+
+        assert!(Shape::load(store.clone(), &mut [0, 3].as_slice()).is_err());
+
+        Ok(())
+    }
 }
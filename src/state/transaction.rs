@@ -0,0 +1,119 @@
+use super::State;
+use crate::store::{BackingStore, BufStore, Shared, Store};
+use crate::{gas, Result};
+
+/// Runs `op` against `value` in a scoped transaction, where `store` is the
+/// store `value` is attached to.
+///
+/// The value is flushed, then reloaded over a [BufStore] wrapping `store` so
+/// that any writes made by `op` are buffered. If `op` succeeds, the buffered
+/// writes are committed to `store`. If it fails, or committing its writes
+/// fails, they are discarded and `value` is restored to its state from before
+/// `op` ran.
+///
+/// Transactions may be nested, in which case the inner transaction's writes
+/// are committed into the outer transaction's buffer.
+///
+/// Flushing and committing are not metered by the [Gas](crate::gas::Gas)
+/// context, and reads which miss the buffer are only charged once.
+pub fn transaction<T, F, R>(value: &mut T, store: &Store, op: F) -> Result<R>
+where
+    T: State + Default,
+    F: FnOnce(&mut T) -> Result<R>,
+{
+    let mut bytes = vec![];
+    gas::unmetered(|| std::mem::take(value).flush(&mut bytes))?;
+
+    let res = run_buffered(&bytes, store, op).and_then(|(res, scoped_bytes)| {
+        Ok((res, T::load(store.clone(), &mut scoped_bytes.as_slice())?))
+    });
+    match res {
+        Ok((res, scoped)) => {
+            *value = scoped;
+            Ok(res)
+        }
+        Err(err) => {
+            *value = T::load(store.clone(), &mut bytes.as_slice())?;
+            Err(err)
+        }
+    }
+}
+
+/// Loads a value from `bytes` over a [BufStore] wrapping `store` and runs `op`
+/// against it, committing the buffered writes to `store` if it succeeds.
+/// Returns the result of `op` and the encoding of the resulting value.
+fn run_buffered<T, F, R>(bytes: &[u8], store: &Store, op: F) -> Result<(R, Vec<u8>)>
+where
+    T: State,
+    F: FnOnce(&mut T) -> Result<R>,
+{
+    // the buffer wraps the backing store directly, so that reads are only
+    // charged by the scoped store
+    let mut buf = Shared::new(BufStore::wrap(store.backing_store()));
+    let buf_store = Store::new(BackingStore::Other(Shared::new(Box::new(buf.clone()))))
+        .with_prefix(store.prefix().to_vec());
+    let mut scoped = T::load(buf_store, &mut &bytes[..])?;
+
+    let res = op(&mut scoped)?;
+    let scoped_bytes = gas::unmetered(|| -> Result<_> {
+        let mut scoped_bytes = vec![];
+        scoped.flush(&mut scoped_bytes)?;
+        buf.borrow_mut().flush()?;
+        Ok(scoped_bytes)
+    })?;
+
+    Ok((res, scoped_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::Map;
+    use crate::store::{MapStore, Read, ReadOnly};
+    use crate::Error;
+
+    #[test]
+    fn commit_and_rollback() -> Result<()> {
+        let store = Store::with_map_store();
+        let mut map: Map<u32, u32> = Map::with_store(store.clone())?;
+        map.insert(1, 10)?;
+
+        transaction(&mut map, &store, |map| map.insert(2, 20))?;
+        assert_eq!(*map.get(2)?.unwrap(), 20);
+        assert!(store.get(&2u32.to_be_bytes())?.is_some());
+
+        let res = transaction(&mut map, &store, |map| {
+            map.insert(3, 30)?;
+            map.remove(1)?;
+            Err::<(), _>(Error::App("failed".into()))
+        });
+        assert!(res.is_err());
+        assert_eq!(*map.get(1)?.unwrap(), 10);
+        assert!(map.get(3)?.is_none());
+        assert!(store.get(&3u32.to_be_bytes())?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_commit_restores_value() -> Result<()> {
+        let inner = Shared::new(MapStore::new());
+        let mut map: Map<u32, u32> =
+            Map::with_store(Store::new(BackingStore::MapStore(inner.clone())))?;
+        map.insert(1, 10)?;
+        let mut bytes = vec![];
+        map.flush(&mut bytes)?;
+
+        // committing to a read-only store fails after `op` has succeeded
+        let store = Store::new(BackingStore::Other(Shared::new(Box::new(ReadOnly::new(
+            inner,
+        )))));
+        let mut map: Map<u32, u32> = Map::load(store.clone(), &mut bytes.as_slice())?;
+        let res = transaction(&mut map, &store, |map| map.insert(2, 20));
+        assert!(res.is_err());
+        assert_eq!(*map.get(1)?.unwrap(), 10);
+        assert!(map.get(2)?.is_none());
+
+        Ok(())
+    }
+}
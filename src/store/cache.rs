@@ -0,0 +1,214 @@
+//! Caching reads for a store.
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use super::*;
+
+/// The default number of entries kept by a [CacheStore].
+pub const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
+/// A store which wraps another store and keeps a least-recently-used cache of
+/// the results of `get` calls, so repeated reads of the same keys (e.g. on hot
+/// query paths) do not reach the inner store.
+///
+/// Writes are passed through to the inner store and invalidate the cached
+/// entry for their key. If the inner store may be written to other than
+/// through the cache (e.g. when a new block is committed), [CacheStore::reset]
+/// must be called to clear the cache.
+pub struct CacheStore<S> {
+    inner: S,
+    cache: Mutex<Lru>,
+}
+
+impl<S> CacheStore<S> {
+    /// Creates a new cache store wrapping `inner`, keeping up to
+    /// [DEFAULT_CACHE_CAPACITY] entries.
+    pub fn new(inner: S) -> Self {
+        Self::with_capacity(inner, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Creates a new cache store wrapping `inner`, keeping up to `capacity`
+    /// entries.
+    pub fn with_capacity(inner: S, capacity: usize) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(Lru::new(capacity)),
+        }
+    }
+
+    /// Clears the cache, e.g. between blocks.
+    pub fn reset(&mut self) {
+        self.cache.get_mut().unwrap().clear();
+    }
+
+    /// Returns the number of cached entries.
+    pub fn len(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    /// Returns `true` if no entries are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a reference to the wrapped store.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Consumes the cache store, returning the wrapped store.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Read> Read for CacheStore<S> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if let Some(value) = self.cache.lock().unwrap().get(key) {
+            return Ok(value);
+        }
+
+        let value = self.inner.get(key)?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(key.to_vec(), value.clone());
+
+        Ok(value)
+    }
+
+    #[inline]
+    fn get_next(&self, key: &[u8]) -> Result<Option<KV>> {
+        self.inner.get_next(key)
+    }
+
+    #[inline]
+    fn get_prev(&self, key: Option<&[u8]>) -> Result<Option<KV>> {
+        self.inner.get_prev(key)
+    }
+}
+
+impl<S: Write> Write for CacheStore<S> {
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        self.cache.get_mut().unwrap().remove(key.as_slice());
+        self.inner.put(key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.cache.get_mut().unwrap().remove(key);
+        self.inner.delete(key)
+    }
+}
+
+/// A least-recently-used map of keys to the results of reading them.
+struct Lru {
+    capacity: usize,
+    /// The cached values, and the tick at which each was last used.
+    entries: HashMap<Vec<u8>, (Option<Vec<u8>>, u64)>,
+    /// The cached keys, by the tick at which each was last used.
+    recency: BTreeMap<u64, Vec<u8>>,
+    tick: u64,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    fn get(&mut self, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        self.tick += 1;
+        let (value, last_used) = self.entries.get_mut(key)?;
+        let key = self.recency.remove(last_used).unwrap();
+        *last_used = self.tick;
+        self.recency.insert(self.tick, key);
+
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.remove(key.as_slice());
+        while self.entries.len() >= self.capacity {
+            let (_, oldest) = self.recency.pop_first().unwrap();
+            self.entries.remove(&oldest);
+        }
+
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, self.tick));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        if let Some((_, last_used)) = self.entries.remove(key) {
+            self.recency.remove(&last_used);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::log::ReadLog;
+
+    #[test]
+    fn no_stale_reads() {
+        let mut store = CacheStore::new(MapStore::new());
+        store.put(vec![1], vec![2]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![2]));
+
+        store.put(vec![1], vec![3]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![3]));
+
+        store.delete(&[1]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), None);
+
+        store.put(vec![1], vec![4]).unwrap();
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![4]));
+    }
+
+    #[test]
+    fn hits_skip_inner_store() {
+        let mut inner = MapStore::new();
+        for i in 0..4 {
+            inner.put(vec![i], vec![i]).unwrap();
+        }
+        let mut store = CacheStore::with_capacity(ReadLog::new(inner), 2);
+
+        for _ in 0..100 {
+            assert_eq!(store.get(&[0]).unwrap(), Some(vec![0]));
+            assert_eq!(store.get(&[9]).unwrap(), None);
+        }
+        assert_eq!(store.inner().reads().len(), 2);
+
+        // reading a third key evicts the least recently used one
+        store.get(&[0]).unwrap();
+        store.get(&[1]).unwrap();
+        assert_eq!(store.len(), 2);
+        store.get(&[0]).unwrap();
+        assert_eq!(store.inner().reads().len(), 3);
+        store.get(&[9]).unwrap();
+        assert_eq!(store.inner().reads().len(), 4);
+
+        store.reset();
+        assert!(store.is_empty());
+        store.get(&[0]).unwrap();
+        assert_eq!(store.inner().reads().len(), 5);
+    }
+}
@@ -0,0 +1,187 @@
+//! Recording metrics for a store.
+
+use crate::Result;
+
+use super::{Read, Write, KV};
+
+/// The number of reads (`get`, `get_next`, and `get_prev` calls).
+pub const GETS: &str = "orga_store_gets_total";
+/// The number of `put` calls.
+pub const PUTS: &str = "orga_store_puts_total";
+/// The number of `delete` calls.
+pub const DELETES: &str = "orga_store_deletes_total";
+/// The number of key and value bytes of the entries found by reads. For `get`,
+/// this is the length of the requested key and the returned value, so every
+/// read method counts the same bytes for the same entry.
+pub const BYTES_READ: &str = "orga_store_bytes_read_total";
+/// The number of key and value bytes passed to `put`.
+pub const BYTES_WRITTEN: &str = "orga_store_bytes_written_total";
+/// The duration of `commit` calls, in seconds.
+pub const COMMIT_SECONDS: &str = "orga_store_commit_seconds";
+
+/// A store which wraps another store and records metrics for its operations
+/// through the [metrics] facade, passing each operation through unchanged.
+///
+/// Metrics are only exported once the app installs a [metrics] recorder, e.g.
+/// a Prometheus exporter. Rates such as gets per second can be derived from
+/// the counters by the monitoring system.
+///
+/// With the `abci` feature, this also implements `ABCIStore` for any wrapped
+/// `ABCIStore`, recording the latency of each commit. Nodes record the app's
+/// store operations and the latency of each commit with this wrapper.
+pub struct MeteredStore<S> {
+    inner: S,
+}
+
+impl<S> MeteredStore<S> {
+    /// Creates a new metered store wrapping `inner`.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    /// Returns a reference to the wrapped store.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped store.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consumes the metered store, returning the wrapped store.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+/// Records a read which found the entry with the given key and value, if any.
+fn record_read(entry: Option<(&[u8], &[u8])>) {
+    metrics::counter!(GETS).increment(1);
+    if let Some((key, value)) = entry {
+        metrics::counter!(BYTES_READ).increment((key.len() + value.len()) as u64);
+    }
+}
+
+impl<S: Read> Read for MeteredStore<S> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let value = self.inner.get(key)?;
+        record_read(value.as_deref().map(|value| (key, value)));
+
+        Ok(value)
+    }
+
+    fn get_next(&self, key: &[u8]) -> Result<Option<KV>> {
+        let entry = self.inner.get_next(key)?;
+        record_read(entry.as_ref().map(|(k, v)| (k.as_slice(), v.as_slice())));
+
+        Ok(entry)
+    }
+
+    fn get_prev(&self, key: Option<&[u8]>) -> Result<Option<KV>> {
+        let entry = self.inner.get_prev(key)?;
+        record_read(entry.as_ref().map(|(k, v)| (k.as_slice(), v.as_slice())));
+
+        Ok(entry)
+    }
+}
+
+impl<S: Write> Write for MeteredStore<S> {
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        metrics::counter!(PUTS).increment(1);
+        metrics::counter!(BYTES_WRITTEN).increment((key.len() + value.len()) as u64);
+        self.inner.put(key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        metrics::counter!(DELETES).increment(1);
+        self.inner.delete(key)
+    }
+}
+
+#[cfg(feature = "abci")]
+mod abci {
+    use super::*;
+    use crate::abci::ABCIStore;
+    use std::time::Instant;
+    use tendermint_proto::v0_34::abci::{
+        RequestApplySnapshotChunk, RequestLoadSnapshotChunk, RequestOfferSnapshot,
+        ResponseOfferSnapshot, Snapshot,
+    };
+    use tendermint_proto::v0_34::types::Header;
+
+    impl<S: ABCIStore> ABCIStore for MeteredStore<S> {
+        fn height(&self) -> Result<u64> {
+            self.inner.height()
+        }
+
+        fn root_hash(&self) -> Result<Vec<u8>> {
+            self.inner.root_hash()
+        }
+
+        fn commit(&mut self, header: Header) -> Result<()> {
+            let start = Instant::now();
+            let res = self.inner.commit(header);
+            metrics::histogram!(COMMIT_SECONDS).record(start.elapsed().as_secs_f64());
+
+            res
+        }
+
+        fn list_snapshots(&self) -> Result<Vec<Snapshot>> {
+            self.inner.list_snapshots()
+        }
+
+        fn load_snapshot_chunk(&self, req: RequestLoadSnapshotChunk) -> Result<Vec<u8>> {
+            self.inner.load_snapshot_chunk(req)
+        }
+
+        fn offer_snapshot(&mut self, req: RequestOfferSnapshot) -> Result<ResponseOfferSnapshot> {
+            self.inner.offer_snapshot(req)
+        }
+
+        fn apply_snapshot_chunk(&mut self, req: RequestApplySnapshotChunk) -> Result<()> {
+            self.inner.apply_snapshot_chunk(req)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MapStore;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use std::collections::HashMap;
+
+    #[test]
+    fn counters() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        let mut store = MeteredStore::new(MapStore::new());
+        metrics::with_local_recorder(&recorder, || {
+            store.put(vec![1], vec![2, 3]).unwrap();
+            store.put(vec![4], vec![5]).unwrap();
+            assert_eq!(store.get(&[1]).unwrap(), Some(vec![2, 3]));
+            assert_eq!(store.get(&[9]).unwrap(), None);
+            assert_eq!(store.get_next(&[1]).unwrap(), Some((vec![4], vec![5])));
+            store.delete(&[4]).unwrap();
+        });
+
+        let counters: HashMap<_, _> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .filter_map(|(key, _, _, value)| match value {
+                DebugValue::Counter(n) => Some((key.key().name().to_string(), n)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(counters[PUTS], 2);
+        assert_eq!(counters[BYTES_WRITTEN], 5);
+        assert_eq!(counters[GETS], 3);
+        assert_eq!(counters[BYTES_READ], 5);
+        assert_eq!(counters[DELETES], 1);
+
+        assert!(store.into_inner().get(&[4]).unwrap().is_none());
+    }
+}
@@ -7,19 +7,29 @@ use thiserror::Error;
 
 pub mod backingstore;
 pub mod bufstore;
+#[cfg(feature = "store-cache")]
+pub mod cache;
 pub mod iter;
 pub mod log;
+#[cfg(feature = "metrics")]
+pub mod metered;
 pub mod null;
 pub mod partialmap;
+pub mod readonly;
 pub mod share;
 #[allow(clippy::module_inception)]
 pub mod store;
 
 pub use backingstore::BackingStore;
 pub use bufstore::{BufStore, Map as BufStoreMap, MapStore};
+#[cfg(feature = "store-cache")]
+pub use cache::CacheStore;
 pub use iter::Iter;
+#[cfg(feature = "metrics")]
+pub use metered::MeteredStore;
 pub use null::Empty;
 pub use partialmap::PartialMapStore;
+pub use readonly::ReadOnly;
 pub use share::Shared;
 pub use store::{DefaultBackingStore, Store};
 
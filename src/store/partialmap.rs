@@ -6,7 +6,7 @@ use super::*;
 
 /// A store backed by an in-memory map which may have missing values, and may be
 /// joined with other partial map stores.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct PartialMapStore {
     map: BTreeMap<Vec<u8>, (bool, Vec<u8>)>,
     right_edge: bool,
@@ -42,6 +42,72 @@ impl PartialMapStore {
             right_edge: self.right_edge || other.right_edge,
         }
     }
+
+    /// Marks the key range `[start, end)` as known, given all of the entries
+    /// in the range (e.g. from a verified range proof). An empty `end` marks
+    /// the range as extending to the end of the keyspace.
+    ///
+    /// Reads within the range will return the given entries, or `None` for
+    /// absent keys, rather than erroring. The gap between the last entry and a
+    /// bounded `end` is only retained if the key at `end` is already present.
+    ///
+    /// Errors if the entries are not sorted, fall outside of the range, or
+    /// conflict with entries already in the store.
+    pub fn mark_range_known(
+        &mut self,
+        start: Vec<u8>,
+        end: Vec<u8>,
+        entries: Vec<KV>,
+    ) -> Result<()> {
+        let end_bound = if end.is_empty() {
+            Bound::Unbounded
+        } else {
+            Bound::Excluded(end.clone())
+        };
+        let range = (Bound::Included(start.clone()), end_bound);
+
+        if !entries.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+            return Err(crate::Error::Store(
+                "Range entries must be sorted by key".into(),
+            ));
+        }
+        if let Some((key, _)) = entries.iter().find(|(key, _)| !range.contains(key)) {
+            return Err(crate::Error::Store(format!(
+                "Range entry {:?} is outside of the range",
+                key
+            )));
+        }
+        for (key, (_, value)) in self.map.range(range.clone()) {
+            match entries.binary_search_by(|(k, _)| k.cmp(key)) {
+                Ok(i) if entries[i].1 == *value => {}
+                _ => {
+                    return Err(crate::Error::Store(format!(
+                        "Range entries conflict with existing entry for key {:?}",
+                        key
+                    )))
+                }
+            }
+        }
+
+        // the range is only known to be contiguous with the key preceding it if
+        // it starts at the beginning of the keyspace
+        let reaches_end = !entries.is_empty() || start.is_empty();
+        for (i, (key, value)) in entries.into_iter().enumerate() {
+            let contiguous =
+                i > 0 || start.is_empty() || self.map.get(&key).map_or(false, |(c, _)| *c);
+            self.map.insert(key, (contiguous, value));
+        }
+
+        if reaches_end {
+            if end.is_empty() {
+                self.right_edge = true;
+            } else if let Some((contiguous, _)) = self.map.get_mut(&end) {
+                *contiguous = true;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Read for PartialMapStore {
@@ -129,3 +195,39 @@ fn exclusive_range_starting_from(start: &[u8]) -> (Bound<Vec<u8>>, Bound<Vec<u8>
 fn exclusive_range_ending_at(start: &[u8]) -> (Bound<Vec<u8>>, Bound<Vec<u8>>) {
     (Bound::Unbounded, Bound::Excluded(start.to_vec()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mark_range_known() -> Result<()> {
+        let mut store = PartialMapStore::new();
+        store.mark_range_known(
+            vec![3],
+            vec![],
+            vec![(vec![3], vec![3]), (vec![4, 1], vec![4])],
+        )?;
+        assert_eq!(store.get_next(&[3])?, Some((vec![4, 1], vec![4])));
+        assert_eq!(store.get_next(&[4, 1])?, None);
+        assert_eq!(store.get(&[4])?, None);
+        assert!(store.get_next(&[2]).is_err());
+        assert!(store.get(&[2]).is_err());
+
+        store.mark_range_known(vec![1], vec![3], vec![(vec![2], vec![2])])?;
+        assert_eq!(store.get(&[2])?, Some(vec![2]));
+        assert_eq!(store.get_next(&[2])?, Some((vec![3], vec![3])));
+        assert_eq!(store.get(&[2, 5])?, None);
+        assert_eq!(store.get_prev(Some(&[3]))?, Some((vec![2], vec![2])));
+        assert!(store.get_next(&[0]).is_err());
+
+        assert!(store
+            .mark_range_known(vec![1], vec![3], vec![(vec![2], vec![9])])
+            .is_err());
+        assert!(store
+            .mark_range_known(vec![1], vec![3], vec![(vec![3], vec![3])])
+            .is_err());
+
+        Ok(())
+    }
+}
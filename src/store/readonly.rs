@@ -0,0 +1,82 @@
+//! Read-only views of stores.
+use super::*;
+use crate::Error as OrgaError;
+
+/// A store which wraps another store, delegating reads to it and rejecting all
+/// writes with an error. Useful for guaranteeing that code which should only
+/// read state (e.g. query handlers) can not mutate it.
+#[derive(Default, Clone)]
+pub struct ReadOnly<S> {
+    inner: S,
+}
+
+impl<S> ReadOnly<S> {
+    /// Creates a new read-only view of `inner`.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    /// Consumes the view, returning the wrapped store.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Read> Read for ReadOnly<S> {
+    #[inline]
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.inner.get(key)
+    }
+
+    #[inline]
+    fn get_next(&self, key: &[u8]) -> Result<Option<KV>> {
+        self.inner.get_next(key)
+    }
+
+    #[inline]
+    fn get_next_inclusive(&self, key: &[u8]) -> Result<Option<KV>> {
+        self.inner.get_next_inclusive(key)
+    }
+
+    #[inline]
+    fn get_prev(&self, key: Option<&[u8]>) -> Result<Option<KV>> {
+        self.inner.get_prev(key)
+    }
+
+    #[inline]
+    fn get_prev_inclusive(&self, key: Option<&[u8]>) -> Result<Option<KV>> {
+        self.inner.get_prev_inclusive(key)
+    }
+}
+
+impl<S: Read> Write for ReadOnly<S> {
+    fn put(&mut self, _key: Vec<u8>, _value: Vec<u8>) -> Result<()> {
+        Err(OrgaError::Store("read-only store".into()))
+    }
+
+    fn delete(&mut self, _key: &[u8]) -> Result<()> {
+        Err(OrgaError::Store("read-only store".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_writes() {
+        let mut inner = MapStore::new();
+        inner.put(vec![1], vec![2]).unwrap();
+
+        let mut store = ReadOnly::new(inner);
+        assert_eq!(store.get(&[1]).unwrap(), Some(vec![2]));
+        assert_eq!(store.get_next(&[0]).unwrap(), Some((vec![1], vec![2])));
+
+        assert!(store.put(vec![3], vec![4]).is_err());
+        assert!(store.delete(&[1]).is_err());
+
+        let inner = store.into_inner();
+        assert_eq!(inner.get(&[1]).unwrap(), Some(vec![2]));
+        assert_eq!(inner.get(&[3]).unwrap(), None);
+    }
+}
@@ -36,6 +36,15 @@ impl<T> Shared<T> {
         }
     }
 
+    /// Consumes the `Shared` and returns the inner store, or returns the
+    /// `Shared` back if there are other references to the store.
+    pub fn try_into_inner(self) -> std::result::Result<T, Self> {
+        match Arc::try_unwrap(self.0) {
+            Ok(inner) => Ok(inner.into_inner().unwrap()),
+            Err(arc) => Err(Shared(arc)),
+        }
+    }
+
     /// Returns a mutable reference to the inner store.
     ///
     /// # Panics
@@ -8,7 +8,7 @@ use crate::encoding::{Decode, Encode, LengthVec, Terminated};
 use crate::migrate::Migrate;
 use crate::query::FieldQuery;
 use crate::state::State;
-use crate::{orga, Error, Result};
+use crate::{gas, orga, Error, Result};
 
 // TODO: figure out how to let users set DefaultBackingStore, similar to setting
 // the global allocator in the standard library
@@ -64,7 +64,9 @@ where
     Self: State + 'static,
 {
     fn describe() -> crate::describe::Descriptor {
-        crate::describe::Builder::new::<Self>().build()
+        crate::describe::Builder::new::<Self>()
+            .layout_id("Store")
+            .build()
     }
 }
 
@@ -148,6 +150,13 @@ impl<S: Read> Store<S> {
     {
         Read::into_iter(self.clone(), bounds)
     }
+
+    /// Charges the gas for a read which returned the given entry, if any.
+    #[inline]
+    fn charge_read(&self, entry: Option<&KV>) -> Result<()> {
+        let n_bytes = entry.map_or(0, |(k, v)| self.prefix.len() + k.len() + v.len());
+        gas::charge_op(gas::READ_COST, n_bytes)
+    }
 }
 
 #[orga]
@@ -197,7 +206,11 @@ impl<S: Read> Read for Store<S> {
     #[inline]
     fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
         let prefixed = concat(self.prefix.as_slice(), key);
-        self.store.get(prefixed.as_slice())
+        let value = self.store.get(prefixed.as_slice())?;
+        let n_bytes = prefixed.len() + value.as_ref().map_or(0, Vec::len);
+        gas::charge_op(gas::READ_COST, n_bytes)?;
+
+        Ok(value)
     }
 
     #[inline]
@@ -208,6 +221,7 @@ impl<S: Read> Read for Store<S> {
             .get_next(prefixed.as_slice())?
             .filter(|(k, _)| k.starts_with(self.prefix.as_slice()))
             .map(|(k, v)| (k[self.prefix.len()..].into(), v));
+        self.charge_read(maybe_kv.as_ref())?;
         Ok(maybe_kv)
     }
 
@@ -231,6 +245,7 @@ impl<S: Read> Read for Store<S> {
                 .filter(|(k, _)| k.starts_with(self.prefix.as_slice()))
                 .map(|(k, v)| (k[self.prefix.len()..].into(), v))
         };
+        self.charge_read(maybe_kv.as_ref())?;
         Ok(maybe_kv)
     }
 }
@@ -249,12 +264,14 @@ impl<S: Write> Write for Store<S> {
         }
 
         let prefixed = concat(self.prefix.as_slice(), key.as_slice());
+        gas::charge_op(gas::WRITE_COST, prefixed.len() + value.len())?;
         self.store.put(prefixed, value)
     }
 
     #[inline]
     fn delete(&mut self, key: &[u8]) -> Result<()> {
         let prefixed = concat(self.prefix.as_slice(), key);
+        gas::charge_op(gas::WRITE_COST, prefixed.len())?;
         self.store.delete(prefixed.as_slice())
     }
 }
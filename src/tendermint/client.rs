@@ -2,7 +2,7 @@
 use crate::{
     abci::App,
     call::Call,
-    client::Transport,
+    client::{CallResult, MultiEndpointTransport, ProvenResponse, RawTransport, Transport},
     encoding::Encode,
     merk::ProofStore,
     plugins::{ABCICall, ABCIPlugin},
@@ -39,30 +39,26 @@ impl HttpClient {
     }
 }
 
-impl<T: App + Call + Query + State + Default> Transport<ABCIPlugin<T>> for HttpClient {
-    async fn call(&self, call: <ABCIPlugin<T> as Call>::Call) -> Result<()> {
-        // TODO: shouldn't need to deal with ABCIPlugin at this level
-        let call = match call {
-            ABCICall::DeliverTx(call) => call,
-            _ => return Err(Error::Client("Unexpected call type".into())),
-        };
-        let call_bytes = call.encode()?;
-        let res = self.client.broadcast_tx_commit(call_bytes).await?;
-
-        if let tendermint::abci::Code::Err(code) = res.check_tx.code {
-            let msg = format!("code {}: {}", code, res.check_tx.log);
-            return Err(Error::Call(msg));
-        }
-
-        Ok(())
+impl MultiEndpointTransport<HttpClient> {
+    /// Creates a transport which fails over between clients for each of the
+    /// given URLs, in order of preference.
+    pub fn from_urls(urls: &[&str]) -> Result<Self> {
+        let clients = urls
+            .iter()
+            .map(|url| HttpClient::new(url))
+            .collect::<Result<_>>()?;
+        MultiEndpointTransport::new(clients)
     }
+}
 
-    async fn query(&self, query: T::Query) -> Result<Store> {
-        let query_bytes = query.encode()?;
+impl HttpClient {
+    /// Sends an ABCI query, returning the height it was resolved at and the
+    /// response value (the root hash followed by the encoded Merk proof).
+    async fn abci_query(&self, query: Vec<u8>) -> Result<(u64, Vec<u8>)> {
         let maybe_height = self.height.lock().await.map(Into::into);
         let res = self
             .client
-            .abci_query(None, query_bytes, maybe_height, true)
+            .abci_query(None, query, maybe_height, true)
             .await?;
 
         if let tendermint::abci::Code::Err(code) = res.code {
@@ -72,24 +68,86 @@ impl<T: App + Call + Query + State + Default> Transport<ABCIPlugin<T>> for HttpC
 
         self.height.lock().await.replace(res.height.value() as u32);
 
-        // TODO: we shouldn't need to include the root hash in the result, it
-        // should come from a trusted source
-        let root_hash = match res.value[0..32].try_into() {
-            Ok(inner) => inner,
-            _ => {
-                return Err(Error::Tendermint(
-                    "Cannot convert result to fixed size array".into(),
-                ));
-            }
-        };
-        let proof_bytes = &res.value[32..];
+        Ok((res.height.value(), res.value))
+    }
+}
 
-        let map = merk::proofs::query::verify(proof_bytes, root_hash)?;
+/// Splits an ABCI query response value into its root hash and proof.
+fn proven_response(height: u64, value: &[u8]) -> Result<ProvenResponse> {
+    // TODO: we shouldn't need to include the root hash in the result, it
+    // should come from a trusted source
+    let root_hash = match value.get(0..32).map(TryInto::try_into) {
+        Some(Ok(inner)) => inner,
+        _ => {
+            return Err(Error::Tendermint(
+                "Cannot convert result to fixed size array".into(),
+            ));
+        }
+    };
+
+    Ok(ProvenResponse {
+        height,
+        root_hash,
+        proof: value[32..].to_vec(),
+    })
+}
+
+impl RawTransport for HttpClient {
+    async fn query_bytes(&self, query: Vec<u8>) -> Result<Vec<u8>> {
+        let (_, value) = self.abci_query(query).await?;
+        Ok(value)
+    }
+
+    async fn call_bytes(&self, call: Vec<u8>) -> Result<CallResult> {
+        let res = self.client.broadcast_tx_commit(call).await?;
+
+        if let tendermint::abci::Code::Err(code) = res.check_tx.code {
+            let msg = format!("code {}: {}", code, res.check_tx.log);
+            return Err(Error::Call(msg));
+        }
+
+        let txid = res
+            .hash
+            .as_bytes()
+            .try_into()
+            .map_err(|_| Error::Client("Unexpected transaction hash length".into()))?;
+
+        Ok(CallResult {
+            txid,
+            code: res.tx_result.code.value(),
+            log: res.tx_result.log,
+            events: res.tx_result.events.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    fn decode_response(&self, response: &[u8]) -> Result<Store> {
+        let res = proven_response(0, response)?;
+        let map = merk::proofs::query::verify(res.proof.as_slice(), res.root_hash)?;
 
         let store: Shared<ProofStore> = Shared::new(ProofStore(map));
-        let store = Store::new(BackingStore::ProofMap(store));
+        Ok(Store::new(BackingStore::ProofMap(store)))
+    }
+}
+
+impl<T: App + Call + Query + State + Default> Transport<ABCIPlugin<T>> for HttpClient {
+    async fn call(&self, call: <ABCIPlugin<T> as Call>::Call) -> Result<CallResult> {
+        // TODO: shouldn't need to deal with ABCIPlugin at this level
+        let call = match call {
+            ABCICall::DeliverTx(call) => call,
+            _ => return Err(Error::Client("Unexpected call type".into())),
+        };
+
+        self.call_bytes(call.encode()?).await
+    }
+
+    async fn query(&self, query: T::Query) -> Result<Store> {
+        let res = self.query_bytes(query.encode()?).await?;
+        self.decode_response(res.as_slice())
+    }
 
-        Ok(store)
+    async fn query_proof(&self, query: T::Query) -> Result<ProvenResponse> {
+        let (height, value) = self.abci_query(query.encode()?).await?;
+        proven_response(height, value.as_slice())
     }
 }
 
@@ -160,9 +218,10 @@ mod tests {
                 orga::abci::DefaultConfig {
                     seeds: None,
                     timeout_commit: None,
+                    ..Default::default()
                 },
             );
-            node.await.run().await.unwrap();
+            node.await.unwrap().run().await.unwrap();
             home.close().unwrap();
         });
 
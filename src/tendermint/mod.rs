@@ -1,5 +1,6 @@
 pub mod client;
 
+use crate::abci::DefaultConfig;
 use crate::error::{Error, Result};
 use flate2::read::GzDecoder;
 use hex_literal::hex;
@@ -11,6 +12,7 @@ use nom::sequence::separated_pair;
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::io::{prelude::*, BufReader};
+#[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
@@ -25,29 +27,48 @@ static TENDERMINT_BINARY_URL: &str = "https://github.com/informalsystems/tenderm
 static TENDERMINT_BINARY_URL: &str = "https://github.com/informalsystems/tendermint/releases/download/v0.34.26/tendermint_0.34.26_linux_amd64.tar.gz";
 #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
 static TENDERMINT_BINARY_URL: &str = "https://github.com/informalsystems/tendermint/releases/download/v0.34.26/tendermint_0.34.26_linux_arm64.tar.gz";
+#[cfg(all(target_os = "windows", target_arch = "x86_64"))]
+static TENDERMINT_BINARY_URL: &str = "https://github.com/informalsystems/tendermint/releases/download/v0.34.26/tendermint_0.34.26_windows_amd64.tar.gz";
 
 #[cfg(target_os = "macos")]
-static TENDERMINT_ZIP_HASH: [u8; 32] =
-    hex!("39dfde6ccc2c8b4cb699d1f3788b97da16cc8495156c39c82e94fa3834187909");
+static TENDERMINT_ZIP_HASH: Option<[u8; 32]> = Some(hex!(
+    "39dfde6ccc2c8b4cb699d1f3788b97da16cc8495156c39c82e94fa3834187909"
+));
 #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
-static TENDERMINT_ZIP_HASH: [u8; 32] =
-    hex!("70415c1d20f48e4c19d8317ec7befd924681bb2d144ad8fded429041b80b3f79");
+static TENDERMINT_ZIP_HASH: Option<[u8; 32]> = Some(hex!(
+    "70415c1d20f48e4c19d8317ec7befd924681bb2d144ad8fded429041b80b3f79"
+));
 #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
-static TENDERMINT_ZIP_HASH: [u8; 32] =
-    hex!("b0c9b5fae8a7dc53d84d62867204927ef37b1f91be5617f33a8f7fe378dfc5b9");
-
-const TENDERMINT_BINARY_NAME: &str = "tendermint-v0.34.26";
+static TENDERMINT_ZIP_HASH: Option<[u8; 32]> = Some(hex!(
+    "b0c9b5fae8a7dc53d84d62867204927ef37b1f91be5617f33a8f7fe378dfc5b9"
+));
+// TODO: pin the checksum of the Windows release archive. Until then, the
+// expected hash must be passed to `Tendermint::binary_version` (or an existing
+// binary configured with `Tendermint::binary_path`) on Windows, otherwise
+// installing returns an error.
+#[cfg(all(target_os = "windows", target_arch = "x86_64"))]
+static TENDERMINT_ZIP_HASH: Option<[u8; 32]> = None;
+
+const TENDERMINT_VERSION: &str = "v0.34.26";
+
+/// The file name of the installed binary for the given Tendermint version,
+/// e.g. `tendermint-v0.34.26` (or `tendermint-v0.34.26.exe` on Windows).
+fn binary_name(version: &str) -> String {
+    format!("tendermint-{}{}", version, std::env::consts::EXE_SUFFIX)
+}
 
-fn verify_hash(tendermint_bytes: &[u8]) {
+fn verify_hash(tendermint_bytes: &[u8], expected_hash: &[u8; 32]) -> Result<()> {
     let mut hasher = Sha256::new();
     hasher.update(tendermint_bytes);
     let digest = hasher.finalize();
-    let bytes = digest.as_slice();
-    assert_eq!(
-        bytes, TENDERMINT_ZIP_HASH,
-        "Tendermint binary zip did not match expected hash"
-    );
+    if digest.as_slice() != expected_hash {
+        return Err(Error::Tendermint(
+            "Tendermint binary zip did not match expected hash".to_string(),
+        ));
+    }
     info!("Confirmed correct Tendermint zip hash");
+
+    Ok(())
 }
 
 /// Tendermint child process handle.
@@ -71,6 +92,35 @@ impl Child {
     }
 }
 
+/// Applies the settings of a [DefaultConfig] to the contents of a Tendermint
+/// `config.toml`, leaving the settings which are not set untouched.
+pub fn apply_default_config(document: &mut DocumentMut, defaults: &DefaultConfig) {
+    if let Some(seeds) = &defaults.seeds {
+        document["p2p"]["seeds"] = value(seeds.as_str());
+    }
+    if let Some(timeout_commit) = &defaults.timeout_commit {
+        document["consensus"]["timeout_commit"] = value(timeout_commit.as_str());
+    }
+    if let Some(size) = defaults.mempool_size {
+        document["mempool"]["size"] = value(size as i64);
+    }
+    if let Some(max_txs_bytes) = defaults.mempool_max_txs_bytes {
+        document["mempool"]["max_txs_bytes"] = value(max_txs_bytes as i64);
+    }
+    if let Some(max_tx_bytes) = defaults.mempool_max_tx_bytes {
+        document["mempool"]["max_tx_bytes"] = value(max_tx_bytes as i64);
+    }
+    if let Some(payload_size) = defaults.max_packet_msg_payload_size {
+        document["p2p"]["max_packet_msg_payload_size"] = value(payload_size as i64);
+    }
+    if let Some(inbound_peers) = defaults.max_num_inbound_peers {
+        document["p2p"]["max_num_inbound_peers"] = value(inbound_peers as i64);
+    }
+    if let Some(outbound_peers) = defaults.max_num_outbound_peers {
+        document["p2p"]["max_num_outbound_peers"] = value(outbound_peers as i64);
+    }
+}
+
 /// Tendermint process manager.
 #[derive(Debug)]
 pub struct Tendermint {
@@ -79,6 +129,12 @@ pub struct Tendermint {
     genesis_bytes: Option<Vec<u8>>,
     config_contents: Option<toml_edit::DocumentMut>,
     show_logs: bool,
+    version: String,
+    binary_url: String,
+    binary_hash: Option<[u8; 32]>,
+    binary_path: Option<PathBuf>,
+    stdout: Option<Stdio>,
+    stderr: Option<Stdio>,
 }
 
 impl Tendermint {
@@ -93,42 +149,107 @@ impl Tendermint {
         if !path.exists() {
             fs::create_dir(path.clone()).expect("Failed to create Tendermint home directory");
         }
-        let tm_bin_path = path.join(TENDERMINT_BINARY_NAME);
+        // the program is chosen when spawning, since the binary can still be
+        // configured after construction
         let tendermint = Tendermint {
-            command: Command::new(tm_bin_path.to_str().unwrap()),
+            command: Command::new(binary_name(TENDERMINT_VERSION)),
             home: home_path.clone().into(),
             genesis_bytes: None,
             config_contents: None,
             show_logs: false,
+            version: TENDERMINT_VERSION.to_string(),
+            binary_url: TENDERMINT_BINARY_URL.to_string(),
+            binary_hash: TENDERMINT_ZIP_HASH,
+            binary_path: None,
+            stdout: None,
+            stderr: None,
         };
         tendermint.home(home_path.into())
     }
 
-    async fn install(&self) {
-        let tendermint_path = self.home.join(TENDERMINT_BINARY_NAME);
+    /// Sets the Tendermint (or CometBFT) release to install, downloading the
+    /// release archive from `url` and checking it against the expected SHA-256
+    /// `hash`.
+    ///
+    /// The binary is installed in the home directory as `tendermint-{version}`,
+    /// so changing the version installs the new release alongside any
+    /// previously installed one. Defaults to Tendermint v0.34.26 from the
+    /// GitHub releases for the current platform.
+    #[must_use]
+    pub fn binary_version(mut self, version: &str, url: &str, hash: [u8; 32]) -> Self {
+        self.version = version.to_string();
+        self.binary_url = url.to_string();
+        self.binary_hash = Some(hash);
+        self
+    }
+
+    /// Uses an already-installed Tendermint binary at the given path rather
+    /// than downloading one into the home directory.
+    #[must_use]
+    pub fn binary_path<T: Into<PathBuf>>(mut self, path: T) -> Self {
+        self.binary_path = Some(path.into());
+        self
+    }
+
+    fn tendermint_path(&self) -> PathBuf {
+        match &self.binary_path {
+            Some(path) => path.clone(),
+            None => self.home.join(binary_name(&self.version)),
+        }
+    }
+
+    async fn install(&self) -> Result<()> {
+        if self.binary_path.is_some() {
+            debug!("Using existing Tendermint binary");
+            return Ok(());
+        }
+
+        let tendermint_path = self.tendermint_path();
 
         if tendermint_path.is_executable() {
             debug!("Tendermint already installed");
-            return;
+            return Ok(());
         }
 
+        let expected_hash = self.binary_hash.ok_or_else(|| {
+            Error::Tendermint(
+                "No expected hash for the Tendermint release on this platform, set one with \
+                 Tendermint::binary_version"
+                    .to_string(),
+            )
+        })?;
+
         info!("Installing Tendermint to {}", self.home.to_str().unwrap());
-        let buf = reqwest::get(TENDERMINT_BINARY_URL)
+        let buf = reqwest::get(self.binary_url.as_str())
             .await
-            .expect("Failed to download Tendermint zip file from GitHub")
+            .map_err(|err| {
+                Error::Tendermint(format!("Failed to download Tendermint zip file: {}", err))
+            })?
             .bytes()
             .await
-            .expect("Failed to read bytes from Tendermint zip file")
+            .map_err(|err| {
+                Error::Tendermint(format!(
+                    "Failed to read bytes from Tendermint zip file: {}",
+                    err
+                ))
+            })?
             .to_vec();
 
-        verify_hash(&buf);
+        verify_hash(&buf, &expected_hash)?;
 
         let cursor = std::io::Cursor::new(buf);
         let tar = GzDecoder::new(cursor);
         let mut archive = Archive::new(tar);
 
         for item in archive.entries().unwrap() {
-            if item.as_ref().unwrap().path().unwrap().to_str().unwrap() == "tendermint" {
+            let is_binary = {
+                let path = item.as_ref().unwrap().path().unwrap();
+                let name = path.to_str().unwrap();
+                ["tendermint", "cometbft"]
+                    .iter()
+                    .any(|bin| name == format!("{}{}", bin, std::env::consts::EXE_SUFFIX))
+            };
+            if is_binary {
                 let mut tendermint_bytes = vec![];
                 item.unwrap().read_to_end(&mut tendermint_bytes).unwrap();
 
@@ -137,13 +258,18 @@ impl Tendermint {
                 f.write_all(tendermint_bytes.as_slice())
                     .expect("Failed to write Tendermint binary to file system");
 
-                let mut perms = f.metadata().unwrap().permissions();
-                perms.set_mode(0o755);
-                f.set_permissions(perms)
-                    .expect("Failed to set Tendermint binary permissions");
+                #[cfg(unix)]
+                {
+                    let mut perms = f.metadata().unwrap().permissions();
+                    perms.set_mode(0o755);
+                    f.set_permissions(perms)
+                        .expect("Failed to set Tendermint binary permissions");
+                }
                 break;
             }
         }
+
+        Ok(())
     }
 
     /// Sets command line flags for the Tendermint process.
@@ -154,6 +280,19 @@ impl Tendermint {
         self
     }
 
+    fn spawn(&mut self, subcommand: &str) -> std::process::Child {
+        let mut command = Command::new(self.tendermint_path());
+        command.args(self.command.get_args()).arg(subcommand);
+        if let Some(stdout) = self.stdout.take() {
+            command.stdout(stdout);
+        }
+        if let Some(stderr) = self.stderr.take() {
+            command.stderr(stderr);
+        }
+
+        command.spawn().unwrap()
+    }
+
     fn home(mut self, home_path: PathBuf) -> Self {
         let new_home = home_path.to_str().unwrap();
         self.command.arg("--home");
@@ -299,7 +438,7 @@ impl Tendermint {
     /// ```
     #[must_use]
     pub fn stdout<T: Into<Stdio>>(mut self, cfg: T) -> Self {
-        self.command.stdout(cfg);
+        self.stdout = Some(cfg.into());
         self
     }
 
@@ -332,7 +471,7 @@ impl Tendermint {
     /// ```
     #[must_use]
     pub fn stderr<T: Into<Stdio>>(mut self, cfg: T) -> Self {
-        self.command.stderr(cfg);
+        self.stderr = Some(cfg.into());
         self
     }
 
@@ -532,16 +671,16 @@ impl Tendermint {
     /// Calls tendermint start with configured arguments
     ///
     /// Note: This will locally install the Tendermint binary if it is
-    /// not already contained in the Tendermint home directory
-    pub async fn start(mut self) -> Child {
-        self.install().await;
+    /// not already contained in the Tendermint home directory, returning an
+    /// error if it can not be installed.
+    pub async fn start(mut self) -> Result<Child> {
+        self.install().await?;
         self.mutate_configuration();
-        self.command.arg("start");
         if !self.show_logs {
-            self.command.stdout(Stdio::piped());
+            self.stdout = Some(Stdio::piped());
         }
 
-        let mut child = self.command.spawn().unwrap();
+        let mut child = self.spawn("start");
 
         let (tx, rx): (Sender<Option<()>>, Receiver<Option<()>>) = mpsc::channel();
         if !self.show_logs {
@@ -597,33 +736,34 @@ impl Tendermint {
             });
         }
 
-        Child::new(child, tx)
+        Ok(Child::new(child, tx))
     }
 
     /// Calls tendermint init with configured arguments
     ///
     /// Note: This will locally install the Tendermint binary if it is
-    /// not already contained in the Tendermint home directory
-    #[must_use]
-    pub async fn init(mut self) -> Self {
-        self.install().await;
-        self.command.arg("init");
-        let mut child = self.command.spawn().unwrap();
-        child.wait().unwrap();
+    /// not already contained in the Tendermint home directory, returning an
+    /// error if it can not be installed.
+    pub async fn init(mut self) -> Result<Self> {
+        self.install().await?;
+        let mut child = self.spawn("init");
+        child.wait()?;
         self.mutate_configuration();
 
-        self
+        Ok(self)
     }
 
     /// Calls tendermint start with configured arguments
     ///
     /// Note: This will locally install the Tendermint binary if it is
-    /// not already contained in the Tendermint home directory
-    pub async fn unsafe_reset_all(mut self) {
-        self.install().await;
-        self.command.arg("unsafe_reset_all");
-        let mut child = self.command.spawn().unwrap();
-        child.wait().unwrap();
+    /// not already contained in the Tendermint home directory, returning an
+    /// error if it can not be installed.
+    pub async fn unsafe_reset_all(mut self) -> Result<()> {
+        self.install().await?;
+        let mut child = self.spawn("unsafe_reset_all");
+        child.wait()?;
+
+        Ok(())
     }
 }
 
@@ -717,9 +857,91 @@ mod tests {
         let expected: HashSet<String> = HashSet::from([
             "config".to_string(),
             "data".to_string(),
-            TENDERMINT_BINARY_NAME.to_string(),
+            binary_name(TENDERMINT_VERSION),
         ]);
 
         assert_eq!(file_set, expected);
     }
+
+    #[test]
+    fn platform_binary_name() {
+        let name = binary_name(TENDERMINT_VERSION);
+        assert!(name.starts_with("tendermint-v0.34.26"));
+        if cfg!(windows) {
+            assert_eq!(name, "tendermint-v0.34.26.exe");
+        } else {
+            assert_eq!(name, "tendermint-v0.34.26");
+        }
+    }
+
+    #[test]
+    fn default_config_applied() {
+        let mut document = r#"
+[p2p]
+seeds = ""
+max_num_inbound_peers = 40
+
+[mempool]
+size = 5000
+max_txs_bytes = 1073741824
+"#
+        .parse::<DocumentMut>()
+        .unwrap();
+
+        apply_default_config(
+            &mut document,
+            &DefaultConfig {
+                mempool_size: Some(10_000),
+                max_num_outbound_peers: Some(20),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(document["mempool"]["size"].as_integer(), Some(10_000));
+        assert_eq!(
+            document["mempool"]["max_txs_bytes"].as_integer(),
+            Some(1_073_741_824)
+        );
+        assert_eq!(
+            document["p2p"]["max_num_outbound_peers"].as_integer(),
+            Some(20)
+        );
+        assert_eq!(
+            document["p2p"]["max_num_inbound_peers"].as_integer(),
+            Some(40)
+        );
+        assert_eq!(document["p2p"]["seeds"].as_str(), Some(""));
+        assert!(document.to_string().contains("size = 10000\n"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn custom_binary_path_skips_install() {
+        let home = TempDir::new().unwrap();
+        let bin_dir = TempDir::new().unwrap();
+        let bin_path = bin_dir.path().join("tendermint");
+        let args_path = bin_dir.path().join("args");
+
+        // a stand-in binary which records the arguments it was called with
+        fs::write(
+            &bin_path,
+            format!("#!/bin/sh\necho \"$@\" > {}\n", args_path.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&bin_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        Tendermint::new(home.path())
+            .binary_path(&bin_path)
+            .binary_version("v0.0.0", "http://localhost:1/unreachable", [0; 32])
+            .init()
+            .await
+            .unwrap();
+
+        let args = fs::read_to_string(args_path).unwrap();
+        assert_eq!(
+            args.trim(),
+            format!("--home {} init", home.path().display())
+        );
+        assert_eq!(home.path().read_dir().unwrap().count(), 0);
+    }
 }
@@ -1,8 +1,8 @@
 //! Network upgrade coordination module.
 
 use crate::coins::{Address, Amount, Decimal};
-use crate::collections::Map;
-use crate::context::GetContext;
+use crate::collections::{Map, Value};
+use crate::context::{Context, GetContext};
 use crate::encoding::LengthVec;
 use crate::migrate::MigrateFrom;
 use crate::orga;
@@ -47,6 +47,19 @@ pub struct Signal {
     pub time: i64,
 }
 
+/// A snapshot of the state of upgrade signaling, e.g. as returned by
+/// [crate::client::AppClient::query_upgrade_status].
+#[derive(Debug, Clone)]
+pub struct UpgradeStatus {
+    /// The currently active network version.
+    pub current_version: Version,
+    /// The most recent signal of each validator, by consensus key.
+    pub signals: Vec<(PubKey, Signal)>,
+    /// The fraction of voting power counted as signaling each candidate
+    /// version, as returned by [Upgrade::signal_fractions].
+    pub signal_fractions: Vec<(Version, Decimal)>,
+}
+
 /// Network upgrade coordination module.
 ///
 /// A `threshold` is specified as the fraction of total voting power that must
@@ -73,16 +86,12 @@ pub struct Upgrade {
     /// allow reading without needing to first migrate at startup, since it
     /// may determine whether we need to perform a migration.
     #[state(absolute_prefix(b"/version"))]
-    // TODO: use Value/Box instead of Map<(), _>
-    pub current_version: Map<(), Version>,
+    pub current_version: Value<Version>,
 }
 
 impl Default for Upgrade {
     fn default() -> Self {
-        let mut current_version = Map::new();
-        current_version
-            .insert((), vec![0].try_into().unwrap())
-            .unwrap();
+        let current_version = Value::new(vec![0].try_into().unwrap()).unwrap();
         Self {
             signals: Default::default(),
             threshold: (Amount::new(2) / Amount::new(3)).result().unwrap(),
@@ -101,6 +110,56 @@ impl MigrateFrom<UpgradeV0> for UpgradeV1 {
 
 #[orga]
 impl Upgrade {
+    /// Returns the currently active network version.
+    #[query]
+    pub fn current_version(&self) -> Result<Version> {
+        Ok(self.current_version.get()?.clone())
+    }
+
+    /// Returns the most recent signal of each validator, by consensus key.
+    #[query]
+    pub fn signals(&self) -> Result<Vec<(PubKey, Signal)>> {
+        self.signals
+            .iter()?
+            .map(|entry| {
+                let (cons_key, signal) = entry?;
+                Ok((*cons_key, signal.clone()))
+            })
+            .collect()
+    }
+
+    /// Returns the fraction of the current validator set's voting power
+    /// signaling each candidate version, counted the same way as when
+    /// tallying votes in [Upgrade::step] (so signals are only included once
+    /// they are older than `activation_delay_seconds`).
+    #[query]
+    pub fn signal_fractions(&self) -> Result<Vec<(Version, Decimal)>> {
+        let (total_vp, signal_vps) = self.tally()?;
+        if total_vp == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut fractions = signal_vps
+            .into_iter()
+            .map(|(version, vp)| {
+                let fraction = (Amount::new(vp) / Amount::new(total_vp)).result()?;
+                Ok((version, fraction))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        fractions.sort_by(|(a, _), (b, _)| a.as_slice().cmp(b.as_slice()));
+
+        Ok(fractions)
+    }
+
+    /// Returns the current version, signals, and signal tally.
+    pub fn status(&self) -> Result<UpgradeStatus> {
+        Ok(UpgradeStatus {
+            current_version: self.current_version()?,
+            signals: self.signals()?,
+            signal_fractions: self.signal_fractions()?,
+        })
+    }
+
     /// Call for validators to signal readiness for upgrade to a new version.
     #[call]
     pub fn signal(&mut self, version: Version) -> Result<()> {
@@ -128,6 +187,31 @@ impl Upgrade {
         self.signals.insert(cons_key, signal)
     }
 
+    /// Call for validators to withdraw their signal, e.g. if they signaled a
+    /// version by mistake. Subject to the same rate limit as signaling.
+    #[call]
+    pub fn unsignal(&mut self) -> Result<()> {
+        crate::plugins::disable_fee();
+        let cons_key = self.signer_cons_key()?;
+        let now = self.current_seconds()?;
+
+        let prev_signal = self
+            .signals
+            .get(cons_key)?
+            .ok_or_else(|| OrgaError::App("No signal to withdraw".to_string()))?;
+        let soonest = prev_signal.time + self.rate_limit_seconds;
+        if now < soonest {
+            return Err(OrgaError::App(format!(
+                "Must wait {} seconds before withdrawing signal",
+                soonest - now
+            )));
+        }
+
+        self.signals.remove(cons_key)?;
+
+        Ok(())
+    }
+
     /// Tallies votes and possibly transitions to a new network version.
     ///
     /// This should typically be called in a `BeginBlock`. If an
@@ -135,7 +219,7 @@ impl Upgrade {
     /// the software, performing a migration if necessary.
     pub fn step(&mut self, bin_version: &Version, upgrade_authorized: bool) -> Result<()> {
         let bin_version = bin_version.clone();
-        let net_version = self.current_version.get(())?.unwrap().clone();
+        let net_version = self.current_version.get()?.clone();
         if bin_version != net_version {
             return Err(Error::Version {
                 expected: net_version,
@@ -148,13 +232,26 @@ impl Upgrade {
             return Ok(());
         }
         if let Some(new_version) = self.upgrade_ready()? {
-            self.current_version.insert((), new_version)?;
+            self.current_version.set(new_version)?;
         }
 
         Ok(())
     }
 
-    fn upgrade_ready(&mut self) -> Result<Option<Version>> {
+    fn upgrade_ready(&self) -> Result<Option<Version>> {
+        let (total_vp, signal_vps) = self.tally()?;
+        let vp_threshold = (self.threshold * Amount::new(total_vp))?;
+
+        Ok(signal_vps
+            .into_iter()
+            .find(|(_, vp)| Amount::new(*vp) > vp_threshold)
+            .map(|(version, _)| version))
+    }
+
+    /// Returns the total voting power of the current validator set, and the
+    /// voting power of the counted signals for each version other than the
+    /// current one.
+    fn tally(&self) -> Result<(u64, HashMap<Version, u64>)> {
         let now = self.current_seconds()?;
         let latest_counted_time = now - self.activation_delay_seconds;
         let mut total_vp = 0;
@@ -165,24 +262,19 @@ impl Upgrade {
                 if signal.time <= latest_counted_time
                     // TODO: implement comparison between LengthVec and Vec
                     && signal.version.clone()
-                        != *self.current_version.get(())?.unwrap()
+                        != *self.current_version.get()?
                     && validator.power > 0
                 {
                     *signal_vps.entry(signal.version.clone()).or_default() += validator.power;
                 }
             }
         }
-        let vp_threshold = (self.threshold * Amount::new(total_vp))?;
 
-        Ok(signal_vps
-            .into_iter()
-            .find(|(_, vp)| Amount::new(*vp) > vp_threshold)
-            .map(|(version, _)| version))
+        Ok((total_vp, signal_vps))
     }
 
-    fn current_seconds(&mut self) -> Result<i64> {
-        let time = self
-            .context::<Time>()
+    fn current_seconds(&self) -> Result<i64> {
+        let time = Context::resolve::<Time>()
             .ok_or_else(|| OrgaError::Coins("No Time context available".into()))?;
 
         Ok(time.seconds)
@@ -206,9 +298,8 @@ impl Upgrade {
             .ok_or_else(|| OrgaError::App("Signer does not have a consensus key".to_string()))
     }
 
-    fn current_validators(&mut self) -> Result<Vec<ValidatorEntry>> {
-        let validators: &mut Validators = self
-            .context()
+    fn current_validators(&self) -> Result<Vec<ValidatorEntry>> {
+        let validators = Context::resolve::<Validators>()
             .ok_or_else(|| OrgaError::App("No validator context found".to_string()))?;
         validators.entries()
     }
@@ -223,7 +314,6 @@ pub fn load_version(store: Store) -> Result<Option<Vec<u8>>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::context::Context;
     use serial_test::serial;
     use std::cell::RefCell;
     use std::rc::Rc;
@@ -268,11 +358,11 @@ mod tests {
             rate_limit_seconds: 5,
             ..Default::default()
         };
-        upgrade.current_version.insert((), version.clone())?;
+        upgrade.current_version.set(version.clone())?;
 
         assert!(upgrade.upgrade_ready()?.is_none());
         upgrade.step(&version, true)?;
-        assert_eq!(&*upgrade.current_version.get(())?.unwrap(), &version);
+        assert_eq!(&*upgrade.current_version.get()?, &version);
         set_signer([0; 20]);
         upgrade.signal(next_version.clone())?;
         set_time(1);
@@ -282,17 +372,116 @@ mod tests {
         assert!(upgrade.upgrade_ready()?.is_none());
         upgrade.step(&version, true)?;
         assert!(upgrade.step(&next_version, true).is_err());
-        assert_eq!(&*upgrade.current_version.get(())?.unwrap(), &version);
+        assert_eq!(&*upgrade.current_version.get()?, &version);
         set_time(12);
         assert!(upgrade.upgrade_ready()?.unwrap() == next_version);
-        assert_eq!(&*upgrade.current_version.get(())?.unwrap(), &version);
+        assert_eq!(&*upgrade.current_version.get()?, &version);
         upgrade.step(&version, false)?;
-        assert_eq!(&*upgrade.current_version.get(())?.unwrap(), &version);
+        assert_eq!(&*upgrade.current_version.get()?, &version);
         upgrade.step(&version, true)?;
-        assert_eq!(&*upgrade.current_version.get(())?.unwrap(), &next_version);
+        assert_eq!(&*upgrade.current_version.get()?, &next_version);
         assert!(upgrade.step(&version, true).is_err());
         upgrade.step(&next_version, true)?;
 
         Ok(())
     }
+
+    #[test]
+    #[serial]
+    fn queries() -> Result<()> {
+        setup_validators();
+        set_time(0);
+        let version: Version = vec![0].try_into().unwrap();
+        let next_version: Version = vec![1].try_into().unwrap();
+        let other_version: Version = vec![2].try_into().unwrap();
+        let mut upgrade = Upgrade {
+            activation_delay_seconds: 10,
+            ..Default::default()
+        };
+        assert_eq!(upgrade.current_version()?, version);
+        assert!(upgrade.signals()?.is_empty());
+
+        set_signer([0; 20]);
+        upgrade.signal(next_version.clone())?;
+        set_signer([1; 20]);
+        upgrade.signal(other_version.clone())?;
+        set_time(5);
+        set_signer([2; 20]);
+        upgrade.signal(next_version.clone())?;
+
+        let signals = upgrade.signals()?;
+        assert_eq!(signals.len(), 3);
+        assert_eq!(signals[0].0, [0; 32]);
+        assert_eq!(signals[0].1.version, next_version);
+        assert_eq!(signals[1].1.version, other_version);
+        assert_eq!(signals[2].1.time, 5);
+
+        // signals are only counted after the activation delay
+        assert!(upgrade.signal_fractions()?.is_empty());
+        set_time(10);
+        assert_eq!(
+            upgrade.signal_fractions()?,
+            vec![
+                (
+                    next_version.clone(),
+                    (Amount::new(10) / Amount::new(31)).result()?
+                ),
+                (
+                    other_version.clone(),
+                    (Amount::new(10) / Amount::new(31)).result()?
+                ),
+            ]
+        );
+        set_time(15);
+        assert_eq!(
+            upgrade.signal_fractions()?,
+            vec![
+                (
+                    next_version.clone(),
+                    (Amount::new(21) / Amount::new(31)).result()?
+                ),
+                (other_version, (Amount::new(10) / Amount::new(31)).result()?),
+            ]
+        );
+
+        upgrade.step(&version, true)?;
+        assert_eq!(upgrade.current_version()?, next_version);
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn unsignal() -> Result<()> {
+        setup_validators();
+        set_time(0);
+        let version: Version = vec![0].try_into().unwrap();
+        let next_version: Version = vec![1].try_into().unwrap();
+        let mut upgrade = Upgrade {
+            activation_delay_seconds: 10,
+            rate_limit_seconds: 5,
+            ..Default::default()
+        };
+
+        set_signer([1; 20]);
+        assert!(upgrade.unsignal().is_err());
+        upgrade.signal(next_version.clone())?;
+        set_signer([2; 20]);
+        upgrade.signal(next_version.clone())?;
+
+        set_time(4);
+        assert!(upgrade.unsignal().is_err());
+        set_time(5);
+        upgrade.unsignal()?;
+        assert!(upgrade.unsignal().is_err());
+        assert_eq!(upgrade.signals()?.len(), 1);
+
+        // the remaining signal is below the threshold
+        set_time(20);
+        assert!(upgrade.upgrade_ready()?.is_none());
+        upgrade.step(&version, true)?;
+        assert_eq!(upgrade.current_version()?, version);
+
+        Ok(())
+    }
 }